@@ -1,6 +1,17 @@
+pub mod auth;
+mod container;
+pub mod datasets;
 pub mod dtos;
 mod handler;
 pub mod handlers_impl;
+pub mod history;
+pub mod mediator;
+pub mod middlewares;
+pub mod projections;
 pub mod requests;
+mod unit_of_work;
 
+pub use container::{Container, FromContainer};
 pub use handler::Handler;
+pub use mediator::Mediator;
+pub use unit_of_work::UnitOfWork;
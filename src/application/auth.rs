@@ -0,0 +1,237 @@
+use crate::application::mediator::{Middleware, Next, Outcome, Reply, RequestContext};
+use crate::application::UnitOfWork;
+use crate::domain::{AuditEntry, AuditRepository, Permission, User};
+use chrono::Utc;
+use std::cell::RefCell;
+use std::rc::Rc;
+use uuid::Uuid;
+
+// -------------------------------------------------------------------------------------------------
+
+/// ログイン中のユーザー
+#[derive(Default)]
+pub struct Session {
+    user: RefCell<Option<User>>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn sign_in(&self, user: User) {
+        *self.user.borrow_mut() = Some(user);
+    }
+    /// ログアウトしたユーザー
+    pub fn sign_out(&self) -> Option<User> {
+        self.user.borrow_mut().take()
+    }
+    pub fn current_user(&self) -> Option<User> {
+        self.user.borrow().clone()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// ログイン中のユーザーのロールがリクエストに必要な権限を持つときのみハンドラーに渡す
+pub struct PermissionMiddleware {
+    session: Rc<Session>,
+}
+
+impl PermissionMiddleware {
+    pub fn new(session: Rc<Session>) -> Self {
+        Self { session }
+    }
+}
+
+impl Middleware for PermissionMiddleware {
+    fn handle(&self, context: &RequestContext, next: Next<'_>) -> Result<Reply, String> {
+        let permission = match context.permission() {
+            Some(permission) => permission,
+            None => return next(),
+        };
+        match self.session.current_user() {
+            Some(user) if user.role().allows(permission) => next(),
+            Some(user) => Err(format!(
+                "{} requires {} permission, but {} is {}",
+                context.name(),
+                permission,
+                user.username(),
+                user.role()
+            )),
+            None => Err(format!("{} requires login", context.name())),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 成功した変更を実行したユーザーと対象のIDとともに記録する．
+/// 記録は変更と同じ作業単位で保存し，取り消した変更の記録は残さない
+pub struct AuditMiddleware<A: AuditRepository + ?Sized> {
+    audit_repo: Rc<A>,
+    session: Rc<Session>,
+    unit_of_work: UnitOfWork,
+}
+
+impl<A: AuditRepository + ?Sized> AuditMiddleware<A> {
+    pub fn new(audit_repo: Rc<A>, session: Rc<Session>) -> Self {
+        Self {
+            audit_repo,
+            session,
+            unit_of_work: UnitOfWork::new(),
+        }
+    }
+    pub fn with_unit_of_work(mut self, unit_of_work: UnitOfWork) -> Self {
+        self.unit_of_work = unit_of_work;
+        self
+    }
+}
+
+/// 拒否されたか，ハンドラーが失敗を返したときに取り消す
+struct Audited(Result<Reply, String>);
+
+impl Outcome for Audited {
+    fn error(&self) -> Option<&str> {
+        match &self.0 {
+            Ok(reply) => reply.error(),
+            Err(reason) => Some(reason),
+        }
+    }
+}
+
+impl<A: AuditRepository + ?Sized> Middleware for AuditMiddleware<A> {
+    fn handle(&self, context: &RequestContext, next: Next<'_>) -> Result<Reply, String> {
+        let is_change = matches!(
+            context.permission(),
+            Some(Permission::Write | Permission::Administer)
+        );
+        // ログインやログアウトでユーザーが替わる前の時点で実行者とする
+        let user = match (is_change, self.session.current_user()) {
+            (true, Some(user)) => user,
+            _ => return next(),
+        };
+        // 対象はリクエストの最初のID
+        let subject_id = context
+            .fields()
+            .iter()
+            .find_map(|(_, value)| Uuid::parse_str(value).ok());
+        let Audited(reply) = self.unit_of_work.run(|| {
+            let reply = next();
+            if matches!(&reply, Ok(reply) if reply.error().is_none()) {
+                self.audit_repo.record(AuditEntry::new(
+                    &user,
+                    context.name(),
+                    subject_id,
+                    Utc::now(),
+                ));
+            }
+            Audited(reply)
+        });
+        reply
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AuditMiddleware, PermissionMiddleware};
+    use crate::application::requests::{
+        CreateClientUseCaseRequest, CreateUserUseCaseRequest, DeleteClientUseCaseRequest,
+        GetAllClientUseCaseRequest, GetAuditLogUseCaseRequest, ListUsersUseCaseRequest,
+        LoginUseCaseRequest, LogoutUseCaseRequest, SetupAdminUseCaseRequest,
+    };
+    use crate::application::{Container, FromContainer, Mediator};
+    use crate::domain::AuditRepository;
+    use crate::infrastructure::{
+        InMemoryAuditRepository, InMemoryClientRepository, InMemoryOutboxRepository,
+        InMemoryProjectRepository, InMemoryRelationshipRepository, InMemoryUserRepository,
+        InMemoryWebhookRepository,
+    };
+    use assert_matches::assert_matches;
+    use std::rc::Rc;
+
+    #[test]
+    fn check_permissions_and_record_changes() {
+        let audit_repo = Rc::new(InMemoryAuditRepository::new());
+        let container = Container::new(
+            Rc::new(InMemoryClientRepository::new()),
+            Rc::new(InMemoryProjectRepository::new()),
+            Rc::new(InMemoryRelationshipRepository::new()),
+            Rc::new(InMemoryOutboxRepository::new()),
+            Rc::new(InMemoryWebhookRepository::new()),
+            Rc::new(InMemoryUserRepository::new()),
+            Rc::clone(&audit_repo) as Rc<dyn AuditRepository>,
+        );
+        let mediator = Mediator::from_container(&container)
+            .with_middleware(PermissionMiddleware::new(container.session()))
+            .with_middleware(AuditMiddleware::new(
+                container.audit_repo(),
+                container.session(),
+            ));
+        let create_client = || {
+            mediator.send(CreateClientUseCaseRequest::new(
+                "Taro".to_string(),
+                "Tokyo".to_string(),
+            ))
+        };
+
+        // ログインするまではリクエストを拒否する
+        let res = mediator.send(GetAllClientUseCaseRequest::new());
+        assert_matches!(res, Err(err) if err.contains("requires login"));
+
+        let res = mediator.send(SetupAdminUseCaseRequest::new(
+            "admin".to_string(),
+            "correct horse".to_string(),
+        ));
+        assert_matches!(res, Ok(Ok(_)));
+        let res = mediator.send(CreateUserUseCaseRequest::new(
+            "viewer".to_string(),
+            "battery staple".to_string(),
+            "viewer".to_string(),
+        ));
+        assert_matches!(res, Ok(Ok(_)));
+        assert_matches!(mediator.send(LogoutUseCaseRequest), Ok(Ok(())));
+
+        let res = mediator.send(LoginUseCaseRequest::new(
+            "viewer".to_string(),
+            "battery staple".to_string(),
+        ));
+        assert_matches!(res, Ok(Ok(user)) if user.username() == "viewer");
+        assert_matches!(mediator.send(GetAllClientUseCaseRequest::new()), Ok(_));
+        assert_matches!(create_client(), Err(err) if err.contains("requires write permission"));
+        assert_matches!(mediator.send(ListUsersUseCaseRequest), Err(_));
+
+        let res = mediator.send(LoginUseCaseRequest::new(
+            "admin".to_string(),
+            "correct horse".to_string(),
+        ));
+        assert_matches!(res, Ok(Ok(_)));
+        let client = match create_client() {
+            Ok(Ok(created)) => created.client().clone(),
+            _ => panic!("client should be created"),
+        };
+        // 失敗した変更は記録しない
+        assert_matches!(create_client(), Ok(Err(_)));
+        let res = mediator.send(DeleteClientUseCaseRequest::new(client.id()));
+        assert_matches!(res, Ok(Ok(())));
+
+        let actions = audit_repo
+            .all()
+            .iter()
+            .map(|entry| (entry.action().to_string(), entry.subject_id()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                ("CreateUserUseCaseRequest".to_string(), None),
+                ("CreateClientUseCaseRequest".to_string(), None),
+                ("DeleteClientUseCaseRequest".to_string(), Some(client.id())),
+            ]
+        );
+        assert!(audit_repo
+            .all()
+            .iter()
+            .all(|entry| entry.username() == "admin"));
+        let res = mediator.send(GetAuditLogUseCaseRequest);
+        assert_matches!(res, Ok(entries) if entries.len() == 3);
+    }
+}
@@ -0,0 +1,769 @@
+use crate::application::auth::Session;
+use crate::application::handlers_impl::{
+    AddContactUseCaseHandler, AddNoteUseCaseHandler, AddTagUseCaseHandler,
+    AnonymizeClientsUseCaseHandler, ChangeClientStatusUseCaseHandler, CloseProjectUseCaseHandler,
+    CreateClientUseCaseHandler, CreateProjectUseCaseHandler, CreateUserUseCaseHandler,
+    CreateWebhookUseCaseHandler, DeleteClientUseCaseHandler, DeleteNoteUseCaseHandler,
+    DeleteWebhookUseCaseHandler, EditClientUseCaseHandler, EditNoteUseCaseHandler,
+    EraseClientUseCaseHandler, ExportClientPersonalDataUseCaseHandler, ExportClientsUseCaseHandler,
+    FindDuplicatesUseCaseHandler, GetAllClientUseCaseHandler, GetAuditLogUseCaseHandler,
+    GetClientSummariesUseCaseHandler, GetClientUseCaseHandler, GetEditHistoryUseCaseHandler,
+    GetEventHistoryUseCaseHandler, GetHierarchyUseCaseHandler, GetLocationCountsUseCaseHandler,
+    GetNotesUseCaseHandler, GetOutboxUseCaseHandler, GetTagCountsUseCaseHandler,
+    GetWebhookDeliveriesUseCaseHandler, GroupClientsByRegionUseCaseHandler,
+    IsSetupRequiredUseCaseHandler, LinkClientsUseCaseHandler, ListProjectsUseCaseHandler,
+    ListUsersUseCaseHandler, ListWebhooksUseCaseHandler, LoginUseCaseHandler, LogoutUseCaseHandler,
+    MergeClientsUseCaseHandler, RebuildProjectionsUseCaseHandler, RedoUseCaseHandler,
+    ReencryptClientsUseCaseHandler, RelayOutboxUseCaseHandler, RemoveContactUseCaseHandler,
+    RemoveTagUseCaseHandler, ReplayOutboxMessageUseCaseHandler, SetPrimaryContactUseCaseHandler,
+    SetupAdminUseCaseHandler, UndoUseCaseHandler, UnlinkClientsUseCaseHandler,
+};
+use crate::application::history::EditHistory;
+use crate::application::projections::{ClientProjections, ProjectingEventPublisher};
+use crate::application::{Mediator, UnitOfWork};
+use crate::domain::{
+    AuditRepository, ClientRepository, EventHistory, EventPublisher, EventSink,
+    IdempotencyRepository, KeyRotation, OutboxRepository, ProjectRepository,
+    RelationshipRepository, TenantId, TombstoneRepository, Transactional, UniquenessPolicy,
+    UserRepository, WebhookRepository,
+};
+use chrono::Duration;
+use std::rc::Rc;
+
+/// ハンドラーが依存するリポジトリや設定をまとめて保持する
+#[derive(Clone)]
+pub struct Container {
+    client_repo: Rc<dyn ClientRepository>,
+    project_repo: Rc<dyn ProjectRepository>,
+    relationship_repo: Rc<dyn RelationshipRepository>,
+    outbox_repo: Rc<dyn OutboxRepository>,
+    webhook_repo: Rc<dyn WebhookRepository>,
+    user_repo: Rc<dyn UserRepository>,
+    audit_repo: Rc<dyn AuditRepository>,
+    idempotency_repo: Option<Rc<dyn IdempotencyRepository>>,
+    tombstone_repo: Option<Rc<dyn TombstoneRepository>>,
+    event_publisher: Rc<ProjectingEventPublisher<dyn ClientRepository>>,
+    key_rotation: Option<Rc<dyn KeyRotation>>,
+    event_sinks: Vec<Rc<dyn EventSink>>,
+    event_history: Option<Rc<dyn EventHistory>>,
+    delivery_retries: usize,
+    max_delivery_attempts: Option<u32>,
+    delivery_backoff: Duration,
+    uniqueness_policy: UniquenessPolicy,
+    projections: Rc<ClientProjections>,
+    history: Rc<EditHistory>,
+    session: Rc<Session>,
+    tenant: TenantId,
+    unit_of_work: UnitOfWork,
+}
+
+impl Container {
+    pub fn new(
+        client_repo: Rc<dyn ClientRepository>,
+        project_repo: Rc<dyn ProjectRepository>,
+        relationship_repo: Rc<dyn RelationshipRepository>,
+        outbox_repo: Rc<dyn OutboxRepository>,
+        webhook_repo: Rc<dyn WebhookRepository>,
+        user_repo: Rc<dyn UserRepository>,
+        audit_repo: Rc<dyn AuditRepository>,
+    ) -> Self {
+        let projections = Rc::new(ClientProjections::new());
+        Self {
+            event_publisher: Rc::new(ProjectingEventPublisher::new(
+                Rc::clone(&client_repo),
+                Rc::clone(&projections),
+                None,
+            )),
+            client_repo,
+            project_repo,
+            relationship_repo,
+            outbox_repo,
+            webhook_repo,
+            user_repo,
+            audit_repo,
+            idempotency_repo: None,
+            tombstone_repo: None,
+            key_rotation: None,
+            event_sinks: Vec::new(),
+            event_history: None,
+            delivery_retries: 0,
+            max_delivery_attempts: None,
+            delivery_backoff: Duration::zero(),
+            uniqueness_policy: UniquenessPolicy::default(),
+            projections,
+            history: Rc::new(EditHistory::default()),
+            session: Rc::new(Session::new()),
+            tenant: TenantId::default(),
+            unit_of_work: UnitOfWork::new(),
+        }
+    }
+    /// 作成の冪等キーを記録する
+    pub fn with_idempotency_repo(
+        mut self,
+        idempotency_repo: Rc<dyn IdempotencyRepository>,
+    ) -> Self {
+        self.idempotency_repo = Some(idempotency_repo);
+        self
+    }
+    /// 個人データを消去したクライアントの墓標を保存する
+    pub fn with_tombstone_repo(mut self, tombstone_repo: Rc<dyn TombstoneRepository>) -> Self {
+        self.tombstone_repo = Some(tombstone_repo);
+        self
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Rc::new(ProjectingEventPublisher::new(
+            self.client_repo(),
+            self.projections(),
+            Some(event_publisher),
+        ));
+        self
+    }
+    /// クライアントを暗号化して保存するときの鍵の切り替え
+    pub fn with_key_rotation(mut self, key_rotation: Rc<dyn KeyRotation>) -> Self {
+        self.key_rotation = Some(key_rotation);
+        self
+    }
+    /// アウトボックスのメッセージの配信先
+    pub fn with_event_sink(mut self, event_sink: Rc<dyn EventSink>) -> Self {
+        self.event_sinks.push(event_sink);
+        self
+    }
+    /// 配信されたイベントの履歴
+    pub fn with_event_history(mut self, event_history: Rc<dyn EventHistory>) -> Self {
+        self.event_history = Some(event_history);
+        self
+    }
+    pub fn with_delivery_retries(mut self, delivery_retries: usize) -> Self {
+        self.delivery_retries = delivery_retries;
+        self
+    }
+    /// 配信を諦めてデッドレターにするまでに失敗できる回数
+    pub fn with_max_delivery_attempts(mut self, max_delivery_attempts: u32) -> Self {
+        self.max_delivery_attempts = Some(max_delivery_attempts);
+        self
+    }
+    /// 配信に失敗したメッセージを次に配信するまでの最初の間隔．失敗するたびに倍にする
+    pub fn with_delivery_backoff(mut self, delivery_backoff: Duration) -> Self {
+        self.delivery_backoff = delivery_backoff;
+        self
+    }
+    pub fn with_uniqueness_policy(mut self, uniqueness_policy: UniquenessPolicy) -> Self {
+        self.uniqueness_policy = uniqueness_policy;
+        self
+    }
+    /// 取り消せる変更の数
+    pub fn with_history_size(mut self, history_size: usize) -> Self {
+        self.history = Rc::new(EditHistory::new(history_size));
+        self
+    }
+    /// クライアントのリポジトリが限定されているテナント
+    pub fn with_tenant(mut self, tenant: TenantId) -> Self {
+        self.tenant = tenant;
+        self
+    }
+    /// コマンドの実行時に変更をまとめるリポジトリ
+    pub fn with_unit_of_work(mut self, unit_of_work: UnitOfWork) -> Self {
+        self.unit_of_work = unit_of_work;
+        self
+    }
+    pub fn client_repo(&self) -> Rc<dyn ClientRepository> {
+        Rc::clone(&self.client_repo)
+    }
+    pub fn project_repo(&self) -> Rc<dyn ProjectRepository> {
+        Rc::clone(&self.project_repo)
+    }
+    pub fn relationship_repo(&self) -> Rc<dyn RelationshipRepository> {
+        Rc::clone(&self.relationship_repo)
+    }
+    pub fn outbox_repo(&self) -> Rc<dyn OutboxRepository> {
+        Rc::clone(&self.outbox_repo)
+    }
+    pub fn webhook_repo(&self) -> Rc<dyn WebhookRepository> {
+        Rc::clone(&self.webhook_repo)
+    }
+    pub fn user_repo(&self) -> Rc<dyn UserRepository> {
+        Rc::clone(&self.user_repo)
+    }
+    pub fn audit_repo(&self) -> Rc<dyn AuditRepository> {
+        Rc::clone(&self.audit_repo)
+    }
+    pub fn idempotency_repo(&self) -> Option<Rc<dyn IdempotencyRepository>> {
+        self.idempotency_repo.clone()
+    }
+    pub fn tombstone_repo(&self) -> Option<Rc<dyn TombstoneRepository>> {
+        self.tombstone_repo.clone()
+    }
+    /// 発行したイベントはcommitした後に設定した発行先へ渡し，読み取りモデルにも反映する
+    pub fn event_publisher(&self) -> Rc<dyn EventPublisher> {
+        Rc::clone(&self.event_publisher) as Rc<dyn EventPublisher>
+    }
+    pub fn key_rotation(&self) -> Option<Rc<dyn KeyRotation>> {
+        self.key_rotation.clone()
+    }
+    pub fn event_sinks(&self) -> Vec<Rc<dyn EventSink>> {
+        self.event_sinks.clone()
+    }
+    pub fn event_history(&self) -> Option<Rc<dyn EventHistory>> {
+        self.event_history.clone()
+    }
+    pub fn delivery_retries(&self) -> usize {
+        self.delivery_retries
+    }
+    pub fn max_delivery_attempts(&self) -> Option<u32> {
+        self.max_delivery_attempts
+    }
+    pub fn delivery_backoff(&self) -> Duration {
+        self.delivery_backoff
+    }
+    pub fn uniqueness_policy(&self) -> UniquenessPolicy {
+        self.uniqueness_policy
+    }
+    pub fn projections(&self) -> Rc<ClientProjections> {
+        Rc::clone(&self.projections)
+    }
+    pub fn history(&self) -> Rc<EditHistory> {
+        Rc::clone(&self.history)
+    }
+    /// ログイン中のユーザー
+    pub fn session(&self) -> Rc<Session> {
+        Rc::clone(&self.session)
+    }
+    pub fn tenant(&self) -> TenantId {
+        self.tenant.clone()
+    }
+    /// イベントの発行先は保存するリポジトリより先にcommitする
+    pub fn unit_of_work(&self) -> UnitOfWork {
+        self.unit_of_work
+            .clone()
+            .with_first_participant(Rc::clone(&self.event_publisher) as Rc<dyn Transactional>)
+    }
+}
+
+/// コンテナから依存を取り出して生成できるハンドラー．ハンドラー自体は依存を受け取るコンストラクタを持ち，
+/// テストではモックを直接渡して生成する
+pub trait FromContainer {
+    fn from_container(container: &Container) -> Self;
+}
+
+// クライアントを変更し，変更をイベントとして発行するハンドラー
+macro_rules! impl_from_container_with_client_repo {
+    ($($handler:ident),* $(,)?) => {
+        $(
+            impl FromContainer for $handler<dyn ClientRepository> {
+                fn from_container(container: &Container) -> Self {
+                    $handler::new(container.client_repo())
+                        .with_event_publisher(container.event_publisher())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_container_with_client_repo!(
+    AddContactUseCaseHandler,
+    RemoveContactUseCaseHandler,
+    SetPrimaryContactUseCaseHandler,
+    AddTagUseCaseHandler,
+    RemoveTagUseCaseHandler,
+    AddNoteUseCaseHandler,
+    EditNoteUseCaseHandler,
+    DeleteNoteUseCaseHandler,
+);
+
+// 読み取りモデルのみを参照するハンドラー
+macro_rules! impl_from_container_with_projections {
+    ($($handler:ident),* $(,)?) => {
+        $(
+            impl FromContainer for $handler {
+                fn from_container(container: &Container) -> Self {
+                    $handler::new(container.projections())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_container_with_projections!(
+    GetClientUseCaseHandler,
+    GetAllClientUseCaseHandler,
+    GroupClientsByRegionUseCaseHandler,
+    FindDuplicatesUseCaseHandler,
+    GetTagCountsUseCaseHandler,
+    GetNotesUseCaseHandler,
+    GetClientSummariesUseCaseHandler,
+    GetLocationCountsUseCaseHandler,
+);
+
+impl FromContainer for CreateClientUseCaseHandler<dyn ClientRepository> {
+    fn from_container(container: &Container) -> Self {
+        let mut handler = CreateClientUseCaseHandler::new(container.client_repo())
+            .with_uniqueness_policy(container.uniqueness_policy())
+            .with_history(container.history());
+        if let Some(idempotency_repo) = container.idempotency_repo() {
+            handler = handler.with_idempotency_repo(idempotency_repo);
+        }
+        handler.with_event_publisher(container.event_publisher())
+    }
+}
+
+impl FromContainer for EditClientUseCaseHandler<dyn ClientRepository> {
+    fn from_container(container: &Container) -> Self {
+        EditClientUseCaseHandler::new(container.client_repo())
+            .with_uniqueness_policy(container.uniqueness_policy())
+            .with_history(container.history())
+            .with_event_publisher(container.event_publisher())
+    }
+}
+
+impl FromContainer for ChangeClientStatusUseCaseHandler<dyn ClientRepository> {
+    fn from_container(container: &Container) -> Self {
+        ChangeClientStatusUseCaseHandler::new(container.client_repo())
+            .with_event_publisher(container.event_publisher())
+    }
+}
+
+impl FromContainer
+    for DeleteClientUseCaseHandler<
+        dyn ClientRepository,
+        dyn ProjectRepository,
+        dyn RelationshipRepository,
+    >
+{
+    fn from_container(container: &Container) -> Self {
+        DeleteClientUseCaseHandler::new(
+            container.client_repo(),
+            container.project_repo(),
+            container.relationship_repo(),
+        )
+        .with_history(container.history())
+        .with_event_publisher(container.event_publisher())
+    }
+}
+
+impl FromContainer
+    for MergeClientsUseCaseHandler<
+        dyn ClientRepository,
+        dyn ProjectRepository,
+        dyn RelationshipRepository,
+    >
+{
+    fn from_container(container: &Container) -> Self {
+        MergeClientsUseCaseHandler::new(
+            container.client_repo(),
+            container.project_repo(),
+            container.relationship_repo(),
+        )
+        .with_history(container.history())
+        .with_event_publisher(container.event_publisher())
+    }
+}
+
+impl FromContainer
+    for UndoUseCaseHandler<dyn ClientRepository, dyn ProjectRepository, dyn RelationshipRepository>
+{
+    fn from_container(container: &Container) -> Self {
+        UndoUseCaseHandler::new(
+            container.client_repo(),
+            container.project_repo(),
+            container.relationship_repo(),
+            container.history(),
+        )
+        .with_event_publisher(container.event_publisher())
+    }
+}
+
+impl FromContainer
+    for RedoUseCaseHandler<dyn ClientRepository, dyn ProjectRepository, dyn RelationshipRepository>
+{
+    fn from_container(container: &Container) -> Self {
+        RedoUseCaseHandler::new(
+            container.client_repo(),
+            container.project_repo(),
+            container.relationship_repo(),
+            container.history(),
+        )
+        .with_event_publisher(container.event_publisher())
+    }
+}
+
+impl FromContainer for GetEditHistoryUseCaseHandler {
+    fn from_container(container: &Container) -> Self {
+        GetEditHistoryUseCaseHandler::new(container.history())
+    }
+}
+
+impl FromContainer for CreateProjectUseCaseHandler<dyn ClientRepository, dyn ProjectRepository> {
+    fn from_container(container: &Container) -> Self {
+        CreateProjectUseCaseHandler::new(container.client_repo(), container.project_repo())
+    }
+}
+
+impl FromContainer for ListProjectsUseCaseHandler<dyn ProjectRepository> {
+    fn from_container(container: &Container) -> Self {
+        ListProjectsUseCaseHandler::new(container.projections(), container.project_repo())
+    }
+}
+
+impl FromContainer for CloseProjectUseCaseHandler<dyn ProjectRepository> {
+    fn from_container(container: &Container) -> Self {
+        CloseProjectUseCaseHandler::new(container.project_repo())
+    }
+}
+
+impl FromContainer for LinkClientsUseCaseHandler<dyn ClientRepository, dyn RelationshipRepository> {
+    fn from_container(container: &Container) -> Self {
+        LinkClientsUseCaseHandler::new(container.client_repo(), container.relationship_repo())
+    }
+}
+
+impl FromContainer
+    for UnlinkClientsUseCaseHandler<dyn ClientRepository, dyn RelationshipRepository>
+{
+    fn from_container(container: &Container) -> Self {
+        UnlinkClientsUseCaseHandler::new(container.client_repo(), container.relationship_repo())
+    }
+}
+
+impl FromContainer for GetHierarchyUseCaseHandler<dyn RelationshipRepository> {
+    fn from_container(container: &Container) -> Self {
+        GetHierarchyUseCaseHandler::new(container.projections(), container.relationship_repo())
+    }
+}
+
+impl FromContainer for RebuildProjectionsUseCaseHandler<dyn ClientRepository> {
+    fn from_container(container: &Container) -> Self {
+        let handler =
+            RebuildProjectionsUseCaseHandler::new(container.client_repo(), container.projections());
+        match container.tombstone_repo() {
+            Some(tombstone_repo) => handler.with_tombstone_repo(tombstone_repo),
+            None => handler,
+        }
+    }
+}
+
+impl FromContainer for GetEventHistoryUseCaseHandler {
+    fn from_container(container: &Container) -> Self {
+        let handler = GetEventHistoryUseCaseHandler::new();
+        match container.event_history() {
+            Some(event_history) => handler.with_event_history(event_history),
+            None => handler,
+        }
+    }
+}
+
+impl FromContainer for GetOutboxUseCaseHandler<dyn OutboxRepository> {
+    fn from_container(container: &Container) -> Self {
+        GetOutboxUseCaseHandler::new(container.outbox_repo())
+    }
+}
+
+impl FromContainer for RelayOutboxUseCaseHandler<dyn OutboxRepository> {
+    fn from_container(container: &Container) -> Self {
+        let handler = RelayOutboxUseCaseHandler::new(container.outbox_repo())
+            .with_sinks(container.event_sinks())
+            .with_retries(container.delivery_retries())
+            .with_backoff(container.delivery_backoff());
+        match container.max_delivery_attempts() {
+            Some(max_attempts) => handler.with_max_attempts(max_attempts),
+            None => handler,
+        }
+    }
+}
+
+impl FromContainer for ReplayOutboxMessageUseCaseHandler<dyn OutboxRepository> {
+    fn from_container(container: &Container) -> Self {
+        ReplayOutboxMessageUseCaseHandler::new(container.outbox_repo())
+            .with_sinks(container.event_sinks())
+            .with_retries(container.delivery_retries())
+    }
+}
+
+// Webhookのリポジトリのみに依存するハンドラー
+macro_rules! impl_from_container_with_webhook_repo {
+    ($($handler:ident),* $(,)?) => {
+        $(
+            impl FromContainer for $handler<dyn WebhookRepository> {
+                fn from_container(container: &Container) -> Self {
+                    $handler::new(container.webhook_repo())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_container_with_webhook_repo!(
+    CreateWebhookUseCaseHandler,
+    DeleteWebhookUseCaseHandler,
+    ListWebhooksUseCaseHandler,
+    GetWebhookDeliveriesUseCaseHandler,
+);
+
+// ユーザーのリポジトリのみに依存するハンドラー
+macro_rules! impl_from_container_with_user_repo {
+    ($($handler:ident),* $(,)?) => {
+        $(
+            impl FromContainer for $handler<dyn UserRepository> {
+                fn from_container(container: &Container) -> Self {
+                    $handler::new(container.user_repo())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_container_with_user_repo!(
+    IsSetupRequiredUseCaseHandler,
+    CreateUserUseCaseHandler,
+    ListUsersUseCaseHandler,
+);
+
+impl FromContainer for SetupAdminUseCaseHandler<dyn UserRepository> {
+    fn from_container(container: &Container) -> Self {
+        SetupAdminUseCaseHandler::new(container.user_repo(), container.session())
+    }
+}
+
+impl FromContainer for LoginUseCaseHandler<dyn UserRepository> {
+    fn from_container(container: &Container) -> Self {
+        LoginUseCaseHandler::new(container.user_repo(), container.session())
+    }
+}
+
+impl FromContainer for LogoutUseCaseHandler {
+    fn from_container(container: &Container) -> Self {
+        LogoutUseCaseHandler::new(container.session())
+    }
+}
+
+impl FromContainer for GetAuditLogUseCaseHandler<dyn AuditRepository> {
+    fn from_container(container: &Container) -> Self {
+        GetAuditLogUseCaseHandler::new(container.audit_repo())
+    }
+}
+
+impl FromContainer for ExportClientsUseCaseHandler {
+    fn from_container(container: &Container) -> Self {
+        ExportClientsUseCaseHandler::new(container.projections(), container.tenant())
+    }
+}
+
+impl FromContainer for AnonymizeClientsUseCaseHandler {
+    fn from_container(container: &Container) -> Self {
+        AnonymizeClientsUseCaseHandler::new(container.projections(), container.tenant())
+    }
+}
+
+impl FromContainer
+    for ExportClientPersonalDataUseCaseHandler<
+        dyn ProjectRepository,
+        dyn RelationshipRepository,
+        dyn OutboxRepository,
+        dyn WebhookRepository,
+    >
+{
+    fn from_container(container: &Container) -> Self {
+        let handler = ExportClientPersonalDataUseCaseHandler::new(
+            container.projections(),
+            container.project_repo(),
+            container.relationship_repo(),
+            container.outbox_repo(),
+            container.webhook_repo(),
+        )
+        .with_audit_repo(container.audit_repo());
+        match container.idempotency_repo() {
+            Some(idempotency_repo) => handler.with_idempotency_repo(idempotency_repo),
+            None => handler,
+        }
+    }
+}
+
+impl FromContainer
+    for EraseClientUseCaseHandler<
+        dyn ClientRepository,
+        dyn ProjectRepository,
+        dyn RelationshipRepository,
+        dyn OutboxRepository,
+    >
+{
+    fn from_container(container: &Container) -> Self {
+        let handler = EraseClientUseCaseHandler::new(
+            container.client_repo(),
+            container.project_repo(),
+            container.relationship_repo(),
+            container.outbox_repo(),
+        )
+        .with_event_publisher(container.event_publisher())
+        .with_sinks(container.event_sinks())
+        .with_history(container.history())
+        .with_audit_repo(container.audit_repo());
+        let handler = match container.idempotency_repo() {
+            Some(idempotency_repo) => handler.with_idempotency_repo(idempotency_repo),
+            None => handler,
+        };
+        match container.tombstone_repo() {
+            Some(tombstone_repo) => handler.with_tombstone_repo(tombstone_repo),
+            None => handler,
+        }
+    }
+}
+
+impl FromContainer for ReencryptClientsUseCaseHandler<dyn ClientRepository> {
+    fn from_container(container: &Container) -> Self {
+        let mut handler = ReencryptClientsUseCaseHandler::new(container.client_repo());
+        if let Some(key_rotation) = container.key_rotation() {
+            handler = handler.with_key_rotation(key_rotation);
+        }
+        handler
+    }
+}
+
+/// 全てのハンドラーを登録したメディエーター．読み取りモデルはコマンドのcommit時に更新する
+impl FromContainer for Mediator {
+    fn from_container(container: &Container) -> Self {
+        Mediator::new()
+            .with_unit_of_work(container.unit_of_work())
+            .with_handler(CreateClientUseCaseHandler::from_container(container))
+            .with_handler(GetClientUseCaseHandler::from_container(container))
+            .with_handler(GetAllClientUseCaseHandler::from_container(container))
+            .with_handler(EditClientUseCaseHandler::from_container(container))
+            .with_handler(GroupClientsByRegionUseCaseHandler::from_container(
+                container,
+            ))
+            .with_handler(FindDuplicatesUseCaseHandler::from_container(container))
+            .with_handler(MergeClientsUseCaseHandler::from_container(container))
+            .with_handler(AddContactUseCaseHandler::from_container(container))
+            .with_handler(RemoveContactUseCaseHandler::from_container(container))
+            .with_handler(SetPrimaryContactUseCaseHandler::from_container(container))
+            .with_handler(AddTagUseCaseHandler::from_container(container))
+            .with_handler(RemoveTagUseCaseHandler::from_container(container))
+            .with_handler(GetTagCountsUseCaseHandler::from_container(container))
+            .with_handler(AddNoteUseCaseHandler::from_container(container))
+            .with_handler(GetNotesUseCaseHandler::from_container(container))
+            .with_handler(EditNoteUseCaseHandler::from_container(container))
+            .with_handler(DeleteNoteUseCaseHandler::from_container(container))
+            .with_handler(ChangeClientStatusUseCaseHandler::from_container(container))
+            .with_handler(DeleteClientUseCaseHandler::from_container(container))
+            .with_handler(CreateProjectUseCaseHandler::from_container(container))
+            .with_handler(ListProjectsUseCaseHandler::from_container(container))
+            .with_handler(CloseProjectUseCaseHandler::from_container(container))
+            .with_handler(LinkClientsUseCaseHandler::from_container(container))
+            .with_handler(UnlinkClientsUseCaseHandler::from_container(container))
+            .with_handler(GetHierarchyUseCaseHandler::from_container(container))
+            .with_handler(GetClientSummariesUseCaseHandler::from_container(container))
+            .with_handler(GetLocationCountsUseCaseHandler::from_container(container))
+            .with_handler(RebuildProjectionsUseCaseHandler::from_container(container))
+            .with_handler(GetOutboxUseCaseHandler::from_container(container))
+            .with_handler(GetEventHistoryUseCaseHandler::from_container(container))
+            .with_handler(RelayOutboxUseCaseHandler::from_container(container))
+            .with_handler(ReplayOutboxMessageUseCaseHandler::from_container(container))
+            .with_handler(CreateWebhookUseCaseHandler::from_container(container))
+            .with_handler(DeleteWebhookUseCaseHandler::from_container(container))
+            .with_handler(ListWebhooksUseCaseHandler::from_container(container))
+            .with_handler(GetWebhookDeliveriesUseCaseHandler::from_container(
+                container,
+            ))
+            .with_handler(UndoUseCaseHandler::from_container(container))
+            .with_handler(RedoUseCaseHandler::from_container(container))
+            .with_handler(GetEditHistoryUseCaseHandler::from_container(container))
+            .with_handler(IsSetupRequiredUseCaseHandler::from_container(container))
+            .with_handler(SetupAdminUseCaseHandler::from_container(container))
+            .with_handler(LoginUseCaseHandler::from_container(container))
+            .with_handler(LogoutUseCaseHandler::from_container(container))
+            .with_handler(CreateUserUseCaseHandler::from_container(container))
+            .with_handler(ListUsersUseCaseHandler::from_container(container))
+            .with_handler(GetAuditLogUseCaseHandler::from_container(container))
+            .with_handler(ExportClientsUseCaseHandler::from_container(container))
+            .with_handler(AnonymizeClientsUseCaseHandler::from_container(container))
+            .with_handler(ReencryptClientsUseCaseHandler::from_container(container))
+            .with_handler(ExportClientPersonalDataUseCaseHandler::from_container(
+                container,
+            ))
+            .with_handler(EraseClientUseCaseHandler::from_container(container))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Container, FromContainer};
+    use crate::application::handlers_impl::{
+        ChangeClientStatusUseCaseHandler, CreateClientUseCaseHandler, GetClientUseCaseHandler,
+    };
+    use crate::application::requests::{
+        ChangeClientStatusUseCaseRequest, CreateClientUseCaseRequest, GetClientUseCaseRequest,
+    };
+    use crate::application::Handler;
+    use crate::domain::events::MockEventPublisher;
+    use crate::domain::repositories::{
+        MockAuditRepository, MockClientRepository, MockOutboxRepository, MockProjectRepository,
+        MockRelationshipRepository, MockUserRepository, MockWebhookRepository,
+    };
+    use crate::domain::{Client, UniquenessPolicy};
+    use assert_matches::assert_matches;
+    use fake::faker::{address::en::CityName, name::en::Name};
+    use fake::Fake;
+    use mockall::predicate;
+    use std::rc::Rc;
+
+    #[test]
+    fn resolve_handlers_with_mocks() {
+        let client = Client::new(Name().fake(), CityName().fake());
+        let id = client.id();
+
+        // 作業単位の外で発行したイベントはすぐに読み取りモデルへ反映するため，対象を読み直す
+        let mut mock_client_repo = MockClientRepository::new();
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(2)
+            .return_const(Ok(client.clone()));
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::ne(id))
+            .times(1)
+            .return_const(Err("No client found for given ID".to_string()));
+        mock_client_repo
+            .expect_by_name_and_location()
+            .times(1)
+            .return_const(Vec::new());
+        mock_client_repo.expect_save().times(2).return_const(Ok(()));
+        let mut mock_event_publisher = MockEventPublisher::new();
+        // 作成とステータスの変更で発行する
+        mock_event_publisher
+            .expect_publish()
+            .times(2)
+            .return_const(());
+
+        let container = Container::new(
+            Rc::new(mock_client_repo),
+            Rc::new(MockProjectRepository::new()),
+            Rc::new(MockRelationshipRepository::new()),
+            Rc::new(MockOutboxRepository::new()),
+            Rc::new(MockWebhookRepository::new()),
+            Rc::new(MockUserRepository::new()),
+            Rc::new(MockAuditRepository::new()),
+        )
+        .with_uniqueness_policy(UniquenessPolicy::Warn)
+        .with_event_publisher(Rc::new(mock_event_publisher));
+        container.projections().rebuild(&[client]);
+
+        // クエリのハンドラーにはコンテナの読み取りモデルが注入される
+        let get_client_use_case_handler = GetClientUseCaseHandler::from_container(&container);
+        let res = get_client_use_case_handler.execute(GetClientUseCaseRequest::new(id));
+        assert_matches!(res, Ok(client_dto) if client_dto.id() == id);
+
+        let create_client_use_case_handler = CreateClientUseCaseHandler::from_container(&container);
+        let res = create_client_use_case_handler.execute(CreateClientUseCaseRequest::new(
+            Name().fake(),
+            CityName().fake(),
+        ));
+        assert_matches!(res, Ok(created) if created.warnings().is_empty());
+
+        // コンテナのイベント発行先が注入される
+        let change_client_status_use_case_handler =
+            ChangeClientStatusUseCaseHandler::from_container(&container);
+        let res = change_client_status_use_case_handler.execute(
+            ChangeClientStatusUseCaseRequest::new(id, "active".to_string()),
+        );
+        assert_matches!(res, Ok(_));
+    }
+}
@@ -0,0 +1,461 @@
+use crate::domain::gazetteer::find_city;
+use crate::domain::{
+    normalize_text, Client, ClientStatus, ContactInfo, ContactKind, Location, Tag,
+};
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+// -------------------------------------------------------------------------------------------------
+
+/// 生成する名前と地名の言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    Ja,
+    En,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::Ja, Locale::En];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::Ja => "ja",
+            Locale::En => "en",
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = normalize_text(s);
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.as_str() == normalized)
+            .ok_or_else(|| format!("Unknown locale: {} (expected ja or en)", s.trim()))
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// 名前と地名は多い順に並べ，前にあるものほど選ばれやすくする
+
+const FAMILY_NAMES_JA: &[(&str, &str)] = &[
+    ("佐藤", "sato"),
+    ("鈴木", "suzuki"),
+    ("高橋", "takahashi"),
+    ("田中", "tanaka"),
+    ("伊藤", "ito"),
+    ("渡辺", "watanabe"),
+    ("山本", "yamamoto"),
+    ("中村", "nakamura"),
+    ("小林", "kobayashi"),
+    ("加藤", "kato"),
+    ("吉田", "yoshida"),
+    ("山田", "yamada"),
+    ("佐々木", "sasaki"),
+    ("山口", "yamaguchi"),
+    ("松本", "matsumoto"),
+    ("井上", "inoue"),
+];
+
+const GIVEN_NAMES_JA: &[(&str, &str)] = &[
+    ("翔太", "shota"),
+    ("陽菜", "hina"),
+    ("蓮", "ren"),
+    ("結衣", "yui"),
+    ("大翔", "hiroto"),
+    ("美咲", "misaki"),
+    ("悠真", "yuma"),
+    ("さくら", "sakura"),
+    ("健太", "kenta"),
+    ("葵", "aoi"),
+    ("拓海", "takumi"),
+    ("七海", "nanami"),
+];
+
+const FAMILY_NAMES_EN: &[&str] = &[
+    "Smith", "Johnson", "Williams", "Brown", "Jones", "Garcia", "Miller", "Davis", "Wilson",
+    "Taylor", "Anderson", "Thomas",
+];
+
+const GIVEN_NAMES_EN: &[&str] = &[
+    "James",
+    "Mary",
+    "John",
+    "Patricia",
+    "Robert",
+    "Jennifer",
+    "Michael",
+    "Linda",
+    "William",
+    "Elizabeth",
+    "David",
+    "Emma",
+    "Oliver",
+    "Sophia",
+];
+
+const LOCATIONS_JA: &[&str] = &[
+    "東京都渋谷区",
+    "東京都新宿区",
+    "横浜市",
+    "大阪府大阪市北区",
+    "名古屋市",
+    "東京都港区",
+    "札幌市",
+    "福岡市",
+    "さいたま市",
+    "神戸市",
+    "川崎市",
+    "京都市",
+    "千葉市",
+    "仙台市",
+    "広島市",
+    "北九州市",
+];
+
+const LOCATIONS_EN: &[&str] = &[
+    "New York",
+    "London",
+    "Los Angeles",
+    "Toronto",
+    "Sydney",
+    "San Francisco",
+    "Singapore",
+    "Paris",
+    "Berlin",
+];
+
+const STATUSES: &[(ClientStatus, f64)] = &[
+    (ClientStatus::Active, 0.55),
+    (ClientStatus::Prospect, 0.25),
+    (ClientStatus::Suspended, 0.12),
+    (ClientStatus::Archived, 0.08),
+];
+
+const TAGS: &[(&str, f64)] = &[("newsletter", 0.3), ("vip", 0.1), ("referral", 0.15)];
+
+/// 国も地域も分からない出身地の代わりに使う値
+const UNKNOWN_LOCATION: &str = "Unknown";
+
+// -------------------------------------------------------------------------------------------------
+
+/// 種から決まる擬似乱数(SplitMix64)．実装に依らず同じ種から常に同じ列を作る
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    /// 0以上1未満
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+    fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+    /// 順位の逆数に比例した確率で選ぶ(ジップ分布)
+    fn skewed<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        let total = (1..=items.len()).map(|rank| 1.0 / rank as f64).sum::<f64>();
+        let mut target = self.next_f64() * total;
+        for (index, item) in items.iter().enumerate() {
+            target -= 1.0 / (index + 1) as f64;
+            if target < 0.0 {
+                return item;
+            }
+        }
+        &items[items.len() - 1]
+    }
+    fn weighted<T: Copy>(&mut self, items: &[(T, f64)]) -> T {
+        let mut target = self.next_f64() * items.iter().map(|(_, weight)| weight).sum::<f64>();
+        for (item, weight) in items {
+            target -= weight;
+            if target < 0.0 {
+                return *item;
+            }
+        }
+        items[items.len() - 1].0
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// デモや負荷試験に使う架空のクライアントを作る．同じ種からはIDを除いて同じクライアントを作る
+pub struct DatasetGenerator {
+    rng: SeededRng,
+}
+
+impl DatasetGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: SeededRng(seed),
+        }
+    }
+    /// 出身地は大都市に偏り，状態・タグ・連絡先は実際の顧客台帳に近い割合で付ける
+    pub fn generate(&mut self, count: usize, locale: Locale) -> Vec<Client> {
+        (0..count)
+            .map(|_| {
+                let location = match locale {
+                    Locale::Ja => self.rng.skewed(LOCATIONS_JA),
+                    Locale::En => self.rng.skewed(LOCATIONS_EN),
+                };
+                let (name, email) = self.person(locale);
+                let mut client = Client::new(name, location.to_string());
+                set_status(&mut client, self.rng.weighted(STATUSES));
+                for (tag, probability) in TAGS {
+                    if self.rng.chance(*probability) {
+                        client.add_tag(Tag::new(tag).expect("tags are valid"));
+                    }
+                }
+                for (kind, probability) in [
+                    (ContactKind::Email, 0.8),
+                    (ContactKind::Phone, 0.4),
+                    (ContactKind::PostalAddress, 0.1),
+                ] {
+                    if self.rng.chance(probability) {
+                        let contact = self.contact(kind, locale, &email, location);
+                        client.add_contact(contact);
+                    }
+                }
+                client
+            })
+            .collect()
+    }
+    /// 共有できるように個人を特定できる情報を置き換えたクライアントを作る．
+    /// 名前と連絡先は同じ言語・種類の架空のものにし，出身地は都道府県・主要都市・国のいずれかまでに丸める．
+    /// 状態はそのまま残し，タグは件数の多い順に"tag-1"から番号を振った名前に置き換える．
+    /// メモと統合の記録は含めない
+    pub fn anonymize(&mut self, clients: &[Client]) -> Vec<Client> {
+        let placeholders = placeholders(clients);
+        clients
+            .iter()
+            .map(|original| {
+                let locale = match original.name().is_ascii() {
+                    true => Locale::En,
+                    false => Locale::Ja,
+                };
+                let location = generalize(original.location());
+                let (name, email) = self.person(locale);
+                let mut client = Client::new(name, location.clone());
+                set_status(&mut client, original.status());
+                for tag in original.tags() {
+                    client.add_tag(placeholders[tag].clone());
+                }
+                for contact in original.contacts() {
+                    let contact = self.contact(contact.kind(), locale, &email, &location);
+                    client.add_contact(contact);
+                }
+                client
+            })
+            .collect()
+    }
+    /// 名前とメールアドレスのローカル部
+    fn person(&mut self, locale: Locale) -> (String, String) {
+        let number = self.rng.below(100);
+        match locale {
+            Locale::Ja => {
+                let (family, family_romaji) = self.rng.skewed(FAMILY_NAMES_JA);
+                let (given, given_romaji) = self.rng.skewed(GIVEN_NAMES_JA);
+                (
+                    format!("{} {}", family, given),
+                    format!("{}.{}{}", given_romaji, family_romaji, number),
+                )
+            }
+            Locale::En => {
+                let family = self.rng.skewed(FAMILY_NAMES_EN);
+                let given = self.rng.skewed(GIVEN_NAMES_EN);
+                (
+                    format!("{} {}", given, family),
+                    format!("{}.{}{}", given, family, number).to_lowercase(),
+                )
+            }
+        }
+    }
+    /// 実在しない宛先の連絡先
+    fn contact(
+        &mut self,
+        kind: ContactKind,
+        locale: Locale,
+        email: &str,
+        location: &str,
+    ) -> ContactInfo {
+        let value = match (kind, locale) {
+            (ContactKind::Email, _) => format!("{}@example.com", email),
+            (ContactKind::Phone, Locale::Ja) => {
+                format!("090-0000-{:04}", self.rng.below(10_000))
+            }
+            (ContactKind::Phone, Locale::En) => format!("+1-555-01{:02}", self.rng.below(100)),
+            (ContactKind::PostalAddress, _) => format!("000-0000 {}", location),
+        };
+        ContactInfo::parse(kind, &value).expect("generated contacts are valid")
+    }
+}
+
+/// 許可された遷移をたどって状態を変える
+fn set_status(client: &mut Client, status: ClientStatus) {
+    let path: &[ClientStatus] = match status {
+        ClientStatus::Prospect => &[],
+        ClientStatus::Active => &[ClientStatus::Active],
+        ClientStatus::Suspended => &[ClientStatus::Active, ClientStatus::Suspended],
+        ClientStatus::Archived => &[ClientStatus::Archived],
+    };
+    for status in path {
+        client
+            .change_status(*status, Utc::now())
+            .expect("path follows allowed transitions");
+    }
+}
+
+/// 同じタグには同じ置き換え先を使い，タグごとの件数を保つ
+fn placeholders(clients: &[Client]) -> BTreeMap<Tag, Tag> {
+    let mut counts = BTreeMap::new();
+    for tag in clients.iter().flat_map(|client| client.tags()) {
+        *counts.entry(tag).or_insert(0usize) += 1;
+    }
+    let mut tags = counts.into_iter().collect::<Vec<_>>();
+    tags.sort_by(|(_, a), (_, b)| b.cmp(a));
+    tags.into_iter()
+        .enumerate()
+        .map(|(i, (tag, _))| {
+            let placeholder = Tag::new(&format!("tag-{}", i + 1)).expect("placeholders are valid");
+            (tag.clone(), placeholder)
+        })
+        .collect()
+}
+
+/// 国内は都道府県，国外は辞書にある主要都市か国コードまでに丸める
+fn generalize(location: &Location) -> String {
+    match (location.country(), location.region(), location.city()) {
+        (Some("JP"), Some(region), _) => region.to_string(),
+        (_, _, Some(city)) if find_city(&normalize_text(city)).is_some() => city.to_string(),
+        (Some(country), _, _) => country.to_string(),
+        _ => UNKNOWN_LOCATION.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DatasetGenerator, Locale};
+    use crate::domain::{Client, ClientStatus, ContactInfo, ContactKind, Tag};
+    use assert_matches::assert_matches;
+    use std::collections::BTreeMap;
+
+    // IDを除いた内容
+    fn contents(clients: &[Client]) -> Vec<(String, String, ClientStatus, Vec<String>)> {
+        clients
+            .iter()
+            .map(|client| {
+                (
+                    client.name().to_string(),
+                    client.location().to_string(),
+                    client.status(),
+                    client
+                        .contacts()
+                        .iter()
+                        .map(|contact| contact.info().to_string())
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_locale() {
+        assert_matches!("JA".parse::<Locale>(), Ok(Locale::Ja));
+        assert_matches!("en".parse::<Locale>(), Ok(Locale::En));
+        assert_matches!("fr".parse::<Locale>(), Err(_));
+    }
+
+    #[test]
+    fn generate_deterministically() {
+        let clients = DatasetGenerator::new(42).generate(200, Locale::Ja);
+        assert_eq!(clients.len(), 200);
+        assert_eq!(
+            contents(&clients),
+            contents(&DatasetGenerator::new(42).generate(200, Locale::Ja))
+        );
+        assert_ne!(
+            contents(&clients),
+            contents(&DatasetGenerator::new(43).generate(200, Locale::Ja))
+        );
+        assert!(clients
+            .iter()
+            .all(|client| client.location().country() == Some("JP")));
+
+        // 大都市に偏る
+        let mut counts = BTreeMap::new();
+        for client in clients.iter() {
+            *counts.entry(client.location().to_string()).or_insert(0) += 1;
+        }
+        assert!(counts["東京都渋谷区"] > counts["北九州市"] * 3);
+
+        let clients = DatasetGenerator::new(42).generate(50, Locale::En);
+        assert!(clients.iter().all(|client| client.name().is_ascii()
+            && client.location().is_recognized()
+            && client.location().country() != Some("JP")));
+    }
+
+    #[test]
+    fn anonymize_preserving_distributions() {
+        let mut taro = Client::new("山田 太郎".to_string(), "東京都渋谷区神南1-2-3".to_string());
+        taro.change_status(ClientStatus::Archived, chrono::Utc::now())
+            .unwrap();
+        taro.add_tag(Tag::new("vip").unwrap());
+        taro.add_contact(ContactInfo::parse(ContactKind::Email, "taro@example.org").unwrap());
+        taro.add_contact(
+            ContactInfo::parse(ContactKind::PostalAddress, "150-0041 東京都渋谷区神南1-2-3")
+                .unwrap(),
+        );
+        taro.add_note("met at the conference", chrono::Utc::now())
+            .unwrap();
+        let mut john = Client::new("John Doe".to_string(), "Brooklyn, New York".to_string());
+        john.add_tag(Tag::new("vip").unwrap());
+        john.add_tag(Tag::new("newsletter").unwrap());
+        let unknown = Client::new("Jane Roe".to_string(), "12 Elm Street".to_string());
+        let originals = [taro.clone(), john, unknown];
+
+        let anonymized = DatasetGenerator::new(7).anonymize(&originals);
+        let locations = anonymized
+            .iter()
+            .map(|client| client.location().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(locations, vec!["東京都", "New York", "Unknown"]);
+
+        let anonymized_taro = &anonymized[0];
+        assert_ne!(anonymized_taro.id(), taro.id());
+        assert!(!anonymized_taro.name().is_ascii());
+        assert!(anonymized[1].name().is_ascii());
+        assert_eq!(anonymized_taro.status(), ClientStatus::Archived);
+        // 元のタグ名は残さず，同じタグは同じ名前に置き換える
+        let tags = anonymized
+            .iter()
+            .map(|client| client.tags().iter().map(Tag::as_str).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(tags, vec![vec!["tag-1"], vec!["tag-1", "tag-2"], vec![]]);
+        assert!(anonymized_taro.notes().is_empty());
+        let contacts = anonymized_taro
+            .contacts()
+            .iter()
+            .map(|contact| (contact.kind(), contact.info().to_string()))
+            .collect::<Vec<_>>();
+        assert_matches!(contacts.as_slice(), [
+            (ContactKind::Email, email),
+            (ContactKind::PostalAddress, address),
+        ] if email.ends_with("@example.com") && address == "〒000-0000 東京都");
+    }
+}
@@ -1,4 +1,9 @@
-use crate::domain::Client;
+use crate::application::history::ClientChange;
+use crate::domain::{
+    AuditEntry, Client, ClientEvent, Contact, DuplicateGroup, Note, OutboxMessage, Project,
+    Relationship, Role, Tag, TenantId, User, WebhookDelivery, WebhookSubscription,
+};
+use chrono::{DateTime, Utc};
 use std::ops::Index;
 use std::slice::SliceIndex;
 use uuid::Uuid;
@@ -17,7 +22,28 @@ impl ClientDto {
         self.0.name()
     }
     pub fn location(&self) -> &str {
-        self.0.location()
+        self.0.location().as_str()
+    }
+    pub fn country(&self) -> Option<&str> {
+        self.0.location().country()
+    }
+    pub fn region(&self) -> Option<&str> {
+        self.0.location().region()
+    }
+    pub fn city(&self) -> Option<&str> {
+        self.0.location().city()
+    }
+    pub fn aliases(&self) -> &[Uuid] {
+        self.0.aliases()
+    }
+    pub fn contacts(&self) -> &[Contact] {
+        self.0.contacts()
+    }
+    pub fn tags(&self) -> Vec<&str> {
+        self.0.tags().iter().map(Tag::as_str).collect()
+    }
+    pub fn status(&self) -> &'static str {
+        self.0.status().as_str()
     }
 }
 
@@ -27,6 +53,721 @@ impl From<Client> for ClientDto {
     }
 }
 
+// -------------------------------------------------------------------------------------------------
+// ClientExportDto
+
+/// テナントのクライアントを書き出したもの
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClientExportDto {
+    tenant: TenantId,
+    clients: DtoList<ClientDto>,
+    exported_at: DateTime<Utc>,
+}
+
+impl ClientExportDto {
+    pub fn new(tenant: TenantId, clients: DtoList<ClientDto>, exported_at: DateTime<Utc>) -> Self {
+        Self {
+            tenant,
+            clients,
+            exported_at,
+        }
+    }
+    pub fn tenant(&self) -> &str {
+        self.tenant.as_str()
+    }
+    pub fn clients(&self) -> &DtoList<ClientDto> {
+        &self.clients
+    }
+    pub fn exported_at(&self) -> DateTime<Utc> {
+        self.exported_at
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// ClientPersonalDataDto
+
+/// 1つのクライアントについて保存している全てのデータ
+#[derive(Debug, PartialEq, Eq)]
+pub struct ClientPersonalDataDto {
+    client: ClientDto,
+    notes: DtoList<NoteDto>,
+    projects: DtoList<ProjectDto>,
+    relationships: DtoList<RelationshipDto>,
+    events: DtoList<OutboxMessageDto>,
+    deliveries: DtoList<WebhookDeliveryDto>,
+    idempotency_keys: Vec<String>,
+    audit_entries: DtoList<AuditEntryDto>,
+    exported_at: DateTime<Utc>,
+}
+
+impl ClientPersonalDataDto {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client: ClientDto,
+        notes: DtoList<NoteDto>,
+        projects: DtoList<ProjectDto>,
+        relationships: DtoList<RelationshipDto>,
+        events: DtoList<OutboxMessageDto>,
+        deliveries: DtoList<WebhookDeliveryDto>,
+        idempotency_keys: Vec<String>,
+        audit_entries: DtoList<AuditEntryDto>,
+        exported_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            client,
+            notes,
+            projects,
+            relationships,
+            events,
+            deliveries,
+            idempotency_keys,
+            audit_entries,
+            exported_at,
+        }
+    }
+    pub fn client(&self) -> &ClientDto {
+        &self.client
+    }
+    pub fn notes(&self) -> &DtoList<NoteDto> {
+        &self.notes
+    }
+    pub fn projects(&self) -> &DtoList<ProjectDto> {
+        &self.projects
+    }
+    pub fn relationships(&self) -> &DtoList<RelationshipDto> {
+        &self.relationships
+    }
+    /// アウトボックスに残っているイベント
+    pub fn events(&self) -> &DtoList<OutboxMessageDto> {
+        &self.events
+    }
+    /// イベントをWebhookへ送った記録
+    pub fn deliveries(&self) -> &DtoList<WebhookDeliveryDto> {
+        &self.deliveries
+    }
+    /// クライアントを作成したときの冪等キー
+    pub fn idempotency_keys(&self) -> &[String] {
+        &self.idempotency_keys
+    }
+    /// クライアントを対象にした変更の記録
+    pub fn audit_entries(&self) -> &DtoList<AuditEntryDto> {
+        &self.audit_entries
+    }
+    pub fn exported_at(&self) -> DateTime<Utc> {
+        self.exported_at
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// ErasureReportDto
+
+/// クライアントの個人データを消去した結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErasureReportDto {
+    client_id: Uuid,
+    closed_projects: usize,
+    relationships: usize,
+    redacted_events: usize,
+    redacted_audit_entries: usize,
+    forgotten_changes: usize,
+}
+
+impl ErasureReportDto {
+    pub fn new(
+        client_id: Uuid,
+        closed_projects: usize,
+        relationships: usize,
+        redacted_events: usize,
+        redacted_audit_entries: usize,
+        forgotten_changes: usize,
+    ) -> Self {
+        Self {
+            client_id,
+            closed_projects,
+            relationships,
+            redacted_events,
+            redacted_audit_entries,
+            forgotten_changes,
+        }
+    }
+    pub fn client_id(&self) -> Uuid {
+        self.client_id
+    }
+    /// 完了にした未完了のプロジェクトの数
+    pub fn closed_projects(&self) -> usize {
+        self.closed_projects
+    }
+    /// 削除した関係の数
+    pub fn relationships(&self) -> usize {
+        self.relationships
+    }
+    /// 名前と出身地を消去したイベントの数．アウトボックスと配信先の記録を合わせて数える
+    pub fn redacted_events(&self) -> usize {
+        self.redacted_events
+    }
+    /// 対象のIDを外した監査ログの記録の数
+    pub fn redacted_audit_entries(&self) -> usize {
+        self.redacted_audit_entries
+    }
+    /// 取り消しの履歴から除いた変更の数
+    pub fn forgotten_changes(&self) -> usize {
+        self.forgotten_changes
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// NoteDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteDto(Note);
+
+impl NoteDto {
+    pub fn id(&self) -> Uuid {
+        self.0.id()
+    }
+    pub fn body(&self) -> &str {
+        self.0.body()
+    }
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at()
+    }
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.0.updated_at()
+    }
+}
+
+impl From<Note> for NoteDto {
+    fn from(note: Note) -> NoteDto {
+        NoteDto(note)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// ProjectDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectDto(Project);
+
+impl ProjectDto {
+    pub fn id(&self) -> Uuid {
+        self.0.id()
+    }
+    pub fn client_id(&self) -> Uuid {
+        self.0.client_id()
+    }
+    pub fn name(&self) -> &str {
+        self.0.name()
+    }
+    pub fn status(&self) -> &'static str {
+        self.0.status().as_str()
+    }
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at()
+    }
+    pub fn closed_at(&self) -> Option<DateTime<Utc>> {
+        self.0.closed_at()
+    }
+}
+
+impl From<Project> for ProjectDto {
+    fn from(project: Project) -> ProjectDto {
+        ProjectDto(project)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// RelationshipDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationshipDto(Relationship);
+
+impl RelationshipDto {
+    pub fn source_id(&self) -> Uuid {
+        self.0.from()
+    }
+    pub fn target_id(&self) -> Uuid {
+        self.0.to()
+    }
+    pub fn kind(&self) -> &'static str {
+        self.0.kind().as_str()
+    }
+}
+
+impl From<Relationship> for RelationshipDto {
+    fn from(relationship: Relationship) -> RelationshipDto {
+        RelationshipDto(relationship)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// HierarchyDto
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct HierarchyDto {
+    client: ClientDto,
+    /// 表示の起点となったクライアントか
+    focused: bool,
+    children: Vec<HierarchyDto>,
+}
+
+impl HierarchyDto {
+    pub fn new(client: ClientDto, focused: bool, children: Vec<HierarchyDto>) -> Self {
+        Self {
+            client,
+            focused,
+            children,
+        }
+    }
+    pub fn client(&self) -> &ClientDto {
+        &self.client
+    }
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+    pub fn children(&self) -> &[HierarchyDto] {
+        &self.children
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// ClientEventDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientEventDto(ClientEvent);
+
+impl ClientEventDto {
+    pub fn event(&self) -> &ClientEvent {
+        &self.0
+    }
+    pub fn client_id(&self) -> Uuid {
+        self.0.client_id()
+    }
+}
+
+impl From<ClientEvent> for ClientEventDto {
+    fn from(event: ClientEvent) -> ClientEventDto {
+        ClientEventDto(event)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// OutboxMessageDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxMessageDto(OutboxMessage);
+
+impl OutboxMessageDto {
+    pub fn id(&self) -> Uuid {
+        self.0.id()
+    }
+    pub fn event(&self) -> ClientEventDto {
+        self.0.event().clone().into()
+    }
+    pub fn attempts(&self) -> u32 {
+        self.0.attempts()
+    }
+    pub fn last_error(&self) -> Option<&str> {
+        self.0.last_error()
+    }
+    pub fn delivered_to(&self) -> &[String] {
+        self.0.delivered_to()
+    }
+    pub fn delivered_at(&self) -> Option<DateTime<Utc>> {
+        self.0.delivered_at()
+    }
+    pub fn dead_lettered_at(&self) -> Option<DateTime<Utc>> {
+        self.0.dead_lettered_at()
+    }
+}
+
+impl From<OutboxMessage> for OutboxMessageDto {
+    fn from(message: OutboxMessage) -> OutboxMessageDto {
+        OutboxMessageDto(message)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// RelayReportDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayReportDto {
+    delivered: usize,
+    failed: usize,
+    dead_lettered: usize,
+}
+
+impl RelayReportDto {
+    pub fn new(delivered: usize, failed: usize, dead_lettered: usize) -> Self {
+        Self {
+            delivered,
+            failed,
+            dead_lettered,
+        }
+    }
+    pub fn delivered(&self) -> usize {
+        self.delivered
+    }
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+    /// 配信を諦めたメッセージの数
+    pub fn dead_lettered(&self) -> usize {
+        self.dead_lettered
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// CreatedClientDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatedClientDto {
+    client: ClientDto,
+    warnings: Vec<String>,
+    replayed: bool,
+}
+
+impl CreatedClientDto {
+    pub fn new(client: ClientDto, warnings: Vec<String>, replayed: bool) -> Self {
+        Self {
+            client,
+            warnings,
+            replayed,
+        }
+    }
+    pub fn client(&self) -> &ClientDto {
+        &self.client
+    }
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+    /// 冪等キーが処理済みで，新たに作成しなかったか
+    pub fn replayed(&self) -> bool {
+        self.replayed
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// ClientChangeDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientChangeDto(ClientChange);
+
+impl ClientChangeDto {
+    pub fn change(&self) -> &ClientChange {
+        &self.0
+    }
+    pub fn client_id(&self) -> Uuid {
+        self.0.client_id()
+    }
+}
+
+impl From<ClientChange> for ClientChangeDto {
+    fn from(change: ClientChange) -> ClientChangeDto {
+        ClientChangeDto(change)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// EditHistoryDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditHistoryDto {
+    undo: Option<ClientChangeDto>,
+    redo: Option<ClientChangeDto>,
+    undo_count: usize,
+    redo_count: usize,
+}
+
+impl EditHistoryDto {
+    pub fn new(
+        undo: Option<ClientChangeDto>,
+        redo: Option<ClientChangeDto>,
+        undo_count: usize,
+        redo_count: usize,
+    ) -> Self {
+        Self {
+            undo,
+            redo,
+            undo_count,
+            redo_count,
+        }
+    }
+    /// 取り消したときに適用する変更
+    pub fn undo(&self) -> Option<&ClientChangeDto> {
+        self.undo.as_ref()
+    }
+    /// 再実行したときに適用する変更
+    pub fn redo(&self) -> Option<&ClientChangeDto> {
+        self.redo.as_ref()
+    }
+    pub fn undo_count(&self) -> usize {
+        self.undo_count
+    }
+    pub fn redo_count(&self) -> usize {
+        self.redo_count
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// WebhookSubscriptionDto
+
+/// 秘密鍵は公開しない
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookSubscriptionDto(WebhookSubscription);
+
+impl WebhookSubscriptionDto {
+    pub fn id(&self) -> Uuid {
+        self.0.id()
+    }
+    pub fn url(&self) -> &str {
+        self.0.url()
+    }
+    pub fn event_types(&self) -> Vec<&str> {
+        self.0.event_types().iter().map(String::as_str).collect()
+    }
+}
+
+impl From<WebhookSubscription> for WebhookSubscriptionDto {
+    fn from(subscription: WebhookSubscription) -> WebhookSubscriptionDto {
+        WebhookSubscriptionDto(subscription)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// WebhookDeliveryDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookDeliveryDto(WebhookDelivery);
+
+impl WebhookDeliveryDto {
+    pub fn subscription_id(&self) -> Uuid {
+        self.0.subscription_id()
+    }
+    pub fn message_id(&self) -> Uuid {
+        self.0.message_id()
+    }
+    pub fn event_type(&self) -> &str {
+        self.0.event_type()
+    }
+    pub fn attempt(&self) -> u32 {
+        self.0.attempt()
+    }
+    pub fn result(&self) -> &Result<u16, String> {
+        self.0.result()
+    }
+    pub fn attempted_at(&self) -> DateTime<Utc> {
+        self.0.attempted_at()
+    }
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryDto {
+    fn from(delivery: WebhookDelivery) -> WebhookDeliveryDto {
+        WebhookDeliveryDto(delivery)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// UserDto
+
+/// パスワードのハッシュは含めない
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDto {
+    id: Uuid,
+    username: String,
+    role: Role,
+}
+
+impl UserDto {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+    pub fn role(&self) -> Role {
+        self.role
+    }
+}
+
+impl From<User> for UserDto {
+    fn from(user: User) -> UserDto {
+        UserDto {
+            id: user.id(),
+            username: user.username().to_string(),
+            role: user.role(),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// AuditEntryDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntryDto(AuditEntry);
+
+impl AuditEntryDto {
+    pub fn user_id(&self) -> Uuid {
+        self.0.user_id()
+    }
+    pub fn username(&self) -> &str {
+        self.0.username()
+    }
+    pub fn action(&self) -> &str {
+        self.0.action()
+    }
+    pub fn subject_id(&self) -> Option<Uuid> {
+        self.0.subject_id()
+    }
+    pub fn occurred_at(&self) -> DateTime<Utc> {
+        self.0.occurred_at()
+    }
+}
+
+impl From<AuditEntry> for AuditEntryDto {
+    fn from(entry: AuditEntry) -> AuditEntryDto {
+        AuditEntryDto(entry)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// DuplicateGroupDto
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroupDto(DuplicateGroup);
+
+impl DuplicateGroupDto {
+    pub fn clients(&self) -> DtoList<ClientDto> {
+        self.0.clients.iter().cloned().map(Into::into).collect()
+    }
+    pub fn score(&self) -> f64 {
+        self.0.score
+    }
+}
+
+impl From<DuplicateGroup> for DuplicateGroupDto {
+    fn from(group: DuplicateGroup) -> DuplicateGroupDto {
+        DuplicateGroupDto(group)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// RegionGroupDto
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RegionGroupDto {
+    country: Option<String>,
+    region: Option<String>,
+    clients: DtoList<ClientDto>,
+}
+
+impl RegionGroupDto {
+    pub fn new(
+        country: Option<String>,
+        region: Option<String>,
+        clients: DtoList<ClientDto>,
+    ) -> Self {
+        Self {
+            country,
+            region,
+            clients,
+        }
+    }
+    pub fn country(&self) -> Option<&str> {
+        self.country.as_deref()
+    }
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+    pub fn clients(&self) -> &DtoList<ClientDto> {
+        &self.clients
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// TagCountDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagCountDto {
+    tag: String,
+    count: usize,
+}
+
+impl TagCountDto {
+    pub fn new(tag: String, count: usize) -> Self {
+        Self { tag, count }
+    }
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// ClientSummaryDto
+
+/// 一覧表示用の読み取りモデル
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientSummaryDto {
+    id: Uuid,
+    name: String,
+    location: String,
+    status: &'static str,
+    tag_count: usize,
+}
+
+impl ClientSummaryDto {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+    pub fn status(&self) -> &'static str {
+        self.status
+    }
+    pub fn tag_count(&self) -> usize {
+        self.tag_count
+    }
+}
+
+impl From<&Client> for ClientSummaryDto {
+    fn from(client: &Client) -> ClientSummaryDto {
+        ClientSummaryDto {
+            id: client.id(),
+            name: client.name().to_string(),
+            location: client.location().as_str().to_string(),
+            status: client.status().as_str(),
+            tag_count: client.tags().len(),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// LocationCountDto
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationCountDto {
+    location: String,
+    count: usize,
+}
+
+impl LocationCountDto {
+    pub fn new(location: String, count: usize) -> Self {
+        Self { location, count }
+    }
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 // DtoList
 
@@ -123,7 +864,10 @@ mod test {
         let client_dto: ClientDto = client.clone().into();
         assert_eq!(client_dto.id(), client.id());
         assert_eq!(client_dto.name(), client.name());
-        assert_eq!(client_dto.location(), client.location());
+        assert_eq!(client_dto.location(), client.location().as_str());
+        assert_eq!(client_dto.region(), client.location().region());
+        assert_eq!(client_dto.contacts(), client.contacts());
+        assert_eq!(client_dto.tags().len(), client.tags().len());
     }
 
     #[test]
@@ -152,7 +896,7 @@ mod test {
         }
 
         // into_iter
-        for (client, client_dto) in zip(clients_vec.into_iter(), clients_dto_list.into_iter()) {
+        for (client, client_dto) in zip(clients_vec, clients_dto_list) {
             let client_dto2: ClientDto = client.clone().into();
             assert_eq!(client_dto, client_dto2);
         }
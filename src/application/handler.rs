@@ -1,9 +1,6 @@
-use crate::domain::repositories::ClientRepository;
-use std::rc::Rc;
-
-pub trait Handler<T: ClientRepository> {
+/// ユースケースの実行．依存の受け取り方は各ハンドラーのコンストラクタに任せる
+pub trait Handler {
     type Request;
     type Output;
-    fn new(client_repository: Rc<T>) -> Self;
     fn execute(&self, request: Self::Request) -> Self::Output;
 }
@@ -1,64 +1,202 @@
-use crate::application::dtos::{ClientDto, DtoList};
+use crate::application::auth::Session;
+use crate::application::datasets::DatasetGenerator;
+use crate::application::dtos::{
+    AuditEntryDto, ClientChangeDto, ClientDto, ClientEventDto, ClientExportDto,
+    ClientPersonalDataDto, ClientSummaryDto, CreatedClientDto, DtoList, DuplicateGroupDto,
+    EditHistoryDto, ErasureReportDto, HierarchyDto, LocationCountDto, NoteDto, OutboxMessageDto,
+    ProjectDto, RegionGroupDto, RelayReportDto, TagCountDto, UserDto, WebhookDeliveryDto,
+    WebhookSubscriptionDto,
+};
+use crate::application::history::{ClientChange, EditHistory};
+use crate::application::projections::ClientProjections;
 use crate::application::requests::{
-    CreateClientUseCaseRequest, EditClientUseCaseRequest, GetClientUseCaseRequest, NoneRequest,
+    AddContactUseCaseRequest, AddNoteUseCaseRequest, AddTagUseCaseRequest,
+    AnonymizeClientsUseCaseRequest, ChangeClientStatusUseCaseRequest, CloseProjectUseCaseRequest,
+    CreateClientUseCaseRequest, CreateProjectUseCaseRequest, CreateUserUseCaseRequest,
+    CreateWebhookUseCaseRequest, DeleteClientUseCaseRequest, DeleteNoteUseCaseRequest,
+    DeleteWebhookUseCaseRequest, EditClientUseCaseRequest, EditNoteUseCaseRequest,
+    EraseClientUseCaseRequest, ExportClientPersonalDataUseCaseRequest, ExportClientsUseCaseRequest,
+    FindDuplicatesUseCaseRequest, GetAllClientUseCaseRequest, GetAuditLogUseCaseRequest,
+    GetClientSummariesUseCaseRequest, GetClientUseCaseRequest, GetEditHistoryUseCaseRequest,
+    GetEventHistoryUseCaseRequest, GetHierarchyUseCaseRequest, GetLocationCountsUseCaseRequest,
+    GetNotesUseCaseRequest, GetOutboxUseCaseRequest, GetTagCountsUseCaseRequest,
+    GetWebhookDeliveriesUseCaseRequest, GroupClientsByRegionUseCaseRequest,
+    IsSetupRequiredUseCaseRequest, LinkClientsUseCaseRequest, ListProjectsUseCaseRequest,
+    ListUsersUseCaseRequest, ListWebhooksUseCaseRequest, LoginUseCaseRequest, LogoutUseCaseRequest,
+    MergeClientsUseCaseRequest, RebuildProjectionsUseCaseRequest, RedoUseCaseRequest,
+    ReencryptClientsUseCaseRequest, RelayOutboxUseCaseRequest, RemoveContactUseCaseRequest,
+    RemoveTagUseCaseRequest, ReplayOutboxMessageUseCaseRequest, SetPrimaryContactUseCaseRequest,
+    SetupAdminUseCaseRequest, UndoUseCaseRequest, UnlinkClientsUseCaseRequest,
 };
 use crate::application::Handler;
-use crate::domain::{Client, ClientRepository};
+use crate::domain::{
+    build_hierarchy, projects_of_client, validate_client_deletion, validate_merge,
+    validate_relationship, AuditRepository, Client, ClientEvent, ClientRepository, ClientStatus,
+    ContactInfo, DomainError, DuplicateDetectionService, EventHistory, EventPublisher, EventSink,
+    HierarchyNode, IdempotencyRecord, IdempotencyRepository, KeyRotation, Location, OutboxMessage,
+    OutboxRepository, Project, ProjectRepository, RelationKind, Relationship,
+    RelationshipRepository, Role, Tag, TenantId, Tombstone, TombstoneRepository, UniquenessPolicy,
+    User, UserRepository, WebhookRepository, WebhookSubscription,
+};
+use chrono::{Duration, Utc};
+use std::collections::BTreeMap;
 use std::rc::Rc;
+use uuid::Uuid;
 
 // -------------------------------------------------------------------------------------------------
 
-pub struct CreateClientUseCaseHandler<T: ClientRepository> {
+pub struct CreateClientUseCaseHandler<T: ClientRepository + ?Sized> {
     client_repo: Rc<T>,
+    uniqueness_policy: UniquenessPolicy,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+    history: Option<Rc<EditHistory>>,
+    idempotency_repo: Option<Rc<dyn IdempotencyRepository>>,
 }
 
-impl<T: ClientRepository> Handler<T> for CreateClientUseCaseHandler<T> {
-    type Request = CreateClientUseCaseRequest;
-    type Output = ();
-    fn new(client_repo: Rc<T>) -> Self {
-        Self { client_repo }
+impl<T: ClientRepository + ?Sized> CreateClientUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            uniqueness_policy: UniquenessPolicy::default(),
+            event_publisher: None,
+            history: None,
+            idempotency_repo: None,
+        }
+    }
+    pub fn with_idempotency_repo(
+        mut self,
+        idempotency_repo: Rc<dyn IdempotencyRepository>,
+    ) -> Self {
+        self.idempotency_repo = Some(idempotency_repo);
+        self
     }
+    pub fn with_history(mut self, history: Rc<EditHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+    pub fn with_uniqueness_policy(mut self, uniqueness_policy: UniquenessPolicy) -> Self {
+        self.uniqueness_policy = uniqueness_policy;
+        self
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
+
+impl<T: ClientRepository + ?Sized> Handler for CreateClientUseCaseHandler<T> {
+    type Request = CreateClientUseCaseRequest;
+    /// 成功時は作成したクライアントと警告のリストを返す．
+    /// 処理済みの冪等キーには，その後の変更や削除によらず最初に作成したときの結果を返す
+    type Output = Result<CreatedClientDto, String>;
     fn execute(&self, request: Self::Request) -> Self::Output {
-        let client = Client::new(request.name, request.location);
-        self.client_repo.save(client);
+        let idempotency = match (&self.idempotency_repo, request.idempotency_key) {
+            (Some(idempotency_repo), Some(key)) => Some((idempotency_repo, key)),
+            _ => None,
+        };
+        if let Some((idempotency_repo, key)) = &idempotency {
+            if let Some(record) = idempotency_repo.by_key(key) {
+                if !record.matches(&request.name, &request.location) {
+                    return Err(format!(
+                        "Idempotency key {} was already used with a different name or location",
+                        key
+                    ));
+                }
+                return Ok(CreatedClientDto::new(
+                    record.client().clone().into(),
+                    record.warnings().to_vec(),
+                    true,
+                ));
+            }
+        }
+        let client = Client::new(request.name.clone(), request.location.clone());
+        let warnings = self
+            .uniqueness_policy
+            .enforce(&client, self.client_repo.as_ref())?;
+        self.client_repo.save(client.clone())?;
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(ClientEvent::created(&client, Utc::now()));
+        }
+        if let Some(history) = &self.history {
+            history.record(ClientChange::created(&client));
+        }
+        if let Some((idempotency_repo, key)) = idempotency {
+            idempotency_repo.save(IdempotencyRecord::new(
+                key,
+                &request.name,
+                &request.location,
+                client.clone(),
+                warnings.clone(),
+            ));
+        }
+        Ok(CreatedClientDto::new(client.into(), warnings, false))
     }
 }
 
 // -------------------------------------------------------------------------------------------------
 
-pub struct GetClientUseCaseHandler<T: ClientRepository> {
-    client_repo: Rc<T>,
+/// 読み取りモデルのみを参照する
+pub struct GetClientUseCaseHandler {
+    projections: Rc<ClientProjections>,
+}
+
+impl GetClientUseCaseHandler {
+    pub fn new(projections: Rc<ClientProjections>) -> Self {
+        Self { projections }
+    }
 }
 
-impl<T: ClientRepository> Handler<T> for GetClientUseCaseHandler<T> {
+impl Handler for GetClientUseCaseHandler {
     type Request = GetClientUseCaseRequest;
     type Output = Result<ClientDto, String>;
-    fn new(client_repo: Rc<T>) -> Self {
-        Self { client_repo }
-    }
     fn execute(&self, request: Self::Request) -> Self::Output {
-        self.client_repo
-            .by_id(request.id)
+        self.projections
+            .client(request.id)
             .map(|client| client.into())
     }
 }
 
 // -------------------------------------------------------------------------------------------------
 
-pub struct GetAllClientUseCaseHandler<T: ClientRepository> {
-    client_repo: Rc<T>,
+/// 読み取りモデルのみを参照する
+pub struct GetAllClientUseCaseHandler {
+    projections: Rc<ClientProjections>,
 }
 
-impl<T: ClientRepository> Handler<T> for GetAllClientUseCaseHandler<T> {
-    type Request = NoneRequest;
-    type Output = DtoList<ClientDto>;
-    fn new(client_repo: Rc<T>) -> Self {
-        Self { client_repo }
+impl GetAllClientUseCaseHandler {
+    pub fn new(projections: Rc<ClientProjections>) -> Self {
+        Self { projections }
     }
-    fn execute(&self, _: Self::Request) -> Self::Output {
-        self.client_repo
-            .all()
+}
+
+impl Handler for GetAllClientUseCaseHandler {
+    type Request = GetAllClientUseCaseRequest;
+    type Output = DtoList<ClientDto>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let area = request.region.as_deref().map(Location::new);
+        // 不正なタグ名で絞り込んだ場合は何も一致しない
+        let tag = request.tag.as_deref().map(|tag| Tag::new(tag).ok());
+        let status = request
+            .status
+            .as_deref()
+            .map(|status| status.parse::<ClientStatus>().ok());
+        self.projections
+            .clients()
             .into_iter()
+            .filter(|client| match &area {
+                Some(area) => client.location().is_in(area),
+                None => true,
+            })
+            .filter(|client| match &tag {
+                Some(Some(tag)) => client.has_tag(tag),
+                Some(None) => false,
+                None => true,
+            })
+            .filter(|client| match &status {
+                Some(Some(status)) => client.status() == *status,
+                Some(None) => false,
+                None => true,
+            })
             .map(Into::into)
             .collect::<DtoList<ClientDto>>()
     }
@@ -66,176 +204,4199 @@ impl<T: ClientRepository> Handler<T> for GetAllClientUseCaseHandler<T> {
 
 // -------------------------------------------------------------------------------------------------
 
-pub struct EditClientUseCaseHandler<T: ClientRepository> {
+/// 読み取りモデルのみを参照する
+pub struct GroupClientsByRegionUseCaseHandler {
+    projections: Rc<ClientProjections>,
+}
+
+impl GroupClientsByRegionUseCaseHandler {
+    pub fn new(projections: Rc<ClientProjections>) -> Self {
+        Self { projections }
+    }
+}
+
+impl Handler for GroupClientsByRegionUseCaseHandler {
+    type Request = GroupClientsByRegionUseCaseRequest;
+    type Output = DtoList<RegionGroupDto>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        // 国・地域の順で並べ，地名辞書で解釈できなかったものは最後にまとめる
+        let mut groups: BTreeMap<(bool, Option<String>, Option<String>), Vec<Client>> =
+            BTreeMap::new();
+        for client in self.projections.clients() {
+            let location = client.location();
+            let key = (
+                !location.is_recognized(),
+                location.country().map(str::to_string),
+                location.region().map(str::to_string),
+            );
+            groups.entry(key).or_default().push(client);
+        }
+
+        groups
+            .into_iter()
+            .map(|((_, country, region), mut clients)| {
+                clients.sort_by(|a, b| a.name().cmp(b.name()));
+                RegionGroupDto::new(
+                    country,
+                    region,
+                    clients.into_iter().map(Into::into).collect(),
+                )
+            })
+            .collect::<DtoList<RegionGroupDto>>()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct EditClientUseCaseHandler<T: ClientRepository + ?Sized> {
     client_repo: Rc<T>,
+    uniqueness_policy: UniquenessPolicy,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+    history: Option<Rc<EditHistory>>,
 }
 
-impl<T: ClientRepository> Handler<T> for EditClientUseCaseHandler<T> {
-    type Request = EditClientUseCaseRequest;
-    type Output = Result<(), String>;
-    fn new(client_repo: Rc<T>) -> Self {
-        Self { client_repo }
+impl<T: ClientRepository + ?Sized> EditClientUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            uniqueness_policy: UniquenessPolicy::default(),
+            event_publisher: None,
+            history: None,
+        }
+    }
+    pub fn with_history(mut self, history: Rc<EditHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+    pub fn with_uniqueness_policy(mut self, uniqueness_policy: UniquenessPolicy) -> Self {
+        self.uniqueness_policy = uniqueness_policy;
+        self
     }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
+
+impl<T: ClientRepository + ?Sized> Handler for EditClientUseCaseHandler<T> {
+    type Request = EditClientUseCaseRequest;
+    /// 成功時は警告のリストを返す
+    type Output = Result<Vec<String>, String>;
     fn execute(&self, request: Self::Request) -> Self::Output {
         let mut client = self.client_repo.by_id(request.id)?;
+        let before = client.clone();
         client.edit(request.name, request.location);
-        self.client_repo.save(client);
-        Ok(())
+        let warnings = self
+            .uniqueness_policy
+            .enforce(&client, self.client_repo.as_ref())?;
+        self.client_repo.save(client.clone())?;
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(ClientEvent::edited(&client, Utc::now()));
+        }
+        if let Some(history) = &self.history {
+            history.record(ClientChange::edited(&before, &client));
+        }
+        Ok(warnings)
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::{
-        CreateClientUseCaseHandler, EditClientUseCaseHandler, GetAllClientUseCaseHandler,
-        GetClientUseCaseHandler, Handler,
-    };
-    use crate::application::dtos::{ClientDto, DtoList};
-    use crate::application::requests::{
-        CreateClientUseCaseRequest, EditClientUseCaseRequest, GetClientUseCaseRequest, NoneRequest,
-    };
-    use assert_matches::assert_matches;
-    use fake::faker::{address::en::CityName, name::en::Name};
-    use fake::{Fake, Faker};
-    use mockall::predicate;
-    use std::rc::Rc;
+// -------------------------------------------------------------------------------------------------
 
-    use crate::domain::repositories::MockClientRepository;
-    use crate::domain::Client;
+/// 読み取りモデルのみを参照する
+pub struct FindDuplicatesUseCaseHandler {
+    projections: Rc<ClientProjections>,
+}
 
-    #[test]
-    fn create_client_use_case_handler_execute() {
-        let mut mock_repo = MockClientRepository::new();
-        let name = Name().fake::<String>();
-        let location = CityName().fake::<String>();
+impl FindDuplicatesUseCaseHandler {
+    pub fn new(projections: Rc<ClientProjections>) -> Self {
+        Self { projections }
+    }
+}
 
-        let cloned_name = name.clone();
-        let cloned_location = location.clone();
+impl Handler for FindDuplicatesUseCaseHandler {
+    type Request = FindDuplicatesUseCaseRequest;
+    type Output = DtoList<DuplicateGroupDto>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        DuplicateDetectionService::new(request.threshold)
+            .find_groups(&self.projections.clients())
+            .into_iter()
+            .map(Into::into)
+            .collect::<DtoList<DuplicateGroupDto>>()
+    }
+}
 
-        mock_repo
-            .expect_save()
-            .withf(move |client| {
-                client.name() == cloned_name && client.location() == cloned_location
-            })
-            .times(1)
-            .return_const(());
+// -------------------------------------------------------------------------------------------------
+
+pub struct MergeClientsUseCaseHandler<
+    T: ClientRepository + ?Sized,
+    P: ProjectRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+> {
+    client_repo: Rc<T>,
+    project_repo: Rc<P>,
+    relationship_repo: Rc<R>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+    history: Option<Rc<EditHistory>>,
+}
 
-        let create_client_use_case_handler = CreateClientUseCaseHandler::new(Rc::new(mock_repo));
-        create_client_use_case_handler.execute(CreateClientUseCaseRequest::new(name, location))
+impl<
+        T: ClientRepository + ?Sized,
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+    > MergeClientsUseCaseHandler<T, P, R>
+{
+    pub fn new(client_repo: Rc<T>, project_repo: Rc<P>, relationship_repo: Rc<R>) -> Self {
+        Self {
+            client_repo,
+            project_repo,
+            relationship_repo,
+            event_publisher: None,
+            history: None,
+        }
+    }
+    pub fn with_history(mut self, history: Rc<EditHistory>) -> Self {
+        self.history = Some(history);
+        self
     }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
 
-    #[test]
-    fn get_client_use_case_handler_execute_ok() {
-        let client = Faker.fake::<Client>();
-        let client_dto: ClientDto = client.clone().into();
-        let id = client.id();
+impl<
+        T: ClientRepository + ?Sized,
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+    > Handler for MergeClientsUseCaseHandler<T, P, R>
+{
+    type Request = MergeClientsUseCaseRequest;
+    type Output = Result<ClientDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        validate_merge(request.keep_id, &request.merge_ids)?;
 
-        let mut mock_repo = MockClientRepository::new();
+        let before = self.client_repo.by_id(request.keep_id)?;
+        let mut merged_clients: Vec<Client> = Vec::with_capacity(request.merge_ids.len());
+        for id in request.merge_ids {
+            let merged_client = self.client_repo.by_id(id)?;
+            // エイリアスによって統合先自身や，既に指定したクライアントが返る場合
+            if merged_client.id() == before.id() {
+                return Err("Cannot merge a client into itself".to_string());
+            }
+            if merged_clients
+                .iter()
+                .any(|client| client.id() == merged_client.id())
+            {
+                return Err(format!(
+                    "Client #{} is specified more than once",
+                    merged_client.id().hyphenated()
+                ));
+            }
+            merged_clients.push(merged_client);
+        }
 
-        mock_repo
-            .expect_by_id()
-            .with(predicate::eq(id))
-            .times(1)
-            .return_const(Ok(client));
+        let mut after = before.clone();
+        for merged_client in merged_clients.iter() {
+            after.merge(merged_client.clone());
+        }
+        let applied = apply_change(
+            &ClientChange::merged(
+                &before,
+                &after,
+                merged_clients
+                    .into_iter()
+                    .map(|client| (client, Vec::new()))
+                    .collect(),
+            ),
+            self.client_repo.as_ref(),
+            self.project_repo.as_ref(),
+            self.relationship_repo.as_ref(),
+            self.event_publisher.as_ref(),
+        )?;
+        if let Some(history) = &self.history {
+            history.record(applied);
+        }
+        Ok(after.into())
+    }
+}
 
-        let get_client_use_case_handler = GetClientUseCaseHandler::new(Rc::new(mock_repo));
-        let res_client_dto = get_client_use_case_handler.execute(GetClientUseCaseRequest::new(id));
-        assert_eq!(res_client_dto, Ok(client_dto))
+// -------------------------------------------------------------------------------------------------
+
+pub struct AddContactUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
+
+impl<T: ClientRepository + ?Sized> AddContactUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            event_publisher: None,
+        }
     }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
 
-    #[test]
-    fn get_client_use_case_handler_execute_err() {
-        let client = Faker.fake::<Client>();
-        let id = client.id();
+impl<T: ClientRepository + ?Sized> Handler for AddContactUseCaseHandler<T> {
+    type Request = AddContactUseCaseRequest;
+    type Output = Result<ClientDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let info = ContactInfo::parse(request.kind, &request.value)?;
+        let mut client = self.client_repo.by_id(request.client_id)?;
+        client.add_contact(info);
+        self.client_repo.save(client.clone())?;
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(ClientEvent::edited(&client, Utc::now()));
+        }
+        Ok(client.into())
+    }
+}
 
-        let mut mock_repo = MockClientRepository::new();
+// -------------------------------------------------------------------------------------------------
 
-        mock_repo
-            .expect_by_id()
-            .with(predicate::eq(id))
-            .times(1)
-            .return_const(Err("some_error".to_string()));
+pub struct RemoveContactUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
 
-        let get_client_use_case_handler = GetClientUseCaseHandler::new(Rc::new(mock_repo));
-        let res_client_dto = get_client_use_case_handler.execute(GetClientUseCaseRequest::new(id));
+impl<T: ClientRepository + ?Sized> RemoveContactUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            event_publisher: None,
+        }
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
 
-        assert_matches!(res_client_dto, Err(_));
+impl<T: ClientRepository + ?Sized> Handler for RemoveContactUseCaseHandler<T> {
+    type Request = RemoveContactUseCaseRequest;
+    type Output = Result<ClientDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let mut client = self.client_repo.by_id(request.client_id)?;
+        client.remove_contact(request.contact_id)?;
+        self.client_repo.save(client.clone())?;
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(ClientEvent::edited(&client, Utc::now()));
+        }
+        Ok(client.into())
     }
+}
 
-    #[test]
-    fn get_all_clients_use_case_handler_execute() {
-        let clients: Vec<Client> = vec![Faker.fake(), Faker.fake(), Faker.fake()];
-        let client_dtos = clients
-            .iter()
-            .cloned()
-            .map(Into::into)
-            .collect::<DtoList<ClientDto>>();
+// -------------------------------------------------------------------------------------------------
 
-        let mut mock_repo = MockClientRepository::new();
-        mock_repo.expect_all().times(1).return_const(clients);
+pub struct SetPrimaryContactUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
 
-        let get_all_clients_use_case_handler = GetAllClientUseCaseHandler::new(Rc::new(mock_repo));
+impl<T: ClientRepository + ?Sized> SetPrimaryContactUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            event_publisher: None,
+        }
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
 
-        let client_dtos2 = get_all_clients_use_case_handler.execute(NoneRequest);
-        assert_eq!(client_dtos, client_dtos2);
+impl<T: ClientRepository + ?Sized> Handler for SetPrimaryContactUseCaseHandler<T> {
+    type Request = SetPrimaryContactUseCaseRequest;
+    type Output = Result<ClientDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let mut client = self.client_repo.by_id(request.client_id)?;
+        client.set_primary_contact(request.contact_id)?;
+        self.client_repo.save(client.clone())?;
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(ClientEvent::edited(&client, Utc::now()));
+        }
+        Ok(client.into())
     }
+}
 
-    #[test]
-    fn edit_client_use_case_handler_execute_ok() {
-        let client = Faker.fake::<Client>();
-        let new_name = Name().fake::<String>();
-        let new_location = Name().fake::<String>();
+// -------------------------------------------------------------------------------------------------
 
-        let mut mock_repo = MockClientRepository::new();
-        mock_repo
-            .expect_by_id()
-            .with(predicate::eq(client.id()))
-            .times(1)
-            .return_const(Ok(client.clone()));
+pub struct AddTagUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
 
-        let cloned_new_name = new_name.clone();
-        let cloned_new_location = new_location.clone();
+impl<T: ClientRepository + ?Sized> AddTagUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            event_publisher: None,
+        }
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
 
-        mock_repo
-            .expect_save()
-            .withf(move |client| {
-                client.name() == cloned_new_name && client.location() == cloned_new_location
-            })
-            .times(1)
-            .return_const(());
+impl<T: ClientRepository + ?Sized> Handler for AddTagUseCaseHandler<T> {
+    type Request = AddTagUseCaseRequest;
+    type Output = Result<ClientDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let tag = Tag::new(&request.tag)?;
+        let mut client = self.client_repo.by_id(request.client_id)?;
+        if client.add_tag(tag) {
+            self.client_repo.save(client.clone())?;
+            if let Some(event_publisher) = &self.event_publisher {
+                event_publisher.publish(ClientEvent::edited(&client, Utc::now()));
+            }
+        }
+        Ok(client.into())
+    }
+}
 
-        let edit_client_use_case_handler = EditClientUseCaseHandler::new(Rc::new(mock_repo));
+// -------------------------------------------------------------------------------------------------
 
-        let res = edit_client_use_case_handler.execute(EditClientUseCaseRequest::new(
-            client.id(),
-            new_name,
-            new_location,
-        ));
-        assert_eq!(res, Ok(()));
+pub struct RemoveTagUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
+
+impl<T: ClientRepository + ?Sized> RemoveTagUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            event_publisher: None,
+        }
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
     }
+}
 
-    #[test]
-    fn edit_client_use_case_handler_execute_err() {
-        let client = Faker.fake::<Client>();
-        let new_name = Name().fake::<String>();
-        let new_location = Name().fake::<String>();
+impl<T: ClientRepository + ?Sized> Handler for RemoveTagUseCaseHandler<T> {
+    type Request = RemoveTagUseCaseRequest;
+    type Output = Result<ClientDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let tag = Tag::new(&request.tag)?;
+        let mut client = self.client_repo.by_id(request.client_id)?;
+        client.remove_tag(&tag)?;
+        self.client_repo.save(client.clone())?;
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(ClientEvent::edited(&client, Utc::now()));
+        }
+        Ok(client.into())
+    }
+}
 
-        let mut mock_repo = MockClientRepository::new();
-        mock_repo
-            .expect_by_id()
-            .with(predicate::eq(client.id()))
-            .times(1)
-            .return_const(Err("Some Error".to_string()));
+// -------------------------------------------------------------------------------------------------
 
-        mock_repo.expect_save().times(0).return_const(());
+/// 読み取りモデルのみを参照する
+pub struct GetTagCountsUseCaseHandler {
+    projections: Rc<ClientProjections>,
+}
 
-        let edit_client_use_case_handler = EditClientUseCaseHandler::new(Rc::new(mock_repo));
+impl GetTagCountsUseCaseHandler {
+    pub fn new(projections: Rc<ClientProjections>) -> Self {
+        Self { projections }
+    }
+}
 
-        let res = edit_client_use_case_handler.execute(EditClientUseCaseRequest::new(
-            client.id(),
-            new_name,
-            new_location,
-        ));
-        assert_matches!(res, Err(_));
+impl Handler for GetTagCountsUseCaseHandler {
+    type Request = GetTagCountsUseCaseRequest;
+    type Output = DtoList<TagCountDto>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        let mut counts: BTreeMap<Tag, usize> = BTreeMap::new();
+        for client in self.projections.clients() {
+            for tag in client.tags() {
+                *counts.entry(tag.clone()).or_default() += 1;
+            }
+        }
+
+        // 件数の多い順，同数ならタグ名順
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+        counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+        counts
+            .into_iter()
+            .map(|(tag, count)| TagCountDto::new(tag.to_string(), count))
+            .collect::<DtoList<TagCountDto>>()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct AddNoteUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
+
+impl<T: ClientRepository + ?Sized> AddNoteUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            event_publisher: None,
+        }
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
+
+impl<T: ClientRepository + ?Sized> Handler for AddNoteUseCaseHandler<T> {
+    type Request = AddNoteUseCaseRequest;
+    type Output = Result<NoteDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let mut client = self.client_repo.by_id(request.client_id)?;
+        let note_id = client.add_note(&request.body, Utc::now())?;
+        let note = client.note(note_id)?.clone();
+        self.client_repo.save(client.clone())?;
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(ClientEvent::edited(&client, Utc::now()));
+        }
+        Ok(note.into())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 読み取りモデルのみを参照する
+pub struct GetNotesUseCaseHandler {
+    projections: Rc<ClientProjections>,
+}
+
+impl GetNotesUseCaseHandler {
+    pub fn new(projections: Rc<ClientProjections>) -> Self {
+        Self { projections }
+    }
+}
+
+impl Handler for GetNotesUseCaseHandler {
+    type Request = GetNotesUseCaseRequest;
+    type Output = Result<DtoList<NoteDto>, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let client = self.projections.client(request.client_id)?;
+        Ok(client
+            .notes()
+            .into_iter()
+            .cloned()
+            .map(Into::into)
+            .collect::<DtoList<NoteDto>>())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct EditNoteUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
+
+impl<T: ClientRepository + ?Sized> EditNoteUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            event_publisher: None,
+        }
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
+
+impl<T: ClientRepository + ?Sized> Handler for EditNoteUseCaseHandler<T> {
+    type Request = EditNoteUseCaseRequest;
+    type Output = Result<NoteDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let mut client = self.client_repo.by_id(request.client_id)?;
+        client.edit_note(request.note_id, &request.body, Utc::now())?;
+        let note = client.note(request.note_id)?.clone();
+        self.client_repo.save(client.clone())?;
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(ClientEvent::edited(&client, Utc::now()));
+        }
+        Ok(note.into())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct DeleteNoteUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
+
+impl<T: ClientRepository + ?Sized> DeleteNoteUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            event_publisher: None,
+        }
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
+
+impl<T: ClientRepository + ?Sized> Handler for DeleteNoteUseCaseHandler<T> {
+    type Request = DeleteNoteUseCaseRequest;
+    type Output = Result<(), String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let mut client = self.client_repo.by_id(request.client_id)?;
+        client.delete_note(request.note_id)?;
+        self.client_repo.save(client.clone())?;
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(ClientEvent::edited(&client, Utc::now()));
+        }
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct ChangeClientStatusUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
+
+impl<T: ClientRepository + ?Sized> ChangeClientStatusUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            event_publisher: None,
+        }
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
+
+impl<T: ClientRepository + ?Sized> Handler for ChangeClientStatusUseCaseHandler<T> {
+    type Request = ChangeClientStatusUseCaseRequest;
+    type Output = Result<ClientDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let status = request.status.parse::<ClientStatus>()?;
+        let mut client = self.client_repo.by_id(request.id)?;
+        let event = client.change_status(status, Utc::now())?;
+        self.client_repo.save(client.clone())?;
+        // 保存に成功してからイベントを発行する
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(event);
+        }
+        Ok(client.into())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct DeleteClientUseCaseHandler<
+    T: ClientRepository + ?Sized,
+    P: ProjectRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+> {
+    client_repo: Rc<T>,
+    project_repo: Rc<P>,
+    relationship_repo: Rc<R>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+    history: Option<Rc<EditHistory>>,
+}
+
+impl<
+        T: ClientRepository + ?Sized,
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+    > DeleteClientUseCaseHandler<T, P, R>
+{
+    pub fn new(client_repo: Rc<T>, project_repo: Rc<P>, relationship_repo: Rc<R>) -> Self {
+        Self {
+            client_repo,
+            project_repo,
+            relationship_repo,
+            event_publisher: None,
+            history: None,
+        }
+    }
+    pub fn with_history(mut self, history: Rc<EditHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
+
+impl<
+        T: ClientRepository + ?Sized,
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+    > Handler for DeleteClientUseCaseHandler<T, P, R>
+{
+    type Request = DeleteClientUseCaseRequest;
+    type Output = Result<(), String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let client = self.client_repo.by_id(request.id)?;
+        let relationships = delete_client(
+            &client,
+            self.client_repo.as_ref(),
+            self.project_repo.as_ref(),
+            self.relationship_repo.as_ref(),
+        )?;
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(ClientEvent::deleted(client.id(), Utc::now()));
+        }
+        if let Some(history) = &self.history {
+            history.record(ClientChange::deleted(&client, relationships));
+        }
+        Ok(())
+    }
+}
+
+/// 削除したクライアント(統合したクライアントを含む)の関係も削除し，削除した関係を返す．子会社は最上位になる
+fn delete_client<T, P, R>(
+    client: &Client,
+    client_repo: &T,
+    project_repo: &P,
+    relationship_repo: &R,
+) -> Result<Vec<Relationship>, String>
+where
+    T: ClientRepository + ?Sized,
+    P: ProjectRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+{
+    validate_client_deletion(client, project_repo)?;
+    client_repo.delete(client.id())?;
+    let mut relationships = Vec::new();
+    for id in std::iter::once(client.id()).chain(client.aliases().iter().copied()) {
+        for relationship in relationship_repo.by_client(id) {
+            relationship_repo.delete(&relationship)?;
+            relationships.push(relationship);
+        }
+    }
+    Ok(relationships)
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct CreateProjectUseCaseHandler<T: ClientRepository + ?Sized, P: ProjectRepository + ?Sized>
+{
+    client_repo: Rc<T>,
+    project_repo: Rc<P>,
+}
+
+impl<T: ClientRepository + ?Sized, P: ProjectRepository + ?Sized>
+    CreateProjectUseCaseHandler<T, P>
+{
+    pub fn new(client_repo: Rc<T>, project_repo: Rc<P>) -> Self {
+        Self {
+            client_repo,
+            project_repo,
+        }
+    }
+}
+
+impl<T: ClientRepository + ?Sized, P: ProjectRepository + ?Sized> Handler
+    for CreateProjectUseCaseHandler<T, P>
+{
+    type Request = CreateProjectUseCaseRequest;
+    type Output = Result<ProjectDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        // 削除済みのクライアントは見つからない．統合済みのIDは統合先に読み替える
+        let client = self.client_repo.by_id(request.client_id)?;
+        if client.status() == ClientStatus::Archived {
+            return Err("Cannot create a project for an archived client".to_string());
+        }
+        let project = Project::new(client.id(), &request.name, Utc::now())?;
+        self.project_repo.save(project.clone());
+        Ok(project.into())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct ListProjectsUseCaseHandler<P: ProjectRepository + ?Sized> {
+    projections: Rc<ClientProjections>,
+    project_repo: Rc<P>,
+}
+
+impl<P: ProjectRepository + ?Sized> ListProjectsUseCaseHandler<P> {
+    pub fn new(projections: Rc<ClientProjections>, project_repo: Rc<P>) -> Self {
+        Self {
+            projections,
+            project_repo,
+        }
+    }
+}
+
+impl<P: ProjectRepository + ?Sized> Handler for ListProjectsUseCaseHandler<P> {
+    type Request = ListProjectsUseCaseRequest;
+    type Output = Result<DtoList<ProjectDto>, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let projects = match request.client_id {
+            Some(client_id) => {
+                let client = self.projections.client(client_id)?;
+                projects_of_client(&client, self.project_repo.as_ref())
+            }
+            None => {
+                let mut projects = self.project_repo.all();
+                projects.sort_by_key(Project::created_at);
+                projects
+            }
+        };
+        Ok(projects
+            .into_iter()
+            .map(Into::into)
+            .collect::<DtoList<ProjectDto>>())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct CloseProjectUseCaseHandler<P: ProjectRepository + ?Sized> {
+    project_repo: Rc<P>,
+}
+
+impl<P: ProjectRepository + ?Sized> CloseProjectUseCaseHandler<P> {
+    pub fn new(project_repo: Rc<P>) -> Self {
+        Self { project_repo }
+    }
+}
+
+impl<P: ProjectRepository + ?Sized> Handler for CloseProjectUseCaseHandler<P> {
+    type Request = CloseProjectUseCaseRequest;
+    type Output = Result<ProjectDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let mut project = self.project_repo.by_id(request.id)?;
+        project.close(Utc::now())?;
+        self.project_repo.save(project.clone());
+        Ok(project.into())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct LinkClientsUseCaseHandler<
+    T: ClientRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+> {
+    client_repo: Rc<T>,
+    relationship_repo: Rc<R>,
+}
+
+impl<T: ClientRepository + ?Sized, R: RelationshipRepository + ?Sized>
+    LinkClientsUseCaseHandler<T, R>
+{
+    pub fn new(client_repo: Rc<T>, relationship_repo: Rc<R>) -> Self {
+        Self {
+            client_repo,
+            relationship_repo,
+        }
+    }
+}
+
+impl<T: ClientRepository + ?Sized, R: RelationshipRepository + ?Sized> Handler
+    for LinkClientsUseCaseHandler<T, R>
+{
+    type Request = LinkClientsUseCaseRequest;
+    type Output = Result<(), String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let kind = request.kind.parse::<RelationKind>()?;
+        let from = self.client_repo.by_id(request.from)?;
+        let to = self.client_repo.by_id(request.to)?;
+        let relationship = Relationship::new(from.id(), to.id(), kind)?;
+        validate_relationship(&relationship, &self.relationship_repo.all())?;
+        self.relationship_repo.save(relationship);
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct UnlinkClientsUseCaseHandler<
+    T: ClientRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+> {
+    client_repo: Rc<T>,
+    relationship_repo: Rc<R>,
+}
+
+impl<T: ClientRepository + ?Sized, R: RelationshipRepository + ?Sized>
+    UnlinkClientsUseCaseHandler<T, R>
+{
+    pub fn new(client_repo: Rc<T>, relationship_repo: Rc<R>) -> Self {
+        Self {
+            client_repo,
+            relationship_repo,
+        }
+    }
+}
+
+impl<T: ClientRepository + ?Sized, R: RelationshipRepository + ?Sized> Handler
+    for UnlinkClientsUseCaseHandler<T, R>
+{
+    type Request = UnlinkClientsUseCaseRequest;
+    type Output = Result<(), String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let kind = request.kind.parse::<RelationKind>()?;
+        let from = self.client_repo.by_id(request.from)?;
+        let to = self.client_repo.by_id(request.to)?;
+        let relationship = Relationship::new(from.id(), to.id(), kind)?;
+        self.relationship_repo
+            .delete(&relationship)
+            .map_err(|_| DomainError::RelationshipNotFound.into())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct GetHierarchyUseCaseHandler<R: RelationshipRepository + ?Sized> {
+    projections: Rc<ClientProjections>,
+    relationship_repo: Rc<R>,
+}
+
+impl<R: RelationshipRepository + ?Sized> GetHierarchyUseCaseHandler<R> {
+    pub fn new(projections: Rc<ClientProjections>, relationship_repo: Rc<R>) -> Self {
+        Self {
+            projections,
+            relationship_repo,
+        }
+    }
+    fn to_dto(&self, node: HierarchyNode, focused_id: Uuid) -> Result<HierarchyDto, String> {
+        let client = self.projections.client(node.client_id)?;
+        let children = node
+            .children
+            .into_iter()
+            .map(|child| self.to_dto(child, focused_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(HierarchyDto::new(
+            client.into(),
+            node.client_id == focused_id,
+            children,
+        ))
+    }
+}
+
+impl<R: RelationshipRepository + ?Sized> Handler for GetHierarchyUseCaseHandler<R> {
+    type Request = GetHierarchyUseCaseRequest;
+    type Output = Result<HierarchyDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let client = self.projections.client(request.client_id)?;
+        let hierarchy = build_hierarchy(client.id(), &self.relationship_repo.all());
+        self.to_dto(hierarchy, client.id())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 読み取りモデルのみを参照する
+pub struct GetClientSummariesUseCaseHandler {
+    projections: Rc<ClientProjections>,
+}
+
+impl GetClientSummariesUseCaseHandler {
+    pub fn new(projections: Rc<ClientProjections>) -> Self {
+        Self { projections }
+    }
+}
+
+impl Handler for GetClientSummariesUseCaseHandler {
+    type Request = GetClientSummariesUseCaseRequest;
+    type Output = DtoList<ClientSummaryDto>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        DtoList::new(self.projections.summaries())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 読み取りモデルのみを参照する
+pub struct GetLocationCountsUseCaseHandler {
+    projections: Rc<ClientProjections>,
+}
+
+impl GetLocationCountsUseCaseHandler {
+    pub fn new(projections: Rc<ClientProjections>) -> Self {
+        Self { projections }
+    }
+}
+
+impl Handler for GetLocationCountsUseCaseHandler {
+    type Request = GetLocationCountsUseCaseRequest;
+    type Output = DtoList<LocationCountDto>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        DtoList::new(self.projections.location_counts())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct RebuildProjectionsUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    projections: Rc<ClientProjections>,
+    tombstone_repo: Option<Rc<dyn TombstoneRepository>>,
+}
+
+impl<T: ClientRepository + ?Sized> RebuildProjectionsUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>, projections: Rc<ClientProjections>) -> Self {
+        Self {
+            client_repo,
+            projections,
+            tombstone_repo: None,
+        }
+    }
+    /// 消去したクライアントの墓標も読み取りモデルに反映する
+    pub fn with_tombstone_repo(mut self, tombstone_repo: Rc<dyn TombstoneRepository>) -> Self {
+        self.tombstone_repo = Some(tombstone_repo);
+        self
+    }
+}
+
+impl<T: ClientRepository + ?Sized> Handler for RebuildProjectionsUseCaseHandler<T> {
+    type Request = RebuildProjectionsUseCaseRequest;
+    /// 成功時は適用したクライアントの数を返す
+    type Output = Result<usize, String>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        let count = self.projections.rebuild(&self.client_repo.all());
+        if let Some(tombstone_repo) = &self.tombstone_repo {
+            for tombstone in tombstone_repo.all() {
+                self.projections.bury(tombstone);
+            }
+        }
+        Ok(count)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct GetOutboxUseCaseHandler<O: OutboxRepository + ?Sized> {
+    outbox_repo: Rc<O>,
+}
+
+impl<O: OutboxRepository + ?Sized> GetOutboxUseCaseHandler<O> {
+    pub fn new(outbox_repo: Rc<O>) -> Self {
+        Self { outbox_repo }
+    }
+}
+
+impl<O: OutboxRepository + ?Sized> Handler for GetOutboxUseCaseHandler<O> {
+    type Request = GetOutboxUseCaseRequest;
+    type Output = DtoList<OutboxMessageDto>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        self.outbox_repo
+            .all()
+            .into_iter()
+            .map(Into::into)
+            .collect::<DtoList<OutboxMessageDto>>()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 配信先に届いたイベント．履歴を持たないときは空
+#[derive(Default)]
+pub struct GetEventHistoryUseCaseHandler {
+    event_history: Option<Rc<dyn EventHistory>>,
+}
+
+impl GetEventHistoryUseCaseHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_event_history(mut self, event_history: Rc<dyn EventHistory>) -> Self {
+        self.event_history = Some(event_history);
+        self
+    }
+}
+
+impl Handler for GetEventHistoryUseCaseHandler {
+    type Request = GetEventHistoryUseCaseRequest;
+    type Output = DtoList<ClientEventDto>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        self.event_history
+            .iter()
+            .flat_map(|event_history| event_history.events())
+            .map(Into::into)
+            .collect::<DtoList<ClientEventDto>>()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+enum DeliveryOutcome {
+    Delivered,
+    Failed,
+    DeadLettered,
+}
+
+/// まだ届いていない配信先にのみ配信し，全ての配信先に届いたら配信済みにする．
+/// 失敗した回数が`max_attempts`に達したらデッドレターにし，そうでなければ`backoff`を失敗のたびに倍にして次の配信を待つ
+fn deliver_message(
+    message: &mut OutboxMessage,
+    sinks: &[Rc<dyn EventSink>],
+    retries: usize,
+    max_attempts: Option<u32>,
+    backoff: Duration,
+) -> DeliveryOutcome {
+    let mut errors = Vec::new();
+    for sink in sinks {
+        let name = sink.name();
+        if message.is_delivered_to(&name) {
+            continue;
+        }
+        let mut result = sink.deliver(message);
+        for _ in 0..retries {
+            if result.is_ok() {
+                break;
+            }
+            result = sink.deliver(message);
+        }
+        match result {
+            Ok(()) => message.mark_delivered_to(name),
+            Err(err) => errors.push(format!("{}: {}", name, err)),
+        }
+    }
+    if errors.is_empty() {
+        message.mark_delivered(Utc::now());
+        return DeliveryOutcome::Delivered;
+    }
+    message.mark_failed(errors.join(", "));
+    match max_attempts {
+        Some(max_attempts) if message.attempts() >= max_attempts => {
+            message.mark_dead_lettered(Utc::now());
+            DeliveryOutcome::DeadLettered
+        }
+        _ => {
+            let factor = 1_i32 << (message.attempts() - 1).min(16);
+            message.schedule_retry(Utc::now() + backoff * factor);
+            DeliveryOutcome::Failed
+        }
+    }
+}
+
+pub struct RelayOutboxUseCaseHandler<O: OutboxRepository + ?Sized> {
+    outbox_repo: Rc<O>,
+    sinks: Vec<Rc<dyn EventSink>>,
+    retries: usize,
+    max_attempts: Option<u32>,
+    backoff: Duration,
+}
+
+impl<O: OutboxRepository + ?Sized> RelayOutboxUseCaseHandler<O> {
+    pub fn new(outbox_repo: Rc<O>) -> Self {
+        Self {
+            outbox_repo,
+            sinks: Vec::new(),
+            retries: 0,
+            max_attempts: None,
+            backoff: Duration::zero(),
+        }
+    }
+    pub fn with_sinks(mut self, sinks: Vec<Rc<dyn EventSink>>) -> Self {
+        self.sinks = sinks;
+        self
+    }
+    /// 1回の配信で失敗したときに繰り返す回数
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+    /// 配信を諦めてデッドレターにするまでに失敗できる回数
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+    /// 配信に失敗したメッセージを次に配信するまでの最初の間隔
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+}
+
+impl<O: OutboxRepository + ?Sized> Handler for RelayOutboxUseCaseHandler<O> {
+    type Request = RelayOutboxUseCaseRequest;
+    type Output = Result<RelayReportDto, String>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        let mut delivered = 0;
+        let mut failed = 0;
+        let mut dead_lettered = 0;
+        let now = Utc::now();
+        for mut message in self
+            .outbox_repo
+            .pending()
+            .into_iter()
+            .filter(|message| message.is_due(now))
+        {
+            match deliver_message(
+                &mut message,
+                &self.sinks,
+                self.retries,
+                self.max_attempts,
+                self.backoff,
+            ) {
+                DeliveryOutcome::Delivered => delivered += 1,
+                DeliveryOutcome::Failed => failed += 1,
+                DeliveryOutcome::DeadLettered => dead_lettered += 1,
+            }
+            self.outbox_repo.save(message);
+        }
+        Ok(RelayReportDto::new(delivered, failed, dead_lettered))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct ReplayOutboxMessageUseCaseHandler<O: OutboxRepository + ?Sized> {
+    outbox_repo: Rc<O>,
+    sinks: Vec<Rc<dyn EventSink>>,
+    retries: usize,
+}
+
+impl<O: OutboxRepository + ?Sized> ReplayOutboxMessageUseCaseHandler<O> {
+    pub fn new(outbox_repo: Rc<O>) -> Self {
+        Self {
+            outbox_repo,
+            sinks: Vec::new(),
+            retries: 0,
+        }
+    }
+    pub fn with_sinks(mut self, sinks: Vec<Rc<dyn EventSink>>) -> Self {
+        self.sinks = sinks;
+        self
+    }
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+}
+
+impl<O: OutboxRepository + ?Sized> Handler for ReplayOutboxMessageUseCaseHandler<O> {
+    type Request = ReplayOutboxMessageUseCaseRequest;
+    /// 配信に失敗してもメッセージは未配信として残るため，結果はメッセージの状態で返す
+    type Output = Result<OutboxMessageDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let mut message = self.outbox_repo.by_id(request.id)?;
+        message.requeue(Utc::now());
+        // 手動の再配信ではデッドレターにせず，失敗したら次の中継ですぐに再送する
+        deliver_message(
+            &mut message,
+            &self.sinks,
+            self.retries,
+            None,
+            Duration::zero(),
+        );
+        self.outbox_repo.save(message.clone());
+        Ok(message.into())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct CreateWebhookUseCaseHandler<W: WebhookRepository + ?Sized> {
+    webhook_repo: Rc<W>,
+}
+
+impl<W: WebhookRepository + ?Sized> CreateWebhookUseCaseHandler<W> {
+    pub fn new(webhook_repo: Rc<W>) -> Self {
+        Self { webhook_repo }
+    }
+}
+
+impl<W: WebhookRepository + ?Sized> Handler for CreateWebhookUseCaseHandler<W> {
+    type Request = CreateWebhookUseCaseRequest;
+    type Output = Result<WebhookSubscriptionDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let subscription =
+            WebhookSubscription::new(&request.url, &request.event_types, &request.secret)?;
+        self.webhook_repo.save_subscription(subscription.clone());
+        Ok(subscription.into())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct DeleteWebhookUseCaseHandler<W: WebhookRepository + ?Sized> {
+    webhook_repo: Rc<W>,
+}
+
+impl<W: WebhookRepository + ?Sized> DeleteWebhookUseCaseHandler<W> {
+    pub fn new(webhook_repo: Rc<W>) -> Self {
+        Self { webhook_repo }
+    }
+}
+
+impl<W: WebhookRepository + ?Sized> Handler for DeleteWebhookUseCaseHandler<W> {
+    type Request = DeleteWebhookUseCaseRequest;
+    type Output = Result<(), String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        self.webhook_repo.delete_subscription(request.id)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct ListWebhooksUseCaseHandler<W: WebhookRepository + ?Sized> {
+    webhook_repo: Rc<W>,
+}
+
+impl<W: WebhookRepository + ?Sized> ListWebhooksUseCaseHandler<W> {
+    pub fn new(webhook_repo: Rc<W>) -> Self {
+        Self { webhook_repo }
+    }
+}
+
+impl<W: WebhookRepository + ?Sized> Handler for ListWebhooksUseCaseHandler<W> {
+    type Request = ListWebhooksUseCaseRequest;
+    type Output = DtoList<WebhookSubscriptionDto>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        self.webhook_repo
+            .subscriptions()
+            .into_iter()
+            .map(Into::into)
+            .collect::<DtoList<WebhookSubscriptionDto>>()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct GetWebhookDeliveriesUseCaseHandler<W: WebhookRepository + ?Sized> {
+    webhook_repo: Rc<W>,
+}
+
+impl<W: WebhookRepository + ?Sized> GetWebhookDeliveriesUseCaseHandler<W> {
+    pub fn new(webhook_repo: Rc<W>) -> Self {
+        Self { webhook_repo }
+    }
+}
+
+impl<W: WebhookRepository + ?Sized> Handler for GetWebhookDeliveriesUseCaseHandler<W> {
+    type Request = GetWebhookDeliveriesUseCaseRequest;
+    type Output = Result<DtoList<WebhookDeliveryDto>, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        if let Some(subscription_id) = request.subscription_id {
+            self.webhook_repo.subscription_by_id(subscription_id)?;
+        }
+        Ok(self
+            .webhook_repo
+            .deliveries()
+            .into_iter()
+            .filter(|delivery| match request.subscription_id {
+                Some(subscription_id) => delivery.subscription_id() == subscription_id,
+                None => true,
+            })
+            .map(Into::into)
+            .collect::<DtoList<WebhookDeliveryDto>>())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 削除したクライアントを保存し直し，相手のクライアントが残っている関係のみ復元する
+fn restore_clients<T, R>(
+    clients: &[(Client, Vec<Relationship>)],
+    client_repo: &T,
+    relationship_repo: &R,
+) -> Result<Vec<(Client, Vec<Relationship>)>, String>
+where
+    T: ClientRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+{
+    for (client, _) in clients.iter() {
+        if client_repo.by_id(client.id()).is_ok() {
+            return Err(format!(
+                "Client #{} already exists",
+                client.id().hyphenated()
+            ));
+        }
+    }
+    for (client, _) in clients.iter() {
+        client_repo.save(client.clone())?;
+    }
+    Ok(clients
+        .iter()
+        .map(|(client, relationships)| {
+            let relationships = relationships
+                .iter()
+                .filter(|relationship| {
+                    [relationship.from(), relationship.to()]
+                        .into_iter()
+                        .all(|id| client_repo.by_id(id).is_ok())
+                })
+                .copied()
+                .collect::<Vec<_>>();
+            for relationship in relationships.iter() {
+                relationship_repo.save(*relationship);
+            }
+            (client.clone(), relationships)
+        })
+        .collect())
+}
+
+/// 統合したクライアント(そのエイリアスを含む)の関係を統合先に付け替え，元の関係を返す．
+/// 統合先との関係は付け替えると自身との関係になるため外し，既にある関係は追加しない
+fn move_relationships<R: RelationshipRepository + ?Sized>(
+    client: &Client,
+    keep_id: Uuid,
+    relationship_repo: &R,
+    moved: &mut Vec<Relationship>,
+) -> Result<Vec<Relationship>, String> {
+    let ids = std::iter::once(client.id())
+        .chain(client.aliases().iter().copied())
+        .collect::<Vec<_>>();
+    let mut relationships = Vec::new();
+    for id in ids.iter() {
+        for relationship in relationship_repo.by_client(*id) {
+            relationship_repo.delete(&relationship)?;
+            relationships.push(relationship);
+        }
+    }
+    let keep = |id: Uuid| if ids.contains(&id) { keep_id } else { id };
+    for relationship in relationships.iter() {
+        let Ok(relationship) = Relationship::new(
+            keep(relationship.from()),
+            keep(relationship.to()),
+            relationship.kind(),
+        ) else {
+            continue;
+        };
+        let existing = relationship_repo.all();
+        if existing.contains(&relationship) {
+            continue;
+        }
+        validate_relationship(&relationship, &existing)?;
+        relationship_repo.save(relationship);
+        moved.push(relationship);
+    }
+    Ok(relationships)
+}
+
+/// 変更を適用し，実際に適用した変更を返す．削除は現在のクライアントとその関係を記録し直す
+fn apply_change<T, P, R>(
+    change: &ClientChange,
+    client_repo: &T,
+    project_repo: &P,
+    relationship_repo: &R,
+    event_publisher: Option<&Rc<dyn EventPublisher>>,
+) -> Result<ClientChange, String>
+where
+    T: ClientRepository + ?Sized,
+    P: ProjectRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+{
+    let (applied, events) = match change {
+        ClientChange::Created {
+            client,
+            relationships,
+        } => {
+            let (client, relationships) = restore_clients(
+                &[(client.clone(), relationships.clone())],
+                client_repo,
+                relationship_repo,
+            )?
+            .remove(0);
+            let event = ClientEvent::created(&client, Utc::now());
+            (
+                ClientChange::Created {
+                    client,
+                    relationships,
+                },
+                vec![event],
+            )
+        }
+        ClientChange::Edited {
+            client_id,
+            before,
+            after: (name, location),
+        } => {
+            let mut client = client_repo.by_id(*client_id)?;
+            client.edit(name.clone(), location.clone());
+            client_repo.save(client.clone())?;
+            (
+                ClientChange::Edited {
+                    client_id: *client_id,
+                    before: before.clone(),
+                    after: (name.clone(), location.clone()),
+                },
+                vec![ClientEvent::edited(&client, Utc::now())],
+            )
+        }
+        ClientChange::Deleted { client, .. } => {
+            let client = client_repo.by_id(client.id())?;
+            let relationships =
+                delete_client(&client, client_repo, project_repo, relationship_repo)?;
+            (
+                ClientChange::deleted(&client, relationships),
+                vec![ClientEvent::deleted(client.id(), Utc::now())],
+            )
+        }
+        // 統合したクライアントのプロジェクトはエイリアスで統合先から参照できるため，そのまま残す
+        ClientChange::Merged { after, merged, .. } => {
+            let before = client_repo.by_id(after.id())?;
+            let mut deleted = Vec::with_capacity(merged.len());
+            let mut moved = Vec::new();
+            let mut events = Vec::with_capacity(merged.len() + 1);
+            for (client, _) in merged.iter() {
+                let client = client_repo.by_id(client.id())?;
+                client_repo.delete(client.id())?;
+                let relationships =
+                    move_relationships(&client, after.id(), relationship_repo, &mut moved)?;
+                events.push(ClientEvent::deleted(client.id(), Utc::now()));
+                deleted.push((client, relationships));
+            }
+            client_repo.save(after.clone())?;
+            events.push(ClientEvent::edited(after, Utc::now()));
+            (
+                ClientChange::Merged {
+                    before,
+                    after: after.clone(),
+                    merged: deleted,
+                    moved,
+                },
+                events,
+            )
+        }
+        ClientChange::Unmerged {
+            after,
+            restored,
+            moved,
+            ..
+        } => {
+            // 統合先がエイリアスで復元するクライアントを指さないように，先に元に戻す
+            let before = client_repo.by_id(after.id())?;
+            let existing = relationship_repo.all();
+            for relationship in moved.iter().filter(|r| existing.contains(r)) {
+                relationship_repo.delete(relationship)?;
+            }
+            client_repo.save(after.clone())?;
+            let restored = restore_clients(restored, client_repo, relationship_repo)?;
+            let mut events = restored
+                .iter()
+                .map(|(client, _)| ClientEvent::created(client, Utc::now()))
+                .collect::<Vec<_>>();
+            events.push(ClientEvent::edited(after, Utc::now()));
+            (
+                ClientChange::Unmerged {
+                    before,
+                    after: after.clone(),
+                    restored,
+                    moved: moved.clone(),
+                },
+                events,
+            )
+        }
+    };
+    if let Some(event_publisher) = event_publisher {
+        for event in events {
+            event_publisher.publish(event);
+        }
+    }
+    Ok(applied)
+}
+
+pub struct UndoUseCaseHandler<
+    T: ClientRepository + ?Sized,
+    P: ProjectRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+> {
+    client_repo: Rc<T>,
+    project_repo: Rc<P>,
+    relationship_repo: Rc<R>,
+    history: Rc<EditHistory>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
+
+impl<
+        T: ClientRepository + ?Sized,
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+    > UndoUseCaseHandler<T, P, R>
+{
+    pub fn new(
+        client_repo: Rc<T>,
+        project_repo: Rc<P>,
+        relationship_repo: Rc<R>,
+        history: Rc<EditHistory>,
+    ) -> Self {
+        Self {
+            client_repo,
+            project_repo,
+            relationship_repo,
+            history,
+            event_publisher: None,
+        }
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
+
+impl<
+        T: ClientRepository + ?Sized,
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+    > Handler for UndoUseCaseHandler<T, P, R>
+{
+    type Request = UndoUseCaseRequest;
+    /// 成功時は取り消しのために適用した変更を返す
+    type Output = Result<ClientChangeDto, String>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        let change = self
+            .history
+            .next_undo()
+            .ok_or_else(|| "Nothing to undo".to_string())?;
+        let applied = apply_change(
+            &change.inverse(),
+            self.client_repo.as_ref(),
+            self.project_repo.as_ref(),
+            self.relationship_repo.as_ref(),
+            self.event_publisher.as_ref(),
+        )?;
+        self.history.undone(&applied);
+        Ok(applied.into())
+    }
+}
+
+pub struct RedoUseCaseHandler<
+    T: ClientRepository + ?Sized,
+    P: ProjectRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+> {
+    client_repo: Rc<T>,
+    project_repo: Rc<P>,
+    relationship_repo: Rc<R>,
+    history: Rc<EditHistory>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+}
+
+impl<
+        T: ClientRepository + ?Sized,
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+    > RedoUseCaseHandler<T, P, R>
+{
+    pub fn new(
+        client_repo: Rc<T>,
+        project_repo: Rc<P>,
+        relationship_repo: Rc<R>,
+        history: Rc<EditHistory>,
+    ) -> Self {
+        Self {
+            client_repo,
+            project_repo,
+            relationship_repo,
+            history,
+            event_publisher: None,
+        }
+    }
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+}
+
+impl<
+        T: ClientRepository + ?Sized,
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+    > Handler for RedoUseCaseHandler<T, P, R>
+{
+    type Request = RedoUseCaseRequest;
+    type Output = Result<ClientChangeDto, String>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        let change = self
+            .history
+            .next_redo()
+            .ok_or_else(|| "Nothing to redo".to_string())?;
+        let applied = apply_change(
+            &change,
+            self.client_repo.as_ref(),
+            self.project_repo.as_ref(),
+            self.relationship_repo.as_ref(),
+            self.event_publisher.as_ref(),
+        )?;
+        self.history.redone(applied.clone());
+        Ok(applied.into())
+    }
+}
+
+pub struct GetEditHistoryUseCaseHandler {
+    history: Rc<EditHistory>,
+}
+
+impl GetEditHistoryUseCaseHandler {
+    pub fn new(history: Rc<EditHistory>) -> Self {
+        Self { history }
+    }
+}
+
+impl Handler for GetEditHistoryUseCaseHandler {
+    type Request = GetEditHistoryUseCaseRequest;
+    type Output = Result<EditHistoryDto, String>;
+    /// 取り消しは元に戻すために適用する変更を見せる
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        Ok(EditHistoryDto::new(
+            self.history
+                .next_undo()
+                .map(|change| change.inverse().into()),
+            self.history.next_redo().map(Into::into),
+            self.history.undo_count(),
+            self.history.redo_count(),
+        ))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct IsSetupRequiredUseCaseHandler<U: UserRepository + ?Sized> {
+    user_repo: Rc<U>,
+}
+
+impl<U: UserRepository + ?Sized> IsSetupRequiredUseCaseHandler<U> {
+    pub fn new(user_repo: Rc<U>) -> Self {
+        Self { user_repo }
+    }
+}
+
+impl<U: UserRepository + ?Sized> Handler for IsSetupRequiredUseCaseHandler<U> {
+    type Request = IsSetupRequiredUseCaseRequest;
+    type Output = Result<bool, String>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        Ok(self.user_repo.all().is_empty())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct SetupAdminUseCaseHandler<U: UserRepository + ?Sized> {
+    user_repo: Rc<U>,
+    session: Rc<Session>,
+}
+
+impl<U: UserRepository + ?Sized> SetupAdminUseCaseHandler<U> {
+    pub fn new(user_repo: Rc<U>, session: Rc<Session>) -> Self {
+        Self { user_repo, session }
+    }
+}
+
+impl<U: UserRepository + ?Sized> Handler for SetupAdminUseCaseHandler<U> {
+    type Request = SetupAdminUseCaseRequest;
+    type Output = Result<UserDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        if !self.user_repo.all().is_empty() {
+            return Err("Setup has already been completed".to_string());
+        }
+        let user = User::new(&request.username, &request.password, Role::Admin)?;
+        self.user_repo.save(user.clone());
+        self.session.sign_in(user.clone());
+        Ok(user.into())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct LoginUseCaseHandler<U: UserRepository + ?Sized> {
+    user_repo: Rc<U>,
+    session: Rc<Session>,
+}
+
+impl<U: UserRepository + ?Sized> LoginUseCaseHandler<U> {
+    pub fn new(user_repo: Rc<U>, session: Rc<Session>) -> Self {
+        Self { user_repo, session }
+    }
+}
+
+impl<U: UserRepository + ?Sized> Handler for LoginUseCaseHandler<U> {
+    type Request = LoginUseCaseRequest;
+    type Output = Result<UserDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        // ユーザー名とパスワードのどちらが誤っているかは伝えない
+        match self.user_repo.by_username(request.username.trim()) {
+            Some(user) if user.verify_password(&request.password) => {
+                self.session.sign_in(user.clone());
+                Ok(user.into())
+            }
+            _ => Err("Invalid username or password".to_string()),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct LogoutUseCaseHandler {
+    session: Rc<Session>,
+}
+
+impl LogoutUseCaseHandler {
+    pub fn new(session: Rc<Session>) -> Self {
+        Self { session }
+    }
+}
+
+impl Handler for LogoutUseCaseHandler {
+    type Request = LogoutUseCaseRequest;
+    type Output = Result<(), String>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        self.session
+            .sign_out()
+            .map(|_| ())
+            .ok_or_else(|| "Not logged in".to_string())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct CreateUserUseCaseHandler<U: UserRepository + ?Sized> {
+    user_repo: Rc<U>,
+}
+
+impl<U: UserRepository + ?Sized> CreateUserUseCaseHandler<U> {
+    pub fn new(user_repo: Rc<U>) -> Self {
+        Self { user_repo }
+    }
+}
+
+impl<U: UserRepository + ?Sized> Handler for CreateUserUseCaseHandler<U> {
+    type Request = CreateUserUseCaseRequest;
+    type Output = Result<UserDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let role = request.role.parse::<Role>()?;
+        let user = User::new(&request.username, &request.password, role)?;
+        if self.user_repo.by_username(user.username()).is_some() {
+            return Err(format!("Username already exists: {}", user.username()));
+        }
+        self.user_repo.save(user.clone());
+        Ok(user.into())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct ListUsersUseCaseHandler<U: UserRepository + ?Sized> {
+    user_repo: Rc<U>,
+}
+
+impl<U: UserRepository + ?Sized> ListUsersUseCaseHandler<U> {
+    pub fn new(user_repo: Rc<U>) -> Self {
+        Self { user_repo }
+    }
+}
+
+impl<U: UserRepository + ?Sized> Handler for ListUsersUseCaseHandler<U> {
+    type Request = ListUsersUseCaseRequest;
+    type Output = DtoList<UserDto>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        self.user_repo
+            .all()
+            .into_iter()
+            .map(Into::into)
+            .collect::<DtoList<UserDto>>()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct GetAuditLogUseCaseHandler<A: AuditRepository + ?Sized> {
+    audit_repo: Rc<A>,
+}
+
+impl<A: AuditRepository + ?Sized> GetAuditLogUseCaseHandler<A> {
+    pub fn new(audit_repo: Rc<A>) -> Self {
+        Self { audit_repo }
+    }
+}
+
+impl<A: AuditRepository + ?Sized> Handler for GetAuditLogUseCaseHandler<A> {
+    type Request = GetAuditLogUseCaseRequest;
+    type Output = DtoList<AuditEntryDto>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        self.audit_repo
+            .all()
+            .into_iter()
+            .map(Into::into)
+            .collect::<DtoList<AuditEntryDto>>()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct ExportClientsUseCaseHandler {
+    projections: Rc<ClientProjections>,
+    tenant: TenantId,
+}
+
+impl ExportClientsUseCaseHandler {
+    pub fn new(projections: Rc<ClientProjections>, tenant: TenantId) -> Self {
+        Self {
+            projections,
+            tenant,
+        }
+    }
+}
+
+impl Handler for ExportClientsUseCaseHandler {
+    type Request = ExportClientsUseCaseRequest;
+    type Output = Result<ClientExportDto, String>;
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        Ok(ClientExportDto::new(
+            self.tenant.clone(),
+            self.projections
+                .clients()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            Utc::now(),
+        ))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct AnonymizeClientsUseCaseHandler {
+    projections: Rc<ClientProjections>,
+    tenant: TenantId,
+}
+
+impl AnonymizeClientsUseCaseHandler {
+    pub fn new(projections: Rc<ClientProjections>, tenant: TenantId) -> Self {
+        Self {
+            projections,
+            tenant,
+        }
+    }
+}
+
+impl Handler for AnonymizeClientsUseCaseHandler {
+    type Request = AnonymizeClientsUseCaseRequest;
+    type Output = Result<ClientExportDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        // 名前順に並べたものを匿名化するため，保存順に依らず同じ結果になる
+        let clients = self.projections.clients();
+        let mut anonymized = DatasetGenerator::new(request.seed).anonymize(&clients);
+        // 元の並び順から名前を推測されないようにする
+        anonymized.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(ClientExportDto::new(
+            self.tenant.clone(),
+            anonymized.into_iter().map(Into::into).collect(),
+            Utc::now(),
+        ))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct ExportClientPersonalDataUseCaseHandler<
+    P: ProjectRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+    O: OutboxRepository + ?Sized,
+    W: WebhookRepository + ?Sized,
+> {
+    projections: Rc<ClientProjections>,
+    project_repo: Rc<P>,
+    relationship_repo: Rc<R>,
+    outbox_repo: Rc<O>,
+    webhook_repo: Rc<W>,
+    idempotency_repo: Option<Rc<dyn IdempotencyRepository>>,
+    audit_repo: Option<Rc<dyn AuditRepository>>,
+}
+
+impl<
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+        O: OutboxRepository + ?Sized,
+        W: WebhookRepository + ?Sized,
+    > ExportClientPersonalDataUseCaseHandler<P, R, O, W>
+{
+    pub fn new(
+        projections: Rc<ClientProjections>,
+        project_repo: Rc<P>,
+        relationship_repo: Rc<R>,
+        outbox_repo: Rc<O>,
+        webhook_repo: Rc<W>,
+    ) -> Self {
+        Self {
+            projections,
+            project_repo,
+            relationship_repo,
+            outbox_repo,
+            webhook_repo,
+            idempotency_repo: None,
+            audit_repo: None,
+        }
+    }
+    pub fn with_idempotency_repo(
+        mut self,
+        idempotency_repo: Rc<dyn IdempotencyRepository>,
+    ) -> Self {
+        self.idempotency_repo = Some(idempotency_repo);
+        self
+    }
+    pub fn with_audit_repo(mut self, audit_repo: Rc<dyn AuditRepository>) -> Self {
+        self.audit_repo = Some(audit_repo);
+        self
+    }
+}
+
+impl<
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+        O: OutboxRepository + ?Sized,
+        W: WebhookRepository + ?Sized,
+    > Handler for ExportClientPersonalDataUseCaseHandler<P, R, O, W>
+{
+    type Request = ExportClientPersonalDataUseCaseRequest;
+    type Output = Result<ClientPersonalDataDto, String>;
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let client = self.projections.client(request.id)?;
+        // 統合したクライアントのIDを参照する記録も含める
+        let ids = client_ids(&client);
+        let mut relationships = Vec::new();
+        for id in ids.iter() {
+            for relationship in self.relationship_repo.by_client(*id) {
+                if !relationships.contains(&relationship) {
+                    relationships.push(relationship);
+                }
+            }
+        }
+        let events = self
+            .outbox_repo
+            .all()
+            .into_iter()
+            .filter(|message| ids.contains(&message.event().client_id()))
+            .collect::<Vec<_>>();
+        let deliveries = self
+            .webhook_repo
+            .deliveries()
+            .into_iter()
+            .filter(|delivery| {
+                events
+                    .iter()
+                    .any(|message| message.id() == delivery.message_id())
+            })
+            .map(Into::into)
+            .collect();
+        let idempotency_keys = match &self.idempotency_repo {
+            Some(idempotency_repo) => ids
+                .iter()
+                .flat_map(|id| idempotency_repo.keys_for(*id))
+                .collect(),
+            None => Vec::new(),
+        };
+        let audit_entries = match &self.audit_repo {
+            Some(audit_repo) => audit_repo
+                .all()
+                .into_iter()
+                .filter(|entry| matches!(entry.subject_id(), Some(id) if ids.contains(&id)))
+                .map(Into::into)
+                .collect(),
+            None => DtoList::new(Vec::new()),
+        };
+        Ok(ClientPersonalDataDto::new(
+            client.clone().into(),
+            client
+                .notes()
+                .into_iter()
+                .cloned()
+                .map(Into::into)
+                .collect(),
+            projects_of_client(&client, self.project_repo.as_ref())
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            relationships.into_iter().map(Into::into).collect(),
+            events.into_iter().map(Into::into).collect(),
+            deliveries,
+            idempotency_keys,
+            audit_entries,
+            Utc::now(),
+        ))
+    }
+}
+
+/// クライアント自身と統合したクライアントのID
+fn client_ids(client: &Client) -> Vec<Uuid> {
+    std::iter::once(client.id())
+        .chain(client.aliases().iter().copied())
+        .collect()
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// クライアントと関係を削除し，イベントに残る名前と出身地を消去する．
+/// プロジェクトはIDのみを参照するため残し，監査ログの記録からは対象のIDを外す．
+/// 消去したことを示すイベントを発行し，消去したIDを引けるように墓標を保存する
+pub struct EraseClientUseCaseHandler<
+    T: ClientRepository + ?Sized,
+    P: ProjectRepository + ?Sized,
+    R: RelationshipRepository + ?Sized,
+    O: OutboxRepository + ?Sized,
+> {
+    client_repo: Rc<T>,
+    project_repo: Rc<P>,
+    relationship_repo: Rc<R>,
+    outbox_repo: Rc<O>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+    sinks: Vec<Rc<dyn EventSink>>,
+    history: Option<Rc<EditHistory>>,
+    idempotency_repo: Option<Rc<dyn IdempotencyRepository>>,
+    audit_repo: Option<Rc<dyn AuditRepository>>,
+    tombstone_repo: Option<Rc<dyn TombstoneRepository>>,
+}
+
+impl<
+        T: ClientRepository + ?Sized,
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+        O: OutboxRepository + ?Sized,
+    > EraseClientUseCaseHandler<T, P, R, O>
+{
+    pub fn new(
+        client_repo: Rc<T>,
+        project_repo: Rc<P>,
+        relationship_repo: Rc<R>,
+        outbox_repo: Rc<O>,
+    ) -> Self {
+        Self {
+            client_repo,
+            project_repo,
+            relationship_repo,
+            outbox_repo,
+            event_publisher: None,
+            sinks: Vec::new(),
+            history: None,
+            idempotency_repo: None,
+            audit_repo: None,
+            tombstone_repo: None,
+        }
+    }
+    /// 配信済みのイベントを記録している配信先．消去するクライアントの記録を消去する
+    pub fn with_sinks(mut self, sinks: Vec<Rc<dyn EventSink>>) -> Self {
+        self.sinks = sinks;
+        self
+    }
+    /// 墓標のイベントの発行先．なければアウトボックスに直接保存する
+    pub fn with_event_publisher(mut self, event_publisher: Rc<dyn EventPublisher>) -> Self {
+        self.event_publisher = Some(event_publisher);
+        self
+    }
+    pub fn with_history(mut self, history: Rc<EditHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+    /// 冪等キーの記録は作成したときの名前と出身地を持つため，キーごと削除する
+    pub fn with_idempotency_repo(
+        mut self,
+        idempotency_repo: Rc<dyn IdempotencyRepository>,
+    ) -> Self {
+        self.idempotency_repo = Some(idempotency_repo);
+        self
+    }
+    pub fn with_audit_repo(mut self, audit_repo: Rc<dyn AuditRepository>) -> Self {
+        self.audit_repo = Some(audit_repo);
+        self
+    }
+    pub fn with_tombstone_repo(mut self, tombstone_repo: Rc<dyn TombstoneRepository>) -> Self {
+        self.tombstone_repo = Some(tombstone_repo);
+        self
+    }
+}
+
+impl<
+        T: ClientRepository + ?Sized,
+        P: ProjectRepository + ?Sized,
+        R: RelationshipRepository + ?Sized,
+        O: OutboxRepository + ?Sized,
+    > Handler for EraseClientUseCaseHandler<T, P, R, O>
+{
+    type Request = EraseClientUseCaseRequest;
+    type Output = Result<ErasureReportDto, String>;
+    /// 未完了のプロジェクトは拒否せずに完了にする．消去済みのクライアントはその旨を返す
+    fn execute(&self, request: Self::Request) -> Self::Output {
+        let client = self.client_repo.by_id(request.id).map_err(|err| {
+            self.tombstone_repo
+                .as_ref()
+                .and_then(|tombstone_repo| tombstone_repo.by_client(request.id))
+                .map_or(err, |tombstone| tombstone.error(request.id))
+        })?;
+        let mut closed_projects = 0;
+        for mut project in projects_of_client(&client, self.project_repo.as_ref()) {
+            if project.is_open() {
+                project.close(Utc::now())?;
+                self.project_repo.save(project);
+                closed_projects += 1;
+            }
+        }
+        let relationships = delete_client(
+            &client,
+            self.client_repo.as_ref(),
+            self.project_repo.as_ref(),
+            self.relationship_repo.as_ref(),
+        )?;
+        let ids = client_ids(&client);
+        let mut redacted_events = 0;
+        for mut message in self.outbox_repo.all() {
+            if ids.contains(&message.event().client_id()) && message.event().has_personal_data() {
+                message.redact();
+                self.outbox_repo.save(message);
+                redacted_events += 1;
+            }
+        }
+        // ファイルなどの配信先は作業単位で取り消せないため，保存先の変更を全て終えてから書き換える
+        for sink in self.sinks.iter() {
+            redacted_events += sink.redact(&ids)?;
+        }
+        let forgotten_changes = match &self.history {
+            Some(history) => ids.iter().map(|id| history.forget(*id)).sum(),
+            None => 0,
+        };
+        if let Some(idempotency_repo) = &self.idempotency_repo {
+            for id in ids.iter() {
+                idempotency_repo.forget(*id);
+            }
+        }
+        let redacted_audit_entries = match &self.audit_repo {
+            Some(audit_repo) => audit_repo.redact(&ids),
+            None => 0,
+        };
+        let erased_at = Utc::now();
+        if let Some(tombstone_repo) = &self.tombstone_repo {
+            tombstone_repo.save(Tombstone::new(&client, erased_at));
+        }
+        let erased = ClientEvent::erased(client.id(), erased_at);
+        match &self.event_publisher {
+            Some(event_publisher) => event_publisher.publish(erased),
+            None => self.outbox_repo.save(OutboxMessage::new(erased)),
+        }
+        Ok(ErasureReportDto::new(
+            client.id(),
+            closed_projects,
+            relationships.len(),
+            redacted_events,
+            redacted_audit_entries,
+            forgotten_changes,
+        ))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub struct ReencryptClientsUseCaseHandler<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    key_rotation: Option<Rc<dyn KeyRotation>>,
+}
+
+impl<T: ClientRepository + ?Sized> ReencryptClientsUseCaseHandler<T> {
+    pub fn new(client_repo: Rc<T>) -> Self {
+        Self {
+            client_repo,
+            key_rotation: None,
+        }
+    }
+    pub fn with_key_rotation(mut self, key_rotation: Rc<dyn KeyRotation>) -> Self {
+        self.key_rotation = Some(key_rotation);
+        self
+    }
+}
+
+impl<T: ClientRepository + ?Sized> Handler for ReencryptClientsUseCaseHandler<T> {
+    type Request = ReencryptClientsUseCaseRequest;
+    type Output = Result<usize, String>;
+    /// 暗号化した保存先は全てのテナントで共有するため，現在のテナントのクライアントだけを暗号化し直す
+    fn execute(&self, _: Self::Request) -> Self::Output {
+        let key_rotation = self
+            .key_rotation
+            .as_ref()
+            .ok_or_else(|| "Client encryption is not enabled".to_string())?;
+        let ids = self
+            .client_repo
+            .all()
+            .iter()
+            .map(Client::id)
+            .collect::<Vec<_>>();
+        key_rotation.reencrypt(&ids)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        AddContactUseCaseHandler, AddNoteUseCaseHandler, AddTagUseCaseHandler,
+        AnonymizeClientsUseCaseHandler, ChangeClientStatusUseCaseHandler,
+        CloseProjectUseCaseHandler, CreateClientUseCaseHandler, CreateProjectUseCaseHandler,
+        CreateUserUseCaseHandler, CreateWebhookUseCaseHandler, DeleteClientUseCaseHandler,
+        DeleteNoteUseCaseHandler, DeleteWebhookUseCaseHandler, EditClientUseCaseHandler,
+        EditNoteUseCaseHandler, EraseClientUseCaseHandler, ExportClientPersonalDataUseCaseHandler,
+        FindDuplicatesUseCaseHandler, GetAllClientUseCaseHandler, GetClientSummariesUseCaseHandler,
+        GetClientUseCaseHandler, GetHierarchyUseCaseHandler, GetLocationCountsUseCaseHandler,
+        GetNotesUseCaseHandler, GetOutboxUseCaseHandler, GetTagCountsUseCaseHandler,
+        GetWebhookDeliveriesUseCaseHandler, GroupClientsByRegionUseCaseHandler, Handler,
+        LinkClientsUseCaseHandler, ListProjectsUseCaseHandler, ListWebhooksUseCaseHandler,
+        LoginUseCaseHandler, MergeClientsUseCaseHandler, RebuildProjectionsUseCaseHandler,
+        ReencryptClientsUseCaseHandler, RelayOutboxUseCaseHandler, RemoveContactUseCaseHandler,
+        RemoveTagUseCaseHandler, ReplayOutboxMessageUseCaseHandler,
+        SetPrimaryContactUseCaseHandler, UndoUseCaseHandler, UnlinkClientsUseCaseHandler,
+    };
+    use crate::application::dtos::{
+        AuditEntryDto, ClientDto, ClientExportDto, DtoList, ErasureReportDto, NoteDto, ProjectDto,
+        TagCountDto,
+    };
+    use crate::application::requests::{
+        AddContactUseCaseRequest, AddNoteUseCaseRequest, AddTagUseCaseRequest,
+        AnonymizeClientsUseCaseRequest, ChangeClientStatusUseCaseRequest,
+        CloseProjectUseCaseRequest, CreateClientUseCaseRequest, CreateProjectUseCaseRequest,
+        CreateUserUseCaseRequest, CreateWebhookUseCaseRequest, DeleteClientUseCaseRequest,
+        DeleteNoteUseCaseRequest, DeleteWebhookUseCaseRequest, EditClientUseCaseRequest,
+        EditNoteUseCaseRequest, EraseClientUseCaseRequest, ExportClientPersonalDataUseCaseRequest,
+        FindDuplicatesUseCaseRequest, GetAllClientUseCaseRequest, GetClientSummariesUseCaseRequest,
+        GetClientUseCaseRequest, GetHierarchyUseCaseRequest, GetLocationCountsUseCaseRequest,
+        GetNotesUseCaseRequest, GetOutboxUseCaseRequest, GetTagCountsUseCaseRequest,
+        GetWebhookDeliveriesUseCaseRequest, GroupClientsByRegionUseCaseRequest,
+        LinkClientsUseCaseRequest, ListProjectsUseCaseRequest, ListWebhooksUseCaseRequest,
+        LoginUseCaseRequest, MergeClientsUseCaseRequest, RebuildProjectionsUseCaseRequest,
+        ReencryptClientsUseCaseRequest, RelayOutboxUseCaseRequest, RemoveContactUseCaseRequest,
+        RemoveTagUseCaseRequest, ReplayOutboxMessageUseCaseRequest,
+        SetPrimaryContactUseCaseRequest, UndoUseCaseRequest, UnlinkClientsUseCaseRequest,
+    };
+    use assert_matches::assert_matches;
+    use chrono::{Duration, Utc};
+    use fake::faker::{address::en::CityName, name::en::Name};
+    use fake::{Fake, Faker};
+    use mockall::{predicate, Sequence};
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+    use uuid::Uuid;
+
+    use crate::application::auth::Session;
+    use crate::application::history::{ClientChange, EditHistory};
+    use crate::application::projections::ClientProjections;
+
+    use crate::domain::events::{MockEventPublisher, MockEventSink};
+    use crate::domain::repositories::{
+        MockAuditRepository, MockClientRepository, MockIdempotencyRepository, MockKeyRotation,
+        MockOutboxRepository, MockProjectRepository, MockRelationshipRepository,
+        MockTombstoneRepository, MockUserRepository, MockWebhookRepository,
+    };
+    use crate::domain::{
+        AuditEntry, Client, ClientEvent, ClientStatus, ContactInfo, ContactKind,
+        DuplicateDetectionService, OutboxMessage, Project, RelationKind, Relationship, Role, Tag,
+        TenantId, Tombstone, UniquenessPolicy, User, WebhookDelivery, WebhookSubscription,
+    };
+
+    /// クライアントを適用した読み取りモデル
+    fn projections_of(clients: &[Client]) -> Rc<ClientProjections> {
+        let projections = ClientProjections::new();
+        projections.rebuild(clients);
+        Rc::new(projections)
+    }
+
+    #[test]
+    fn create_client_use_case_handler_execute() {
+        let mut mock_repo = MockClientRepository::new();
+        let name = Name().fake::<String>();
+        let location = CityName().fake::<String>();
+
+        let cloned_name = name.clone();
+        let cloned_location = location.clone();
+
+        mock_repo
+            .expect_save()
+            .withf(move |client| {
+                client.name() == cloned_name && client.location().as_str() == cloned_location
+            })
+            .times(1)
+            .return_const(Ok(()));
+        mock_repo
+            .expect_by_name_and_location()
+            .times(1)
+            .return_const(Vec::new());
+        let cloned_name = name.clone();
+        let mut mock_publisher = MockEventPublisher::new();
+        mock_publisher
+            .expect_publish()
+            .withf(move |event| {
+                matches!(event, ClientEvent::Created { name, .. } if *name == cloned_name)
+            })
+            .times(1)
+            .return_const(());
+
+        let create_client_use_case_handler = CreateClientUseCaseHandler::new(Rc::new(mock_repo))
+            .with_event_publisher(Rc::new(mock_publisher));
+        let res = create_client_use_case_handler
+            .execute(CreateClientUseCaseRequest::new(name.clone(), location));
+        assert_matches!(
+            res,
+            Ok(created) if created.client().name() == name && created.warnings().is_empty() && !created.replayed()
+        );
+    }
+
+    #[test]
+    fn create_client_use_case_handler_execute_idempotent() {
+        let client = Client::new(Name().fake(), CityName().fake());
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_name_and_location()
+            .times(1)
+            .return_const(Vec::new());
+        mock_repo.expect_save().times(1).return_const(Ok(()));
+        // 1回目は未処理のキーとして作成し，以降は記録した結果を返す．クライアントは読み直さない
+        let saved = Arc::new(Mutex::new(None));
+        let mut sequence = Sequence::new();
+        let mut mock_idempotency_repo = MockIdempotencyRepository::new();
+        mock_idempotency_repo
+            .expect_by_key()
+            .with(predicate::eq("create-1"))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_const(None);
+        let save_to = Arc::clone(&saved);
+        mock_idempotency_repo
+            .expect_save()
+            .withf(|record| record.key() == "create-1")
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |record| *save_to.lock().unwrap() = Some(record));
+        mock_idempotency_repo
+            .expect_by_key()
+            .with(predicate::eq("create-1"))
+            .times(2)
+            .in_sequence(&mut sequence)
+            .returning(move |_| saved.lock().unwrap().clone());
+
+        let create_client_use_case_handler = CreateClientUseCaseHandler::new(Rc::new(mock_repo))
+            .with_idempotency_repo(Rc::new(mock_idempotency_repo));
+        let request = CreateClientUseCaseRequest::new(
+            client.name().to_string(),
+            client.location().to_string(),
+        )
+        .with_idempotency_key("create-1".to_string());
+        let res = create_client_use_case_handler.execute(request.clone());
+        let first = assert_matches!(res, Ok(created) if !created.replayed() => created);
+        let res = create_client_use_case_handler.execute(request);
+        assert_matches!(res, Ok(created) => {
+            assert!(created.replayed());
+            assert_eq!(created.client(), first.client());
+        });
+
+        // 同じキーで別の内容は作成しない
+        let res = create_client_use_case_handler.execute(
+            CreateClientUseCaseRequest::new(Name().fake(), client.location().to_string())
+                .with_idempotency_key("create-1".to_string()),
+        );
+        assert_matches!(res, Err(err) if err.contains("create-1"));
+    }
+
+    #[test]
+    fn create_client_use_case_handler_execute_duplicated() {
+        let existing_client = Client::new(Name().fake(), CityName().fake());
+        let name = existing_client.name().to_string();
+        let location = existing_client.location().to_string();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_name_and_location()
+            .times(2)
+            .return_const(vec![existing_client]);
+        // warn, offのときのみ保存する
+        mock_repo.expect_save().times(2).return_const(Ok(()));
+
+        let mock_repo = Rc::new(mock_repo);
+
+        let reject_handler = CreateClientUseCaseHandler::new(Rc::clone(&mock_repo))
+            .with_uniqueness_policy(UniquenessPolicy::Reject);
+        let res = reject_handler.execute(CreateClientUseCaseRequest::new(
+            name.clone(),
+            location.clone(),
+        ));
+        assert_matches!(res, Err(_));
+
+        let warn_handler = CreateClientUseCaseHandler::new(Rc::clone(&mock_repo))
+            .with_uniqueness_policy(UniquenessPolicy::Warn);
+        let res = warn_handler.execute(CreateClientUseCaseRequest::new(
+            name.clone(),
+            location.clone(),
+        ));
+        assert_matches!(res, Ok(created) if created.warnings().len() == 1);
+
+        let off_handler = CreateClientUseCaseHandler::new(Rc::clone(&mock_repo))
+            .with_uniqueness_policy(UniquenessPolicy::Off);
+        let res = off_handler.execute(CreateClientUseCaseRequest::new(name, location));
+        assert_matches!(res, Ok(created) if created.warnings().is_empty());
+    }
+
+    #[test]
+    fn get_client_use_case_handler_execute_ok() {
+        let client = Faker.fake::<Client>();
+        let client_dto: ClientDto = client.clone().into();
+        let id = client.id();
+
+        let get_client_use_case_handler = GetClientUseCaseHandler::new(projections_of(&[client]));
+        let res_client_dto = get_client_use_case_handler.execute(GetClientUseCaseRequest::new(id));
+        assert_eq!(res_client_dto, Ok(client_dto))
+    }
+
+    #[test]
+    fn get_client_use_case_handler_execute_err() {
+        let client = Faker.fake::<Client>();
+        let id = Uuid::new_v4();
+
+        let get_client_use_case_handler = GetClientUseCaseHandler::new(projections_of(&[client]));
+        let res_client_dto = get_client_use_case_handler.execute(GetClientUseCaseRequest::new(id));
+
+        assert_matches!(res_client_dto, Err(_));
+    }
+
+    #[test]
+    fn get_all_clients_use_case_handler_execute() {
+        let mut clients: Vec<Client> = vec![Faker.fake(), Faker.fake(), Faker.fake()];
+        let get_all_clients_use_case_handler =
+            GetAllClientUseCaseHandler::new(projections_of(&clients));
+
+        // 名前順に並ぶ
+        clients.sort_by(|a, b| a.name().cmp(b.name()).then(a.id().cmp(&b.id())));
+        let client_dtos = clients
+            .into_iter()
+            .map(Into::into)
+            .collect::<DtoList<ClientDto>>();
+
+        let client_dtos2 =
+            get_all_clients_use_case_handler.execute(GetAllClientUseCaseRequest::new());
+        assert_eq!(client_dtos, client_dtos2);
+    }
+
+    #[test]
+    fn get_all_clients_use_case_handler_execute_with_region() {
+        let tokyo_client = Client::new("Hanako".to_string(), "東京都渋谷区".to_string());
+        let tokyo_client2 = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let osaka_client = Client::new(Name().fake(), "大阪".to_string());
+        let clients = vec![tokyo_client2.clone(), osaka_client, tokyo_client.clone()];
+
+        let get_all_clients_use_case_handler =
+            GetAllClientUseCaseHandler::new(projections_of(&clients));
+
+        let client_dtos = get_all_clients_use_case_handler
+            .execute(GetAllClientUseCaseRequest::new().with_region("とうきょう".to_string()));
+        assert_eq!(
+            client_dtos,
+            vec![tokyo_client, tokyo_client2]
+                .into_iter()
+                .map(Into::into)
+                .collect::<DtoList<ClientDto>>()
+        );
+    }
+
+    #[test]
+    fn group_clients_by_region_use_case_handler_execute() {
+        let clients = vec![
+            Client::new("Jiro".to_string(), "東京".to_string()),
+            Client::new("Hanako".to_string(), "Atlantis".to_string()),
+            Client::new("Taro".to_string(), "tokyo".to_string()),
+            Client::new("Saburo".to_string(), "Osaka".to_string()),
+        ];
+
+        let group_clients_by_region_use_case_handler =
+            GroupClientsByRegionUseCaseHandler::new(projections_of(&clients));
+        let groups =
+            group_clients_by_region_use_case_handler.execute(GroupClientsByRegionUseCaseRequest);
+
+        let summary = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.region(),
+                    group
+                        .clients()
+                        .iter()
+                        .map(|client| client.name().to_string())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("大阪府"), vec!["Saburo".to_string()]),
+                (Some("東京都"), vec!["Jiro".to_string(), "Taro".to_string()]),
+                (None, vec!["Hanako".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn edit_client_use_case_handler_execute_ok() {
+        let client = Client::new(Name().fake(), CityName().fake());
+        let new_name = Name().fake::<String>();
+        let new_location = Name().fake::<String>();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(client.id()))
+            .times(1)
+            .return_const(Ok(client.clone()));
+
+        let cloned_new_name = new_name.clone();
+        let cloned_new_location = new_location.clone();
+
+        mock_repo
+            .expect_save()
+            .withf(move |client| {
+                client.name() == cloned_new_name
+                    && client.location().as_str() == cloned_new_location
+            })
+            .times(1)
+            .return_const(Ok(()));
+        // 編集対象自身は重複として扱わない
+        mock_repo
+            .expect_by_name_and_location()
+            .times(1)
+            .return_const(vec![client.clone()]);
+
+        let edit_client_use_case_handler = EditClientUseCaseHandler::new(Rc::new(mock_repo));
+
+        let res = edit_client_use_case_handler.execute(EditClientUseCaseRequest::new(
+            client.id(),
+            new_name,
+            new_location,
+        ));
+        assert_eq!(res, Ok(Vec::new()));
+    }
+
+    #[test]
+    fn edit_client_use_case_handler_execute_duplicated() {
+        let client = Client::new(Name().fake(), CityName().fake());
+        let other_client = Client::new(Name().fake(), CityName().fake());
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(client.id()))
+            .times(1)
+            .return_const(Ok(client.clone()));
+        mock_repo
+            .expect_by_name_and_location()
+            .times(1)
+            .return_const(vec![other_client.clone()]);
+        mock_repo.expect_save().times(0).return_const(Ok(()));
+
+        let edit_client_use_case_handler = EditClientUseCaseHandler::new(Rc::new(mock_repo));
+
+        let res = edit_client_use_case_handler.execute(EditClientUseCaseRequest::new(
+            client.id(),
+            other_client.name().to_string(),
+            other_client.location().to_string(),
+        ));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn edit_client_use_case_handler_execute_err() {
+        let client = Faker.fake::<Client>();
+        let new_name = Name().fake::<String>();
+        let new_location = Name().fake::<String>();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(client.id()))
+            .times(1)
+            .return_const(Err("Some Error".to_string()));
+
+        mock_repo.expect_save().times(0).return_const(Ok(()));
+
+        let edit_client_use_case_handler = EditClientUseCaseHandler::new(Rc::new(mock_repo));
+
+        let res = edit_client_use_case_handler.execute(EditClientUseCaseRequest::new(
+            client.id(),
+            new_name,
+            new_location,
+        ));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn find_duplicates_use_case_handler_execute() {
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let taro2 = Client::new("ﾀﾛｳ".to_string(), "東京".to_string());
+        let taro3 = Client::new("ｔａｒｏ".to_string(), "TOKYO".to_string());
+        let clients = vec![taro.clone(), taro2, taro3.clone()];
+
+        let find_duplicates_use_case_handler =
+            FindDuplicatesUseCaseHandler::new(projections_of(&clients));
+        let groups = find_duplicates_use_case_handler.execute(FindDuplicatesUseCaseRequest::new(
+            DuplicateDetectionService::DEFAULT_THRESHOLD,
+        ));
+
+        let mut expected_ids = vec![taro.id(), taro3.id()];
+        expected_ids.sort();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0]
+                .clients()
+                .iter()
+                .map(ClientDto::id)
+                .collect::<Vec<_>>(),
+            expected_ids
+        );
+    }
+
+    #[test]
+    fn merge_clients_use_case_handler_execute_ok() {
+        let client = Faker.fake::<Client>();
+        let merged_client = Client::new(Name().fake(), CityName().fake());
+        let merged_id = merged_client.id();
+
+        // 検証と適用でそれぞれ読み込む
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(client.id()))
+            .times(2)
+            .return_const(Ok(client.clone()));
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(merged_id))
+            .times(2)
+            .return_const(Ok(merged_client));
+        mock_repo
+            .expect_delete()
+            .with(predicate::eq(merged_id))
+            .times(1)
+            .return_const(Ok(()));
+
+        let cloned_client = client.clone();
+        mock_repo
+            .expect_save()
+            .withf(move |saved| {
+                saved.id() == cloned_client.id()
+                    && saved.name() == cloned_client.name()
+                    && saved.aliases().contains(&merged_id)
+            })
+            .times(1)
+            .return_const(Ok(()));
+        // プロジェクトはエイリアスで参照するため読み込まない
+        let mock_project_repo = MockProjectRepository::new();
+        let mut mock_relationship_repo = MockRelationshipRepository::new();
+        mock_relationship_repo
+            .expect_by_client()
+            .with(predicate::eq(merged_id))
+            .times(1)
+            .return_const(Vec::new());
+        // 統合したクライアントの削除と統合先の変更を通知する
+        let mut mock_event_publisher = MockEventPublisher::new();
+        mock_event_publisher
+            .expect_publish()
+            .withf(move |event| matches!(event, ClientEvent::Deleted { client_id, .. } if *client_id == merged_id))
+            .times(1)
+            .return_const(());
+        let client_id = client.id();
+        mock_event_publisher
+            .expect_publish()
+            .withf(move |event| matches!(event, ClientEvent::Edited { client_id: id, .. } if *id == client_id))
+            .times(1)
+            .return_const(());
+        let history = Rc::new(EditHistory::new(10));
+
+        let merge_clients_use_case_handler = MergeClientsUseCaseHandler::new(
+            Rc::new(mock_repo),
+            Rc::new(mock_project_repo),
+            Rc::new(mock_relationship_repo),
+        )
+        .with_event_publisher(Rc::new(mock_event_publisher))
+        .with_history(Rc::clone(&history));
+        let res = merge_clients_use_case_handler.execute(MergeClientsUseCaseRequest::new(
+            client.id(),
+            vec![merged_id],
+        ));
+        assert_matches!(res, Ok(client_dto) if client_dto.id() == client.id());
+        assert_matches!(
+            history.next_undo(),
+            Some(ClientChange::Merged { before, merged, .. })
+                if before == client && merged.len() == 1 && merged[0].0.id() == merged_id
+        );
+    }
+
+    #[test]
+    fn merge_clients_use_case_handler_execute_err() {
+        let client = Faker.fake::<Client>();
+        let missing_id = Faker.fake();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(client.id()))
+            .return_const(Ok(client.clone()));
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(missing_id))
+            .times(1)
+            .return_const(Err("Some Error".to_string()));
+        mock_repo.expect_delete().times(0).return_const(Ok(()));
+        mock_repo.expect_save().times(0).return_const(Ok(()));
+
+        let merge_clients_use_case_handler = MergeClientsUseCaseHandler::new(
+            Rc::new(mock_repo),
+            Rc::new(MockProjectRepository::new()),
+            Rc::new(MockRelationshipRepository::new()),
+        );
+
+        // 存在しないクライアント
+        let res = merge_clients_use_case_handler.execute(MergeClientsUseCaseRequest::new(
+            client.id(),
+            vec![missing_id],
+        ));
+        assert_matches!(res, Err(_));
+
+        // 自分自身
+        let res = merge_clients_use_case_handler.execute(MergeClientsUseCaseRequest::new(
+            client.id(),
+            vec![client.id()],
+        ));
+        assert_matches!(res, Err(_));
+
+        // 同じクライアントを重ねて指定する
+        let res = merge_clients_use_case_handler.execute(MergeClientsUseCaseRequest::new(
+            client.id(),
+            vec![missing_id, missing_id],
+        ));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn add_contact_use_case_handler_execute_ok() {
+        let client = Client::new(Name().fake(), CityName().fake());
+        let id = client.id();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(client));
+        mock_repo
+            .expect_save()
+            .withf(|client| {
+                client.contacts().len() == 1
+                    && client.contacts()[0].is_primary()
+                    && client.contacts()[0].info().to_string() == "+819012345678"
+            })
+            .times(1)
+            .return_const(Ok(()));
+
+        let add_contact_use_case_handler = AddContactUseCaseHandler::new(Rc::new(mock_repo));
+        let res = add_contact_use_case_handler.execute(AddContactUseCaseRequest::new(
+            id,
+            ContactKind::Phone,
+            "090-1234-5678".to_string(),
+        ));
+        assert_matches!(res, Ok(client_dto) if client_dto.contacts().len() == 1);
+    }
+
+    #[test]
+    fn add_contact_use_case_handler_execute_invalid() {
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo.expect_by_id().times(0);
+        mock_repo.expect_save().times(0).return_const(Ok(()));
+
+        let add_contact_use_case_handler = AddContactUseCaseHandler::new(Rc::new(mock_repo));
+        let res = add_contact_use_case_handler.execute(AddContactUseCaseRequest::new(
+            Faker.fake(),
+            ContactKind::Email,
+            "invalid email".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn remove_contact_use_case_handler_execute() {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        let contact_id =
+            client.add_contact(ContactInfo::parse(ContactKind::Email, "taro@example.com").unwrap());
+        let id = client.id();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(2)
+            .return_const(Ok(client));
+        mock_repo
+            .expect_save()
+            .withf(|client| client.contacts().is_empty())
+            .times(1)
+            .return_const(Ok(()));
+
+        let remove_contact_use_case_handler = RemoveContactUseCaseHandler::new(Rc::new(mock_repo));
+        let res = remove_contact_use_case_handler
+            .execute(RemoveContactUseCaseRequest::new(id, contact_id));
+        assert_matches!(res, Ok(client_dto) if client_dto.contacts().is_empty());
+
+        // 存在しない連絡先
+        let res = remove_contact_use_case_handler
+            .execute(RemoveContactUseCaseRequest::new(id, Faker.fake()));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn set_primary_contact_use_case_handler_execute() {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        client.add_contact(ContactInfo::parse(ContactKind::Email, "taro@example.com").unwrap());
+        let contact_id =
+            client.add_contact(ContactInfo::parse(ContactKind::Email, "taro@example.jp").unwrap());
+        let id = client.id();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(client));
+        mock_repo
+            .expect_save()
+            .withf(move |client| {
+                client
+                    .primary_contact(ContactKind::Email)
+                    .map(|contact| contact.id())
+                    == Some(contact_id)
+            })
+            .times(1)
+            .return_const(Ok(()));
+
+        let set_primary_contact_use_case_handler =
+            SetPrimaryContactUseCaseHandler::new(Rc::new(mock_repo));
+        let res = set_primary_contact_use_case_handler
+            .execute(SetPrimaryContactUseCaseRequest::new(id, contact_id));
+        assert_matches!(res, Ok(_));
+    }
+
+    #[test]
+    fn add_tag_use_case_handler_execute() {
+        let client = Client::new(Name().fake(), CityName().fake());
+        let id = client.id();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(client));
+        mock_repo
+            .expect_save()
+            .withf(|client| client.has_tag(&Tag::new("vip").unwrap()))
+            .times(1)
+            .return_const(Ok(()));
+
+        let add_tag_use_case_handler = AddTagUseCaseHandler::new(Rc::new(mock_repo));
+        let res =
+            add_tag_use_case_handler.execute(AddTagUseCaseRequest::new(id, "VIP".to_string()));
+        assert_matches!(res, Ok(client_dto) if client_dto.tags() == vec!["vip"]);
+
+        // 不正なタグ名
+        let res = add_tag_use_case_handler
+            .execute(AddTagUseCaseRequest::new(id, "not a tag".to_string()));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn remove_tag_use_case_handler_execute() {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        client.add_tag(Tag::new("vip").unwrap());
+        let id = client.id();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(2)
+            .return_const(Ok(client));
+        mock_repo
+            .expect_save()
+            .withf(|client| client.tags().is_empty())
+            .times(1)
+            .return_const(Ok(()));
+
+        let remove_tag_use_case_handler = RemoveTagUseCaseHandler::new(Rc::new(mock_repo));
+        let res = remove_tag_use_case_handler
+            .execute(RemoveTagUseCaseRequest::new(id, "vip".to_string()));
+        assert_matches!(res, Ok(client_dto) if client_dto.tags().is_empty());
+
+        // 付いていないタグ
+        let res = remove_tag_use_case_handler
+            .execute(RemoveTagUseCaseRequest::new(id, "prospect".to_string()));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn get_all_clients_use_case_handler_execute_with_tag() {
+        let mut vip_client = Client::new(Name().fake(), CityName().fake());
+        vip_client.add_tag(Tag::new("vip").unwrap());
+        let clients = vec![
+            vip_client.clone(),
+            Client::new(Name().fake(), CityName().fake()),
+        ];
+
+        let get_all_clients_use_case_handler =
+            GetAllClientUseCaseHandler::new(projections_of(&clients));
+
+        let client_dtos = get_all_clients_use_case_handler
+            .execute(GetAllClientUseCaseRequest::new().with_tag("ＶＩＰ".to_string()));
+        assert_eq!(client_dtos, DtoList::new(vec![vip_client.into()]));
+
+        let client_dtos = get_all_clients_use_case_handler
+            .execute(GetAllClientUseCaseRequest::new().with_tag("not a tag".to_string()));
+        assert!(client_dtos.is_empty());
+    }
+
+    #[test]
+    fn get_tag_counts_use_case_handler_execute() {
+        let tags = ["vip", "prospect", "churned"].map(|tag| Tag::new(tag).unwrap());
+        let mut clients = (0..3)
+            .map(|_| Client::new(Name().fake(), CityName().fake()))
+            .collect::<Vec<_>>();
+        clients[0].add_tag(tags[0].clone());
+        clients[1].add_tag(tags[0].clone());
+        clients[1].add_tag(tags[1].clone());
+        clients[2].add_tag(tags[2].clone());
+
+        let get_tag_counts_use_case_handler =
+            GetTagCountsUseCaseHandler::new(projections_of(&clients));
+        let counts = get_tag_counts_use_case_handler.execute(GetTagCountsUseCaseRequest);
+        assert_eq!(
+            counts,
+            DtoList::new(vec![
+                TagCountDto::new("vip".to_string(), 2),
+                TagCountDto::new("churned".to_string(), 1),
+                TagCountDto::new("prospect".to_string(), 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn add_note_use_case_handler_execute() {
+        let client = Client::new(Name().fake(), CityName().fake());
+        let id = client.id();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(2)
+            .return_const(Ok(client));
+        mock_repo
+            .expect_save()
+            .withf(|client| client.notes().len() == 1 && client.notes()[0].body() == "wants quote")
+            .times(1)
+            .return_const(Ok(()));
+
+        let add_note_use_case_handler = AddNoteUseCaseHandler::new(Rc::new(mock_repo));
+        let res = add_note_use_case_handler
+            .execute(AddNoteUseCaseRequest::new(id, "wants quote".to_string()));
+        assert_matches!(res, Ok(note_dto) if note_dto.body() == "wants quote");
+
+        // 空のメモ
+        let res =
+            add_note_use_case_handler.execute(AddNoteUseCaseRequest::new(id, " ".to_string()));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn get_notes_use_case_handler_execute() {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        let now = Utc::now();
+        client.add_note("second", now).unwrap();
+        client.add_note("first", now - Duration::days(1)).unwrap();
+        let id = client.id();
+
+        let get_notes_use_case_handler = GetNotesUseCaseHandler::new(projections_of(&[client]));
+        let notes = get_notes_use_case_handler
+            .execute(GetNotesUseCaseRequest::new(id))
+            .unwrap();
+        assert_eq!(
+            notes.iter().map(NoteDto::body).collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+    }
+
+    #[test]
+    fn edit_note_use_case_handler_execute() {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        let note_id = client.add_note("called", Utc::now()).unwrap();
+        let id = client.id();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(2)
+            .return_const(Ok(client));
+        mock_repo
+            .expect_save()
+            .withf(|client| client.notes()[0].body() == "called again")
+            .times(1)
+            .return_const(Ok(()));
+
+        let edit_note_use_case_handler = EditNoteUseCaseHandler::new(Rc::new(mock_repo));
+        let res = edit_note_use_case_handler.execute(EditNoteUseCaseRequest::new(
+            id,
+            note_id,
+            "called again".to_string(),
+        ));
+        assert_matches!(res, Ok(note_dto) if note_dto.updated_at().is_some());
+
+        // 存在しないメモ
+        let res = edit_note_use_case_handler.execute(EditNoteUseCaseRequest::new(
+            id,
+            Faker.fake(),
+            "called again".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn delete_note_use_case_handler_execute() {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        let note_id = client.add_note("called", Utc::now()).unwrap();
+        let id = client.id();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(client));
+        mock_repo
+            .expect_save()
+            .withf(|client| client.notes().is_empty())
+            .times(1)
+            .return_const(Ok(()));
+
+        let delete_note_use_case_handler = DeleteNoteUseCaseHandler::new(Rc::new(mock_repo));
+        let res = delete_note_use_case_handler.execute(DeleteNoteUseCaseRequest::new(id, note_id));
+        assert_eq!(res, Ok(()));
+    }
+
+    #[test]
+    fn change_client_status_use_case_handler_execute() {
+        let client = Client::new(Name().fake(), CityName().fake());
+        let id = client.id();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(2)
+            .return_const(Ok(client));
+        mock_repo
+            .expect_save()
+            .withf(|client| client.status() == ClientStatus::Active)
+            .times(1)
+            .return_const(Ok(()));
+        let mut mock_publisher = MockEventPublisher::new();
+        mock_publisher
+            .expect_publish()
+            .withf(move |event| {
+                matches!(event, ClientEvent::StatusChanged { client_id, from: ClientStatus::Prospect, to: ClientStatus::Active, .. } if *client_id == id)
+            })
+            .times(1)
+            .return_const(());
+
+        let change_client_status_use_case_handler =
+            ChangeClientStatusUseCaseHandler::new(Rc::new(mock_repo))
+                .with_event_publisher(Rc::new(mock_publisher));
+        let res = change_client_status_use_case_handler.execute(
+            ChangeClientStatusUseCaseRequest::new(id, "active".to_string()),
+        );
+        assert_matches!(res, Ok(client_dto) if client_dto.status() == "active");
+
+        // 許可されていない遷移では保存もイベントの発行もしない
+        let res = change_client_status_use_case_handler.execute(
+            ChangeClientStatusUseCaseRequest::new(id, "suspended".to_string()),
+        );
+        assert_matches!(res, Err(_));
+
+        // 不正なステータス
+        let res = change_client_status_use_case_handler.execute(
+            ChangeClientStatusUseCaseRequest::new(id, "deleted".to_string()),
+        );
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn get_all_client_use_case_handler_execute_with_status() {
+        let prospect = Client::new(Name().fake(), CityName().fake());
+        let mut active = Client::new(Name().fake(), CityName().fake());
+        active
+            .change_status(ClientStatus::Active, Utc::now())
+            .unwrap();
+        let active_id = active.id();
+
+        let get_all_client_use_case_handler =
+            GetAllClientUseCaseHandler::new(projections_of(&[prospect, active]));
+        let clients = get_all_client_use_case_handler
+            .execute(GetAllClientUseCaseRequest::new().with_status("Active".to_string()));
+        assert_eq!(
+            clients.iter().map(ClientDto::id).collect::<Vec<_>>(),
+            vec![active_id]
+        );
+
+        // 不正なステータスでは何も一致しない
+        let clients = get_all_client_use_case_handler
+            .execute(GetAllClientUseCaseRequest::new().with_status("unknown".to_string()));
+        assert!(clients.is_empty());
+    }
+
+    #[test]
+    fn delete_client_use_case_handler_execute() {
+        let client = Client::new(Name().fake(), CityName().fake());
+        let id = client.id();
+        let open_project = Project::new(id, "Website renewal", Utc::now()).unwrap();
+        let subsidiary = Relationship::new(Faker.fake(), id, RelationKind::SubsidiaryOf).unwrap();
+
+        let mut mock_client_repo = MockClientRepository::new();
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(2)
+            .return_const(Ok(client));
+        mock_client_repo
+            .expect_delete()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(()));
+        let mut mock_project_repo = MockProjectRepository::new();
+        mock_project_repo
+            .expect_by_client()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(vec![open_project]);
+        mock_project_repo
+            .expect_by_client()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Vec::new());
+        let mut mock_relationship_repo = MockRelationshipRepository::new();
+        mock_relationship_repo
+            .expect_by_client()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(vec![subsidiary]);
+        mock_relationship_repo
+            .expect_delete()
+            .with(predicate::eq(subsidiary))
+            .times(1)
+            .return_const(Ok(()));
+        // 削除に成功したときのみ発行する
+        let mut mock_publisher = MockEventPublisher::new();
+        mock_publisher
+            .expect_publish()
+            .withf(move |event| matches!(event, ClientEvent::Deleted { client_id, .. } if *client_id == id))
+            .times(1)
+            .return_const(());
+
+        let delete_client_use_case_handler = DeleteClientUseCaseHandler::new(
+            Rc::new(mock_client_repo),
+            Rc::new(mock_project_repo),
+            Rc::new(mock_relationship_repo),
+        )
+        .with_event_publisher(Rc::new(mock_publisher));
+        // 未完了のプロジェクトがあるときは削除しない
+        let res = delete_client_use_case_handler.execute(DeleteClientUseCaseRequest::new(id));
+        assert_matches!(res, Err(_));
+
+        // 削除したクライアントの関係も削除する
+        let res = delete_client_use_case_handler.execute(DeleteClientUseCaseRequest::new(id));
+        assert_eq!(res, Ok(()));
+    }
+
+    #[test]
+    fn create_project_use_case_handler_execute() {
+        let client = Client::new(Name().fake(), CityName().fake());
+        let id = client.id();
+        let mut archived = Client::new(Name().fake(), CityName().fake());
+        archived
+            .change_status(ClientStatus::Archived, Utc::now())
+            .unwrap();
+        let archived_id = archived.id();
+        let unknown_id = Faker.fake();
+
+        let mut mock_client_repo = MockClientRepository::new();
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(client));
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(archived_id))
+            .times(1)
+            .return_const(Ok(archived));
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(unknown_id))
+            .times(1)
+            .return_const(Err("No client found for given ID".to_string()));
+        let mut mock_project_repo = MockProjectRepository::new();
+        mock_project_repo
+            .expect_save()
+            .withf(move |project| project.client_id() == id && project.is_open())
+            .times(1)
+            .return_const(());
+
+        let create_project_use_case_handler =
+            CreateProjectUseCaseHandler::new(Rc::new(mock_client_repo), Rc::new(mock_project_repo));
+        let res = create_project_use_case_handler.execute(CreateProjectUseCaseRequest::new(
+            id,
+            "Website renewal".to_string(),
+        ));
+        assert_matches!(res, Ok(project_dto) if project_dto.client_id() == id);
+
+        let res = create_project_use_case_handler.execute(CreateProjectUseCaseRequest::new(
+            archived_id,
+            "Website renewal".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+
+        let res = create_project_use_case_handler.execute(CreateProjectUseCaseRequest::new(
+            unknown_id,
+            "Website renewal".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn list_projects_use_case_handler_execute() {
+        let client = Client::new(Name().fake(), CityName().fake());
+        let id = client.id();
+        let now = Utc::now();
+        let first = Project::new(id, "first", now - Duration::days(1)).unwrap();
+        let second = Project::new(id, "second", now).unwrap();
+        let other = Project::new(Faker.fake(), "other", now - Duration::days(2)).unwrap();
+
+        let mut mock_project_repo = MockProjectRepository::new();
+        mock_project_repo
+            .expect_by_client()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(vec![second.clone(), first.clone()]);
+        mock_project_repo
+            .expect_all()
+            .times(1)
+            .return_const(vec![second, other, first]);
+
+        let list_projects_use_case_handler =
+            ListProjectsUseCaseHandler::new(projections_of(&[client]), Rc::new(mock_project_repo));
+        let projects = list_projects_use_case_handler
+            .execute(ListProjectsUseCaseRequest::new().with_client(id))
+            .unwrap();
+        assert_eq!(
+            projects.iter().map(ProjectDto::name).collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+
+        let projects = list_projects_use_case_handler
+            .execute(ListProjectsUseCaseRequest::new())
+            .unwrap();
+        assert_eq!(
+            projects.iter().map(ProjectDto::name).collect::<Vec<_>>(),
+            vec!["other", "first", "second"]
+        );
+    }
+
+    #[test]
+    fn close_project_use_case_handler_execute() {
+        let project = Project::new(Faker.fake(), "Website renewal", Utc::now()).unwrap();
+        let id = project.id();
+        let mut closed = project.clone();
+        closed.close(Utc::now()).unwrap();
+
+        let mut mock_project_repo = MockProjectRepository::new();
+        mock_project_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(project));
+        mock_project_repo
+            .expect_save()
+            .withf(|project| !project.is_open())
+            .times(1)
+            .return_const(());
+
+        let close_project_use_case_handler =
+            CloseProjectUseCaseHandler::new(Rc::new(mock_project_repo));
+        let res = close_project_use_case_handler.execute(CloseProjectUseCaseRequest::new(id));
+        assert_matches!(res, Ok(project_dto) if project_dto.status() == "closed");
+
+        // 完了済みのプロジェクト
+        let mut mock_project_repo = MockProjectRepository::new();
+        mock_project_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(closed));
+        let close_project_use_case_handler =
+            CloseProjectUseCaseHandler::new(Rc::new(mock_project_repo));
+        let res = close_project_use_case_handler.execute(CloseProjectUseCaseRequest::new(id));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn link_clients_use_case_handler_execute() {
+        let parent = Client::new(Name().fake(), CityName().fake());
+        let child = Client::new(Name().fake(), CityName().fake());
+        let (parent_id, child_id) = (parent.id(), child.id());
+
+        let mut mock_client_repo = MockClientRepository::new();
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(parent_id))
+            .return_const(Ok(parent));
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(child_id))
+            .return_const(Ok(child));
+        let mut mock_relationship_repo = MockRelationshipRepository::new();
+        mock_relationship_repo
+            .expect_all()
+            .times(1)
+            .return_const(Vec::new());
+        mock_relationship_repo
+            .expect_all()
+            .times(1)
+            .return_const(vec![Relationship::new(
+                child_id,
+                parent_id,
+                RelationKind::SubsidiaryOf,
+            )
+            .unwrap()]);
+        mock_relationship_repo
+            .expect_save()
+            .with(predicate::eq(
+                Relationship::new(child_id, parent_id, RelationKind::SubsidiaryOf).unwrap(),
+            ))
+            .times(1)
+            .return_const(());
+
+        let link_clients_use_case_handler = LinkClientsUseCaseHandler::new(
+            Rc::new(mock_client_repo),
+            Rc::new(mock_relationship_repo),
+        );
+        let res = link_clients_use_case_handler.execute(LinkClientsUseCaseRequest::new(
+            child_id,
+            parent_id,
+            "subsidiary_of".to_string(),
+        ));
+        assert_eq!(res, Ok(()));
+
+        // 循環する関係は保存しない
+        let res = link_clients_use_case_handler.execute(LinkClientsUseCaseRequest::new(
+            parent_id,
+            child_id,
+            "subsidiary_of".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+
+        // 自身との関係
+        let res = link_clients_use_case_handler.execute(LinkClientsUseCaseRequest::new(
+            child_id,
+            child_id,
+            "referred_by".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+
+        // 不正な種類
+        let res = link_clients_use_case_handler.execute(LinkClientsUseCaseRequest::new(
+            child_id,
+            parent_id,
+            "friend_of".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn unlink_clients_use_case_handler_execute() {
+        let referrer = Client::new(Name().fake(), CityName().fake());
+        let client = Client::new(Name().fake(), CityName().fake());
+        let (referrer_id, client_id) = (referrer.id(), client.id());
+        let referral = Relationship::new(client_id, referrer_id, RelationKind::ReferredBy).unwrap();
+
+        let mut mock_client_repo = MockClientRepository::new();
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(referrer_id))
+            .return_const(Ok(referrer));
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(client_id))
+            .return_const(Ok(client));
+        let mut mock_relationship_repo = MockRelationshipRepository::new();
+        mock_relationship_repo
+            .expect_delete()
+            .with(predicate::eq(referral))
+            .times(1)
+            .return_const(Ok(()));
+        mock_relationship_repo
+            .expect_delete()
+            .times(1)
+            .return_const(Err("No relationship found".to_string()));
+
+        let unlink_clients_use_case_handler = UnlinkClientsUseCaseHandler::new(
+            Rc::new(mock_client_repo),
+            Rc::new(mock_relationship_repo),
+        );
+        let res = unlink_clients_use_case_handler.execute(UnlinkClientsUseCaseRequest::new(
+            client_id,
+            referrer_id,
+            "referred_by".to_string(),
+        ));
+        assert_eq!(res, Ok(()));
+
+        let res = unlink_clients_use_case_handler.execute(UnlinkClientsUseCaseRequest::new(
+            client_id,
+            referrer_id,
+            "subsidiary_of".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn get_hierarchy_use_case_handler_execute() {
+        let parent = Client::new(Name().fake(), CityName().fake());
+        let child = Client::new(Name().fake(), CityName().fake());
+        let (parent_id, child_id) = (parent.id(), child.id());
+        let projections = projections_of(&[parent, child]);
+
+        let mut mock_relationship_repo = MockRelationshipRepository::new();
+        mock_relationship_repo
+            .expect_all()
+            .times(1)
+            .return_const(vec![Relationship::new(
+                child_id,
+                parent_id,
+                RelationKind::SubsidiaryOf,
+            )
+            .unwrap()]);
+
+        let get_hierarchy_use_case_handler =
+            GetHierarchyUseCaseHandler::new(projections, Rc::new(mock_relationship_repo));
+        let hierarchy = get_hierarchy_use_case_handler
+            .execute(GetHierarchyUseCaseRequest::new(child_id))
+            .unwrap();
+        assert_eq!(hierarchy.client().id(), parent_id);
+        assert!(!hierarchy.is_focused());
+        assert_eq!(hierarchy.children().len(), 1);
+        assert_eq!(hierarchy.children()[0].client().id(), child_id);
+        assert!(hierarchy.children()[0].is_focused());
+    }
+
+    #[test]
+    fn query_handlers_read_only_from_projections() {
+        let clients = vec![
+            Client::new("Taro".to_string(), "Tokyo".to_string()),
+            Client::new("Jiro".to_string(), "Tokyo".to_string()),
+            Client::new("Hanako".to_string(), "Osaka".to_string()),
+        ];
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo.expect_all().times(1).return_const(clients);
+        let projections = Rc::new(ClientProjections::new());
+
+        let get_client_summaries_use_case_handler =
+            GetClientSummariesUseCaseHandler::new(Rc::clone(&projections));
+        let get_location_counts_use_case_handler =
+            GetLocationCountsUseCaseHandler::new(Rc::clone(&projections));
+
+        // 再構築するまでは読み取りモデルが空
+        let summaries =
+            get_client_summaries_use_case_handler.execute(GetClientSummariesUseCaseRequest);
+        assert!(summaries.is_empty());
+
+        let rebuild_projections_use_case_handler =
+            RebuildProjectionsUseCaseHandler::new(Rc::new(mock_repo), Rc::clone(&projections));
+        let res = rebuild_projections_use_case_handler.execute(RebuildProjectionsUseCaseRequest);
+        assert_matches!(res, Ok(3));
+
+        let summaries =
+            get_client_summaries_use_case_handler.execute(GetClientSummariesUseCaseRequest);
+        let names = summaries
+            .iter()
+            .map(|summary| summary.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Hanako", "Jiro", "Taro"]);
+
+        let counts = get_location_counts_use_case_handler.execute(GetLocationCountsUseCaseRequest);
+        let counts = counts
+            .iter()
+            .map(|count| (count.location(), count.count()))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![("Osaka", 1), ("Tokyo", 2)]);
+    }
+
+    #[test]
+    fn reencrypt_clients_use_case_handler_execute() {
+        let clients = vec![
+            Client::new(Name().fake(), CityName().fake()),
+            Client::new(Name().fake(), CityName().fake()),
+        ];
+        let ids = clients.iter().map(Client::id).collect::<Vec<_>>();
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo.expect_all().return_const(clients);
+
+        let reencrypt_clients_use_case_handler =
+            ReencryptClientsUseCaseHandler::new(Rc::new(mock_repo));
+        let res = reencrypt_clients_use_case_handler.execute(ReencryptClientsUseCaseRequest);
+        assert_matches!(res, Err(err) if err == "Client encryption is not enabled");
+
+        // 見えているクライアントだけを暗号化し直す
+        let mut mock_key_rotation = MockKeyRotation::new();
+        mock_key_rotation
+            .expect_reencrypt()
+            .withf(move |requested| requested == ids.as_slice())
+            .times(1)
+            .return_const(Ok(2));
+        let reencrypt_clients_use_case_handler =
+            reencrypt_clients_use_case_handler.with_key_rotation(Rc::new(mock_key_rotation));
+        let res = reencrypt_clients_use_case_handler.execute(ReencryptClientsUseCaseRequest);
+        assert_matches!(res, Ok(2));
+    }
+
+    fn outbox_messages(count: usize) -> Vec<OutboxMessage> {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        [
+            ClientStatus::Active,
+            ClientStatus::Suspended,
+            ClientStatus::Active,
+        ]
+        .into_iter()
+        .take(count)
+        .map(|status| OutboxMessage::new(client.change_status(status, Utc::now()).unwrap()))
+        .collect()
+    }
+
+    #[test]
+    fn relay_outbox_use_case_handler_execute() {
+        let messages = outbox_messages(2);
+        let (flaky_id, broken_id) = (messages[0].id(), messages[1].id());
+
+        let mut mock_outbox_repo = MockOutboxRepository::new();
+        mock_outbox_repo
+            .expect_pending()
+            .times(1)
+            .return_const(messages);
+        mock_outbox_repo
+            .expect_save()
+            .withf(move |message| {
+                message.id() == flaky_id && !message.is_pending() && message.attempts() == 1
+            })
+            .times(1)
+            .return_const(());
+        mock_outbox_repo
+            .expect_save()
+            .withf(move |message| {
+                message.id() == broken_id
+                    && message.is_pending()
+                    && message.last_error() == Some("webhook: connection refused")
+                    && !message.is_due(Utc::now() + Duration::seconds(59))
+                    && message.is_due(Utc::now() + Duration::seconds(61))
+            })
+            .times(1)
+            .return_const(());
+
+        // 1つ目のメッセージは再試行で成功し，2つ目は失敗し続ける
+        let mut mock_sink = MockEventSink::new();
+        mock_sink.expect_name().return_const("webhook".to_string());
+        let mut flaky_attempts = 0;
+        mock_sink
+            .expect_deliver()
+            .times(4)
+            .returning(move |message| {
+                if message.id() == flaky_id {
+                    flaky_attempts += 1;
+                    if flaky_attempts > 1 {
+                        return Ok(());
+                    }
+                }
+                Err("connection refused".to_string())
+            });
+
+        let relay_outbox_use_case_handler =
+            RelayOutboxUseCaseHandler::new(Rc::new(mock_outbox_repo))
+                .with_sinks(vec![Rc::new(mock_sink)])
+                .with_retries(1)
+                .with_backoff(Duration::minutes(1));
+        let res = relay_outbox_use_case_handler.execute(RelayOutboxUseCaseRequest);
+        assert_matches!(res, Ok(report) if report.delivered() == 1 && report.failed() == 1);
+    }
+
+    #[test]
+    fn relay_outbox_resends_only_to_failed_sinks() {
+        let mut messages = outbox_messages(2);
+        let mut message = messages.remove(0);
+        message.mark_delivered_to("history".to_string());
+        message.mark_failed("webhook: connection refused".to_string());
+        // 再試行の日時を過ぎていないメッセージは送らない
+        let mut waiting = messages.remove(0);
+        waiting.mark_failed("webhook: connection refused".to_string());
+        waiting.schedule_retry(Utc::now() + Duration::minutes(1));
+
+        let mut mock_outbox_repo = MockOutboxRepository::new();
+        mock_outbox_repo
+            .expect_pending()
+            .times(1)
+            .return_const(vec![message, waiting]);
+        // 2回目の失敗で上限に達し，デッドレターになる
+        mock_outbox_repo
+            .expect_save()
+            .withf(|message| {
+                !message.is_pending()
+                    && message.delivered_at().is_none()
+                    && message.dead_lettered_at().is_some()
+                    && message.attempts() == 2
+                    && message.delivered_to() == ["history".to_string()]
+            })
+            .times(1)
+            .return_const(());
+
+        // 届いている配信先には送り直さない
+        let mut mock_history = MockEventSink::new();
+        mock_history
+            .expect_name()
+            .return_const("history".to_string());
+        mock_history.expect_deliver().never();
+        let mut mock_webhook = MockEventSink::new();
+        mock_webhook
+            .expect_name()
+            .return_const("webhook".to_string());
+        mock_webhook
+            .expect_deliver()
+            .times(1)
+            .return_const(Err("connection refused".to_string()));
+
+        let relay_outbox_use_case_handler =
+            RelayOutboxUseCaseHandler::new(Rc::new(mock_outbox_repo))
+                .with_sinks(vec![Rc::new(mock_history), Rc::new(mock_webhook)])
+                .with_max_attempts(2);
+        let res = relay_outbox_use_case_handler.execute(RelayOutboxUseCaseRequest);
+        assert_matches!(
+            res,
+            Ok(report) if report.delivered() == 0 && report.failed() == 0 && report.dead_lettered() == 1
+        );
+    }
+
+    #[test]
+    fn replay_outbox_message_use_case_handler_execute() {
+        let mut message = outbox_messages(1).remove(0);
+        message.mark_delivered(Utc::now());
+        let id = message.id();
+
+        let mut mock_outbox_repo = MockOutboxRepository::new();
+        mock_outbox_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(message));
+        mock_outbox_repo
+            .expect_by_id()
+            .with(predicate::ne(id))
+            .times(1)
+            .return_const(Err("No outbox message found for given ID".to_string()));
+        mock_outbox_repo
+            .expect_save()
+            .withf(|message| !message.is_pending() && message.attempts() == 2)
+            .times(1)
+            .return_const(());
+        let mut mock_sink = MockEventSink::new();
+        mock_sink.expect_name().return_const("stdout".to_string());
+        mock_sink
+            .expect_deliver()
+            .withf(move |message| message.id() == id)
+            .times(1)
+            .return_const(Ok(()));
+
+        let replay_outbox_message_use_case_handler =
+            ReplayOutboxMessageUseCaseHandler::new(Rc::new(mock_outbox_repo))
+                .with_sinks(vec![Rc::new(mock_sink)]);
+        // 配信済みのメッセージも再配信できる
+        let res = replay_outbox_message_use_case_handler
+            .execute(ReplayOutboxMessageUseCaseRequest::new(id));
+        assert_matches!(res, Ok(message_dto) if message_dto.id() == id);
+
+        let res = replay_outbox_message_use_case_handler
+            .execute(ReplayOutboxMessageUseCaseRequest::new(Faker.fake()));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn get_outbox_use_case_handler_execute() {
+        let messages = outbox_messages(3);
+        let mut mock_outbox_repo = MockOutboxRepository::new();
+        mock_outbox_repo
+            .expect_all()
+            .times(1)
+            .return_const(messages.clone());
+
+        let get_outbox_use_case_handler = GetOutboxUseCaseHandler::new(Rc::new(mock_outbox_repo));
+        let res = get_outbox_use_case_handler.execute(GetOutboxUseCaseRequest);
+        let ids = res.iter().map(|message| message.id()).collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            messages.iter().map(OutboxMessage::id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn create_webhook_use_case_handler_execute() {
+        let mut mock_webhook_repo = MockWebhookRepository::new();
+        mock_webhook_repo
+            .expect_save_subscription()
+            .withf(|subscription| {
+                subscription.url() == "http://localhost:8080/hooks"
+                    && subscription.secret() == "secret"
+            })
+            .times(1)
+            .return_const(());
+
+        let create_webhook_use_case_handler =
+            CreateWebhookUseCaseHandler::new(Rc::new(mock_webhook_repo));
+        let res = create_webhook_use_case_handler.execute(CreateWebhookUseCaseRequest::new(
+            "http://localhost:8080/hooks".to_string(),
+            vec!["client.created".to_string()],
+            "secret".to_string(),
+        ));
+        assert_matches!(res, Ok(subscription) if subscription.event_types() == vec!["client.created"]);
+
+        // 不正な購読は保存しない
+        let res = create_webhook_use_case_handler.execute(CreateWebhookUseCaseRequest::new(
+            "http://localhost:8080/hooks".to_string(),
+            vec!["client.archived".to_string()],
+            "secret".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn delete_webhook_use_case_handler_execute() {
+        let id = Uuid::new_v4();
+        let mut mock_webhook_repo = MockWebhookRepository::new();
+        mock_webhook_repo
+            .expect_delete_subscription()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(()));
+
+        let delete_webhook_use_case_handler =
+            DeleteWebhookUseCaseHandler::new(Rc::new(mock_webhook_repo));
+        let res = delete_webhook_use_case_handler.execute(DeleteWebhookUseCaseRequest::new(id));
+        assert_matches!(res, Ok(()));
+    }
+
+    #[test]
+    fn list_webhooks_use_case_handler_execute() {
+        let subscription = WebhookSubscription::new(
+            "http://localhost/hooks",
+            &["client.created".to_string()],
+            "secret",
+        )
+        .unwrap();
+        let mut mock_webhook_repo = MockWebhookRepository::new();
+        mock_webhook_repo
+            .expect_subscriptions()
+            .times(1)
+            .return_const(vec![subscription.clone()]);
+
+        let list_webhooks_use_case_handler =
+            ListWebhooksUseCaseHandler::new(Rc::new(mock_webhook_repo));
+        let res = list_webhooks_use_case_handler.execute(ListWebhooksUseCaseRequest);
+        let ids = res.iter().map(|dto| dto.id()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![subscription.id()]);
+    }
+
+    #[test]
+    fn get_webhook_deliveries_use_case_handler_execute() {
+        let subscription = WebhookSubscription::new(
+            "http://localhost/hooks",
+            &["client.created".to_string()],
+            "secret",
+        )
+        .unwrap();
+        let delivery = |subscription_id| {
+            WebhookDelivery::new(
+                subscription_id,
+                Uuid::new_v4(),
+                "client.created",
+                1,
+                Ok(200),
+                Utc::now(),
+            )
+        };
+        let deliveries = vec![delivery(subscription.id()), delivery(Uuid::new_v4())];
+
+        let mut mock_webhook_repo = MockWebhookRepository::new();
+        mock_webhook_repo
+            .expect_deliveries()
+            .times(2)
+            .return_const(deliveries);
+        let subscription_id = subscription.id();
+        mock_webhook_repo
+            .expect_subscription_by_id()
+            .returning(move |id| match id == subscription_id {
+                true => Ok(subscription.clone()),
+                false => Err("Webhook not found".to_string()),
+            });
+
+        let get_webhook_deliveries_use_case_handler =
+            GetWebhookDeliveriesUseCaseHandler::new(Rc::new(mock_webhook_repo));
+        let res = get_webhook_deliveries_use_case_handler
+            .execute(GetWebhookDeliveriesUseCaseRequest::new());
+        assert_matches!(res, Ok(deliveries) if deliveries.len() == 2);
+        let res = get_webhook_deliveries_use_case_handler
+            .execute(GetWebhookDeliveriesUseCaseRequest::new().with_subscription(subscription_id));
+        assert_matches!(res, Ok(deliveries) if deliveries.iter().all(|dto| dto.subscription_id() == subscription_id) && deliveries.len() == 1);
+        let res = get_webhook_deliveries_use_case_handler
+            .execute(GetWebhookDeliveriesUseCaseRequest::new().with_subscription(Uuid::new_v4()));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn undo_use_case_handler_execute() {
+        let before = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let mut after = before.clone();
+        after.edit("Jiro".to_string(), "Osaka".to_string());
+        let id = before.id();
+        let history = Rc::new(EditHistory::new(10));
+        history.record(ClientChange::edited(&before, &after));
+
+        let mut mock_client_repo = MockClientRepository::new();
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(after.clone()));
+        mock_client_repo
+            .expect_save()
+            .withf(|client| client.name() == "Taro" && client.location().as_str() == "Tokyo")
+            .times(1)
+            .return_const(Ok(()));
+        let mut mock_publisher = MockEventPublisher::new();
+        mock_publisher
+            .expect_publish()
+            .withf(|event| matches!(event, ClientEvent::Edited { name, .. } if name == "Taro"))
+            .times(1)
+            .return_const(());
+
+        let undo_use_case_handler = UndoUseCaseHandler::new(
+            Rc::new(mock_client_repo),
+            Rc::new(MockProjectRepository::new()),
+            Rc::new(MockRelationshipRepository::new()),
+            Rc::clone(&history),
+        )
+        .with_event_publisher(Rc::new(mock_publisher));
+        let res = undo_use_case_handler.execute(UndoUseCaseRequest);
+        assert_matches!(res, Ok(change) if change.client_id() == id);
+        assert_eq!(history.undo_count(), 0);
+        assert_eq!(
+            history.next_redo(),
+            Some(ClientChange::edited(&before, &after))
+        );
+
+        // 取り消す変更が無い
+        let res = undo_use_case_handler.execute(UndoUseCaseRequest);
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn export_and_erase_personal_data_use_case_handlers_execute() {
+        let client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let id = client.id();
+        let other = Client::new("Jiro".to_string(), "Osaka".to_string());
+        let relationship = Relationship::new(id, other.id(), RelationKind::ReferredBy).unwrap();
+        let mut project = Project::new(id, "Website", Utc::now()).unwrap();
+        project.close(Utc::now()).unwrap();
+        let created = OutboxMessage::new(ClientEvent::created(&client, Utc::now()));
+        let unrelated = OutboxMessage::new(ClientEvent::created(&other, Utc::now()));
+        let delivery = |message: &OutboxMessage| {
+            WebhookDelivery::new(
+                Uuid::new_v4(),
+                message.id(),
+                "client.created",
+                1,
+                Ok(200),
+                Utc::now(),
+            )
+        };
+
+        let mut mock_client_repo = MockClientRepository::new();
+        mock_client_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .return_const(Ok(client.clone()));
+        mock_client_repo
+            .expect_delete()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(()));
+        let mut mock_project_repo = MockProjectRepository::new();
+        mock_project_repo
+            .expect_by_client()
+            .return_const(vec![project.clone()]);
+        let mut mock_relationship_repo = MockRelationshipRepository::new();
+        mock_relationship_repo
+            .expect_by_client()
+            .return_const(vec![relationship]);
+        mock_relationship_repo
+            .expect_delete()
+            .with(predicate::eq(relationship))
+            .times(1)
+            .return_const(Ok(()));
+        let mut mock_outbox_repo = MockOutboxRepository::new();
+        mock_outbox_repo
+            .expect_all()
+            .return_const(vec![created.clone(), unrelated.clone()]);
+        // 名前と出身地を消去したイベントと墓標を保存する
+        let created_id = created.id();
+        mock_outbox_repo
+            .expect_save()
+            .withf(move |message| {
+                message.id() == created_id && !message.event().has_personal_data()
+            })
+            .times(1)
+            .return_const(());
+        mock_outbox_repo
+            .expect_save()
+            .withf(move |message| {
+                matches!(message.event(), ClientEvent::Erased { client_id, .. } if *client_id == id)
+            })
+            .times(1)
+            .return_const(());
+        let mut mock_webhook_repo = MockWebhookRepository::new();
+        mock_webhook_repo
+            .expect_deliveries()
+            .return_const(vec![delivery(&created), delivery(&unrelated)]);
+        let mut mock_idempotency_repo = MockIdempotencyRepository::new();
+        mock_idempotency_repo
+            .expect_keys_for()
+            .with(predicate::eq(id))
+            .return_const(vec!["create-1".to_string()]);
+        let admin = User::new("admin", "correct horse", Role::Admin).unwrap();
+        let audited = AuditEntry::new(&admin, "AddNoteUseCaseRequest", Some(id), Utc::now());
+        let mut mock_audit_repo = MockAuditRepository::new();
+        mock_audit_repo.expect_all().return_const(vec![
+            audited.clone(),
+            AuditEntry::new(
+                &admin,
+                "AddNoteUseCaseRequest",
+                Some(other.id()),
+                Utc::now(),
+            ),
+            AuditEntry::new(&admin, "CreateUserUseCaseRequest", None, Utc::now()),
+        ]);
+
+        let client_repo = Rc::new(mock_client_repo);
+        let project_repo = Rc::new(mock_project_repo);
+        let relationship_repo = Rc::new(mock_relationship_repo);
+        let outbox_repo = Rc::new(mock_outbox_repo);
+        let export_use_case_handler = ExportClientPersonalDataUseCaseHandler::new(
+            projections_of(std::slice::from_ref(&client)),
+            Rc::clone(&project_repo),
+            Rc::clone(&relationship_repo),
+            Rc::clone(&outbox_repo),
+            Rc::new(mock_webhook_repo),
+        )
+        .with_idempotency_repo(Rc::new(mock_idempotency_repo))
+        .with_audit_repo(Rc::new(mock_audit_repo));
+        let res = export_use_case_handler.execute(ExportClientPersonalDataUseCaseRequest::new(id));
+        assert_matches!(res, Ok(personal_data) => {
+            assert_eq!(personal_data.client(), &ClientDto::from(client.clone()));
+            assert_eq!(personal_data.projects(), &DtoList::new(vec![ProjectDto::from(project.clone())]));
+            assert_eq!(personal_data.relationships().len(), 1);
+            assert_matches!(personal_data.events().iter().map(|message| message.id()).collect::<Vec<_>>().as_slice(), [message_id] if *message_id == created.id());
+            assert_matches!(personal_data.deliveries().iter().map(|delivery| delivery.message_id()).collect::<Vec<_>>().as_slice(), [message_id] if *message_id == created.id());
+            assert_eq!(personal_data.idempotency_keys(), ["create-1"]);
+            assert_eq!(personal_data.audit_entries(), &DtoList::new(vec![AuditEntryDto::from(audited)]));
+        });
+
+        // 未完了のプロジェクトは完了にしてから消去する
+        let open = Project::new(id, "Support", Utc::now()).unwrap();
+        let open_id = open.id();
+        let mut closed = open.clone();
+        closed.close(Utc::now()).unwrap();
+        let mut sequence = Sequence::new();
+        let mut mock_project_repo = MockProjectRepository::new();
+        mock_project_repo
+            .expect_by_client()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_const(vec![project.clone(), open]);
+        mock_project_repo
+            .expect_save()
+            .withf(move |project| project.id() == open_id && !project.is_open())
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_const(());
+        mock_project_repo
+            .expect_by_client()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_const(vec![project, closed]);
+        let mut mock_sink = MockEventSink::new();
+        mock_sink
+            .expect_redact()
+            .withf(move |ids| ids == [id])
+            .times(1)
+            .return_const(Ok(2));
+        // 作成したときの名前と出身地を持つ冪等キーの記録も削除する
+        let mut mock_idempotency_repo = MockIdempotencyRepository::new();
+        mock_idempotency_repo
+            .expect_forget()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(1usize);
+        // 監査ログは記録を残して対象のIDだけを外し，消去したIDを引けるように墓標を残す
+        let mut mock_audit_repo = MockAuditRepository::new();
+        mock_audit_repo
+            .expect_redact()
+            .withf(move |ids| ids == [id])
+            .times(1)
+            .return_const(2usize);
+        let mut mock_tombstone_repo = MockTombstoneRepository::new();
+        mock_tombstone_repo
+            .expect_save()
+            .withf(move |tombstone| tombstone.client_id() == id)
+            .times(1)
+            .return_const(());
+
+        let history = Rc::new(EditHistory::new(10));
+        history.record(ClientChange::created(&client));
+        history.record(ClientChange::created(&other));
+        let erase_use_case_handler = EraseClientUseCaseHandler::new(
+            client_repo,
+            Rc::new(mock_project_repo),
+            relationship_repo,
+            outbox_repo,
+        )
+        .with_sinks(vec![Rc::new(mock_sink)])
+        .with_history(Rc::clone(&history))
+        .with_idempotency_repo(Rc::new(mock_idempotency_repo))
+        .with_audit_repo(Rc::new(mock_audit_repo))
+        .with_tombstone_repo(Rc::new(mock_tombstone_repo));
+        let res = erase_use_case_handler.execute(EraseClientUseCaseRequest::new(id));
+        assert_eq!(res, Ok(ErasureReportDto::new(id, 1, 1, 3, 2, 1)));
+        assert_eq!(history.undo_count(), 1);
+
+        // 消去済みのクライアントは見つからないのではなく消去したと答える
+        let mut mock_client_repo = MockClientRepository::new();
+        mock_client_repo
+            .expect_by_id()
+            .return_const(Err("No client found for given ID".to_string()));
+        let mut mock_tombstone_repo = MockTombstoneRepository::new();
+        mock_tombstone_repo
+            .expect_by_client()
+            .with(predicate::eq(id))
+            .return_const(Some(Tombstone::new(&client, Utc::now())));
+        let erase_use_case_handler = EraseClientUseCaseHandler::new(
+            Rc::new(mock_client_repo),
+            Rc::new(MockProjectRepository::new()),
+            Rc::new(MockRelationshipRepository::new()),
+            Rc::new(MockOutboxRepository::new()),
+        )
+        .with_tombstone_repo(Rc::new(mock_tombstone_repo));
+        let res = erase_use_case_handler.execute(EraseClientUseCaseRequest::new(id));
+        assert_matches!(res, Err(err) if err.contains("was erased"));
+    }
+
+    #[test]
+    fn anonymize_clients_use_case_handler_execute() {
+        let clients = vec![
+            Client::new("山田 太郎".to_string(), "東京都渋谷区".to_string()),
+            Client::new("John Doe".to_string(), "London".to_string()),
+        ];
+        let reversed = clients.iter().rev().cloned().collect::<Vec<_>>();
+        let anonymize = |clients: &[Client]| {
+            AnonymizeClientsUseCaseHandler::new(projections_of(clients), TenantId::default())
+                .execute(AnonymizeClientsUseCaseRequest::new(1))
+        };
+
+        // 保存順に依らず同じ名前と出身地になる
+        let names_and_locations = |export: ClientExportDto| {
+            export
+                .clients()
+                .iter()
+                .map(|client| (client.name().to_string(), client.location().to_string()))
+                .collect::<Vec<_>>()
+        };
+        let first = anonymize(&clients).map(names_and_locations).unwrap();
+        let second = anonymize(&reversed).map(names_and_locations).unwrap();
+        assert_eq!(first, second);
+        assert!(first
+            .iter()
+            .all(|(name, _)| clients.iter().all(|client| client.name() != name)));
+        let mut locations = first
+            .into_iter()
+            .map(|(_, location)| location)
+            .collect::<Vec<_>>();
+        locations.sort();
+        assert_eq!(locations, vec!["London", "東京都"]);
+    }
+
+    #[test]
+    fn login_and_create_user_use_case_handlers_execute() {
+        let admin = User::new("admin", "correct horse", Role::Admin).unwrap();
+        let mut mock_user_repo = MockUserRepository::new();
+        mock_user_repo
+            .expect_by_username()
+            .with(predicate::eq("admin"))
+            .return_const(Some(admin.clone()));
+        mock_user_repo
+            .expect_by_username()
+            .return_const(None::<User>);
+        mock_user_repo
+            .expect_save()
+            .withf(|user| user.username() == "hanako" && user.role() == Role::Editor)
+            .times(1)
+            .return_const(());
+        let user_repo = Rc::new(mock_user_repo);
+        let session = Rc::new(Session::new());
+
+        let login_use_case_handler =
+            LoginUseCaseHandler::new(Rc::clone(&user_repo), Rc::clone(&session));
+        let res = login_use_case_handler.execute(LoginUseCaseRequest::new(
+            "admin".to_string(),
+            "wrong password".to_string(),
+        ));
+        assert_matches!(res, Err(err) if err == "Invalid username or password");
+        assert_eq!(session.current_user(), None);
+        let res = login_use_case_handler.execute(LoginUseCaseRequest::new(
+            "admin".to_string(),
+            "correct horse".to_string(),
+        ));
+        assert_matches!(res, Ok(user_dto) if user_dto.id() == admin.id());
+        assert_eq!(session.current_user(), Some(admin));
+
+        let create_user_use_case_handler = CreateUserUseCaseHandler::new(Rc::clone(&user_repo));
+        let res = create_user_use_case_handler.execute(CreateUserUseCaseRequest::new(
+            "hanako".to_string(),
+            "correct horse".to_string(),
+            "editor".to_string(),
+        ));
+        assert_matches!(res, Ok(user_dto) if user_dto.role() == Role::Editor);
+        // 既存のユーザー名，未定義のロール，短いパスワード
+        for (username, password, role) in [
+            ("admin", "correct horse", "viewer"),
+            ("jiro", "correct horse", "owner"),
+            ("jiro", "short", "viewer"),
+        ] {
+            let res = create_user_use_case_handler.execute(CreateUserUseCaseRequest::new(
+                username.to_string(),
+                password.to_string(),
+                role.to_string(),
+            ));
+            assert_matches!(res, Err(_));
+        }
     }
 }
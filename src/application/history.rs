@@ -0,0 +1,519 @@
+use crate::domain::{Client, Relationship};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use uuid::Uuid;
+
+// -------------------------------------------------------------------------------------------------
+
+/// 取り消しと再実行ができるクライアントへの変更
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientChange {
+    /// 関係は削除を取り消したときに復元するもの
+    Created {
+        client: Client,
+        relationships: Vec<Relationship>,
+    },
+    Edited {
+        client_id: Uuid,
+        before: (String, String),
+        after: (String, String),
+    },
+    Deleted {
+        client: Client,
+        relationships: Vec<Relationship>,
+    },
+    /// 統合先の変更前後と，統合して削除したクライアントとその関係．
+    /// movedは統合したクライアントの関係を統合先に付け替えて追加したもの
+    Merged {
+        before: Client,
+        after: Client,
+        merged: Vec<(Client, Vec<Relationship>)>,
+        moved: Vec<Relationship>,
+    },
+    /// 統合の取り消し．付け替えた関係を外して統合先を元に戻し，削除したクライアントを復元する
+    Unmerged {
+        before: Client,
+        after: Client,
+        restored: Vec<(Client, Vec<Relationship>)>,
+        moved: Vec<Relationship>,
+    },
+}
+
+impl ClientChange {
+    pub fn created(client: &Client) -> Self {
+        ClientChange::Created {
+            client: client.clone(),
+            relationships: Vec::new(),
+        }
+    }
+    pub fn edited(before: &Client, after: &Client) -> Self {
+        ClientChange::Edited {
+            client_id: after.id(),
+            before: (before.name().to_string(), before.location().to_string()),
+            after: (after.name().to_string(), after.location().to_string()),
+        }
+    }
+    pub fn deleted(client: &Client, relationships: Vec<Relationship>) -> Self {
+        ClientChange::Deleted {
+            client: client.clone(),
+            relationships,
+        }
+    }
+    pub fn merged(
+        before: &Client,
+        after: &Client,
+        merged: Vec<(Client, Vec<Relationship>)>,
+    ) -> Self {
+        ClientChange::Merged {
+            before: before.clone(),
+            after: after.clone(),
+            merged,
+            moved: Vec::new(),
+        }
+    }
+    /// 変更の対象か，統合したクライアントか，復元する関係に含まれるクライアント
+    pub fn involves(&self, client_id: Uuid) -> bool {
+        let clients = match self {
+            ClientChange::Created {
+                client,
+                relationships,
+            }
+            | ClientChange::Deleted {
+                client,
+                relationships,
+            } => vec![(client, relationships)],
+            ClientChange::Edited { .. } => Vec::new(),
+            ClientChange::Merged {
+                merged: clients, ..
+            }
+            | ClientChange::Unmerged {
+                restored: clients, ..
+            } => clients
+                .iter()
+                .map(|(client, relationships)| (client, relationships))
+                .collect(),
+        };
+        self.client_id() == client_id
+            || clients.into_iter().any(|(client, relationships)| {
+                client.id() == client_id
+                    || relationships
+                        .iter()
+                        .any(|relationship| relationship.involves(client_id))
+            })
+    }
+    pub fn client_id(&self) -> Uuid {
+        match self {
+            ClientChange::Created { client, .. } | ClientChange::Deleted { client, .. } => {
+                client.id()
+            }
+            ClientChange::Edited { client_id, .. } => *client_id,
+            ClientChange::Merged { after, .. } | ClientChange::Unmerged { after, .. } => after.id(),
+        }
+    }
+    /// 適用すると元の状態に戻る変更
+    pub fn inverse(&self) -> Self {
+        match self.clone() {
+            ClientChange::Created {
+                client,
+                relationships,
+            } => ClientChange::Deleted {
+                client,
+                relationships,
+            },
+            ClientChange::Edited {
+                client_id,
+                before,
+                after,
+            } => ClientChange::Edited {
+                client_id,
+                before: after,
+                after: before,
+            },
+            ClientChange::Deleted {
+                client,
+                relationships,
+            } => ClientChange::Created {
+                client,
+                relationships,
+            },
+            ClientChange::Merged {
+                before,
+                after,
+                merged,
+                moved,
+            } => ClientChange::Unmerged {
+                before: after,
+                after: before,
+                restored: merged,
+                moved,
+            },
+            ClientChange::Unmerged {
+                before,
+                after,
+                restored,
+                moved,
+            } => ClientChange::Merged {
+                before: after,
+                after: before,
+                merged: restored,
+                moved,
+            },
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// セッション中の変更の履歴．取り消し側は上限を超えると古いものから捨てる
+pub struct EditHistory {
+    undo_stack: RefCell<VecDeque<ClientChange>>,
+    redo_stack: RefCell<Vec<ClientChange>>,
+    capacity: usize,
+}
+
+impl EditHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            undo_stack: RefCell::new(VecDeque::new()),
+            redo_stack: RefCell::new(Vec::new()),
+            capacity,
+        }
+    }
+    /// 新しい変更を記録すると再実行できる変更は無くなる
+    pub fn record(&self, change: ClientChange) {
+        let mut undo_stack = self.undo_stack.borrow_mut();
+        undo_stack.push_back(change);
+        while undo_stack.len() > self.capacity {
+            undo_stack.pop_front();
+        }
+        self.redo_stack.borrow_mut().clear();
+    }
+    /// 次に取り消す変更
+    pub fn next_undo(&self) -> Option<ClientChange> {
+        self.undo_stack.borrow().back().cloned()
+    }
+    /// 次に再実行する変更
+    pub fn next_redo(&self) -> Option<ClientChange> {
+        self.redo_stack.borrow().last().cloned()
+    }
+    pub fn undo_count(&self) -> usize {
+        self.undo_stack.borrow().len()
+    }
+    pub fn redo_count(&self) -> usize {
+        self.redo_stack.borrow().len()
+    }
+    /// 取り消しに成功した変更を再実行側へ移す．appliedは実際に適用した逆の変更
+    pub fn undone(&self, applied: &ClientChange) {
+        self.undo_stack.borrow_mut().pop_back();
+        self.redo_stack.borrow_mut().push(applied.inverse());
+    }
+    /// 再実行に成功した変更を取り消し側へ戻す
+    pub fn redone(&self, applied: ClientChange) {
+        self.redo_stack.borrow_mut().pop();
+        let mut undo_stack = self.undo_stack.borrow_mut();
+        undo_stack.push_back(applied);
+        while undo_stack.len() > self.capacity {
+            undo_stack.pop_front();
+        }
+    }
+    /// クライアントに関わる変更を取り消しと再実行の両方から除き，除いた数を返す
+    pub fn forget(&self, client_id: Uuid) -> usize {
+        let mut undo_stack = self.undo_stack.borrow_mut();
+        let mut redo_stack = self.redo_stack.borrow_mut();
+        let count = undo_stack.len() + redo_stack.len();
+        undo_stack.retain(|change| !change.involves(client_id));
+        redo_stack.retain(|change| !change.involves(client_id));
+        count - undo_stack.len() - redo_stack.len()
+    }
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(50)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ClientChange, EditHistory};
+    use crate::application::handlers_impl::{
+        CreateClientUseCaseHandler, DeleteClientUseCaseHandler, EditClientUseCaseHandler,
+        GetEditHistoryUseCaseHandler, MergeClientsUseCaseHandler, RedoUseCaseHandler,
+        UndoUseCaseHandler,
+    };
+    use crate::application::requests::{
+        CreateClientUseCaseRequest, DeleteClientUseCaseRequest, EditClientUseCaseRequest,
+        GetEditHistoryUseCaseRequest, MergeClientsUseCaseRequest, RedoUseCaseRequest,
+        UndoUseCaseRequest,
+    };
+    use crate::application::Mediator;
+    use crate::domain::{
+        projects_of_client, Client, ClientRepository, Project, ProjectRepository, RelationKind,
+        Relationship, RelationshipRepository,
+    };
+    use crate::infrastructure::{
+        InMemoryClientRepository, InMemoryProjectRepository, InMemoryRelationshipRepository,
+    };
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+    use std::rc::Rc;
+
+    #[test]
+    fn bounded_undo_and_redo_stacks() {
+        let clients = (0..3)
+            .map(|i| Client::new(format!("Client{}", i), "Tokyo".to_string()))
+            .collect::<Vec<_>>();
+        let history = EditHistory::new(2);
+        for client in clients.iter() {
+            history.record(ClientChange::created(client));
+        }
+        // 上限を超えた最も古い変更は捨てる
+        assert_eq!(history.undo_count(), 2);
+        assert_eq!(
+            history.next_undo(),
+            Some(ClientChange::created(&clients[2]))
+        );
+
+        let applied = history.next_undo().unwrap().inverse();
+        history.undone(&applied);
+        assert_eq!(history.undo_count(), 1);
+        assert_eq!(
+            history.next_redo(),
+            Some(ClientChange::created(&clients[2]))
+        );
+
+        let applied = history.next_redo().unwrap();
+        history.redone(applied);
+        assert_eq!((history.undo_count(), history.redo_count()), (2, 0));
+
+        // 新しい変更を記録すると再実行できなくなる
+        let applied = history.next_undo().unwrap().inverse();
+        history.undone(&applied);
+        history.record(ClientChange::created(&clients[0]));
+        assert_eq!(history.next_redo(), None);
+    }
+
+    #[test]
+    fn inverse_of_changes() {
+        let before = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let mut after = before.clone();
+        after.edit("Jiro".to_string(), "Osaka".to_string());
+
+        let edited = ClientChange::edited(&before, &after);
+        assert_eq!(edited.inverse(), ClientChange::edited(&after, &before));
+        assert_eq!(edited.inverse().inverse(), edited);
+
+        let deleted = ClientChange::deleted(&before, Vec::new());
+        assert_eq!(deleted.inverse(), ClientChange::created(&before));
+        assert_eq!(deleted.client_id(), before.id());
+    }
+
+    #[test]
+    fn forget_changes_of_client() {
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let jiro = Client::new("Jiro".to_string(), "Osaka".to_string());
+        let relationship =
+            Relationship::new(jiro.id(), taro.id(), RelationKind::SubsidiaryOf).unwrap();
+        let history = EditHistory::new(10);
+        history.record(ClientChange::created(&taro));
+        history.record(ClientChange::deleted(&jiro, vec![relationship]));
+        history.record(ClientChange::edited(&jiro, &jiro));
+        let applied = history.next_undo().unwrap().inverse();
+        history.undone(&applied);
+
+        // 関係に含まれるだけの変更も除く
+        assert_eq!(history.forget(taro.id()), 2);
+        assert_eq!((history.undo_count(), history.redo_count()), (0, 1));
+        assert_eq!(history.forget(taro.id()), 0);
+    }
+
+    #[test]
+    fn undo_and_redo_create_edit_delete() {
+        let client_repo = Rc::new(InMemoryClientRepository::new());
+        let project_repo = Rc::new(InMemoryProjectRepository::new());
+        let relationship_repo = Rc::new(InMemoryRelationshipRepository::new());
+        let history = Rc::new(EditHistory::new(10));
+        let mediator = Mediator::new()
+            .with_handler(
+                CreateClientUseCaseHandler::new(Rc::clone(&client_repo))
+                    .with_history(Rc::clone(&history)),
+            )
+            .with_handler(
+                EditClientUseCaseHandler::new(Rc::clone(&client_repo))
+                    .with_history(Rc::clone(&history)),
+            )
+            .with_handler(
+                DeleteClientUseCaseHandler::new(
+                    Rc::clone(&client_repo),
+                    Rc::clone(&project_repo),
+                    Rc::clone(&relationship_repo),
+                )
+                .with_history(Rc::clone(&history)),
+            )
+            .with_handler(UndoUseCaseHandler::new(
+                Rc::clone(&client_repo),
+                Rc::clone(&project_repo),
+                Rc::clone(&relationship_repo),
+                Rc::clone(&history),
+            ))
+            .with_handler(RedoUseCaseHandler::new(
+                Rc::clone(&client_repo),
+                Rc::clone(&project_repo),
+                Rc::clone(&relationship_repo),
+                Rc::clone(&history),
+            ))
+            .with_handler(GetEditHistoryUseCaseHandler::new(Rc::clone(&history)));
+
+        let res = mediator.send(UndoUseCaseRequest);
+        assert_matches!(res, Ok(Err(err)) if err == "Nothing to undo");
+
+        let res = mediator.send(CreateClientUseCaseRequest::new(
+            "Taro".to_string(),
+            "Tokyo".to_string(),
+        ));
+        assert_matches!(res, Ok(Ok(_)));
+        let id = client_repo.all()[0].id();
+        let res = mediator.send(EditClientUseCaseRequest::new(
+            id,
+            "Jiro".to_string(),
+            "Osaka".to_string(),
+        ));
+        assert_matches!(res, Ok(Ok(_)));
+
+        let other = Client::new("Hanako".to_string(), "Tokyo".to_string());
+        client_repo.save(other.clone()).unwrap();
+        let relationship = Relationship::new(id, other.id(), RelationKind::SubsidiaryOf).unwrap();
+        relationship_repo.save(relationship);
+        let res = mediator.send(DeleteClientUseCaseRequest::new(id));
+        assert_matches!(res, Ok(Ok(())));
+        assert!(relationship_repo.all().is_empty());
+
+        // 取り消す前に適用する変更を確認できる
+        let history_dto = mediator
+            .send(GetEditHistoryUseCaseRequest)
+            .and_then(|res| res)
+            .unwrap();
+        assert_matches!(
+            history_dto.undo().map(|change| change.change()),
+            Some(ClientChange::Created { client, relationships })
+                if client.id() == id && relationships == &vec![relationship]
+        );
+
+        // 削除を取り消すと関係も戻る
+        let res = mediator.send(UndoUseCaseRequest);
+        assert_matches!(res, Ok(Ok(_)));
+        assert_matches!(client_repo.by_id(id), Ok(client) if client.name() == "Jiro");
+        assert_eq!(relationship_repo.all(), vec![relationship]);
+
+        let res = mediator.send(UndoUseCaseRequest);
+        assert_matches!(res, Ok(Ok(_)));
+        assert_matches!(client_repo.by_id(id), Ok(client) if client.name() == "Taro");
+
+        let res = mediator.send(UndoUseCaseRequest);
+        assert_matches!(res, Ok(Ok(_)));
+        assert_matches!(client_repo.by_id(id), Err(_));
+
+        let res = mediator.send(RedoUseCaseRequest);
+        assert_matches!(res, Ok(Ok(_)));
+        assert_matches!(client_repo.by_id(id), Ok(client) if client.name() == "Taro");
+        let res = mediator.send(RedoUseCaseRequest);
+        assert_matches!(res, Ok(Ok(_)));
+        assert_matches!(client_repo.by_id(id), Ok(client) if client.name() == "Jiro");
+        assert_eq!(history.redo_count(), 1);
+    }
+
+    #[test]
+    fn undo_and_redo_merge() {
+        let client_repo = Rc::new(InMemoryClientRepository::new());
+        let project_repo = Rc::new(InMemoryProjectRepository::new());
+        let relationship_repo = Rc::new(InMemoryRelationshipRepository::new());
+        let history = Rc::new(EditHistory::new(10));
+        let mediator = Mediator::new()
+            .with_handler(
+                MergeClientsUseCaseHandler::new(
+                    Rc::clone(&client_repo),
+                    Rc::clone(&project_repo),
+                    Rc::clone(&relationship_repo),
+                )
+                .with_history(Rc::clone(&history)),
+            )
+            .with_handler(UndoUseCaseHandler::new(
+                Rc::clone(&client_repo),
+                Rc::clone(&project_repo),
+                Rc::clone(&relationship_repo),
+                Rc::clone(&history),
+            ))
+            .with_handler(RedoUseCaseHandler::new(
+                Rc::clone(&client_repo),
+                Rc::clone(&project_repo),
+                Rc::clone(&relationship_repo),
+                Rc::clone(&history),
+            ));
+
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let duplicate = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let parent = Client::new("Hanako".to_string(), "Osaka".to_string());
+        for client in [&taro, &duplicate, &parent] {
+            client_repo.save(client.clone()).unwrap();
+        }
+        let relationship =
+            Relationship::new(duplicate.id(), parent.id(), RelationKind::SubsidiaryOf).unwrap();
+        relationship_repo.save(relationship);
+        let project = Project::new(duplicate.id(), "Website", Utc::now()).unwrap();
+        project_repo.save(project.clone());
+        let moved = Relationship::new(taro.id(), parent.id(), RelationKind::SubsidiaryOf).unwrap();
+
+        // 同じクライアントを重ねて指定しても何も変更しない
+        let res = mediator.send(MergeClientsUseCaseRequest::new(
+            taro.id(),
+            vec![duplicate.id(), duplicate.id()],
+        ));
+        assert_matches!(res, Ok(Err(_)));
+        assert_eq!(client_repo.all().len(), 3);
+
+        // 未完了のプロジェクトがあっても統合でき，関係は統合先に付け替える
+        let res = mediator.send(MergeClientsUseCaseRequest::new(
+            taro.id(),
+            vec![duplicate.id()],
+        ));
+        assert_matches!(res, Ok(Ok(_)));
+        assert_eq!(client_repo.all().len(), 2);
+        assert_eq!(relationship_repo.all(), vec![moved]);
+        let merged = client_repo.by_id(taro.id()).unwrap();
+        assert_eq!(
+            projects_of_client(&merged, project_repo.as_ref()),
+            vec![project.clone()]
+        );
+
+        // 取り消すと統合先が元に戻り，統合したクライアントと関係が復元される
+        let res = mediator.send(UndoUseCaseRequest);
+        assert_matches!(res, Ok(Ok(_)));
+        assert_matches!(client_repo.by_id(taro.id()), Ok(client) if client.aliases().is_empty());
+        assert_matches!(client_repo.by_id(duplicate.id()), Ok(client) if client.id() == duplicate.id());
+        assert_eq!(relationship_repo.all(), vec![relationship]);
+        let restored = client_repo.by_id(duplicate.id()).unwrap();
+        assert_eq!(
+            projects_of_client(&restored, project_repo.as_ref()),
+            vec![project]
+        );
+
+        let res = mediator.send(RedoUseCaseRequest);
+        assert_matches!(res, Ok(Ok(_)));
+        assert_matches!(
+            client_repo.by_id(duplicate.id()),
+            Ok(client) if client.id() == taro.id()
+        );
+        assert_eq!(relationship_repo.all(), vec![moved]);
+
+        // 統合先との関係は付け替えると自身との関係になるため外す
+        let res = mediator.send(MergeClientsUseCaseRequest::new(
+            taro.id(),
+            vec![parent.id()],
+        ));
+        assert_matches!(res, Ok(Ok(_)));
+        assert!(relationship_repo.all().is_empty());
+        let res = mediator.send(UndoUseCaseRequest);
+        assert_matches!(res, Ok(Ok(_)));
+        assert_eq!(relationship_repo.all(), vec![moved]);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::application::dtos::DtoList;
+use crate::application::{Handler, UnitOfWork};
+use crate::domain::Permission;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+// -------------------------------------------------------------------------------------------------
+// Request
+
+/// 書き込みを伴うコマンドか，読み取りのみのクエリか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    Command,
+    Query,
+}
+
+/// メディエーターに送信できるリクエスト．リトライのために複製できる必要がある
+pub trait Request: Clone + 'static {
+    type Output: Outcome + 'static;
+    const NAME: &'static str;
+    const KIND: RequestKind;
+    /// 実行に必要な権限．Noneはログインせずに実行できることを表す
+    const PERMISSION: Option<Permission> = match Self::KIND {
+        RequestKind::Command => Some(Permission::Write),
+        RequestKind::Query => Some(Permission::Read),
+    };
+    /// ハンドラーに渡す前の入力検証
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+    /// ログに添える項目．個人データを含めないようにIDなどに限る
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
+
+/// ハンドラーの出力が失敗を表すか
+pub trait Outcome {
+    fn error(&self) -> Option<&str>;
+}
+
+impl<T> Outcome for Result<T, String> {
+    fn error(&self) -> Option<&str> {
+        self.as_ref().err().map(String::as_str)
+    }
+}
+
+impl<T> Outcome for DtoList<T> {
+    fn error(&self) -> Option<&str> {
+        None
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Middleware
+
+/// ミドルウェアから見えるリクエストの情報
+pub struct RequestContext {
+    name: &'static str,
+    kind: RequestKind,
+    permission: Option<Permission>,
+    validation: Result<(), String>,
+    fields: Vec<(&'static str, String)>,
+}
+
+impl RequestContext {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+    pub fn kind(&self) -> RequestKind {
+        self.kind
+    }
+    pub fn permission(&self) -> Option<Permission> {
+        self.permission
+    }
+    pub fn validation(&self) -> &Result<(), String> {
+        &self.validation
+    }
+    pub fn fields(&self) -> &[(&'static str, String)] {
+        &self.fields
+    }
+}
+
+/// 型を消したハンドラーの出力
+pub struct Reply {
+    value: Box<dyn Any>,
+    error: Option<String>,
+}
+
+impl Reply {
+    /// ハンドラーが失敗を返したときのエラー
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+/// 次のミドルウェア(最後はハンドラー)を呼び出す．Errはミドルウェアによる拒否を表す
+pub type Next<'a> = &'a dyn Fn() -> Result<Reply, String>;
+
+pub trait Middleware {
+    fn handle(&self, context: &RequestContext, next: Next<'_>) -> Result<Reply, String>;
+}
+
+// -------------------------------------------------------------------------------------------------
+// Mediator
+
+type BoxedHandler<R> = Box<dyn Fn(R) -> <R as Request>::Output>;
+
+/// リクエストの型ごとに登録されたハンドラーへ，ミドルウェアを通して振り分ける．
+/// コマンドのハンドラーは1回の実行ごとに作業単位の中で実行する
+#[derive(Default)]
+pub struct Mediator {
+    handlers: HashMap<TypeId, Box<dyn Any>>,
+    middlewares: Vec<Box<dyn Middleware>>,
+    unit_of_work: UnitOfWork,
+}
+
+impl Mediator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_handler<H>(mut self, handler: H) -> Self
+    where
+        H: Handler + 'static,
+        H::Request: Request<Output = H::Output>,
+    {
+        let boxed: BoxedHandler<H::Request> = Box::new(move |request| handler.execute(request));
+        self.handlers
+            .insert(TypeId::of::<H::Request>(), Box::new(boxed));
+        self
+    }
+    /// 先に追加したものほど外側で実行する
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+    pub fn with_unit_of_work(mut self, unit_of_work: UnitOfWork) -> Self {
+        self.unit_of_work = unit_of_work;
+        self
+    }
+    /// Errはハンドラーが未登録か，ミドルウェアに拒否されたことを表す
+    pub fn send<R: Request>(&self, request: R) -> Result<R::Output, String> {
+        let handler = self
+            .handlers
+            .get(&TypeId::of::<R>())
+            .and_then(|handler| handler.downcast_ref::<BoxedHandler<R>>())
+            .ok_or_else(|| format!("No handler registered for {}", R::NAME))?;
+        let context = RequestContext {
+            name: R::NAME,
+            kind: R::KIND,
+            permission: R::PERMISSION,
+            validation: request.validate(),
+            fields: request.fields(),
+        };
+        let call_handler = || {
+            let output = match R::KIND {
+                RequestKind::Command => self.unit_of_work.run(|| handler(request.clone())),
+                RequestKind::Query => handler(request.clone()),
+            };
+            let error = output.error().map(str::to_string);
+            Ok(Reply {
+                value: Box::new(output),
+                error,
+            })
+        };
+        let reply = self.dispatch(0, &context, &call_handler)?;
+        Ok(*reply
+            .value
+            .downcast::<R::Output>()
+            .expect("reply type must match the request"))
+    }
+    fn dispatch(
+        &self,
+        index: usize,
+        context: &RequestContext,
+        call_handler: Next<'_>,
+    ) -> Result<Reply, String> {
+        match self.middlewares.get(index) {
+            Some(middleware) => {
+                middleware.handle(context, &|| self.dispatch(index + 1, context, call_handler))
+            }
+            None => call_handler(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Mediator, Middleware, Next, Reply, RequestContext};
+    use crate::application::handlers_impl::{DeleteNoteUseCaseHandler, GetClientUseCaseHandler};
+    use crate::application::projections::ClientProjections;
+    use crate::application::requests::{
+        DeleteNoteUseCaseRequest, GetClientUseCaseRequest, GetTagCountsUseCaseRequest,
+    };
+    use crate::application::UnitOfWork;
+    use crate::domain::repositories::{MockClientRepository, MockTransactional};
+    use crate::domain::Client;
+    use assert_matches::assert_matches;
+    use fake::{Fake, Faker};
+    use mockall::predicate;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct RecordingMiddleware {
+        label: &'static str,
+        records: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Middleware for RecordingMiddleware {
+        fn handle(&self, context: &RequestContext, next: Next<'_>) -> Result<Reply, String> {
+            self.records
+                .borrow_mut()
+                .push(format!("{} before {}", self.label, context.name()));
+            let reply = next();
+            self.records
+                .borrow_mut()
+                .push(format!("{} after {}", self.label, context.name()));
+            reply
+        }
+    }
+
+    #[test]
+    fn send_through_middlewares() {
+        let client = Client::new(Faker.fake(), Faker.fake());
+        let id = client.id();
+        let projections = ClientProjections::new();
+        projections.rebuild(&[client]);
+        let records = Rc::new(RefCell::new(Vec::new()));
+
+        let mediator = Mediator::new()
+            .with_handler(GetClientUseCaseHandler::new(Rc::new(projections)))
+            .with_middleware(RecordingMiddleware {
+                label: "outer",
+                records: Rc::clone(&records),
+            })
+            .with_middleware(RecordingMiddleware {
+                label: "inner",
+                records: Rc::clone(&records),
+            });
+
+        let res = mediator.send(GetClientUseCaseRequest::new(id));
+        assert_matches!(res, Ok(Ok(client_dto)) if client_dto.id() == id);
+        assert_eq!(
+            *records.borrow(),
+            vec![
+                "outer before GetClientUseCaseRequest",
+                "inner before GetClientUseCaseRequest",
+                "inner after GetClientUseCaseRequest",
+                "outer after GetClientUseCaseRequest",
+            ]
+        );
+
+        // 登録されていないリクエスト
+        let res = mediator.send(GetTagCountsUseCaseRequest);
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn run_commands_in_unit_of_work() {
+        let client = Client::new(Faker.fake(), Faker.fake());
+        let id = client.id();
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(id))
+            .times(1)
+            .return_const(Ok(client.clone()));
+        let projections = ClientProjections::new();
+        projections.rebuild(&[client]);
+        // 失敗したコマンドのみ取り消し，クエリは作業単位を開始しない
+        let mut mock_participant = MockTransactional::new();
+        mock_participant.expect_begin().times(1).return_const(());
+        mock_participant.expect_rollback().times(1).return_const(());
+        mock_participant.expect_commit().never();
+        let mock_repo = Rc::new(mock_repo);
+
+        let mediator = Mediator::new()
+            .with_unit_of_work(UnitOfWork::new().with_participant(Rc::new(mock_participant)))
+            .with_handler(GetClientUseCaseHandler::new(Rc::new(projections)))
+            .with_handler(DeleteNoteUseCaseHandler::new(mock_repo));
+
+        let res = mediator.send(GetClientUseCaseRequest::new(id));
+        assert_matches!(res, Ok(Ok(_)));
+        let res = mediator.send(DeleteNoteUseCaseRequest::new(id, Faker.fake()));
+        assert_matches!(res, Ok(Err(_)));
+    }
+}
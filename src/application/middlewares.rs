@@ -0,0 +1,412 @@
+use crate::application::mediator::{Middleware, Next, Reply, RequestContext, RequestKind};
+use crate::domain::{ClientRepository, TenantId};
+use crate::telemetry::{Level, MetricsRegistry, Tracer, REQUEST_TARGET};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Instant;
+
+// -------------------------------------------------------------------------------------------------
+
+/// リクエストの開始と結果を出力する
+pub struct LoggingMiddleware {
+    sink: Box<dyn Fn(String)>,
+}
+
+impl LoggingMiddleware {
+    pub fn new<F: Fn(String) + 'static>(sink: F) -> Self {
+        Self {
+            sink: Box::new(sink),
+        }
+    }
+}
+
+impl Middleware for LoggingMiddleware {
+    fn handle(&self, context: &RequestContext, next: Next<'_>) -> Result<Reply, String> {
+        (self.sink)(format!("-> {}", context.name()));
+        let result = next();
+        let message = match &result {
+            Ok(reply) => match reply.error() {
+                Some(error) => format!("<- {} failed: {}", context.name(), error),
+                None => format!("<- {} ok", context.name()),
+            },
+            Err(reason) => format!("<- {} rejected: {}", context.name(), reason),
+        };
+        (self.sink)(message);
+        result
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// リクエストの処理時間を出力する
+pub struct TimingMiddleware {
+    sink: Box<dyn Fn(String)>,
+}
+
+impl TimingMiddleware {
+    pub fn new<F: Fn(String) + 'static>(sink: F) -> Self {
+        Self {
+            sink: Box::new(sink),
+        }
+    }
+}
+
+impl Middleware for TimingMiddleware {
+    fn handle(&self, context: &RequestContext, next: Next<'_>) -> Result<Reply, String> {
+        let started_at = Instant::now();
+        let result = next();
+        (self.sink)(format!(
+            "{} took {:.3}ms",
+            context.name(),
+            started_at.elapsed().as_secs_f64() * 1000.0
+        ));
+        result
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// リクエストごとにスパンを開き，内側のリポジトリの呼び出しをその子として記録する．
+/// 個人データを含みうるエラーの内容は記録せず，結果の種類だけを残す
+pub struct TracingMiddleware {
+    tracer: Rc<Tracer>,
+}
+
+impl TracingMiddleware {
+    pub fn new(tracer: Rc<Tracer>) -> Self {
+        Self { tracer }
+    }
+}
+
+impl Middleware for TracingMiddleware {
+    fn handle(&self, context: &RequestContext, next: Next<'_>) -> Result<Reply, String> {
+        let mut span = self.tracer.span(
+            Level::Info,
+            REQUEST_TARGET,
+            context.name(),
+            context.fields().to_vec(),
+        );
+        let result = next();
+        let outcome = match &result {
+            Ok(reply) if reply.error().is_none() => "ok",
+            Ok(_) => "failed",
+            Err(_) => "rejected",
+        };
+        span.record_str("outcome", outcome);
+        if outcome != "ok" {
+            span.raise_to(Level::Warn);
+        }
+        result
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// データを変更しうるコマンドを処理するたびに，テナントのクライアント数のゲージを更新する
+pub struct ClientCountMiddleware {
+    metrics: Arc<MetricsRegistry>,
+    client_repo: Rc<dyn ClientRepository>,
+    tenant: TenantId,
+}
+
+impl ClientCountMiddleware {
+    /// 最初のコマンドより前に取得されても値があるように，作成時にも数える
+    pub fn new(
+        metrics: Arc<MetricsRegistry>,
+        client_repo: Rc<dyn ClientRepository>,
+        tenant: TenantId,
+    ) -> Self {
+        let middleware = Self {
+            metrics,
+            client_repo,
+            tenant,
+        };
+        middleware.refresh();
+        middleware
+    }
+    fn refresh(&self) {
+        self.metrics
+            .set_client_count(&self.tenant.to_string(), self.client_repo.all().len());
+    }
+}
+
+impl Middleware for ClientCountMiddleware {
+    fn handle(&self, context: &RequestContext, next: Next<'_>) -> Result<Reply, String> {
+        let result = next();
+        if context.kind() == RequestKind::Command {
+            self.refresh();
+        }
+        result
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 入力検証に失敗したリクエストをハンドラーに渡さない
+pub struct ValidationMiddleware;
+
+impl Middleware for ValidationMiddleware {
+    fn handle(&self, context: &RequestContext, next: Next<'_>) -> Result<Reply, String> {
+        context.validation().clone()?;
+        next()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+pub type Policy = dyn Fn(&RequestContext) -> Result<(), String>;
+
+/// ポリシーが許可したリクエストのみハンドラーに渡す
+pub struct AuthorizationMiddleware {
+    policy: Box<Policy>,
+}
+
+impl AuthorizationMiddleware {
+    pub fn new<F: Fn(&RequestContext) -> Result<(), String> + 'static>(policy: F) -> Self {
+        Self {
+            policy: Box::new(policy),
+        }
+    }
+}
+
+impl Middleware for AuthorizationMiddleware {
+    fn handle(&self, context: &RequestContext, next: Next<'_>) -> Result<Reply, String> {
+        (self.policy)(context)?;
+        next()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// ハンドラーが失敗を返したときに最大`max_retries`回まで再実行する
+pub struct RetryMiddleware {
+    max_retries: usize,
+}
+
+impl RetryMiddleware {
+    pub fn new(max_retries: usize) -> Self {
+        Self { max_retries }
+    }
+}
+
+impl Middleware for RetryMiddleware {
+    fn handle(&self, _: &RequestContext, next: Next<'_>) -> Result<Reply, String> {
+        let mut result = next();
+        for _ in 0..self.max_retries {
+            match &result {
+                Ok(reply) if reply.error().is_some() => result = next(),
+                _ => break,
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        AuthorizationMiddleware, ClientCountMiddleware, LoggingMiddleware, RetryMiddleware,
+        TimingMiddleware, TracingMiddleware, ValidationMiddleware,
+    };
+    use crate::application::handlers_impl::{
+        CreateClientUseCaseHandler, GetAllClientUseCaseHandler, GetClientUseCaseHandler,
+    };
+    use crate::application::mediator::{Mediator, RequestKind};
+    use crate::application::projections::ClientProjections;
+    use crate::application::requests::{
+        CreateClientUseCaseRequest, GetAllClientUseCaseRequest, GetClientUseCaseRequest,
+    };
+    use crate::domain::repositories::MockClientRepository;
+    use crate::domain::{Client, TenantId, UniquenessPolicy};
+    use crate::telemetry::{Level, LogFormat, MetricsRegistry, Tracer};
+    use assert_matches::assert_matches;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    #[test]
+    fn logging_and_timing() {
+        let logs = Rc::new(RefCell::new(Vec::new()));
+
+        let (logging_logs, timing_logs) = (Rc::clone(&logs), Rc::clone(&logs));
+        let mediator = Mediator::new()
+            .with_handler(GetAllClientUseCaseHandler::new(Rc::new(
+                ClientProjections::new(),
+            )))
+            .with_middleware(LoggingMiddleware::new(move |log| {
+                logging_logs.borrow_mut().push(log)
+            }))
+            .with_middleware(TimingMiddleware::new(move |log| {
+                timing_logs.borrow_mut().push(log)
+            }));
+
+        let res = mediator.send(GetAllClientUseCaseRequest::new());
+        assert_matches!(res, Ok(clients) if clients.is_empty());
+
+        let logs = logs.borrow();
+        assert_eq!(logs.len(), 3);
+        assert_eq!(logs[0], "-> GetAllClientUseCaseRequest");
+        assert!(logs[1].starts_with("GetAllClientUseCaseRequest took "));
+        assert_eq!(logs[2], "<- GetAllClientUseCaseRequest ok");
+    }
+
+    #[test]
+    fn tracing_records_ids_and_outcome() {
+        let id = Uuid::new_v4();
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&lines);
+        let tracer = Rc::new(Tracer::new(Level::Info, LogFormat::Human, move |line| {
+            sink.borrow_mut().push(line)
+        }));
+        let mediator = Mediator::new()
+            .with_handler(GetClientUseCaseHandler::new(Rc::new(
+                ClientProjections::new(),
+            )))
+            .with_handler(CreateClientUseCaseHandler::new(Rc::new(
+                MockClientRepository::new(),
+            )))
+            .with_middleware(TracingMiddleware::new(tracer))
+            .with_middleware(ValidationMiddleware);
+
+        let res = mediator.send(GetClientUseCaseRequest::new(id));
+        assert_matches!(res, Ok(Err(_)));
+        let res = mediator.send(CreateClientUseCaseRequest::new(
+            "Taro".to_string(),
+            " ".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+
+        let lines = lines.borrow();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(&format!(
+            " WARN  mediator.GetClientUseCaseRequest id={} outcome=failed ",
+            id
+        )));
+        // 名前などの入力は記録しない
+        assert!(lines[1].contains(" WARN  mediator.CreateClientUseCaseRequest outcome=rejected "));
+        assert!(!lines[1].contains("Taro"));
+    }
+
+    #[test]
+    fn metrics_count_requests_and_clients() {
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_name_and_location()
+            .times(1)
+            .return_const(Vec::new());
+        mock_repo.expect_save().times(1).return_const(Ok(()));
+        // 作成時とコマンドの後にだけ数える
+        let mut gauge_repo = MockClientRepository::new();
+        gauge_repo.expect_all().times(1).return_const(Vec::new());
+        gauge_repo
+            .expect_all()
+            .times(1)
+            .return_const(vec![Client::new("Taro".to_string(), "Tokyo".to_string())]);
+
+        let metrics = Arc::new(MetricsRegistry::new());
+        let tracer =
+            Tracer::new(Level::Off, LogFormat::Human, |_| {}).with_metrics(Arc::clone(&metrics));
+        let mediator = Mediator::new()
+            .with_handler(CreateClientUseCaseHandler::new(Rc::new(mock_repo)))
+            .with_handler(GetAllClientUseCaseHandler::new(Rc::new(
+                ClientProjections::new(),
+            )))
+            .with_middleware(TracingMiddleware::new(Rc::new(tracer)))
+            .with_middleware(ClientCountMiddleware::new(
+                Arc::clone(&metrics),
+                Rc::new(gauge_repo),
+                TenantId::default(),
+            ));
+        assert!(metrics
+            .render()
+            .contains("ddd_example_clients{tenant=\"default\"} 0\n"));
+
+        let res = mediator.send(CreateClientUseCaseRequest::new(
+            "Taro".to_string(),
+            "Tokyo".to_string(),
+        ));
+        assert_matches!(res, Ok(Ok(_)));
+        assert_matches!(mediator.send(GetAllClientUseCaseRequest::new()), Ok(_));
+
+        let text = metrics.render();
+        assert!(text.contains("ddd_example_clients{tenant=\"default\"} 1\n"));
+        assert!(text.contains(
+            "ddd_example_requests_total{request=\"CreateClientUseCaseRequest\",outcome=\"ok\"} 1\n"
+        ));
+        assert!(text.contains(
+            "ddd_example_request_duration_seconds_count{request=\"GetAllClientUseCaseRequest\"} 1\n"
+        ));
+    }
+
+    #[test]
+    fn validation_rejects_invalid_request() {
+        // 検証に失敗したときはハンドラーを呼び出さない
+        let mock_repo = MockClientRepository::new();
+        let mediator = Mediator::new()
+            .with_handler(CreateClientUseCaseHandler::new(Rc::new(mock_repo)))
+            .with_middleware(ValidationMiddleware);
+
+        let res = mediator.send(CreateClientUseCaseRequest::new(
+            " ".to_string(),
+            "Tokyo".to_string(),
+        ));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn authorization_policy() {
+        let mediator = Mediator::new()
+            .with_handler(GetAllClientUseCaseHandler::new(Rc::new(
+                ClientProjections::new(),
+            )))
+            .with_handler(CreateClientUseCaseHandler::new(Rc::new(
+                MockClientRepository::new(),
+            )))
+            .with_middleware(AuthorizationMiddleware::new(|context| {
+                match context.kind() {
+                    RequestKind::Command => Err(format!("{} is not allowed", context.name())),
+                    RequestKind::Query => Ok(()),
+                }
+            }));
+
+        assert_matches!(mediator.send(GetAllClientUseCaseRequest::new()), Ok(_));
+        assert_matches!(
+            mediator.send(CreateClientUseCaseRequest::new(
+                "Taro".to_string(),
+                "Tokyo".to_string()
+            )),
+            Err(reason) if reason == "CreateClientUseCaseRequest is not allowed"
+        );
+    }
+
+    #[test]
+    fn retry_failed_handler() {
+        let existing = Client::new("Taro".to_string(), "Tokyo".to_string());
+
+        // 1回目は重複で失敗し，2回目で成功する
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_name_and_location()
+            .times(1)
+            .return_const(vec![existing]);
+        mock_repo
+            .expect_by_name_and_location()
+            .times(1)
+            .return_const(Vec::new());
+        mock_repo.expect_save().times(1).return_const(Ok(()));
+
+        let mediator = Mediator::new()
+            .with_handler(
+                CreateClientUseCaseHandler::new(Rc::new(mock_repo))
+                    .with_uniqueness_policy(UniquenessPolicy::Reject),
+            )
+            .with_middleware(RetryMiddleware::new(2));
+
+        let res = mediator.send(CreateClientUseCaseRequest::new(
+            "Taro".to_string(),
+            "Tokyo".to_string(),
+        ));
+        assert_matches!(res, Ok(Ok(_)));
+    }
+}
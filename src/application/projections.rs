@@ -0,0 +1,412 @@
+use crate::application::dtos::{ClientSummaryDto, LocationCountDto};
+use crate::domain::{
+    Client, ClientEvent, ClientRepository, EventPublisher, Tombstone, Transactional,
+};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use uuid::Uuid;
+
+// -------------------------------------------------------------------------------------------------
+
+/// 書き込み側のクライアントから導出するクエリ専用の読み取りモデル．
+/// コマンドが発行したイベントの対象のクライアントのみを更新する
+#[derive(Default)]
+pub struct ClientProjections {
+    clients: RefCell<HashMap<Uuid, Client>>,
+    summaries: RefCell<HashMap<Uuid, ClientSummaryDto>>,
+    location_counts: RefCell<BTreeMap<String, usize>>,
+    // 消去したクライアントと統合したクライアントのID -> 墓標
+    tombstones: RefCell<HashMap<Uuid, Tombstone>>,
+}
+
+impl ClientProjections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// 空の状態から全てのクライアントを適用し直し，件数を返す．墓標も空にする
+    pub fn rebuild(&self, clients: &[Client]) -> usize {
+        self.clients.borrow_mut().clear();
+        self.summaries.borrow_mut().clear();
+        self.location_counts.borrow_mut().clear();
+        self.tombstones.borrow_mut().clear();
+        for client in clients {
+            self.upsert(client.clone());
+        }
+        clients.len()
+    }
+    /// イベントの対象のクライアントのみを書き込み側から読み直して反映する．
+    /// 削除されたか，統合されて統合先に読み替えられるクライアントは除く
+    pub fn apply<T: ClientRepository + ?Sized>(&self, event: &ClientEvent, client_repo: &T) {
+        let id = event.client_id();
+        match (event, client_repo.by_id(id)) {
+            (ClientEvent::Deleted { .. }, _) => self.remove(id),
+            (ClientEvent::Erased { occurred_at, .. }, _) => {
+                let tombstone = self
+                    .client(id)
+                    .map(|client| Tombstone::new(&client, *occurred_at));
+                self.remove(id);
+                if let Ok(tombstone) = tombstone {
+                    self.bury(tombstone);
+                }
+            }
+            (_, Ok(client)) if client.id() == id => self.upsert(client),
+            _ => self.remove(id),
+        }
+    }
+    /// 統合したクライアントのIDは統合先に読み替える
+    pub fn client(&self, id: Uuid) -> Result<Client, String> {
+        let clients = self.clients.borrow();
+        clients
+            .get(&id)
+            .or_else(|| {
+                clients
+                    .values()
+                    .find(|client| client.aliases().contains(&id))
+            })
+            .cloned()
+            .ok_or_else(|| match self.tombstones.borrow().get(&id) {
+                Some(tombstone) => tombstone.error(id),
+                None => "No client found for given ID".to_string(),
+            })
+    }
+    /// 消去したクライアントのIDを引いたときに，見つからない代わりに消去したことを返す
+    pub fn bury(&self, tombstone: Tombstone) {
+        let mut tombstones = self.tombstones.borrow_mut();
+        for id in tombstone.ids() {
+            tombstones.insert(id, tombstone.clone());
+        }
+    }
+    /// 名前順のクライアント
+    pub fn clients(&self) -> Vec<Client> {
+        let mut clients = self.clients.borrow().values().cloned().collect::<Vec<_>>();
+        clients.sort_by(|a, b| a.name().cmp(b.name()).then(a.id().cmp(&b.id())));
+        clients
+    }
+    /// 名前順のクライアント一覧
+    pub fn summaries(&self) -> Vec<ClientSummaryDto> {
+        let mut summaries = self
+            .summaries
+            .borrow()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        summaries.sort_by(|a, b| a.name().cmp(b.name()).then(a.id().cmp(&b.id())));
+        summaries
+    }
+    /// 所在地ごとのクライアント数
+    pub fn location_counts(&self) -> Vec<LocationCountDto> {
+        self.location_counts
+            .borrow()
+            .iter()
+            .map(|(location, count)| LocationCountDto::new(location.clone(), *count))
+            .collect()
+    }
+    fn upsert(&self, client: Client) {
+        let summary = ClientSummaryDto::from(&client);
+        self.clients.borrow_mut().insert(client.id(), client);
+        let previous = {
+            let mut summaries = self.summaries.borrow_mut();
+            if summaries.get(&summary.id()) == Some(&summary) {
+                return;
+            }
+            summaries.insert(summary.id(), summary.clone())
+        };
+        if let Some(previous) = previous {
+            self.decrement(previous.location());
+        }
+        *self
+            .location_counts
+            .borrow_mut()
+            .entry(summary.location().to_string())
+            .or_default() += 1;
+    }
+    fn remove(&self, id: Uuid) {
+        self.clients.borrow_mut().remove(&id);
+        let removed = self.summaries.borrow_mut().remove(&id);
+        if let Some(removed) = removed {
+            self.decrement(removed.location());
+        }
+    }
+    fn decrement(&self, location: &str) {
+        let mut location_counts = self.location_counts.borrow_mut();
+        if let Some(count) = location_counts.get_mut(location) {
+            *count -= 1;
+            if *count == 0 {
+                location_counts.remove(location);
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 作業単位に参加し，commitしたイベントだけを発行先へ渡して読み取りモデルへ反映する．
+/// 取り消した変更のイベントは捨てる．作業単位の外で発行したイベントはすぐに渡す
+pub struct ProjectingEventPublisher<T: ClientRepository + ?Sized> {
+    client_repo: Rc<T>,
+    projections: Rc<ClientProjections>,
+    event_publisher: Option<Rc<dyn EventPublisher>>,
+    pending: RefCell<Vec<ClientEvent>>,
+    /// 開いている作業単位ごとの，開始時点で保留していたイベントの数
+    marks: RefCell<Vec<usize>>,
+}
+
+impl<T: ClientRepository + ?Sized> ProjectingEventPublisher<T> {
+    pub fn new(
+        client_repo: Rc<T>,
+        projections: Rc<ClientProjections>,
+        event_publisher: Option<Rc<dyn EventPublisher>>,
+    ) -> Self {
+        Self {
+            client_repo,
+            projections,
+            event_publisher,
+            pending: RefCell::new(Vec::new()),
+            marks: RefCell::new(Vec::new()),
+        }
+    }
+    fn deliver(&self, event: ClientEvent) {
+        self.projections.apply(&event, self.client_repo.as_ref());
+        if let Some(event_publisher) = &self.event_publisher {
+            event_publisher.publish(event);
+        }
+    }
+}
+
+impl<T: ClientRepository + ?Sized> EventPublisher for ProjectingEventPublisher<T> {
+    fn publish(&self, event: ClientEvent) {
+        if self.marks.borrow().is_empty() {
+            self.deliver(event);
+        } else {
+            self.pending.borrow_mut().push(event);
+        }
+    }
+}
+
+/// 発行先のアウトボックスが同じ作業単位で保存できるよう，他の参加者より先にcommitする
+impl<T: ClientRepository + ?Sized> Transactional for ProjectingEventPublisher<T> {
+    fn begin(&self) {
+        let mark = self.pending.borrow().len();
+        self.marks.borrow_mut().push(mark);
+    }
+    fn commit(&self) {
+        let mut marks = self.marks.borrow_mut();
+        marks.pop();
+        if marks.is_empty() {
+            drop(marks);
+            for event in self.pending.take() {
+                self.deliver(event);
+            }
+        }
+    }
+    fn rollback(&self) {
+        if let Some(mark) = self.marks.borrow_mut().pop() {
+            self.pending.borrow_mut().truncate(mark);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ClientProjections, ProjectingEventPublisher};
+    use crate::application::handlers_impl::{
+        AddTagUseCaseHandler, EditClientUseCaseHandler, GetAllClientUseCaseHandler,
+        GetClientUseCaseHandler,
+    };
+    use crate::application::requests::{
+        AddTagUseCaseRequest, EditClientUseCaseRequest, GetAllClientUseCaseRequest,
+        GetClientUseCaseRequest,
+    };
+    use crate::application::{Mediator, UnitOfWork};
+    use crate::domain::events::MockEventPublisher;
+    use crate::domain::repositories::MockClientRepository;
+    use crate::domain::{
+        Client, ClientEvent, ClientRepository, EventPublisher, Tombstone, Transactional,
+    };
+    use crate::infrastructure::InMemoryClientRepository;
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+    use mockall::predicate;
+    use std::rc::Rc;
+    use uuid::Uuid;
+
+    fn location_counts(projections: &ClientProjections) -> Vec<(String, usize)> {
+        projections
+            .location_counts()
+            .iter()
+            .map(|count| (count.location().to_string(), count.count()))
+            .collect()
+    }
+
+    #[test]
+    fn apply_events_and_rebuild() {
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let jiro = Client::new("Jiro".to_string(), "Tokyo".to_string());
+        let hanako = Client::new("Hanako".to_string(), "Osaka".to_string());
+        let mut moved_jiro = jiro.clone();
+        moved_jiro.edit("Jiro".to_string(), "Osaka".to_string());
+
+        // イベントの対象のクライアントのみを読み直し，全件は読まない
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo.expect_all().never();
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(jiro.id()))
+            .times(1)
+            .return_const(Ok(moved_jiro.clone()));
+        mock_repo
+            .expect_by_id()
+            .with(predicate::eq(hanako.id()))
+            .times(1)
+            .return_const(Err("No client found for given ID".to_string()));
+        mock_repo.expect_by_id().return_const(Ok(taro.clone()));
+
+        let projections = ClientProjections::new();
+        assert_eq!(
+            projections.rebuild(&[taro.clone(), jiro.clone(), hanako.clone()]),
+            3
+        );
+        let names = projections
+            .summaries()
+            .iter()
+            .map(|summary| summary.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Hanako", "Jiro", "Taro"]);
+        assert_eq!(
+            location_counts(&projections),
+            vec![("Osaka".to_string(), 1), ("Tokyo".to_string(), 2)]
+        );
+
+        // 移転と削除を反映する
+        projections.apply(&ClientEvent::edited(&moved_jiro, Utc::now()), &mock_repo);
+        projections.apply(&ClientEvent::deleted(taro.id(), Utc::now()), &mock_repo);
+        projections.apply(&ClientEvent::erased(hanako.id(), Utc::now()), &mock_repo);
+        let ids = projections
+            .summaries()
+            .iter()
+            .map(|summary| summary.id())
+            .collect::<Vec<Uuid>>();
+        assert_eq!(ids, vec![jiro.id()]);
+        assert_eq!(
+            location_counts(&projections),
+            vec![("Osaka".to_string(), 1)]
+        );
+        assert_matches!(projections.client(jiro.id()), Ok(client) if client == moved_jiro);
+        assert_matches!(projections.client(taro.id()), Err(err) if err.contains("No client found"));
+        // 消去したクライアントは見つからないのではなく消去したと答える
+        assert_matches!(projections.client(hanako.id()), Err(err) if err.contains("was erased"));
+
+        assert_eq!(projections.rebuild(&[taro]), 1);
+        assert_eq!(projections.clients().len(), 1);
+        assert_eq!(projections.summaries().len(), 1);
+        assert_eq!(projections.location_counts().len(), 1);
+        assert_matches!(projections.client(hanako.id()), Err(err) if err.contains("No client found"));
+        projections.bury(Tombstone::new(&hanako, Utc::now()));
+        assert_matches!(projections.client(hanako.id()), Err(err) if err.contains("was erased"));
+    }
+
+    #[test]
+    fn resolve_merged_ids() {
+        let mut taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let taro2 = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let merged_id = taro2.id();
+        taro.merge(taro2);
+
+        let projections = ClientProjections::new();
+        projections.rebuild(&[taro.clone()]);
+        assert_matches!(projections.client(merged_id), Ok(client) if client.id() == taro.id());
+    }
+
+    #[test]
+    fn update_projections_after_successful_commands() {
+        let client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let id = client.id();
+        let repository = Rc::new(InMemoryClientRepository::new());
+        repository.save(client).unwrap();
+        let projections = Rc::new(ClientProjections::new());
+        let event_publisher = Rc::new(ProjectingEventPublisher::new(
+            Rc::clone(&repository),
+            Rc::clone(&projections),
+            None,
+        ));
+        let unit_of_work = UnitOfWork::new()
+            .with_participant(Rc::clone(&event_publisher) as Rc<dyn Transactional>);
+        let event_publisher = event_publisher as Rc<dyn EventPublisher>;
+
+        let mediator = Mediator::new()
+            .with_unit_of_work(unit_of_work)
+            .with_handler(GetClientUseCaseHandler::new(Rc::clone(&projections)))
+            .with_handler(GetAllClientUseCaseHandler::new(Rc::clone(&projections)))
+            .with_handler(
+                AddTagUseCaseHandler::new(Rc::clone(&repository))
+                    .with_event_publisher(Rc::clone(&event_publisher)),
+            )
+            .with_handler(
+                EditClientUseCaseHandler::new(Rc::clone(&repository))
+                    .with_event_publisher(Rc::clone(&event_publisher)),
+            );
+
+        // クエリは読み取りモデルのみを参照する
+        let res = mediator.send(GetClientUseCaseRequest::new(id));
+        assert_matches!(res, Ok(Err(_)));
+
+        let res = mediator.send(AddTagUseCaseRequest::new(id, "vip".to_string()));
+        assert_matches!(res, Ok(Ok(_)));
+        assert_matches!(projections.summaries().as_slice(), [summary] if summary.tag_count() == 1);
+        let res = mediator.send(GetAllClientUseCaseRequest::new().with_tag("vip".to_string()));
+        assert_matches!(res, Ok(clients) if clients.len() == 1);
+
+        // 失敗したコマンドの後は反映しない
+        projections.rebuild(&[]);
+        let res = mediator.send(EditClientUseCaseRequest::new(
+            Uuid::new_v4(),
+            "Jiro".to_string(),
+            "Osaka".to_string(),
+        ));
+        assert_matches!(res, Ok(Err(_)));
+        assert!(projections.summaries().is_empty());
+    }
+
+    #[test]
+    fn publish_only_committed_events() {
+        let client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let repository = Rc::new(InMemoryClientRepository::new());
+        repository.save(client.clone()).unwrap();
+        let projections = Rc::new(ClientProjections::new());
+        let id = client.id();
+        let mut mock_event_publisher = MockEventPublisher::new();
+        mock_event_publisher
+            .expect_publish()
+            .withf(move |event| event.client_id() == id)
+            .times(2)
+            .return_const(());
+        let event_publisher = ProjectingEventPublisher::new(
+            Rc::clone(&repository),
+            Rc::clone(&projections),
+            Some(Rc::new(mock_event_publisher)),
+        );
+
+        // 取り消した作業単位のイベントは渡さず，反映もしない
+        event_publisher.begin();
+        event_publisher.publish(ClientEvent::created(&client, Utc::now()));
+        event_publisher.rollback();
+        assert!(projections.summaries().is_empty());
+
+        // 内側の作業単位のcommitでは渡さず，最も外側のcommitで渡す
+        event_publisher.begin();
+        event_publisher.begin();
+        event_publisher.publish(ClientEvent::created(&client, Utc::now()));
+        event_publisher.commit();
+        assert!(projections.summaries().is_empty());
+        event_publisher.begin();
+        event_publisher.publish(ClientEvent::edited(&client, Utc::now()));
+        event_publisher.rollback();
+        event_publisher.commit();
+        assert_eq!(projections.summaries().len(), 1);
+
+        // 作業単位の外ではすぐに渡す
+        event_publisher.publish(ClientEvent::deleted(id, Utc::now()));
+        assert!(projections.summaries().is_empty());
+    }
+}
@@ -1,16 +1,38 @@
+use crate::application::dtos::{
+    AuditEntryDto, ClientChangeDto, ClientDto, ClientEventDto, ClientExportDto,
+    ClientPersonalDataDto, ClientSummaryDto, CreatedClientDto, DtoList, DuplicateGroupDto,
+    EditHistoryDto, ErasureReportDto, HierarchyDto, LocationCountDto, NoteDto, OutboxMessageDto,
+    ProjectDto, RegionGroupDto, RelayReportDto, TagCountDto, UserDto, WebhookDeliveryDto,
+    WebhookSubscriptionDto,
+};
+use crate::application::mediator::{Request, RequestKind};
+use crate::domain::{ContactKind, Permission};
+use crate::telemetry::FieldValue;
 use uuid::Uuid;
 
+#[derive(Clone)]
 pub struct CreateClientUseCaseRequest {
     pub name: String,
     pub location: String,
+    /// 同じキーで再送されたときは最初に作成したクライアントを返す
+    pub idempotency_key: Option<String>,
 }
 
 impl CreateClientUseCaseRequest {
     pub fn new(name: String, location: String) -> Self {
-        Self { name, location }
+        Self {
+            name,
+            location,
+            idempotency_key: None,
+        }
+    }
+    pub fn with_idempotency_key(mut self, idempotency_key: String) -> Self {
+        self.idempotency_key = Some(idempotency_key);
+        self
     }
 }
 
+#[derive(Clone)]
 pub struct GetClientUseCaseRequest {
     pub id: Uuid,
 }
@@ -21,6 +43,33 @@ impl GetClientUseCaseRequest {
     }
 }
 
+#[derive(Clone, Default)]
+pub struct GetAllClientUseCaseRequest {
+    /// 地域(都道府県・州)または国で絞り込む
+    pub region: Option<String>,
+    pub tag: Option<String>,
+    pub status: Option<String>,
+}
+
+impl GetAllClientUseCaseRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_region(mut self, region: String) -> Self {
+        self.region = Some(region);
+        self
+    }
+    pub fn with_tag(mut self, tag: String) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+    pub fn with_status(mut self, status: String) -> Self {
+        self.status = Some(status);
+        self
+    }
+}
+
+#[derive(Clone)]
 pub struct EditClientUseCaseRequest {
     pub id: Uuid,
     pub name: String,
@@ -33,4 +82,608 @@ impl EditClientUseCaseRequest {
     }
 }
 
-pub struct NoneRequest;
+#[derive(Clone)]
+pub struct FindDuplicatesUseCaseRequest {
+    pub threshold: f64,
+}
+
+impl FindDuplicatesUseCaseRequest {
+    pub fn new(threshold: f64) -> Self {
+        Self { threshold }
+    }
+}
+
+#[derive(Clone)]
+pub struct MergeClientsUseCaseRequest {
+    pub keep_id: Uuid,
+    pub merge_ids: Vec<Uuid>,
+}
+
+impl MergeClientsUseCaseRequest {
+    pub fn new(keep_id: Uuid, merge_ids: Vec<Uuid>) -> Self {
+        Self { keep_id, merge_ids }
+    }
+}
+
+#[derive(Clone)]
+pub struct AddContactUseCaseRequest {
+    pub client_id: Uuid,
+    pub kind: ContactKind,
+    pub value: String,
+}
+
+impl AddContactUseCaseRequest {
+    pub fn new(client_id: Uuid, kind: ContactKind, value: String) -> Self {
+        Self {
+            client_id,
+            kind,
+            value,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RemoveContactUseCaseRequest {
+    pub client_id: Uuid,
+    pub contact_id: Uuid,
+}
+
+impl RemoveContactUseCaseRequest {
+    pub fn new(client_id: Uuid, contact_id: Uuid) -> Self {
+        Self {
+            client_id,
+            contact_id,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SetPrimaryContactUseCaseRequest {
+    pub client_id: Uuid,
+    pub contact_id: Uuid,
+}
+
+impl SetPrimaryContactUseCaseRequest {
+    pub fn new(client_id: Uuid, contact_id: Uuid) -> Self {
+        Self {
+            client_id,
+            contact_id,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AddTagUseCaseRequest {
+    pub client_id: Uuid,
+    pub tag: String,
+}
+
+impl AddTagUseCaseRequest {
+    pub fn new(client_id: Uuid, tag: String) -> Self {
+        Self { client_id, tag }
+    }
+}
+
+#[derive(Clone)]
+pub struct RemoveTagUseCaseRequest {
+    pub client_id: Uuid,
+    pub tag: String,
+}
+
+impl RemoveTagUseCaseRequest {
+    pub fn new(client_id: Uuid, tag: String) -> Self {
+        Self { client_id, tag }
+    }
+}
+
+#[derive(Clone)]
+pub struct AddNoteUseCaseRequest {
+    pub client_id: Uuid,
+    pub body: String,
+}
+
+impl AddNoteUseCaseRequest {
+    pub fn new(client_id: Uuid, body: String) -> Self {
+        Self { client_id, body }
+    }
+}
+
+#[derive(Clone)]
+pub struct GetNotesUseCaseRequest {
+    pub client_id: Uuid,
+}
+
+impl GetNotesUseCaseRequest {
+    pub fn new(client_id: Uuid) -> Self {
+        Self { client_id }
+    }
+}
+
+#[derive(Clone)]
+pub struct EditNoteUseCaseRequest {
+    pub client_id: Uuid,
+    pub note_id: Uuid,
+    pub body: String,
+}
+
+impl EditNoteUseCaseRequest {
+    pub fn new(client_id: Uuid, note_id: Uuid, body: String) -> Self {
+        Self {
+            client_id,
+            note_id,
+            body,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DeleteNoteUseCaseRequest {
+    pub client_id: Uuid,
+    pub note_id: Uuid,
+}
+
+impl DeleteNoteUseCaseRequest {
+    pub fn new(client_id: Uuid, note_id: Uuid) -> Self {
+        Self { client_id, note_id }
+    }
+}
+
+#[derive(Clone)]
+pub struct ChangeClientStatusUseCaseRequest {
+    pub id: Uuid,
+    pub status: String,
+}
+
+impl ChangeClientStatusUseCaseRequest {
+    pub fn new(id: Uuid, status: String) -> Self {
+        Self { id, status }
+    }
+}
+
+#[derive(Clone)]
+pub struct DeleteClientUseCaseRequest {
+    pub id: Uuid,
+}
+
+impl DeleteClientUseCaseRequest {
+    pub fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+}
+
+#[derive(Clone)]
+pub struct CreateProjectUseCaseRequest {
+    pub client_id: Uuid,
+    pub name: String,
+}
+
+impl CreateProjectUseCaseRequest {
+    pub fn new(client_id: Uuid, name: String) -> Self {
+        Self { client_id, name }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct ListProjectsUseCaseRequest {
+    /// クライアントで絞り込む
+    pub client_id: Option<Uuid>,
+}
+
+impl ListProjectsUseCaseRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_client(mut self, client_id: Uuid) -> Self {
+        self.client_id = Some(client_id);
+        self
+    }
+}
+
+#[derive(Clone)]
+pub struct CloseProjectUseCaseRequest {
+    pub id: Uuid,
+}
+
+impl CloseProjectUseCaseRequest {
+    pub fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+}
+
+#[derive(Clone)]
+pub struct LinkClientsUseCaseRequest {
+    pub from: Uuid,
+    pub to: Uuid,
+    pub kind: String,
+}
+
+impl LinkClientsUseCaseRequest {
+    pub fn new(from: Uuid, to: Uuid, kind: String) -> Self {
+        Self { from, to, kind }
+    }
+}
+
+#[derive(Clone)]
+pub struct UnlinkClientsUseCaseRequest {
+    pub from: Uuid,
+    pub to: Uuid,
+    pub kind: String,
+}
+
+impl UnlinkClientsUseCaseRequest {
+    pub fn new(from: Uuid, to: Uuid, kind: String) -> Self {
+        Self { from, to, kind }
+    }
+}
+
+#[derive(Clone)]
+pub struct GetHierarchyUseCaseRequest {
+    pub client_id: Uuid,
+}
+
+impl GetHierarchyUseCaseRequest {
+    pub fn new(client_id: Uuid) -> Self {
+        Self { client_id }
+    }
+}
+
+#[derive(Clone)]
+pub struct GroupClientsByRegionUseCaseRequest;
+
+#[derive(Clone)]
+pub struct GetTagCountsUseCaseRequest;
+
+#[derive(Clone)]
+pub struct GetClientSummariesUseCaseRequest;
+
+#[derive(Clone)]
+pub struct GetLocationCountsUseCaseRequest;
+
+#[derive(Clone)]
+pub struct RebuildProjectionsUseCaseRequest;
+
+#[derive(Clone)]
+pub struct GetOutboxUseCaseRequest;
+
+#[derive(Clone)]
+pub struct GetEventHistoryUseCaseRequest;
+
+/// 未配信のメッセージを全て配信する
+#[derive(Clone)]
+pub struct RelayOutboxUseCaseRequest;
+
+/// 配信済みかどうかにかかわらず，指定したメッセージを再配信する
+#[derive(Clone)]
+pub struct ReplayOutboxMessageUseCaseRequest {
+    pub id: Uuid,
+}
+
+impl ReplayOutboxMessageUseCaseRequest {
+    pub fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+}
+
+#[derive(Clone)]
+pub struct CreateWebhookUseCaseRequest {
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub secret: String,
+}
+
+impl CreateWebhookUseCaseRequest {
+    pub fn new(url: String, event_types: Vec<String>, secret: String) -> Self {
+        Self {
+            url,
+            event_types,
+            secret,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DeleteWebhookUseCaseRequest {
+    pub id: Uuid,
+}
+
+impl DeleteWebhookUseCaseRequest {
+    pub fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+}
+
+#[derive(Clone)]
+pub struct ListWebhooksUseCaseRequest;
+
+#[derive(Clone, Default)]
+pub struct GetWebhookDeliveriesUseCaseRequest {
+    pub subscription_id: Option<Uuid>,
+}
+
+impl GetWebhookDeliveriesUseCaseRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_subscription(mut self, subscription_id: Uuid) -> Self {
+        self.subscription_id = Some(subscription_id);
+        self
+    }
+}
+
+#[derive(Clone)]
+pub struct UndoUseCaseRequest;
+
+#[derive(Clone)]
+pub struct RedoUseCaseRequest;
+
+#[derive(Clone)]
+pub struct GetEditHistoryUseCaseRequest;
+
+/// ユーザーがまだいないか
+#[derive(Clone)]
+pub struct IsSetupRequiredUseCaseRequest;
+
+/// 最初の管理者を作成してログインする
+#[derive(Clone)]
+pub struct SetupAdminUseCaseRequest {
+    pub username: String,
+    pub password: String,
+}
+
+impl SetupAdminUseCaseRequest {
+    pub fn new(username: String, password: String) -> Self {
+        Self { username, password }
+    }
+}
+
+#[derive(Clone)]
+pub struct LoginUseCaseRequest {
+    pub username: String,
+    pub password: String,
+}
+
+impl LoginUseCaseRequest {
+    pub fn new(username: String, password: String) -> Self {
+        Self { username, password }
+    }
+}
+
+#[derive(Clone)]
+pub struct LogoutUseCaseRequest;
+
+#[derive(Clone)]
+pub struct CreateUserUseCaseRequest {
+    pub username: String,
+    pub password: String,
+    pub role: String,
+}
+
+impl CreateUserUseCaseRequest {
+    pub fn new(username: String, password: String, role: String) -> Self {
+        Self {
+            username,
+            password,
+            role,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ListUsersUseCaseRequest;
+
+#[derive(Clone)]
+pub struct GetAuditLogUseCaseRequest;
+
+/// 選択中のテナントのクライアントを全て書き出す
+#[derive(Clone)]
+pub struct ExportClientsUseCaseRequest;
+
+/// クライアントについて保存している全てのデータを書き出す
+#[derive(Clone)]
+pub struct ExportClientPersonalDataUseCaseRequest {
+    pub id: Uuid,
+}
+
+impl ExportClientPersonalDataUseCaseRequest {
+    pub fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+}
+
+/// クライアントを削除し，他の記録に残る個人データも消去する
+#[derive(Clone)]
+pub struct EraseClientUseCaseRequest {
+    pub id: Uuid,
+}
+
+impl EraseClientUseCaseRequest {
+    pub fn new(id: Uuid) -> Self {
+        Self { id }
+    }
+}
+
+/// 現在の鍵で暗号化されていないクライアントを暗号化し直す
+#[derive(Clone)]
+pub struct ReencryptClientsUseCaseRequest;
+
+/// 選択中のテナントのクライアントを匿名化して書き出す．同じ種と同じクライアントからは同じ結果になる
+#[derive(Clone)]
+pub struct AnonymizeClientsUseCaseRequest {
+    pub seed: u64,
+}
+
+impl AnonymizeClientsUseCaseRequest {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// メディエーターで送信するためのリクエストとハンドラーの出力の対応
+
+// [...]にはログに添えるIDなどの項目を並べる
+macro_rules! impl_request {
+    ($kind:ident: $($request:ident $([$($field:ident),*])? => $output:ty),* $(,)?) => {
+        $(
+            impl Request for $request {
+                type Output = $output;
+                const NAME: &'static str = stringify!($request);
+                const KIND: RequestKind = RequestKind::$kind;
+                $(
+                    fn fields(&self) -> Vec<(&'static str, String)> {
+                        vec![$((stringify!($field), self.$field.field_value())),*]
+                    }
+                )?
+            }
+        )*
+    };
+    // 種類から決まる権限とは異なる権限を要求する
+    ($kind:ident, $permission:expr; $($request:ident $([$($field:ident),*])? => $output:ty),* $(,)?) => {
+        $(
+            impl Request for $request {
+                type Output = $output;
+                const NAME: &'static str = stringify!($request);
+                const KIND: RequestKind = RequestKind::$kind;
+                const PERMISSION: Option<Permission> = $permission;
+                $(
+                    fn fields(&self) -> Vec<(&'static str, String)> {
+                        vec![$((stringify!($field), self.$field.field_value())),*]
+                    }
+                )?
+            }
+        )*
+    };
+}
+
+impl_request!(Query:
+    GetClientUseCaseRequest [id] => Result<ClientDto, String>,
+    GetAllClientUseCaseRequest => DtoList<ClientDto>,
+    GroupClientsByRegionUseCaseRequest => DtoList<RegionGroupDto>,
+    GetTagCountsUseCaseRequest => DtoList<TagCountDto>,
+    GetNotesUseCaseRequest [client_id] => Result<DtoList<NoteDto>, String>,
+    ListProjectsUseCaseRequest [client_id] => Result<DtoList<ProjectDto>, String>,
+    GetHierarchyUseCaseRequest [client_id] => Result<HierarchyDto, String>,
+    GetClientSummariesUseCaseRequest => DtoList<ClientSummaryDto>,
+    GetLocationCountsUseCaseRequest => DtoList<LocationCountDto>,
+    GetEditHistoryUseCaseRequest => Result<EditHistoryDto, String>,
+    GetEventHistoryUseCaseRequest => DtoList<ClientEventDto>,
+);
+
+impl_request!(Query, Some(Permission::Administer);
+    GetOutboxUseCaseRequest => DtoList<OutboxMessageDto>,
+    ListWebhooksUseCaseRequest => DtoList<WebhookSubscriptionDto>,
+    GetWebhookDeliveriesUseCaseRequest [subscription_id] => Result<DtoList<WebhookDeliveryDto>, String>,
+    ListUsersUseCaseRequest => DtoList<UserDto>,
+    GetAuditLogUseCaseRequest => DtoList<AuditEntryDto>,
+    ExportClientsUseCaseRequest => Result<ClientExportDto, String>,
+    ExportClientPersonalDataUseCaseRequest [id] => Result<ClientPersonalDataDto, String>,
+    AnonymizeClientsUseCaseRequest [seed] => Result<ClientExportDto, String>,
+);
+
+// ログインする前にも実行する
+impl_request!(Query, None;
+    IsSetupRequiredUseCaseRequest => Result<bool, String>,
+);
+
+impl_request!(Command:
+    MergeClientsUseCaseRequest [keep_id, merge_ids] => Result<ClientDto, String>,
+    AddContactUseCaseRequest [client_id] => Result<ClientDto, String>,
+    RemoveContactUseCaseRequest [client_id, contact_id] => Result<ClientDto, String>,
+    SetPrimaryContactUseCaseRequest [client_id, contact_id] => Result<ClientDto, String>,
+    AddTagUseCaseRequest [client_id] => Result<ClientDto, String>,
+    RemoveTagUseCaseRequest [client_id] => Result<ClientDto, String>,
+    EditNoteUseCaseRequest [client_id, note_id] => Result<NoteDto, String>,
+    DeleteNoteUseCaseRequest [client_id, note_id] => Result<(), String>,
+    ChangeClientStatusUseCaseRequest [id] => Result<ClientDto, String>,
+    DeleteClientUseCaseRequest [id] => Result<(), String>,
+    CreateProjectUseCaseRequest [client_id] => Result<ProjectDto, String>,
+    CloseProjectUseCaseRequest [id] => Result<ProjectDto, String>,
+    LinkClientsUseCaseRequest [from, to] => Result<(), String>,
+    UnlinkClientsUseCaseRequest [from, to] => Result<(), String>,
+    UndoUseCaseRequest => Result<ClientChangeDto, String>,
+    RedoUseCaseRequest => Result<ClientChangeDto, String>,
+    RelayOutboxUseCaseRequest => Result<RelayReportDto, String>,
+);
+
+impl_request!(Command, Some(Permission::Administer);
+    ReplayOutboxMessageUseCaseRequest [id] => Result<OutboxMessageDto, String>,
+    CreateWebhookUseCaseRequest => Result<WebhookSubscriptionDto, String>,
+    DeleteWebhookUseCaseRequest [id] => Result<(), String>,
+    CreateUserUseCaseRequest => Result<UserDto, String>,
+    ReencryptClientsUseCaseRequest => Result<usize, String>,
+    EraseClientUseCaseRequest [id] => Result<ErasureReportDto, String>,
+);
+
+// ログインする前やアプリケーション自身が実行する
+impl_request!(Command, None;
+    RebuildProjectionsUseCaseRequest => Result<usize, String>,
+    SetupAdminUseCaseRequest => Result<UserDto, String>,
+    LoginUseCaseRequest => Result<UserDto, String>,
+    LogoutUseCaseRequest => Result<(), String>,
+);
+
+fn validate_client_fields(name: &str, location: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Name must not be empty".to_string());
+    }
+    if location.trim().is_empty() {
+        return Err("Location must not be empty".to_string());
+    }
+    Ok(())
+}
+
+impl Request for CreateClientUseCaseRequest {
+    type Output = Result<CreatedClientDto, String>;
+    const NAME: &'static str = "CreateClientUseCaseRequest";
+    const KIND: RequestKind = RequestKind::Command;
+    fn validate(&self) -> Result<(), String> {
+        if let Some(idempotency_key) = &self.idempotency_key {
+            if idempotency_key.trim().is_empty() {
+                return Err("Idempotency key must not be empty".to_string());
+            }
+        }
+        validate_client_fields(&self.name, &self.location)
+    }
+}
+
+impl Request for EditClientUseCaseRequest {
+    type Output = Result<Vec<String>, String>;
+    const NAME: &'static str = "EditClientUseCaseRequest";
+    const KIND: RequestKind = RequestKind::Command;
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![("id", self.id.field_value())]
+    }
+    fn validate(&self) -> Result<(), String> {
+        validate_client_fields(&self.name, &self.location)
+    }
+}
+
+impl Request for FindDuplicatesUseCaseRequest {
+    type Output = DtoList<DuplicateGroupDto>;
+    const NAME: &'static str = "FindDuplicatesUseCaseRequest";
+    const KIND: RequestKind = RequestKind::Query;
+    fn validate(&self) -> Result<(), String> {
+        match (0.0..=1.0).contains(&self.threshold) {
+            true => Ok(()),
+            false => Err(format!(
+                "Threshold must be between 0 and 1: {}",
+                self.threshold
+            )),
+        }
+    }
+}
+
+impl Request for AddNoteUseCaseRequest {
+    type Output = Result<NoteDto, String>;
+    const NAME: &'static str = "AddNoteUseCaseRequest";
+    const KIND: RequestKind = RequestKind::Command;
+    fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![("client_id", self.client_id.field_value())]
+    }
+    fn validate(&self) -> Result<(), String> {
+        match self.body.trim().is_empty() {
+            true => Err("Note must not be empty".to_string()),
+            false => Ok(()),
+        }
+    }
+}
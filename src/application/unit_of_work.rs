@@ -0,0 +1,134 @@
+use crate::application::mediator::Outcome;
+use crate::domain::Transactional;
+use std::rc::Rc;
+
+/// 参加するリポジトリへの変更を，全て反映するか全て取り消すかのどちらかにまとめる
+#[derive(Clone, Default)]
+pub struct UnitOfWork {
+    participants: Vec<Rc<dyn Transactional>>,
+}
+
+impl UnitOfWork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_participant(mut self, participant: Rc<dyn Transactional>) -> Self {
+        self.participants.push(participant);
+        self
+    }
+    /// 他の参加者より先に開始・commitする参加者
+    pub fn with_first_participant(mut self, participant: Rc<dyn Transactional>) -> Self {
+        self.participants.insert(0, participant);
+        self
+    }
+    /// 出力が失敗を表すときや，処理がpanicしたときは，処理中の変更を全て取り消す
+    pub fn run<O: Outcome>(&self, work: impl FnOnce() -> O) -> O {
+        for participant in &self.participants {
+            participant.begin();
+        }
+        let guard = RollbackGuard(&self.participants);
+        let output = work();
+        std::mem::forget(guard);
+        for participant in &self.participants {
+            match output.error() {
+                Some(_) => participant.rollback(),
+                None => participant.commit(),
+            }
+        }
+        output
+    }
+}
+
+/// 処理が戻らずに抜けたときに，開始した変更を取り消す
+struct RollbackGuard<'a>(&'a [Rc<dyn Transactional>]);
+
+impl Drop for RollbackGuard<'_> {
+    fn drop(&mut self) {
+        for participant in self.0 {
+            participant.rollback();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::UnitOfWork;
+    use crate::domain::repositories::MockTransactional;
+    use crate::domain::{Client, ClientRepository};
+    use crate::infrastructure::InMemoryClientRepository;
+    use assert_matches::assert_matches;
+    use mockall::Sequence;
+    use std::panic::AssertUnwindSafe;
+    use std::rc::Rc;
+    use uuid::Uuid;
+
+    #[test]
+    fn commit_on_success_and_rollback_on_failure() {
+        let mut sequence = Sequence::new();
+        let mut mock_participant = MockTransactional::new();
+        mock_participant
+            .expect_begin()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_const(());
+        mock_participant
+            .expect_commit()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_const(());
+        mock_participant
+            .expect_begin()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_const(());
+        mock_participant
+            .expect_rollback()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_const(());
+
+        let unit_of_work = UnitOfWork::new().with_participant(Rc::new(mock_participant));
+        let res: Result<(), String> = unit_of_work.run(|| Ok(()));
+        assert_matches!(res, Ok(()));
+        let res: Result<(), String> = unit_of_work.run(|| Err("failed".to_string()));
+        assert_matches!(res, Err(_));
+    }
+
+    #[test]
+    fn discard_partial_changes() {
+        let repository = Rc::new(InMemoryClientRepository::new());
+        let unit_of_work = UnitOfWork::new().with_participant(repository.clone());
+
+        let res: Result<(), String> = unit_of_work.run(|| {
+            repository
+                .save(Client::new("Taro".to_string(), "Tokyo".to_string()))
+                .unwrap();
+            repository.delete(Uuid::new_v4())
+        });
+        assert_matches!(res, Err(_));
+        assert!(repository.all().is_empty());
+    }
+
+    #[test]
+    fn rollback_on_panic() {
+        let repository = Rc::new(InMemoryClientRepository::new());
+        let unit_of_work = UnitOfWork::new().with_participant(repository.clone());
+
+        let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            unit_of_work.run(|| -> Result<(), String> {
+                repository
+                    .save(Client::new("Taro".to_string(), "Tokyo".to_string()))
+                    .unwrap();
+                panic!("interrupted");
+            })
+        }));
+        assert!(res.is_err());
+        assert!(repository.all().is_empty());
+
+        // 取り消した後も次の作業単位を実行できる
+        let res: Result<(), String> = unit_of_work
+            .run(|| repository.save(Client::new("Jiro".to_string(), "Osaka".to_string())));
+        assert_matches!(res, Ok(()));
+        assert_eq!(repository.all().len(), 1);
+    }
+}
@@ -1,5 +1,16 @@
 pub mod entities;
+pub mod errors;
+pub mod events;
+pub mod gazetteer;
 pub mod repositories;
+pub mod services;
+pub mod specifications;
+pub mod value_objects;
 
 pub use entities::*;
+pub use errors::*;
+pub use events::*;
 pub use repositories::*;
+pub use services::*;
+pub use specifications::*;
+pub use value_objects::*;
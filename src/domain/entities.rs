@@ -1,20 +1,160 @@
+use crate::domain::{
+    normalize_text, ClientEvent, ClientStatus, ContactInfo, ContactKind, DomainError, Location,
+    PasswordHash, ProjectStatus, Role, Tag,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
 use uuid::Uuid;
 
 #[cfg(test)]
 use fake::{Dummy, Fake};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+// -------------------------------------------------------------------------------------------------
+// Contact
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contact {
+    id: Uuid,
+    info: ContactInfo,
+    primary: bool,
+}
+
+impl Contact {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub fn info(&self) -> &ContactInfo {
+        &self.info
+    }
+    pub fn kind(&self) -> ContactKind {
+        self.info.kind()
+    }
+    pub fn is_primary(&self) -> bool {
+        self.primary
+    }
+}
+
+#[cfg(test)]
+impl Dummy<fake::Faker> for Contact {
+    fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &fake::Faker, rng: &mut R) -> Self {
+        use fake::faker::internet::en::SafeEmail;
+        let email = SafeEmail().fake_with_rng::<String, _>(rng);
+        Contact {
+            id: Uuid::new_v4(),
+            info: ContactInfo::parse(ContactKind::Email, &email).unwrap(),
+            primary: false,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Note
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    id: Uuid,
+    body: String,
+    created_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl Note {
+    pub const MAX_LENGTH: usize = 2000;
+
+    fn validate(body: &str) -> Result<String, DomainError> {
+        let body = body.trim();
+        if body.is_empty() {
+            return Err(DomainError::InvalidNote("note is empty".to_string()));
+        }
+        if body.chars().count() > Self::MAX_LENGTH {
+            return Err(DomainError::InvalidNote(format!(
+                "note is longer than {} characters",
+                Self::MAX_LENGTH
+            )));
+        }
+        Ok(body.to_string())
+    }
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.updated_at
+    }
+}
+
+#[cfg(test)]
+impl Dummy<fake::Faker> for Note {
+    fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &fake::Faker, rng: &mut R) -> Self {
+        use fake::faker::lorem::en::Sentence;
+        Note {
+            id: Uuid::new_v4(),
+            body: Sentence(1..10).fake_with_rng(rng),
+            created_at: Utc::now(),
+            updated_at: None,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Client
+
+/// 名前と出身地から検索キーを計算するクライアントを作る
+#[cfg(test)]
+struct DerivedLookupKey;
+
+#[cfg(test)]
+impl Dummy<DerivedLookupKey> for Option<(String, String)> {
+    fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &DerivedLookupKey, _: &mut R) -> Self {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(test, derive(Dummy))]
 pub struct Client {
     id: Uuid,
     name: String,
-    location: String,
+    location: Location,
+    status: ClientStatus,
+    aliases: Vec<Uuid>,
+    contacts: Vec<Contact>,
+    tags: BTreeSet<Tag>,
+    notes: Vec<Note>,
+    // 保存先で名前や出身地を暗号化したときに別に持たせる検索キー
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(test, dummy(faker = "DerivedLookupKey"))]
+    lookup_key: Option<(String, String)>,
 }
 
 impl Client {
     pub fn new(name: String, location: String) -> Self {
         let id = Uuid::new_v4();
-        Self { id, name, location }
+        Self {
+            id,
+            name,
+            location: Location::new(&location),
+            status: ClientStatus::default(),
+            aliases: Vec::new(),
+            contacts: Vec::new(),
+            tags: BTreeSet::new(),
+            notes: Vec::new(),
+            lookup_key: None,
+        }
+    }
+    /// 表記揺れを吸収した(名前, 出身地)の検索キー
+    pub fn lookup_key_of(name: &str, location: &str) -> (String, String) {
+        (
+            normalize_text(name),
+            Location::new(location).normalized_key(),
+        )
     }
     pub fn id(&self) -> Uuid {
         self.id
@@ -22,20 +162,570 @@ impl Client {
     pub fn name(&self) -> &str {
         &self.name
     }
-    pub fn location(&self) -> &str {
+    /// 別に持たせた検索キー．なければ名前と出身地から計算する
+    pub fn lookup_key(&self) -> (String, String) {
+        self.lookup_key
+            .clone()
+            .unwrap_or_else(|| Self::lookup_key_of(&self.name, self.location.as_str()))
+    }
+    /// 保存先で名前や出身地を検索できない形に変えたときに，代わりに使う検索キーを持たせる
+    pub fn set_lookup_key(&mut self, lookup_key: Option<(String, String)>) {
+        self.lookup_key = lookup_key;
+    }
+    pub fn location(&self) -> &Location {
         &self.location
     }
+    pub fn status(&self) -> ClientStatus {
+        self.status
+    }
+    /// 遷移表で許可された場合のみ状態を変更し，発生したイベントを返す
+    pub fn change_status(
+        &mut self,
+        to: ClientStatus,
+        changed_at: DateTime<Utc>,
+    ) -> Result<ClientEvent, DomainError> {
+        let from = self.status;
+        if !from.can_transition_to(to) {
+            return Err(DomainError::InvalidStatusTransition(from, to));
+        }
+        self.status = to;
+        Ok(ClientEvent::StatusChanged {
+            client_id: self.id,
+            from,
+            to,
+            occurred_at: changed_at,
+        })
+    }
+    /// 統合によって削除されたクライアントのID
+    pub fn aliases(&self) -> &[Uuid] {
+        &self.aliases
+    }
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+    /// 同じ種類の連絡先のうち最初のものは主連絡先になる
+    pub fn add_contact(&mut self, info: ContactInfo) -> Uuid {
+        let primary = self.primary_contact(info.kind()).is_none();
+        let contact = Contact {
+            id: Uuid::new_v4(),
+            info,
+            primary,
+        };
+        let id = contact.id;
+        self.contacts.push(contact);
+        id
+    }
+    /// 主連絡先を削除した場合は同じ種類の次の連絡先が主連絡先になる
+    pub fn remove_contact(&mut self, contact_id: Uuid) -> Result<Contact, DomainError> {
+        let index = self
+            .contacts
+            .iter()
+            .position(|contact| contact.id == contact_id)
+            .ok_or(DomainError::ContactNotFound(contact_id))?;
+        let removed = self.contacts.remove(index);
+        if removed.primary {
+            if let Some(next) = self
+                .contacts
+                .iter_mut()
+                .find(|contact| contact.kind() == removed.kind())
+            {
+                next.primary = true;
+            }
+        }
+        Ok(removed)
+    }
+    pub fn set_primary_contact(&mut self, contact_id: Uuid) -> Result<(), DomainError> {
+        let kind = self
+            .contacts
+            .iter()
+            .find(|contact| contact.id == contact_id)
+            .map(Contact::kind)
+            .ok_or(DomainError::ContactNotFound(contact_id))?;
+        for contact in self.contacts.iter_mut().filter(|c| c.kind() == kind) {
+            contact.primary = contact.id == contact_id;
+        }
+        Ok(())
+    }
+    pub fn primary_contact(&self, kind: ContactKind) -> Option<&Contact> {
+        self.contacts
+            .iter()
+            .find(|contact| contact.kind() == kind && contact.primary)
+    }
+    pub fn tags(&self) -> &BTreeSet<Tag> {
+        &self.tags
+    }
+    pub fn has_tag(&self, tag: &Tag) -> bool {
+        self.tags.contains(tag)
+    }
+    /// 既に付いているタグの場合はfalseを返す
+    pub fn add_tag(&mut self, tag: Tag) -> bool {
+        self.tags.insert(tag)
+    }
+    pub fn remove_tag(&mut self, tag: &Tag) -> Result<(), DomainError> {
+        match self.tags.remove(tag) {
+            true => Ok(()),
+            false => Err(DomainError::TagNotFound(tag.to_string())),
+        }
+    }
+    /// 作成日時の古い順
+    pub fn notes(&self) -> Vec<&Note> {
+        let mut notes = self.notes.iter().collect::<Vec<_>>();
+        notes.sort_by_key(|note| note.created_at);
+        notes
+    }
+    pub fn note(&self, note_id: Uuid) -> Result<&Note, DomainError> {
+        self.notes
+            .iter()
+            .find(|note| note.id == note_id)
+            .ok_or(DomainError::NoteNotFound(note_id))
+    }
+    pub fn add_note(&mut self, body: &str, written_at: DateTime<Utc>) -> Result<Uuid, DomainError> {
+        let note = Note {
+            id: Uuid::new_v4(),
+            body: Note::validate(body)?,
+            created_at: written_at,
+            updated_at: None,
+        };
+        let id = note.id;
+        self.notes.push(note);
+        Ok(id)
+    }
+    pub fn edit_note(
+        &mut self,
+        note_id: Uuid,
+        body: &str,
+        edited_at: DateTime<Utc>,
+    ) -> Result<(), DomainError> {
+        let body = Note::validate(body)?;
+        let note = self
+            .notes
+            .iter_mut()
+            .find(|note| note.id == note_id)
+            .ok_or(DomainError::NoteNotFound(note_id))?;
+        note.body = body;
+        note.updated_at = Some(edited_at);
+        Ok(())
+    }
+    pub fn delete_note(&mut self, note_id: Uuid) -> Result<Note, DomainError> {
+        let index = self
+            .notes
+            .iter()
+            .position(|note| note.id == note_id)
+            .ok_or(DomainError::NoteNotFound(note_id))?;
+        Ok(self.notes.remove(index))
+    }
+    /// 別に持たせた検索キーは外す
     pub fn edit(&mut self, name: String, location: String) {
         self.name = name;
-        self.location = location;
+        self.location = Location::new(&location);
+        self.lookup_key = None;
+    }
+    /// 連絡先の値を置き換える．保存先での暗号化と復号に使い，値は検証しない
+    pub fn map_contact_infos<E>(
+        &mut self,
+        mut f: impl FnMut(&Contact) -> Result<ContactInfo, E>,
+    ) -> Result<(), E> {
+        for contact in self.contacts.iter_mut() {
+            contact.info = f(contact)?;
+        }
+        Ok(())
+    }
+    /// メモの本文を置き換える．保存先での暗号化と復号に使い，本文は検証しない
+    pub fn map_note_bodies<E>(
+        &mut self,
+        mut f: impl FnMut(&Note) -> Result<String, E>,
+    ) -> Result<(), E> {
+        for note in self.notes.iter_mut() {
+            note.body = f(note)?;
+        }
+        Ok(())
+    }
+    /// 他のクライアントを統合する．統合されたクライアントのIDとそのエイリアスは自身のエイリアスとして残し，
+    /// 重複しない連絡先とタグ，メモを引き継ぐ
+    pub fn merge(&mut self, other: Client) {
+        if other.id == self.id {
+            return;
+        }
+        self.tags.extend(other.tags);
+        self.notes.extend(other.notes);
+        for contact in other.contacts {
+            if self.contacts.iter().all(|c| c.info != contact.info) {
+                self.add_contact(contact.info);
+            }
+        }
+        self.aliases.push(other.id);
+        for alias in other.aliases {
+            if alias != self.id && !self.aliases.contains(&alias) {
+                self.aliases.push(alias);
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Project
+
+/// クライアントから受けた案件．クライアントとは別の集約としてIDで参照する
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Project {
+    id: Uuid,
+    client_id: Uuid,
+    name: String,
+    status: ProjectStatus,
+    created_at: DateTime<Utc>,
+    closed_at: Option<DateTime<Utc>>,
+}
+
+impl Project {
+    pub const MAX_NAME_LENGTH: usize = 100;
+
+    pub fn new(
+        client_id: Uuid,
+        name: &str,
+        created_at: DateTime<Utc>,
+    ) -> Result<Self, DomainError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > Self::MAX_NAME_LENGTH {
+            return Err(DomainError::InvalidProjectName(name.to_string()));
+        }
+        Ok(Self {
+            id: Uuid::new_v4(),
+            client_id,
+            name: name.to_string(),
+            status: ProjectStatus::Open,
+            created_at,
+            closed_at: None,
+        })
+    }
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub fn client_id(&self) -> Uuid {
+        self.client_id
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn status(&self) -> ProjectStatus {
+        self.status
+    }
+    pub fn is_open(&self) -> bool {
+        self.status == ProjectStatus::Open
+    }
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+    pub fn closed_at(&self) -> Option<DateTime<Utc>> {
+        self.closed_at
+    }
+    pub fn close(&mut self, closed_at: DateTime<Utc>) -> Result<(), DomainError> {
+        if !self.is_open() {
+            return Err(DomainError::ProjectAlreadyClosed(self.id));
+        }
+        self.status = ProjectStatus::Closed;
+        self.closed_at = Some(closed_at);
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// User
+
+/// アプリケーションを操作するユーザー
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    id: Uuid,
+    username: String,
+    password_hash: PasswordHash,
+    role: Role,
+}
+
+impl User {
+    pub fn new(username: &str, password: &str, role: Role) -> Result<Self, DomainError> {
+        let username = username.trim();
+        if username.is_empty() || username.chars().any(char::is_whitespace) {
+            return Err(DomainError::InvalidUsername(username.to_string()));
+        }
+        Ok(Self {
+            id: Uuid::new_v4(),
+            username: username.to_string(),
+            password_hash: PasswordHash::new(password)?,
+            role,
+        })
+    }
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+    pub fn role(&self) -> Role {
+        self.role
+    }
+    pub fn verify_password(&self, password: &str) -> bool {
+        self.password_hash.verify(password)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// AuditEntry
+
+/// ユーザーが実行した変更の記録．subject_idは変更の対象(クライアント，プロジェクトなど)のID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    user_id: Uuid,
+    username: String,
+    action: String,
+    subject_id: Option<Uuid>,
+    occurred_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    pub fn new(
+        user: &User,
+        action: &str,
+        subject_id: Option<Uuid>,
+        occurred_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            user_id: user.id(),
+            username: user.username().to_string(),
+            action: action.to_string(),
+            subject_id,
+            occurred_at,
+        }
+    }
+    pub fn user_id(&self) -> Uuid {
+        self.user_id
+    }
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+    pub fn subject_id(&self) -> Option<Uuid> {
+        self.subject_id
+    }
+    pub fn occurred_at(&self) -> DateTime<Utc> {
+        self.occurred_at
+    }
+    /// 対象のIDを外す．実行したユーザーと操作は残す
+    pub fn redact_subject(&mut self) {
+        self.subject_id = None;
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Tombstone
+
+/// 個人データを消去したクライアントの記録．消去したIDを存在しないIDと区別するために残す
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tombstone {
+    client_id: Uuid,
+    aliases: Vec<Uuid>,
+    erased_at: DateTime<Utc>,
+}
+
+impl Tombstone {
+    pub fn new(client: &Client, erased_at: DateTime<Utc>) -> Self {
+        Self {
+            client_id: client.id(),
+            aliases: client.aliases().to_vec(),
+            erased_at,
+        }
+    }
+    pub fn client_id(&self) -> Uuid {
+        self.client_id
+    }
+    /// クライアント自身と統合したクライアントのID
+    pub fn ids(&self) -> Vec<Uuid> {
+        std::iter::once(self.client_id)
+            .chain(self.aliases.iter().copied())
+            .collect()
+    }
+    /// 消去したIDで引いたときのエラー
+    pub fn error(&self, id: Uuid) -> String {
+        format!(
+            "Client #{} was erased at {}",
+            id.hyphenated(),
+            self.erased_at.to_rfc3339()
+        )
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// WebhookSubscription
+
+/// クライアントの変更を通知する外部のエンドポイント
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookSubscription {
+    id: Uuid,
+    url: String,
+    event_types: BTreeSet<String>,
+    secret: String,
+}
+
+impl WebhookSubscription {
+    pub fn new(url: &str, event_types: &[String], secret: &str) -> Result<Self, DomainError> {
+        let url = url.trim();
+        match url.strip_prefix("http://") {
+            Some(rest) if !rest.is_empty() && !rest.starts_with('/') => {}
+            _ => return Err(DomainError::InvalidWebhookUrl(url.to_string())),
+        }
+        if event_types.is_empty() {
+            return Err(DomainError::InvalidEventType(String::new()));
+        }
+        let event_types = event_types
+            .iter()
+            .map(
+                |event_type| match ClientEvent::TYPES.contains(&event_type.trim()) {
+                    true => Ok(event_type.trim().to_string()),
+                    false => Err(DomainError::InvalidEventType(event_type.clone())),
+                },
+            )
+            .collect::<Result<BTreeSet<_>, _>>()?;
+        if secret.is_empty() {
+            return Err(DomainError::InvalidWebhookSecret);
+        }
+        Ok(Self {
+            id: Uuid::new_v4(),
+            url: url.to_string(),
+            event_types,
+            secret: secret.to_string(),
+        })
+    }
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+    pub fn event_types(&self) -> &BTreeSet<String> {
+        &self.event_types
+    }
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+    pub fn is_subscribed_to(&self, event_type: &str) -> bool {
+        self.event_types.contains(event_type)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// WebhookDelivery
+
+/// Webhookへの1回の送信の記録
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    subscription_id: Uuid,
+    message_id: Uuid,
+    event_type: String,
+    attempt: u32,
+    /// 成功時はステータスコード，失敗時はエラー
+    result: Result<u16, String>,
+    attempted_at: DateTime<Utc>,
+}
+
+impl WebhookDelivery {
+    pub fn new(
+        subscription_id: Uuid,
+        message_id: Uuid,
+        event_type: &str,
+        attempt: u32,
+        result: Result<u16, String>,
+        attempted_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            subscription_id,
+            message_id,
+            event_type: event_type.to_string(),
+            attempt,
+            result,
+            attempted_at,
+        }
+    }
+    pub fn subscription_id(&self) -> Uuid {
+        self.subscription_id
+    }
+    pub fn message_id(&self) -> Uuid {
+        self.message_id
+    }
+    pub fn event_type(&self) -> &str {
+        &self.event_type
+    }
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+    pub fn result(&self) -> &Result<u16, String> {
+        &self.result
+    }
+    pub fn attempted_at(&self) -> DateTime<Utc> {
+        self.attempted_at
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// IdempotencyRecord
+
+/// 冪等キーで作成したクライアントと，そのときの警告．再送には作成した時点の内容を返す．
+/// 入力は同じキーの再利用を見分けるためのハッシュだけを持つ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    key: String,
+    fingerprint: String,
+    client: Client,
+    warnings: Vec<String>,
+}
+
+impl IdempotencyRecord {
+    pub fn new(
+        key: String,
+        name: &str,
+        location: &str,
+        client: Client,
+        warnings: Vec<String>,
+    ) -> Self {
+        Self {
+            key,
+            fingerprint: Self::fingerprint(name, location),
+            client,
+            warnings,
+        }
+    }
+    fn fingerprint(name: &str, location: &str) -> String {
+        Sha256::new()
+            .chain_update(name)
+            .chain_update([0])
+            .chain_update(location)
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+    /// 記録した作成と同じ名前と出身地か
+    pub fn matches(&self, name: &str, location: &str) -> bool {
+        self.fingerprint == Self::fingerprint(name, location)
+    }
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
     }
 }
 
 #[cfg(test)]
 mod test {
+    use assert_matches::assert_matches;
     use fake::{Fake, Faker};
 
-    use super::Client;
+    use super::{Client, Note, Project, WebhookSubscription};
+    use crate::domain::{ClientEvent, ClientStatus, ContactInfo, ContactKind, DomainError, Tag};
+    use chrono::{Duration, Utc};
     #[test]
     fn create_client() {
         let name = Faker.fake::<String>();
@@ -44,7 +734,7 @@ mod test {
         let client = Client::new(name.clone(), location.clone());
 
         assert_eq!(&name, client.name());
-        assert_eq!(&location, client.location());
+        assert_eq!(&location, client.location().as_str());
     }
 
     #[test]
@@ -56,12 +746,233 @@ mod test {
         let new_location = Faker.fake::<String>();
 
         assert_ne!(client.name(), &new_name);
-        assert_ne!(client.location(), &new_location);
+        assert_ne!(client.location().as_str(), &new_location);
 
         client.edit(new_name.clone(), new_location.clone());
 
         assert_eq!(client.id(), id);
         assert_eq!(client.name(), &new_name);
-        assert_eq!(client.location(), &new_location);
+        assert_eq!(client.location().as_str(), &new_location);
+    }
+
+    #[test]
+    fn merge_client() {
+        let mut client = Client::new(Faker.fake(), Faker.fake());
+        let mut other = Client::new(Faker.fake(), Faker.fake());
+        let another = Client::new(Faker.fake(), Faker.fake());
+
+        let id = client.id();
+        let name = client.name().to_string();
+        let other_id = other.id();
+        let another_id = another.id();
+
+        other.merge(another);
+        client.merge(other);
+
+        assert_eq!(client.id(), id);
+        assert_eq!(client.name(), &name);
+        assert_eq!(client.aliases(), &[other_id, another_id]);
+
+        // 自分自身は統合しない
+        client.merge(client.clone());
+        assert_eq!(client.aliases(), &[other_id, another_id]);
+    }
+
+    #[test]
+    fn client_contacts() {
+        let mut client = Client::new(Faker.fake(), Faker.fake());
+        let email = ContactInfo::parse(ContactKind::Email, "taro@example.com").unwrap();
+        let email2 = ContactInfo::parse(ContactKind::Email, "taro@example.jp").unwrap();
+        let phone = ContactInfo::parse(ContactKind::Phone, "090-1234-5678").unwrap();
+
+        let email_id = client.add_contact(email);
+        let email2_id = client.add_contact(email2);
+        let phone_id = client.add_contact(phone);
+
+        // 種類ごとに最初の連絡先が主連絡先
+        assert_eq!(client.contacts().len(), 3);
+        assert_eq!(
+            client.primary_contact(ContactKind::Email).unwrap().id(),
+            email_id
+        );
+        assert_eq!(
+            client.primary_contact(ContactKind::Phone).unwrap().id(),
+            phone_id
+        );
+        assert!(client.primary_contact(ContactKind::PostalAddress).is_none());
+
+        // 主連絡先の変更は同じ種類にのみ影響する
+        client.set_primary_contact(email2_id).unwrap();
+        assert_eq!(
+            client.primary_contact(ContactKind::Email).unwrap().id(),
+            email2_id
+        );
+        assert_eq!(
+            client.primary_contact(ContactKind::Phone).unwrap().id(),
+            phone_id
+        );
+
+        // 主連絡先を削除すると次の連絡先が主連絡先になる
+        client.remove_contact(email2_id).unwrap();
+        assert_eq!(
+            client.primary_contact(ContactKind::Email).unwrap().id(),
+            email_id
+        );
+
+        assert_matches!(client.remove_contact(email2_id), Err(_));
+        assert_matches!(client.set_primary_contact(email2_id), Err(_));
+    }
+
+    #[test]
+    fn client_tags() {
+        let mut client = Client::new(Faker.fake(), Faker.fake());
+        let vip = Tag::new("VIP").unwrap();
+        let prospect = Tag::new("prospect").unwrap();
+
+        assert!(client.add_tag(vip.clone()));
+        assert!(!client.add_tag(Tag::new("vip").unwrap()));
+        assert!(client.add_tag(prospect.clone()));
+        assert_eq!(
+            client.tags().iter().collect::<Vec<_>>(),
+            vec![&prospect, &vip]
+        );
+
+        client.remove_tag(&vip).unwrap();
+        assert!(!client.has_tag(&vip));
+        assert!(client.has_tag(&prospect));
+        assert_matches!(client.remove_tag(&vip), Err(_));
+
+        // 統合すると両方のタグが残る
+        let mut other = Client::new(Faker.fake(), Faker.fake());
+        other.add_tag(vip.clone());
+        client.merge(other);
+        assert!(client.has_tag(&vip) && client.has_tag(&prospect));
+    }
+
+    #[test]
+    fn client_notes() {
+        let mut client = Client::new(Faker.fake(), Faker.fake());
+        let now = Utc::now();
+
+        let later_id = client.add_note("wants quote", now).unwrap();
+        let earlier_id = client
+            .add_note(" called on 10/3 ", now - Duration::days(1))
+            .unwrap();
+
+        // 作成日時の古い順
+        let notes = client.notes();
+        assert_eq!(
+            notes.iter().map(|note| note.id()).collect::<Vec<_>>(),
+            vec![earlier_id, later_id]
+        );
+        assert_eq!(notes[0].body(), "called on 10/3");
+
+        client
+            .edit_note(later_id, "sent quote", now + Duration::hours(1))
+            .unwrap();
+        let note = client.notes()[1].clone();
+        assert_eq!(note.body(), "sent quote");
+        assert_eq!(note.created_at(), now);
+        assert_eq!(note.updated_at(), Some(now + Duration::hours(1)));
+
+        client.delete_note(earlier_id).unwrap();
+        assert_eq!(client.notes().len(), 1);
+
+        assert_matches!(client.add_note("  ", now), Err(_));
+        assert_matches!(
+            client.add_note(&"a".repeat(Note::MAX_LENGTH + 1), now),
+            Err(_)
+        );
+        assert_matches!(client.edit_note(earlier_id, "body", now), Err(_));
+        assert_matches!(client.delete_note(earlier_id), Err(_));
+    }
+
+    #[test]
+    fn client_status() {
+        let mut client = Client::new(Faker.fake(), Faker.fake());
+        assert_eq!(client.status(), ClientStatus::Prospect);
+
+        let now = Utc::now();
+        let event = client.change_status(ClientStatus::Active, now).unwrap();
+        assert_eq!(client.status(), ClientStatus::Active);
+        assert_eq!(
+            event,
+            ClientEvent::StatusChanged {
+                client_id: client.id(),
+                from: ClientStatus::Prospect,
+                to: ClientStatus::Active,
+                occurred_at: now,
+            }
+        );
+
+        // 許可されていない遷移
+        assert_eq!(
+            client.change_status(ClientStatus::Prospect, now),
+            Err(DomainError::InvalidStatusTransition(
+                ClientStatus::Active,
+                ClientStatus::Prospect
+            ))
+        );
+        assert_eq!(client.status(), ClientStatus::Active);
+
+        client.change_status(ClientStatus::Archived, now).unwrap();
+        assert_matches!(client.change_status(ClientStatus::Active, now), Err(_));
+    }
+
+    #[test]
+    fn project_lifecycle() {
+        let client = Client::new(Faker.fake(), Faker.fake());
+        let now = Utc::now();
+
+        let mut project = Project::new(client.id(), " Website renewal ", now).unwrap();
+        assert_eq!(project.client_id(), client.id());
+        assert_eq!(project.name(), "Website renewal");
+        assert!(project.is_open());
+
+        project.close(now).unwrap();
+        assert!(!project.is_open());
+        assert_eq!(project.closed_at(), Some(now));
+        assert_eq!(
+            project.close(now),
+            Err(DomainError::ProjectAlreadyClosed(project.id()))
+        );
+
+        assert_matches!(Project::new(client.id(), " ", now), Err(_));
+        assert_matches!(
+            Project::new(client.id(), &"a".repeat(Project::MAX_NAME_LENGTH + 1), now),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn webhook_subscription_new() {
+        let event_types = vec!["client.created".to_string(), "client.deleted".to_string()];
+        let subscription =
+            WebhookSubscription::new(" http://localhost:8080/hook ", &event_types, "secret")
+                .unwrap();
+        assert_eq!(subscription.url(), "http://localhost:8080/hook");
+        assert!(subscription.is_subscribed_to("client.created"));
+        assert!(!subscription.is_subscribed_to("client.edited"));
+
+        assert_matches!(
+            WebhookSubscription::new("https://example.com", &event_types, "secret"),
+            Err(DomainError::InvalidWebhookUrl(_))
+        );
+        assert_matches!(
+            WebhookSubscription::new("http://", &event_types, "secret"),
+            Err(DomainError::InvalidWebhookUrl(_))
+        );
+        assert_matches!(
+            WebhookSubscription::new("http://localhost", &["client.merged".to_string()], "secret"),
+            Err(DomainError::InvalidEventType(event_type)) if event_type == "client.merged"
+        );
+        assert_matches!(
+            WebhookSubscription::new("http://localhost", &[], "secret"),
+            Err(DomainError::InvalidEventType(_))
+        );
+        assert_matches!(
+            WebhookSubscription::new("http://localhost", &event_types, ""),
+            Err(DomainError::InvalidWebhookSecret)
+        );
     }
 }
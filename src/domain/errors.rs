@@ -0,0 +1,84 @@
+use crate::domain::{ClientEvent, ClientStatus};
+use std::fmt::Display;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomainError {
+    InvalidEmail(String),
+    InvalidPhoneNumber(String),
+    InvalidPostalCode(String),
+    InvalidPostalAddress(String),
+    ContactNotFound(Uuid),
+    InvalidTag(String),
+    TagNotFound(String),
+    InvalidNote(String),
+    NoteNotFound(Uuid),
+    InvalidStatus(String),
+    InvalidStatusTransition(ClientStatus, ClientStatus),
+    InvalidProjectName(String),
+    ProjectAlreadyClosed(Uuid),
+    InvalidRelationship(String),
+    RelationshipNotFound,
+    InvalidWebhookUrl(String),
+    InvalidEventType(String),
+    InvalidWebhookSecret,
+    InvalidUsername(String),
+    InvalidPassword(String),
+    InvalidRole(String),
+    InvalidTenantId(String),
+}
+
+impl Display for DomainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DomainError::InvalidEmail(email) => write!(f, "Invalid email address: {}", email),
+            DomainError::InvalidPhoneNumber(phone) => write!(f, "Invalid phone number: {}", phone),
+            DomainError::InvalidPostalCode(code) => write!(f, "Invalid postal code: {}", code),
+            DomainError::InvalidPostalAddress(address) => {
+                write!(f, "Invalid postal address: {}", address)
+            }
+            DomainError::ContactNotFound(id) => {
+                write!(f, "No contact found for given ID: {}", id.hyphenated())
+            }
+            DomainError::InvalidTag(tag) => write!(f, "Invalid tag: {}", tag),
+            DomainError::TagNotFound(tag) => write!(f, "No tag found: {}", tag),
+            DomainError::InvalidNote(reason) => write!(f, "Invalid note: {}", reason),
+            DomainError::NoteNotFound(id) => {
+                write!(f, "No note found for given ID: {}", id.hyphenated())
+            }
+            DomainError::InvalidStatus(status) => write!(f, "Invalid status: {}", status),
+            DomainError::InvalidStatusTransition(from, to) => {
+                write!(f, "Cannot change status from {} to {}", from, to)
+            }
+            DomainError::InvalidProjectName(name) => write!(f, "Invalid project name: {}", name),
+            DomainError::ProjectAlreadyClosed(id) => {
+                write!(f, "Project is already closed: {}", id.hyphenated())
+            }
+            DomainError::InvalidRelationship(reason) => {
+                write!(f, "Invalid relationship: {}", reason)
+            }
+            DomainError::RelationshipNotFound => write!(f, "No relationship found"),
+            DomainError::InvalidWebhookUrl(url) => write!(f, "Invalid webhook URL: {}", url),
+            DomainError::InvalidEventType(event_type) => write!(
+                f,
+                "Invalid event type: {} (expected one of {})",
+                event_type,
+                ClientEvent::TYPES.join(", ")
+            ),
+            DomainError::InvalidWebhookSecret => write!(f, "Webhook secret must not be empty"),
+            DomainError::InvalidUsername(username) => write!(f, "Invalid username: {}", username),
+            DomainError::InvalidPassword(reason) => write!(f, "Invalid password: {}", reason),
+            DomainError::InvalidRole(role) => write!(f, "Invalid role: {}", role),
+            DomainError::InvalidTenantId(tenant_id) => write!(f, "Invalid tenant: {}", tenant_id),
+        }
+    }
+}
+
+impl std::error::Error for DomainError {}
+
+// ハンドラーのエラーはStringなので変換できるようにする
+impl From<DomainError> for String {
+    fn from(err: DomainError) -> String {
+        err.to_string()
+    }
+}
@@ -0,0 +1,259 @@
+use crate::domain::{Client, ClientStatus};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(test)]
+use mockall::automock;
+
+/// クライアントに起きた出来事
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClientEvent {
+    Created {
+        client_id: Uuid,
+        name: String,
+        location: String,
+        occurred_at: DateTime<Utc>,
+    },
+    Edited {
+        client_id: Uuid,
+        name: String,
+        location: String,
+        occurred_at: DateTime<Utc>,
+    },
+    Deleted {
+        client_id: Uuid,
+        occurred_at: DateTime<Utc>,
+    },
+    StatusChanged {
+        client_id: Uuid,
+        from: ClientStatus,
+        to: ClientStatus,
+        occurred_at: DateTime<Utc>,
+    },
+    /// 個人データの消去．消去したことだけを残す
+    Erased {
+        client_id: Uuid,
+        occurred_at: DateTime<Utc>,
+    },
+}
+
+impl ClientEvent {
+    /// 外部に通知するイベントの種類
+    pub const TYPES: [&'static str; 5] = [
+        "client.created",
+        "client.edited",
+        "client.deleted",
+        "client.status_changed",
+        "client.erased",
+    ];
+    /// 消去した名前や出身地の代わりに残す値
+    pub const REDACTED: &'static str = "[erased]";
+
+    pub fn created(client: &Client, occurred_at: DateTime<Utc>) -> Self {
+        ClientEvent::Created {
+            client_id: client.id(),
+            name: client.name().to_string(),
+            location: client.location().as_str().to_string(),
+            occurred_at,
+        }
+    }
+    pub fn edited(client: &Client, occurred_at: DateTime<Utc>) -> Self {
+        ClientEvent::Edited {
+            client_id: client.id(),
+            name: client.name().to_string(),
+            location: client.location().as_str().to_string(),
+            occurred_at,
+        }
+    }
+    pub fn deleted(client_id: Uuid, occurred_at: DateTime<Utc>) -> Self {
+        ClientEvent::Deleted {
+            client_id,
+            occurred_at,
+        }
+    }
+    pub fn erased(client_id: Uuid, occurred_at: DateTime<Utc>) -> Self {
+        ClientEvent::Erased {
+            client_id,
+            occurred_at,
+        }
+    }
+    pub fn client_id(&self) -> Uuid {
+        match self {
+            ClientEvent::Created { client_id, .. }
+            | ClientEvent::Edited { client_id, .. }
+            | ClientEvent::Deleted { client_id, .. }
+            | ClientEvent::StatusChanged { client_id, .. }
+            | ClientEvent::Erased { client_id, .. } => *client_id,
+        }
+    }
+    pub fn occurred_at(&self) -> DateTime<Utc> {
+        match self {
+            ClientEvent::Created { occurred_at, .. }
+            | ClientEvent::Edited { occurred_at, .. }
+            | ClientEvent::Deleted { occurred_at, .. }
+            | ClientEvent::StatusChanged { occurred_at, .. }
+            | ClientEvent::Erased { occurred_at, .. } => *occurred_at,
+        }
+    }
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            ClientEvent::Created { .. } => Self::TYPES[0],
+            ClientEvent::Edited { .. } => Self::TYPES[1],
+            ClientEvent::Deleted { .. } => Self::TYPES[2],
+            ClientEvent::StatusChanged { .. } => Self::TYPES[3],
+            ClientEvent::Erased { .. } => Self::TYPES[4],
+        }
+    }
+    /// 個人データを含むか
+    pub fn has_personal_data(&self) -> bool {
+        match self {
+            ClientEvent::Created { name, location, .. }
+            | ClientEvent::Edited { name, location, .. } => {
+                name != Self::REDACTED || location != Self::REDACTED
+            }
+            _ => false,
+        }
+    }
+    /// 名前と出身地を消去する
+    pub fn redact(&mut self) {
+        if let ClientEvent::Created { name, location, .. }
+        | ClientEvent::Edited { name, location, .. } = self
+        {
+            *name = Self::REDACTED.to_string();
+            *location = Self::REDACTED.to_string();
+        }
+    }
+}
+
+#[cfg_attr(test, automock)]
+pub trait EventPublisher {
+    fn publish(&self, event: ClientEvent);
+}
+
+/// 配信されたイベントの履歴
+#[cfg_attr(test, automock)]
+pub trait EventHistory {
+    /// 配信した順
+    fn events(&self) -> Vec<ClientEvent>;
+}
+
+/// 配信待ちのイベント．配信に成功するまで保持し，失敗した場合は届いていない配信先にのみ再送する
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutboxMessage {
+    id: Uuid,
+    event: ClientEvent,
+    attempts: u32,
+    last_error: Option<String>,
+    delivered_to: Vec<String>,
+    delivered_at: Option<DateTime<Utc>>,
+    dead_lettered_at: Option<DateTime<Utc>>,
+    next_attempt_at: Option<DateTime<Utc>>,
+    requeued_at: Option<DateTime<Utc>>,
+}
+
+impl OutboxMessage {
+    pub fn new(event: ClientEvent) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            event,
+            attempts: 0,
+            last_error: None,
+            delivered_to: Vec::new(),
+            delivered_at: None,
+            dead_lettered_at: None,
+            next_attempt_at: None,
+            requeued_at: None,
+        }
+    }
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub fn event(&self) -> &ClientEvent {
+        &self.event
+    }
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+    /// 配信に成功した配信先の名前
+    pub fn delivered_to(&self) -> &[String] {
+        &self.delivered_to
+    }
+    pub fn is_delivered_to(&self, sink: &str) -> bool {
+        self.delivered_to.iter().any(|name| name == sink)
+    }
+    pub fn delivered_at(&self) -> Option<DateTime<Utc>> {
+        self.delivered_at
+    }
+    /// 配信を諦めた日時
+    pub fn dead_lettered_at(&self) -> Option<DateTime<Utc>> {
+        self.dead_lettered_at
+    }
+    /// 失敗した配信を次に試みる日時
+    pub fn next_attempt_at(&self) -> Option<DateTime<Utc>> {
+        self.next_attempt_at
+    }
+    /// 配信済みのメッセージを全ての配信先に送り直すことにした日時
+    pub fn requeued_at(&self) -> Option<DateTime<Utc>> {
+        self.requeued_at
+    }
+    pub fn is_pending(&self) -> bool {
+        self.delivered_at.is_none() && self.dead_lettered_at.is_none()
+    }
+    /// 配信待ちで，再試行の日時を過ぎている
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.is_pending() && self.next_attempt_at.is_none_or(|at| at <= now)
+    }
+    /// 配信先に届いたことを記録する．全ての配信先に届いたかどうかは`mark_delivered`で記録する
+    pub fn mark_delivered_to(&mut self, sink: String) {
+        if !self.is_delivered_to(&sink) {
+            self.delivered_to.push(sink);
+        }
+    }
+    pub fn mark_delivered(&mut self, at: DateTime<Utc>) {
+        self.attempts += 1;
+        self.last_error = None;
+        self.delivered_at = Some(at);
+        self.next_attempt_at = None;
+    }
+    pub fn mark_failed(&mut self, error: String) {
+        self.attempts += 1;
+        self.last_error = Some(error);
+    }
+    /// 次の配信を`at`まで待つ
+    pub fn schedule_retry(&mut self, at: DateTime<Utc>) {
+        self.next_attempt_at = Some(at);
+    }
+    /// 配信を諦め，配信待ちから外す
+    pub fn mark_dead_lettered(&mut self, at: DateTime<Utc>) {
+        self.dead_lettered_at = Some(at);
+        self.next_attempt_at = None;
+    }
+    /// イベントに含まれる名前と出身地を消去する．配信の状態は変えない
+    pub fn redact(&mut self) {
+        self.event.redact();
+    }
+    /// 配信済みまたは配信を諦めたメッセージをもう一度配信待ちにする．
+    /// 配信済みなら全ての配信先に送り直し，そうでなければ届いていない配信先にのみ送る
+    pub fn requeue(&mut self, at: DateTime<Utc>) {
+        if self.delivered_at.take().is_some() {
+            self.delivered_to.clear();
+            self.requeued_at = Some(at);
+        }
+        self.dead_lettered_at = None;
+        self.next_attempt_at = None;
+    }
+}
+
+/// アウトボックスのメッセージの配信先
+#[cfg_attr(test, automock)]
+pub trait EventSink {
+    fn name(&self) -> String;
+    fn deliver(&self, message: &OutboxMessage) -> Result<(), String>;
+    /// 配信先に残っている記録から指定したクライアントの名前と出身地を消去し，消去した件数を返す．
+    /// 記録を手元に残さない配信先は何もしない
+    fn redact(&self, client_ids: &[Uuid]) -> Result<usize, String>;
+}
@@ -0,0 +1,351 @@
+//! 地名の表記揺れを吸収するためのオフライン地名辞書
+//!
+//! 別名はすべて`normalize_text`で正規化した形(小文字・ひらがな)で持つ
+
+// -------------------------------------------------------------------------------------------------
+// 国
+
+pub struct CountryEntry {
+    pub code: &'static str,
+    pub aliases: &'static [&'static str],
+}
+
+pub static COUNTRIES: &[CountryEntry] = &[
+    CountryEntry {
+        code: "JP",
+        aliases: &["japan", "jp", "jpn", "日本", "にほん", "にっぽん", "日本国"],
+    },
+    CountryEntry {
+        code: "US",
+        aliases: &[
+            "united states",
+            "united states of america",
+            "usa",
+            "us",
+            "america",
+            "あめりか",
+            "米国",
+            "あめりか合衆国",
+        ],
+    },
+    CountryEntry {
+        code: "GB",
+        aliases: &[
+            "united kingdom",
+            "uk",
+            "great britain",
+            "britain",
+            "いぎりす",
+            "英国",
+        ],
+    },
+    CountryEntry {
+        code: "FR",
+        aliases: &["france", "ふらんす", "仏国"],
+    },
+    CountryEntry {
+        code: "DE",
+        aliases: &["germany", "どいつ", "独国"],
+    },
+    CountryEntry {
+        code: "KR",
+        aliases: &["south korea", "korea", "かんこく", "韓国", "대한민국"],
+    },
+    CountryEntry {
+        code: "CN",
+        aliases: &["china", "中国", "ちゅうごく"],
+    },
+    CountryEntry {
+        code: "TW",
+        aliases: &["taiwan", "台湾", "たいわん"],
+    },
+    CountryEntry {
+        code: "HK",
+        aliases: &["hong kong", "香港", "ほんこん"],
+    },
+    CountryEntry {
+        code: "SG",
+        aliases: &["singapore", "しんがぽーる"],
+    },
+    CountryEntry {
+        code: "AU",
+        aliases: &["australia", "おーすとらりあ", "豪州"],
+    },
+    CountryEntry {
+        code: "CA",
+        aliases: &["canada", "かなだ"],
+    },
+    CountryEntry {
+        code: "TH",
+        aliases: &["thailand", "たい", "たい王国"],
+    },
+];
+
+// -------------------------------------------------------------------------------------------------
+// 都道府県
+
+pub struct PrefectureEntry {
+    /// 正式名称
+    pub name: &'static str,
+    /// 「都道府県」を除いた名称
+    pub short_name: &'static str,
+    pub aliases: &'static [&'static str],
+}
+
+macro_rules! prefecture {
+    ($name:literal, $short:literal, [$($alias:literal),*]) => {
+        PrefectureEntry {
+            name: $name,
+            short_name: $short,
+            aliases: &[$($alias),*],
+        }
+    };
+}
+
+pub static PREFECTURES: &[PrefectureEntry] = &[
+    prefecture!("北海道", "北海道", ["ほっかいどう", "hokkaido"]),
+    prefecture!("青森県", "青森", ["あおもり", "aomori"]),
+    prefecture!("岩手県", "岩手", ["いわて", "iwate"]),
+    prefecture!("宮城県", "宮城", ["みやぎ", "miyagi"]),
+    prefecture!("秋田県", "秋田", ["あきた", "akita"]),
+    prefecture!("山形県", "山形", ["やまがた", "yamagata"]),
+    prefecture!("福島県", "福島", ["ふくしま", "fukushima"]),
+    prefecture!("茨城県", "茨城", ["いばらき", "ibaraki"]),
+    prefecture!("栃木県", "栃木", ["とちぎ", "tochigi"]),
+    prefecture!("群馬県", "群馬", ["ぐんま", "gunma"]),
+    prefecture!("埼玉県", "埼玉", ["さいたま", "saitama"]),
+    prefecture!("千葉県", "千葉", ["ちば", "chiba"]),
+    prefecture!("東京都", "東京", ["とうきょう", "tokyo", "toukyou"]),
+    prefecture!("神奈川県", "神奈川", ["かながわ", "kanagawa"]),
+    prefecture!("新潟県", "新潟", ["にいがた", "niigata"]),
+    prefecture!("富山県", "富山", ["とやま", "toyama"]),
+    prefecture!("石川県", "石川", ["いしかわ", "ishikawa"]),
+    prefecture!("福井県", "福井", ["ふくい", "fukui"]),
+    prefecture!("山梨県", "山梨", ["やまなし", "yamanashi"]),
+    prefecture!("長野県", "長野", ["ながの", "nagano"]),
+    prefecture!("岐阜県", "岐阜", ["ぎふ", "gifu"]),
+    prefecture!("静岡県", "静岡", ["しずおか", "shizuoka"]),
+    prefecture!("愛知県", "愛知", ["あいち", "aichi"]),
+    prefecture!("三重県", "三重", ["みえ", "mie"]),
+    prefecture!("滋賀県", "滋賀", ["しが", "shiga"]),
+    prefecture!("京都府", "京都", ["きょうと", "kyoto"]),
+    prefecture!("大阪府", "大阪", ["おおさか", "osaka"]),
+    prefecture!("兵庫県", "兵庫", ["ひょうご", "hyogo", "hyougo"]),
+    prefecture!("奈良県", "奈良", ["なら", "nara"]),
+    prefecture!("和歌山県", "和歌山", ["わかやま", "wakayama"]),
+    prefecture!("鳥取県", "鳥取", ["とっとり", "tottori"]),
+    prefecture!("島根県", "島根", ["しまね", "shimane"]),
+    prefecture!("岡山県", "岡山", ["おかやま", "okayama"]),
+    prefecture!("広島県", "広島", ["ひろしま", "hiroshima"]),
+    prefecture!("山口県", "山口", ["やまぐち", "yamaguchi"]),
+    prefecture!("徳島県", "徳島", ["とくしま", "tokushima"]),
+    prefecture!("香川県", "香川", ["かがわ", "kagawa"]),
+    prefecture!("愛媛県", "愛媛", ["えひめ", "ehime"]),
+    prefecture!("高知県", "高知", ["こうち", "kochi", "kouchi"]),
+    prefecture!("福岡県", "福岡", ["ふくおか", "fukuoka"]),
+    prefecture!("佐賀県", "佐賀", ["さが", "saga"]),
+    prefecture!("長崎県", "長崎", ["ながさき", "nagasaki"]),
+    prefecture!("熊本県", "熊本", ["くまもと", "kumamoto"]),
+    prefecture!("大分県", "大分", ["おおいた", "oita", "ooita"]),
+    prefecture!("宮崎県", "宮崎", ["みやざき", "miyazaki"]),
+    prefecture!("鹿児島県", "鹿児島", ["かごしま", "kagoshima"]),
+    prefecture!("沖縄県", "沖縄", ["おきなわ", "okinawa"]),
+];
+
+// -------------------------------------------------------------------------------------------------
+// 主要都市
+
+pub struct CityEntry {
+    pub name: &'static str,
+    pub country: &'static str,
+    pub region: &'static str,
+    pub aliases: &'static [&'static str],
+}
+
+macro_rules! city {
+    ($name:literal, $country:literal, $region:literal, [$($alias:literal),*]) => {
+        CityEntry {
+            name: $name,
+            country: $country,
+            region: $region,
+            aliases: &[$($alias),*],
+        }
+    };
+}
+
+pub static CITIES: &[CityEntry] = &[
+    // 国内(都道府県と同名の都市は「市」付きのみ)
+    city!("札幌市", "JP", "北海道", ["札幌", "さっぽろ", "sapporo"]),
+    city!("仙台市", "JP", "宮城県", ["仙台", "せんだい", "sendai"]),
+    city!(
+        "さいたま市",
+        "JP",
+        "埼玉県",
+        ["saitama city", "saitama-shi"]
+    ),
+    city!("千葉市", "JP", "千葉県", ["chiba city", "chiba-shi"]),
+    city!("横浜市", "JP", "神奈川県", ["横浜", "よこはま", "yokohama"]),
+    city!("川崎市", "JP", "神奈川県", ["川崎", "かわさき", "kawasaki"]),
+    city!("名古屋市", "JP", "愛知県", ["名古屋", "なごや", "nagoya"]),
+    city!("京都市", "JP", "京都府", ["kyoto city", "kyoto-shi"]),
+    city!("大阪市", "JP", "大阪府", ["osaka city", "osaka-shi"]),
+    city!("神戸市", "JP", "兵庫県", ["神戸", "こうべ", "kobe"]),
+    city!(
+        "広島市",
+        "JP",
+        "広島県",
+        ["hiroshima city", "hiroshima-shi"]
+    ),
+    city!(
+        "北九州市",
+        "JP",
+        "福岡県",
+        ["北九州", "きたきゅうしゅう", "kitakyushu"]
+    ),
+    city!("福岡市", "JP", "福岡県", ["fukuoka city", "fukuoka-shi"]),
+    city!("那覇市", "JP", "沖縄県", ["那覇", "なは", "naha"]),
+    // 海外
+    city!(
+        "New York",
+        "US",
+        "New York",
+        ["new york", "new york city", "nyc", "にゅーよーく"]
+    ),
+    city!(
+        "Los Angeles",
+        "US",
+        "California",
+        ["los angeles", "ろさんぜるす"]
+    ),
+    city!(
+        "San Francisco",
+        "US",
+        "California",
+        ["san francisco", "さんふらんしすこ"]
+    ),
+    city!("London", "GB", "England", ["london", "ろんどん"]),
+    city!("Paris", "FR", "Île-de-France", ["paris", "ぱり"]),
+    city!("Berlin", "DE", "Berlin", ["berlin", "べるりん"]),
+    city!("Seoul", "KR", "Seoul", ["seoul", "そうる", "서울"]),
+    city!("Beijing", "CN", "Beijing", ["beijing", "北京", "ぺきん"]),
+    city!(
+        "Shanghai",
+        "CN",
+        "Shanghai",
+        ["shanghai", "上海", "しゃんはい"]
+    ),
+    city!("Taipei", "TW", "Taipei", ["taipei", "台北", "たいぺい"]),
+    city!(
+        "Singapore",
+        "SG",
+        "Singapore",
+        ["singapore", "しんがぽーる"]
+    ),
+    city!("Sydney", "AU", "New South Wales", ["sydney", "しどにー"]),
+    city!("Toronto", "CA", "Ontario", ["toronto", "とろんと"]),
+    city!("Bangkok", "TH", "Bangkok", ["bangkok", "ばんこく"]),
+];
+
+// -------------------------------------------------------------------------------------------------
+// 検索
+
+// ローマ字表記の都道府県名に付く接尾辞
+const LATIN_SUFFIXES: &[&str] = &[
+    " prefecture",
+    " metropolis",
+    "-ken",
+    " ken",
+    "-fu",
+    " fu",
+    "-to",
+    " to",
+];
+
+/// 正規化済みの文字列に完全一致する国を返す
+pub fn find_country(normalized: &str) -> Option<&'static CountryEntry> {
+    COUNTRIES
+        .iter()
+        .find(|entry| entry.aliases.contains(&normalized))
+}
+
+/// 正規化済みの文字列に完全一致する都市を返す
+pub fn find_city(normalized: &str) -> Option<&'static CityEntry> {
+    CITIES
+        .iter()
+        .find(|entry| entry.name == normalized || entry.aliases.contains(&normalized))
+}
+
+/// 正規化済みの文字列に完全一致する都道府県を返す
+pub fn find_prefecture(normalized: &str) -> Option<&'static PrefectureEntry> {
+    let stripped = LATIN_SUFFIXES
+        .iter()
+        .find_map(|suffix| normalized.strip_suffix(suffix))
+        .unwrap_or(normalized);
+    PREFECTURES.iter().find(|entry| {
+        entry.name == normalized
+            || entry.short_name == normalized
+            || entry.aliases.contains(&normalized)
+            || entry.aliases.contains(&stripped)
+    })
+}
+
+/// 「東京都渋谷区」のように都道府県名で始まる場合，都道府県と残りの部分を返す
+pub fn find_prefecture_prefix(normalized: &str) -> Option<(&'static PrefectureEntry, &str)> {
+    // 正式名称を優先し，長いものから一致させる
+    let mut candidates = PREFECTURES
+        .iter()
+        .flat_map(|entry| [(entry, entry.name), (entry, entry.short_name)])
+        .filter_map(|(entry, name)| {
+            normalized
+                .strip_prefix(name)
+                .map(|rest| (entry, name, rest))
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|(_, name, _)| std::cmp::Reverse(name.chars().count()));
+    candidates
+        .into_iter()
+        .next()
+        .map(|(entry, _, rest)| (entry, rest.trim()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        find_city, find_country, find_prefecture, find_prefecture_prefix, CITIES, PREFECTURES,
+    };
+    use crate::domain::normalize_text;
+
+    #[test]
+    fn gazetteer_data_is_normalized() {
+        assert_eq!(PREFECTURES.len(), 47);
+        for alias in PREFECTURES
+            .iter()
+            .flat_map(|entry| entry.aliases.iter())
+            .chain(CITIES.iter().flat_map(|entry| entry.aliases.iter()))
+        {
+            assert_eq!(&normalize_text(alias), alias);
+        }
+        for city in CITIES.iter().filter(|city| city.country == "JP") {
+            assert!(PREFECTURES.iter().any(|entry| entry.name == city.region));
+        }
+    }
+
+    #[test]
+    fn find_entries() {
+        assert_eq!(find_prefecture("tokyo").unwrap().name, "東京都");
+        assert_eq!(find_prefecture("tokyo-to").unwrap().name, "東京都");
+        assert_eq!(find_prefecture("東京").unwrap().name, "東京都");
+        assert_eq!(find_prefecture("hyogo prefecture").unwrap().name, "兵庫県");
+        assert!(find_prefecture("shibuya").is_none());
+
+        assert_eq!(find_city("yokohama").unwrap().name, "横浜市");
+        assert_eq!(find_city("にゅーよーく").unwrap().name, "New York");
+        assert_eq!(find_country("日本").unwrap().code, "JP");
+
+        let (entry, rest) = find_prefecture_prefix("東京都渋谷区").unwrap();
+        assert_eq!((entry.name, rest), ("東京都", "渋谷区"));
+        let (entry, rest) = find_prefecture_prefix("京都府京都市").unwrap();
+        assert_eq!((entry.name, rest), ("京都府", "京都市"));
+        assert!(find_prefecture_prefix("渋谷区").is_none());
+    }
+}
@@ -1,12 +1,110 @@
-use crate::domain::Client;
+use crate::domain::{
+    AuditEntry, Client, IdempotencyRecord, OutboxMessage, Project, Relationship, Tombstone, User,
+    WebhookDelivery, WebhookSubscription,
+};
 use uuid::Uuid;
 
 #[cfg(test)]
 use mockall::automock;
 
+/// 作業単位に参加するリポジトリ．beginは入れ子にでき，commitとrollbackは直近のbeginに対応する
+#[cfg_attr(test, automock)]
+pub trait Transactional {
+    fn begin(&self);
+    fn commit(&self);
+    /// 直近のbegin以降の変更を全て取り消す
+    fn rollback(&self);
+}
+
 #[cfg_attr(test, automock)]
 pub trait ClientRepository {
     fn by_id(&self, id: Uuid) -> Result<Client, String>;
-    fn save(&self, client: Client);
+    fn save(&self, client: Client) -> Result<(), String>;
+    fn delete(&self, id: Uuid) -> Result<(), String>;
     fn all(&self) -> Vec<Client>;
+    /// 正規化した名前と出身地が一致するクライアント
+    fn by_name_and_location(&self, name: &str, location: &str) -> Vec<Client>;
+}
+
+#[cfg_attr(test, automock)]
+pub trait ProjectRepository {
+    fn by_id(&self, id: Uuid) -> Result<Project, String>;
+    fn save(&self, project: Project);
+    fn all(&self) -> Vec<Project>;
+    fn by_client(&self, client_id: Uuid) -> Vec<Project>;
+}
+
+#[cfg_attr(test, automock)]
+pub trait RelationshipRepository {
+    fn save(&self, relationship: Relationship);
+    fn delete(&self, relationship: &Relationship) -> Result<(), String>;
+    fn all(&self) -> Vec<Relationship>;
+    /// fromとtoのどちらかが一致する関係
+    fn by_client(&self, client_id: Uuid) -> Vec<Relationship>;
+}
+
+#[cfg_attr(test, automock)]
+pub trait OutboxRepository {
+    fn by_id(&self, id: Uuid) -> Result<OutboxMessage, String>;
+    fn save(&self, message: OutboxMessage);
+    /// 追加した順
+    fn all(&self) -> Vec<OutboxMessage>;
+    /// 未配信のメッセージを追加した順に返す
+    fn pending(&self) -> Vec<OutboxMessage>;
+}
+
+/// 保存時に暗号化するリポジトリの鍵の切り替え
+#[cfg_attr(test, automock)]
+pub trait KeyRotation {
+    /// 指定したクライアントのうち現在の鍵で暗号化されていないものを暗号化し直し，件数を返す
+    fn reencrypt(&self, ids: &[Uuid]) -> Result<usize, String>;
+}
+
+/// 処理済みの冪等キーと，そのキーで作成したクライアントの対応
+#[cfg_attr(test, automock)]
+pub trait IdempotencyRepository {
+    fn by_key(&self, key: &str) -> Option<IdempotencyRecord>;
+    fn save(&self, record: IdempotencyRecord);
+    /// クライアントを作成したキー．キーの順
+    fn keys_for(&self, client_id: Uuid) -> Vec<String>;
+    /// クライアントを作成した記録を削除し，件数を返す
+    fn forget(&self, client_id: Uuid) -> usize;
+}
+
+#[cfg_attr(test, automock)]
+pub trait UserRepository {
+    fn by_username(&self, username: &str) -> Option<User>;
+    fn save(&self, user: User);
+    /// 作成した順
+    fn all(&self) -> Vec<User>;
+}
+
+#[cfg_attr(test, automock)]
+pub trait AuditRepository {
+    fn record(&self, entry: AuditEntry);
+    /// 記録した順
+    fn all(&self) -> Vec<AuditEntry>;
+    /// 対象がいずれかのIDである記録から対象を外し，件数を返す
+    fn redact(&self, subject_ids: &[Uuid]) -> usize;
+}
+
+/// 個人データを消去したクライアントの墓標
+#[cfg_attr(test, automock)]
+pub trait TombstoneRepository {
+    fn save(&self, tombstone: Tombstone);
+    /// 統合したクライアントのIDでも引ける
+    fn by_client(&self, id: Uuid) -> Option<Tombstone>;
+    fn all(&self) -> Vec<Tombstone>;
+}
+
+/// Webhookの購読と送信の記録
+#[cfg_attr(test, automock)]
+pub trait WebhookRepository {
+    fn subscription_by_id(&self, id: Uuid) -> Result<WebhookSubscription, String>;
+    fn save_subscription(&self, subscription: WebhookSubscription);
+    fn delete_subscription(&self, id: Uuid) -> Result<(), String>;
+    fn subscriptions(&self) -> Vec<WebhookSubscription>;
+    fn record_delivery(&self, delivery: WebhookDelivery);
+    /// 記録した順
+    fn deliveries(&self) -> Vec<WebhookDelivery>;
 }
@@ -0,0 +1,536 @@
+use crate::domain::{Client, DomainError, Location, Project, ProjectRepository, Relationship};
+use std::collections::HashMap;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+// -------------------------------------------------------------------------------------------------
+// 正規化
+
+// 半角カタカナ(U+FF61..=U+FF9F)に対応する全角文字
+const HALF_WIDTH_KANA: &str = "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+
+/// 全角英数字・半角カナ・カタカナ・大文字小文字・空白の表記揺れを吸収した文字列を返す
+pub fn normalize_text(text: &str) -> String {
+    let mut chars: Vec<char> = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        let c = match c as u32 {
+            // 全角ASCII
+            0xFF01..=0xFF5E => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            // 全角空白
+            0x3000 => ' ',
+            // 半角カナ
+            0xFF61..=0xFF9F => HALF_WIDTH_KANA
+                .chars()
+                .nth((c as u32 - 0xFF61) as usize)
+                .unwrap_or(c),
+            _ => c,
+        };
+
+        // 濁点・半濁点は直前の文字と合成する
+        if let (Some(prev), '゛' | '゙' | '゜' | '゚') = (chars.last().copied(), c) {
+            if let Some(composed) = compose_voiced_mark(prev, c) {
+                *chars.last_mut().unwrap() = composed;
+                continue;
+            }
+        }
+        chars.push(c);
+    }
+
+    let normalized = chars
+        .into_iter()
+        .map(|c| match c as u32 {
+            // カタカナ -> ひらがな
+            0x30A1..=0x30F6 => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect::<String>();
+
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn compose_voiced_mark(base: char, mark: char) -> Option<char> {
+    let code = base as u32;
+    let semi_voiced = matches!(mark, '゜' | '゚');
+    match code {
+        // ウ -> ヴ
+        0x30A6 if !semi_voiced => Some('ヴ'),
+        // カ行〜ト行(清音のみ)
+        0x30AB..=0x30C8 if !semi_voiced && code != 0x30C3 => {
+            let offset = if code >= 0x30C4 { 0x30C4 } else { 0x30AB };
+            (code - offset)
+                .is_multiple_of(2)
+                .then(|| char::from_u32(code + 1))
+                .flatten()
+        }
+        // ハ行
+        0x30CF..=0x30DB if (code - 0x30CF).is_multiple_of(3) => {
+            char::from_u32(code + if semi_voiced { 2 } else { 1 })
+        }
+        _ => None,
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// 類似度
+
+/// 正規化した文字列同士の類似度(0.0〜1.0)
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a = normalize_text(a).chars().collect::<Vec<_>>();
+    let b = normalize_text(b).chars().collect::<Vec<_>>();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / max_len as f64
+}
+
+/// 地名辞書で解釈できる場合は正規化した地名同士で比較する
+pub fn location_similarity(a: &Location, b: &Location) -> f64 {
+    match (a.is_recognized(), b.is_recognized()) {
+        (true, true) => similarity(&a.normalized_key(), &b.normalized_key()),
+        _ => similarity(a.as_str(), b.as_str()),
+    }
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            current[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut current);
+    }
+    prev[b.len()]
+}
+
+// -------------------------------------------------------------------------------------------------
+// DuplicateDetectionService
+
+/// 重複の可能性があるクライアントのグループ
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    pub clients: Vec<Client>,
+    /// グループ内のペアの最大スコア
+    pub score: f64,
+}
+
+pub struct DuplicateDetectionService {
+    threshold: f64,
+}
+
+impl DuplicateDetectionService {
+    pub const DEFAULT_THRESHOLD: f64 = 0.85;
+
+    pub fn new(threshold: f64) -> Self {
+        Self { threshold }
+    }
+
+    /// 名前と出身地の類似度の平均
+    pub fn score(&self, a: &Client, b: &Client) -> f64 {
+        (similarity(a.name(), b.name()) + location_similarity(a.location(), b.location())) / 2.0
+    }
+
+    /// しきい値以上のスコアを持つペアを推移的にまとめたグループを返す
+    pub fn find_groups(&self, clients: &[Client]) -> Vec<DuplicateGroup> {
+        // union-find
+        let mut parents: Vec<usize> = (0..clients.len()).collect();
+        fn root(parents: &mut [usize], i: usize) -> usize {
+            let mut i = i;
+            while parents[i] != i {
+                parents[i] = parents[parents[i]];
+                i = parents[i];
+            }
+            i
+        }
+
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for i in 0..clients.len() {
+            for j in (i + 1)..clients.len() {
+                let score = self.score(&clients[i], &clients[j]);
+                if score >= self.threshold {
+                    let (ri, rj) = (root(&mut parents, i), root(&mut parents, j));
+                    let max_score = [scores.get(&ri), scores.get(&rj)]
+                        .into_iter()
+                        .flatten()
+                        .fold(score, |acc, s| acc.max(*s));
+                    parents[rj] = ri;
+                    scores.insert(ri, max_score);
+                }
+            }
+        }
+
+        let mut members: HashMap<usize, Vec<Client>> = HashMap::new();
+        for (i, client) in clients.iter().enumerate() {
+            let r = root(&mut parents, i);
+            members.entry(r).or_default().push(client.clone());
+        }
+
+        let mut groups = members
+            .into_iter()
+            .filter(|(_, clients)| clients.len() > 1)
+            .map(|(r, mut clients)| {
+                clients.sort_by_key(Client::id);
+                DuplicateGroup {
+                    clients,
+                    score: scores[&r],
+                }
+            })
+            .collect::<Vec<_>>();
+        groups.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.clients[0].id().cmp(&b.clients[0].id()))
+        });
+        groups
+    }
+}
+
+impl Default for DuplicateDetectionService {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_THRESHOLD)
+    }
+}
+
+/// 統合の対象として妥当か確認する
+pub fn validate_merge(keep_id: Uuid, merge_ids: &[Uuid]) -> Result<(), String> {
+    if merge_ids.is_empty() {
+        return Err("No clients to merge".to_string());
+    }
+    if merge_ids.contains(&keep_id) {
+        return Err("Cannot merge a client into itself".to_string());
+    }
+    if let Some(id) = merge_ids
+        .iter()
+        .enumerate()
+        .find_map(|(i, id)| merge_ids[..i].contains(id).then_some(id))
+    {
+        return Err(format!(
+            "Client #{} is specified more than once",
+            id.hyphenated()
+        ));
+    }
+    Ok(())
+}
+
+/// クライアント(統合したクライアントを含む)のプロジェクト
+pub fn projects_of_client<P: ProjectRepository + ?Sized>(
+    client: &Client,
+    project_repo: &P,
+) -> Vec<Project> {
+    let mut projects = std::iter::once(client.id())
+        .chain(client.aliases().iter().copied())
+        .flat_map(|id| project_repo.by_client(id))
+        .collect::<Vec<_>>();
+    projects.sort_by_key(Project::created_at);
+    projects
+}
+
+/// 未完了のプロジェクトがあるクライアントは削除できない
+pub fn validate_client_deletion<P: ProjectRepository + ?Sized>(
+    client: &Client,
+    project_repo: &P,
+) -> Result<(), String> {
+    let open_projects = projects_of_client(client, project_repo)
+        .into_iter()
+        .filter(Project::is_open)
+        .count();
+    if open_projects > 0 {
+        return Err(format!(
+            "Cannot delete a client with {} open project(s)",
+            open_projects
+        ));
+    }
+    Ok(())
+}
+
+// -------------------------------------------------------------------------------------------------
+// 関係
+
+/// 既存の関係に追加できるか確認する．階層を成す関係では親を1つに限り，循環を許さない
+pub fn validate_relationship(
+    relationship: &Relationship,
+    existing: &[Relationship],
+) -> Result<(), DomainError> {
+    if existing.contains(relationship) {
+        return Err(DomainError::InvalidRelationship(
+            "relationship already exists".to_string(),
+        ));
+    }
+    let kind = relationship.kind();
+    if !kind.is_hierarchical() {
+        return Ok(());
+    }
+
+    let parent_of = |id: Uuid| {
+        existing
+            .iter()
+            .find(|r| r.kind() == kind && r.from() == id)
+            .map(Relationship::to)
+    };
+    if parent_of(relationship.from()).is_some() {
+        return Err(DomainError::InvalidRelationship(format!(
+            "client already has a {} relationship",
+            kind
+        )));
+    }
+    // 新しい親から祖先をたどって自身に戻るなら循環する
+    let mut visited = HashSet::new();
+    let mut current = Some(relationship.to());
+    while let Some(id) = current {
+        if id == relationship.from() {
+            return Err(DomainError::InvalidRelationship(
+                "relationship would create a cycle".to_string(),
+            ));
+        }
+        if !visited.insert(id) {
+            break;
+        }
+        current = parent_of(id);
+    }
+    Ok(())
+}
+
+/// 階層の木構造
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HierarchyNode {
+    pub client_id: Uuid,
+    pub children: Vec<HierarchyNode>,
+}
+
+/// クライアントが属する階層全体を，最上位の親から組み立てる
+pub fn build_hierarchy(client_id: Uuid, relationships: &[Relationship]) -> HierarchyNode {
+    let hierarchical = relationships
+        .iter()
+        .filter(|r| r.kind().is_hierarchical())
+        .collect::<Vec<_>>();
+
+    let mut root = client_id;
+    let mut visited = HashSet::from([root]);
+    while let Some(parent) = hierarchical
+        .iter()
+        .find(|r| r.from() == root)
+        .map(|r| r.to())
+    {
+        if !visited.insert(parent) {
+            break;
+        }
+        root = parent;
+    }
+
+    fn build(
+        id: Uuid,
+        hierarchical: &[&Relationship],
+        visited: &mut HashSet<Uuid>,
+    ) -> HierarchyNode {
+        let mut child_ids = hierarchical
+            .iter()
+            .filter(|r| r.to() == id)
+            .map(|r| r.from())
+            .collect::<Vec<_>>();
+        child_ids.sort();
+        let children = child_ids
+            .into_iter()
+            .filter(|child| visited.insert(*child))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|child| build(child, hierarchical, visited))
+            .collect();
+        HierarchyNode {
+            client_id: id,
+            children,
+        }
+    }
+    let mut visited = HashSet::from([root]);
+    build(root, &hierarchical, &mut visited)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        build_hierarchy, location_similarity, normalize_text, similarity, validate_client_deletion,
+        validate_merge, validate_relationship, DuplicateDetectionService, HierarchyNode,
+    };
+    use crate::domain::repositories::MockProjectRepository;
+    use crate::domain::{Client, Location, Project, RelationKind, Relationship};
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+    use fake::{Fake, Faker};
+    use mockall::predicate;
+    use uuid::Uuid;
+
+    #[test]
+    fn normalize_width_and_kana() {
+        assert_eq!(normalize_text("ＴＡＲＯ　 Tokyo "), "taro tokyo");
+        assert_eq!(normalize_text("ﾀﾛｳ"), "たろう");
+        assert_eq!(normalize_text("タロウ"), "たろう");
+        assert_eq!(normalize_text("ｶﾞｯｺｳ ﾎﾟﾌﾟﾗ ｳﾞ"), "がっこう ぽぷら ゔ");
+        assert_eq!(normalize_text("東京"), "東京");
+    }
+
+    #[test]
+    fn similarity_range() {
+        assert_eq!(similarity("Taro", "ｔａｒｏ"), 1.0);
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+        let s = similarity("Taro", "Tar");
+        assert!(0.0 < s && s < 1.0);
+    }
+
+    #[test]
+    fn location_similarity_uses_gazetteer() {
+        assert_eq!(
+            location_similarity(&Location::new("Tokyo"), &Location::new("東京都")),
+            1.0
+        );
+        assert!(location_similarity(&Location::new("Tokyo"), &Location::new("Kyoto")) < 1.0);
+    }
+
+    #[test]
+    fn find_duplicate_groups() {
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let taro2 = Client::new("ＴＡＲＯ".to_string(), "tokyo ".to_string());
+        let taro3 = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let hanako = Client::new("はなこ".to_string(), "大阪".to_string());
+        let hanako2 = Client::new("ﾊﾅｺ".to_string(), "大阪".to_string());
+        let jiro = Client::new("Jiro".to_string(), "Kyoto".to_string());
+
+        let service = DuplicateDetectionService::default();
+        let groups = service.find_groups(&[
+            taro.clone(),
+            hanako.clone(),
+            jiro,
+            taro2.clone(),
+            hanako2.clone(),
+            taro3.clone(),
+        ]);
+
+        assert_eq!(groups.len(), 2);
+
+        let mut taro_ids = vec![taro.id(), taro2.id(), taro3.id()];
+        taro_ids.sort();
+        let mut hanako_ids = vec![hanako.id(), hanako2.id()];
+        hanako_ids.sort();
+
+        let group_ids = groups
+            .iter()
+            .map(|group| group.clients.iter().map(Client::id).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert!(group_ids.contains(&taro_ids));
+        assert!(group_ids.contains(&hanako_ids));
+        assert!(groups.iter().all(|group| group.score == 1.0));
+    }
+
+    #[test]
+    fn merge_validation() {
+        let keep_id = Faker.fake();
+        assert_matches!(validate_merge(keep_id, &[Faker.fake()]), Ok(()));
+        assert_matches!(validate_merge(keep_id, &[]), Err(_));
+        assert_matches!(validate_merge(keep_id, &[keep_id]), Err(_));
+        let merged_id = Faker.fake();
+        assert_matches!(validate_merge(keep_id, &[merged_id, merged_id]), Err(_));
+    }
+
+    #[test]
+    fn client_deletion_validation() {
+        let mut client = Client::new(Faker.fake(), Faker.fake());
+        let merged = Client::new(Faker.fake(), Faker.fake());
+        let merged_id = merged.id();
+        client.merge(merged);
+
+        let mut closed = Project::new(client.id(), "closed", Utc::now()).unwrap();
+        closed.close(Utc::now()).unwrap();
+        let open = Project::new(merged_id, "open", Utc::now()).unwrap();
+
+        let mut mock_repo = MockProjectRepository::new();
+        mock_repo
+            .expect_by_client()
+            .with(predicate::eq(client.id()))
+            .return_const(vec![closed]);
+        mock_repo
+            .expect_by_client()
+            .with(predicate::eq(merged_id))
+            .times(1)
+            .return_const(vec![open]);
+        // 統合したクライアントの未完了のプロジェクトも数える
+        assert_matches!(validate_client_deletion(&client, &mock_repo), Err(_));
+
+        mock_repo
+            .expect_by_client()
+            .with(predicate::eq(merged_id))
+            .return_const(Vec::new());
+        assert_matches!(validate_client_deletion(&client, &mock_repo), Ok(()));
+    }
+
+    #[test]
+    fn relationship_validation() {
+        let [a, b, c, d]: [Uuid; 4] = [Faker.fake(), Faker.fake(), Faker.fake(), Faker.fake()];
+        let subsidiary =
+            |from, to| Relationship::new(from, to, RelationKind::SubsidiaryOf).unwrap();
+        // a <- b <- c
+        let existing = vec![subsidiary(b, a), subsidiary(c, b)];
+
+        assert_matches!(validate_relationship(&subsidiary(d, c), &existing), Ok(()));
+        // 重複
+        assert_matches!(validate_relationship(&subsidiary(b, a), &existing), Err(_));
+        // 親は1つまで
+        assert_matches!(validate_relationship(&subsidiary(c, d), &existing), Err(_));
+        // 循環
+        assert_matches!(validate_relationship(&subsidiary(a, c), &existing), Err(_));
+        // 紹介は循環してもよい
+        let referral = Relationship::new(a, c, RelationKind::ReferredBy).unwrap();
+        assert_matches!(validate_relationship(&referral, &existing), Ok(()));
+    }
+
+    #[test]
+    fn hierarchy_from_any_member() {
+        let mut ids: [Uuid; 5] = [
+            Faker.fake(),
+            Faker.fake(),
+            Faker.fake(),
+            Faker.fake(),
+            Faker.fake(),
+        ];
+        ids[1..4].sort();
+        let [root, b, c, d, e] = ids;
+        let subsidiary =
+            |from, to| Relationship::new(from, to, RelationKind::SubsidiaryOf).unwrap();
+        // root <- b <- d, root <- c
+        let relationships = vec![
+            subsidiary(b, root),
+            subsidiary(c, root),
+            subsidiary(d, b),
+            Relationship::new(e, d, RelationKind::ReferredBy).unwrap(),
+        ];
+
+        let expected = HierarchyNode {
+            client_id: root,
+            children: vec![
+                HierarchyNode {
+                    client_id: b,
+                    children: vec![HierarchyNode {
+                        client_id: d,
+                        children: Vec::new(),
+                    }],
+                },
+                HierarchyNode {
+                    client_id: c,
+                    children: Vec::new(),
+                },
+            ],
+        };
+        assert_eq!(build_hierarchy(d, &relationships), expected);
+        assert_eq!(build_hierarchy(root, &relationships), expected);
+        // 階層に属さないクライアントは自身のみ
+        assert_eq!(
+            build_hierarchy(e, &relationships),
+            HierarchyNode {
+                client_id: e,
+                children: Vec::new(),
+            }
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::domain::{Client, ClientRepository, ClientStatus};
+use std::fmt::Display;
+use std::str::FromStr;
+
+// -------------------------------------------------------------------------------------------------
+// UniqueNameLocationSpecification
+
+/// 名前と出身地の組がアーカイブされていない他のクライアントと重複しないこと
+pub struct UniqueNameLocationSpecification<'a, T: ClientRepository + ?Sized> {
+    client_repo: &'a T,
+}
+
+impl<'a, T: ClientRepository + ?Sized> UniqueNameLocationSpecification<'a, T> {
+    pub fn new(client_repo: &'a T) -> Self {
+        Self { client_repo }
+    }
+
+    /// 名前と出身地が重複する自分以外のクライアント．アーカイブされたクライアントは重複とみなさない
+    pub fn conflicts(&self, client: &Client) -> Vec<Client> {
+        if client.status() == ClientStatus::Archived {
+            return Vec::new();
+        }
+        self.client_repo
+            .by_name_and_location(client.name(), client.location().as_str())
+            .into_iter()
+            .filter(|other| other.id() != client.id())
+            .filter(|other| other.status() != ClientStatus::Archived)
+            .collect()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// UniquenessPolicy
+
+/// 一意性の制約に違反したときの振る舞い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UniquenessPolicy {
+    Off,
+    Warn,
+    #[default]
+    Reject,
+}
+
+impl UniquenessPolicy {
+    /// ポリシーに従って一意性を確認し，警告のリストを返す
+    pub fn enforce<T: ClientRepository + ?Sized>(
+        &self,
+        client: &Client,
+        client_repo: &T,
+    ) -> Result<Vec<String>, String> {
+        if *self == UniquenessPolicy::Off {
+            return Ok(Vec::new());
+        }
+
+        let conflicts = UniqueNameLocationSpecification::new(client_repo).conflicts(client);
+        if conflicts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let message = format!(
+            "Client with name '{}' and location '{}' already exists: {}",
+            client.name(),
+            client.location(),
+            conflicts
+                .iter()
+                .map(|other| other.id().hyphenated().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        match self {
+            UniquenessPolicy::Reject => Err(message),
+            _ => Ok(vec![message]),
+        }
+    }
+}
+
+impl FromStr for UniquenessPolicy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Ok(UniquenessPolicy::Off),
+            "warn" => Ok(UniquenessPolicy::Warn),
+            "reject" => Ok(UniquenessPolicy::Reject),
+            _ => Err(format!("Unknown uniqueness policy: {}", s)),
+        }
+    }
+}
+
+impl Display for UniquenessPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            UniquenessPolicy::Off => "off",
+            UniquenessPolicy::Warn => "warn",
+            UniquenessPolicy::Reject => "reject",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{UniqueNameLocationSpecification, UniquenessPolicy};
+    use crate::domain::repositories::MockClientRepository;
+    use crate::domain::{Client, ClientStatus};
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+
+    #[test]
+    fn uniqueness_specification() {
+        let client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let other = Client::new("Taro".to_string(), "Tokyo".to_string());
+
+        let mut mock_repo = MockClientRepository::new();
+        let returned = vec![client.clone(), other.clone()];
+        mock_repo
+            .expect_by_name_and_location()
+            .withf(|name, location| name == "Taro" && location == "Tokyo")
+            .return_const(returned);
+
+        let spec = UniqueNameLocationSpecification::new(&mock_repo);
+        assert_eq!(spec.conflicts(&client), vec![other]);
+    }
+
+    #[test]
+    fn uniqueness_specification_ignores_archived_clients() {
+        let client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let mut archived = Client::new("Taro".to_string(), "Tokyo".to_string());
+        archived
+            .change_status(ClientStatus::Archived, Utc::now())
+            .unwrap();
+
+        let mut mock_repo = MockClientRepository::new();
+        let returned = vec![archived.clone()];
+        mock_repo
+            .expect_by_name_and_location()
+            .times(1)
+            .return_const(returned);
+
+        let spec = UniqueNameLocationSpecification::new(&mock_repo);
+        assert!(spec.conflicts(&client).is_empty());
+        // アーカイブされたクライアント自身は他と重複してもよい
+        assert!(spec.conflicts(&archived).is_empty());
+    }
+
+    #[test]
+    fn uniqueness_policy_enforce() {
+        let client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let other = Client::new("Taro".to_string(), "Tokyo".to_string());
+
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo
+            .expect_by_name_and_location()
+            .times(2)
+            .return_const(vec![other]);
+
+        assert_matches!(UniquenessPolicy::Off.enforce(&client, &mock_repo), Ok(warnings) if warnings.is_empty());
+        assert_matches!(UniquenessPolicy::Warn.enforce(&client, &mock_repo), Ok(warnings) if warnings.len() == 1);
+        assert_matches!(
+            UniquenessPolicy::Reject.enforce(&client, &mock_repo),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn uniqueness_policy_from_str() {
+        assert_eq!("off".parse(), Ok(UniquenessPolicy::Off));
+        assert_eq!("Warn".parse(), Ok(UniquenessPolicy::Warn));
+        assert_eq!("reject".parse(), Ok(UniquenessPolicy::Reject));
+        assert_matches!("other".parse::<UniquenessPolicy>(), Err(_));
+    }
+}
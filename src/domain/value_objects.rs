@@ -0,0 +1,1028 @@
+use crate::domain::gazetteer::{find_city, find_country, find_prefecture, find_prefecture_prefix};
+use crate::domain::{normalize_text, DomainError};
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt::Display;
+use std::str::FromStr;
+use uuid::Uuid;
+
+// -------------------------------------------------------------------------------------------------
+// Location
+
+/// 入力された地名と，地名辞書で正規化した国・地域・都市
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    text: String,
+    country: Option<String>,
+    region: Option<String>,
+    city: Option<String>,
+}
+
+impl Location {
+    /// "東京都渋谷区"，"Shibuya, Tokyo"，"New York, USA"のような入力を解釈する
+    pub fn new(text: &str) -> Self {
+        let mut location = Self {
+            text: text.trim().to_string(),
+            country: None,
+            region: None,
+            city: None,
+        };
+
+        let normalized = normalize_text(text);
+        let mut unmatched: Vec<&str> = Vec::new();
+        for part in normalized
+            .split([',', '、'])
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            if let Some(city) = find_city(part) {
+                location.set_region(city.country, city.region);
+                location.city.get_or_insert_with(|| city.name.to_string());
+            } else if let Some(prefecture) = find_prefecture(part) {
+                location.set_region("JP", prefecture.name);
+            } else if let Some(country) = find_country(part) {
+                location
+                    .country
+                    .get_or_insert_with(|| country.code.to_string());
+            } else if let Some((prefecture, rest)) = find_prefecture_prefix(part) {
+                location.set_region("JP", prefecture.name);
+                if rest.starts_with('市') {
+                    // "大阪市北区"のような都道府県と同名の市
+                    unmatched.push(part.strip_prefix(prefecture.name).unwrap_or(part));
+                } else if !rest.is_empty() {
+                    unmatched.push(rest);
+                }
+            } else {
+                unmatched.push(part);
+            }
+        }
+
+        if location.region.is_some() && location.city.is_none() {
+            location.city = unmatched.first().map(|city| city.to_string());
+        }
+        location
+    }
+    fn set_region(&mut self, country: &str, region: &str) {
+        self.country.get_or_insert_with(|| country.to_string());
+        self.region.get_or_insert_with(|| region.to_string());
+    }
+    /// 入力されたままの地名
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+    /// ISO 3166-1 alpha-2の国コード
+    pub fn country(&self) -> Option<&str> {
+        self.country.as_deref()
+    }
+    /// 都道府県・州などの地域
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+    pub fn city(&self) -> Option<&str> {
+        self.city.as_deref()
+    }
+    pub fn is_recognized(&self) -> bool {
+        self.country.is_some()
+    }
+    /// 表記揺れに依らない比較用のキー
+    pub fn normalized_key(&self) -> String {
+        match self.is_recognized() {
+            true => format!(
+                "{}/{}/{}",
+                self.country.as_deref().unwrap_or_default(),
+                self.region.as_deref().unwrap_or_default(),
+                self.city.as_deref().unwrap_or_default()
+            ),
+            false => normalize_text(&self.text),
+        }
+    }
+    /// 地域(なければ国)が一致するか
+    pub fn is_in(&self, area: &Location) -> bool {
+        match (area.region(), area.country()) {
+            (Some(region), _) => self.region() == Some(region),
+            (None, Some(country)) => self.country() == Some(country),
+            (None, None) => normalize_text(&self.text) == normalize_text(&area.text),
+        }
+    }
+}
+
+#[cfg(test)]
+impl fake::Dummy<fake::Faker> for Location {
+    fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &fake::Faker, rng: &mut R) -> Self {
+        use fake::{faker::address::en::CityName, Fake};
+        Location::new(&CityName().fake_with_rng::<String, _>(rng))
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Tag
+
+/// 小文字・半角に正規化したタグ名(英数字・日本語・"-"・"_"のみ，32文字まで)
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Tag(String);
+
+impl Tag {
+    pub const MAX_LENGTH: usize = 32;
+
+    pub fn new(tag: &str) -> Result<Self, DomainError> {
+        let normalized = normalize_text(tag);
+        let length = normalized.chars().count();
+        if length == 0
+            || length > Self::MAX_LENGTH
+            || !normalized
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == 'ー')
+        {
+            return Err(DomainError::InvalidTag(tag.trim().to_string()));
+        }
+        Ok(Self(normalized))
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl fake::Dummy<fake::Faker> for Tag {
+    fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &fake::Faker, rng: &mut R) -> Self {
+        use fake::{faker::lorem::en::Word, Fake};
+        Tag::new(&Word().fake_with_rng::<String, _>(rng)).unwrap()
+    }
+}
+
+impl Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Email
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Email(String);
+
+impl Email {
+    pub fn new(email: &str) -> Result<Self, DomainError> {
+        let email = email.trim();
+        let err = || DomainError::InvalidEmail(email.to_string());
+
+        let (local, domain) = email.rsplit_once('@').ok_or_else(err)?;
+
+        let local_is_valid = !local.is_empty()
+            && local.len() <= 64
+            && !local.starts_with('.')
+            && !local.ends_with('.')
+            && !local.contains("..")
+            && local
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~.".contains(c));
+
+        let labels = domain.split('.').collect::<Vec<_>>();
+        let domain_is_valid = labels.len() >= 2
+            && labels.iter().all(|label| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+
+        if !local_is_valid || !domain_is_valid {
+            return Err(err());
+        }
+        Ok(Self(format!("{}@{}", local, domain.to_ascii_lowercase())))
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// PhoneNumber
+
+/// E.164形式に正規化した電話番号
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhoneNumber(String);
+
+impl PhoneNumber {
+    /// 国番号のない国内番号の国
+    const DOMESTIC_COUNTRY_CODE: &'static str = "81";
+    /// 国番号の後でも先頭の0が番号の一部である国(イタリア)
+    const COUNTRY_CODES_KEEPING_ZERO: [&'static str; 1] = ["39"];
+
+    /// 国番号のない国内番号は日本(+81)として扱う．
+    /// "+81 090-..."や"+81 (0)90-..."のように国番号の後に残った国内のトランクプレフィックス0は除く
+    pub fn new(phone_number: &str) -> Result<Self, DomainError> {
+        let err = || DomainError::InvalidPhoneNumber(phone_number.trim().to_string());
+
+        let is_separator = |c: char| matches!(c, ' ' | '-' | '(' | ')' | '.');
+        let compact = |text: &str| {
+            text.chars()
+                .filter(|c| !is_separator(*c))
+                .collect::<String>()
+        };
+        let normalized = normalize_text(phone_number);
+        let normalized = normalized.trim();
+
+        let digits = match normalized.strip_prefix('+') {
+            Some(international) => {
+                // 区切られていれば最初の数字の並びを国番号とする．区切りがなければ国内の国番号のみ判別する
+                let (country_code, national) = match international.find(is_separator) {
+                    Some(index) if index > 0 => (
+                        international[..index].to_string(),
+                        compact(&international[index..]),
+                    ),
+                    _ => {
+                        let international = compact(international);
+                        match international.strip_prefix(Self::DOMESTIC_COUNTRY_CODE) {
+                            Some(national) => (
+                                Self::DOMESTIC_COUNTRY_CODE.to_string(),
+                                national.to_string(),
+                            ),
+                            None => (String::new(), international),
+                        }
+                    }
+                };
+                let national = match national.strip_prefix('0') {
+                    Some(rest)
+                        if !country_code.is_empty()
+                            && !Self::COUNTRY_CODES_KEEPING_ZERO
+                                .contains(&country_code.as_str()) =>
+                    {
+                        rest.to_string()
+                    }
+                    _ => national,
+                };
+                format!("{}{}", country_code, national)
+            }
+            None => match compact(normalized).strip_prefix('0') {
+                Some(domestic) if !domestic.starts_with('0') => {
+                    format!("{}{}", Self::DOMESTIC_COUNTRY_CODE, domestic)
+                }
+                _ => return Err(err()),
+            },
+        };
+
+        if !(8..=15).contains(&digits.len())
+            || !digits.chars().all(|c| c.is_ascii_digit())
+            || digits.starts_with('0')
+        {
+            return Err(err());
+        }
+        Ok(Self(format!("+{}", digits)))
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// PostalCode
+
+/// 日本の郵便番号(123-4567)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostalCode(String);
+
+impl PostalCode {
+    pub fn new(postal_code: &str) -> Result<Self, DomainError> {
+        let normalized = normalize_text(postal_code);
+        let normalized = normalized.trim_start_matches('〒').trim();
+        let digits = normalized.replacen('-', "", 1);
+
+        let has_valid_format = match normalized.find('-') {
+            Some(index) => index == 3,
+            None => true,
+        };
+        if !has_valid_format || digits.len() != 7 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(DomainError::InvalidPostalCode(
+                postal_code.trim().to_string(),
+            ));
+        }
+        Ok(Self(format!("{}-{}", &digits[..3], &digits[3..])))
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// PostalAddress
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostalAddress {
+    postal_code: PostalCode,
+    address: String,
+}
+
+impl PostalAddress {
+    pub fn new(postal_code: &str, address: &str) -> Result<Self, DomainError> {
+        let postal_code = PostalCode::new(postal_code)?;
+        let address = address.trim();
+        if address.is_empty() {
+            return Err(DomainError::InvalidPostalAddress(address.to_string()));
+        }
+        Ok(Self {
+            postal_code,
+            address: address.to_string(),
+        })
+    }
+    /// "〒123-4567 東京都..."のように郵便番号と住所を空白で区切った文字列から作成する
+    pub fn parse(text: &str) -> Result<Self, DomainError> {
+        let text = text.trim().replace('\u{3000}', " ");
+        match text.split_once(' ') {
+            Some((postal_code, address)) => Self::new(postal_code, address),
+            None => Err(DomainError::InvalidPostalAddress(text)),
+        }
+    }
+    pub fn postal_code(&self) -> &PostalCode {
+        &self.postal_code
+    }
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// ContactInfo
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContactKind {
+    Email,
+    Phone,
+    PostalAddress,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContactInfo {
+    Email(Email),
+    Phone(PhoneNumber),
+    PostalAddress(PostalAddress),
+}
+
+impl ContactInfo {
+    pub fn parse(kind: ContactKind, value: &str) -> Result<Self, DomainError> {
+        Ok(match kind {
+            ContactKind::Email => ContactInfo::Email(Email::new(value)?),
+            ContactKind::Phone => ContactInfo::Phone(PhoneNumber::new(value)?),
+            ContactKind::PostalAddress => ContactInfo::PostalAddress(PostalAddress::parse(value)?),
+        })
+    }
+    /// 検証せずに値をそのまま持たせる．保存先で暗号化した値のように，連絡先として解釈できない値に使う
+    pub fn opaque(kind: ContactKind, value: String) -> Self {
+        match kind {
+            ContactKind::Email => ContactInfo::Email(Email(value)),
+            ContactKind::Phone => ContactInfo::Phone(PhoneNumber(value)),
+            ContactKind::PostalAddress => ContactInfo::PostalAddress(PostalAddress {
+                postal_code: PostalCode(String::new()),
+                address: value,
+            }),
+        }
+    }
+    /// opaqueで持たせた値．郵便での連絡先は住所の部分
+    pub fn opaque_value(&self) -> &str {
+        match self {
+            ContactInfo::Email(email) => email.as_str(),
+            ContactInfo::Phone(phone) => phone.as_str(),
+            ContactInfo::PostalAddress(address) => address.address(),
+        }
+    }
+    pub fn kind(&self) -> ContactKind {
+        match self {
+            ContactInfo::Email(_) => ContactKind::Email,
+            ContactInfo::Phone(_) => ContactKind::Phone,
+            ContactInfo::PostalAddress(_) => ContactKind::PostalAddress,
+        }
+    }
+}
+
+impl Display for ContactInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContactInfo::Email(email) => write!(f, "{}", email.as_str()),
+            ContactInfo::Phone(phone) => write!(f, "{}", phone.as_str()),
+            ContactInfo::PostalAddress(address) => write!(
+                f,
+                "〒{} {}",
+                address.postal_code().as_str(),
+                address.address()
+            ),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// ClientStatus
+
+/// クライアントのライフサイクル上の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ClientStatus {
+    #[default]
+    Prospect,
+    Active,
+    Suspended,
+    Archived,
+}
+
+impl ClientStatus {
+    pub const ALL: [ClientStatus; 4] = [
+        ClientStatus::Prospect,
+        ClientStatus::Active,
+        ClientStatus::Suspended,
+        ClientStatus::Archived,
+    ];
+
+    // 遷移表．ここにない遷移は許可しない
+    const TRANSITIONS: [(ClientStatus, ClientStatus); 6] = [
+        (ClientStatus::Prospect, ClientStatus::Active),
+        (ClientStatus::Prospect, ClientStatus::Archived),
+        (ClientStatus::Active, ClientStatus::Suspended),
+        (ClientStatus::Active, ClientStatus::Archived),
+        (ClientStatus::Suspended, ClientStatus::Active),
+        (ClientStatus::Suspended, ClientStatus::Archived),
+    ];
+
+    pub fn can_transition_to(&self, to: ClientStatus) -> bool {
+        Self::TRANSITIONS.contains(&(*self, to))
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClientStatus::Prospect => "prospect",
+            ClientStatus::Active => "active",
+            ClientStatus::Suspended => "suspended",
+            ClientStatus::Archived => "archived",
+        }
+    }
+}
+
+#[cfg(test)]
+impl fake::Dummy<fake::Faker> for ClientStatus {
+    fn dummy_with_rng<R: rand::Rng + ?Sized>(_: &fake::Faker, rng: &mut R) -> Self {
+        Self::ALL[rng.gen_range(0..Self::ALL.len())]
+    }
+}
+
+impl FromStr for ClientStatus {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = normalize_text(s);
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == normalized)
+            .ok_or_else(|| DomainError::InvalidStatus(s.trim().to_string()))
+    }
+}
+
+impl Display for ClientStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// ProjectStatus
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ProjectStatus {
+    #[default]
+    Open,
+    Closed,
+}
+
+impl ProjectStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProjectStatus::Open => "open",
+            ProjectStatus::Closed => "closed",
+        }
+    }
+}
+
+impl Display for ProjectStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Relationship
+
+/// クライアント間の関係の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RelationKind {
+    /// 子会社(fromがtoの子会社)
+    SubsidiaryOf,
+    /// 紹介(fromがtoから紹介された)
+    ReferredBy,
+}
+
+impl RelationKind {
+    pub const ALL: [RelationKind; 2] = [RelationKind::SubsidiaryOf, RelationKind::ReferredBy];
+
+    /// 階層を成す関係は循環してはならず，親は1つまで
+    pub fn is_hierarchical(&self) -> bool {
+        matches!(self, RelationKind::SubsidiaryOf)
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelationKind::SubsidiaryOf => "subsidiary_of",
+            RelationKind::ReferredBy => "referred_by",
+        }
+    }
+}
+
+impl FromStr for RelationKind {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = normalize_text(s);
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == normalized)
+            .ok_or_else(|| DomainError::InvalidRelationship(format!("unknown kind {}", s.trim())))
+    }
+}
+
+impl Display for RelationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// クライアントのID同士を結ぶ型付きの関係
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Relationship {
+    from: Uuid,
+    to: Uuid,
+    kind: RelationKind,
+}
+
+impl Relationship {
+    pub fn new(from: Uuid, to: Uuid, kind: RelationKind) -> Result<Self, DomainError> {
+        if from == to {
+            return Err(DomainError::InvalidRelationship(
+                "a client cannot be related to itself".to_string(),
+            ));
+        }
+        Ok(Self { from, to, kind })
+    }
+    pub fn from(&self) -> Uuid {
+        self.from
+    }
+    pub fn to(&self) -> Uuid {
+        self.to
+    }
+    pub fn kind(&self) -> RelationKind {
+        self.kind
+    }
+    pub fn involves(&self, client_id: Uuid) -> bool {
+        self.from == client_id || self.to == client_id
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// TenantId
+
+/// データを分離する単位(事業部など)．小文字の英数字と"-"のみ，32文字まで
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TenantId(String);
+
+impl TenantId {
+    pub const MAX_LENGTH: usize = 32;
+
+    pub fn new(tenant_id: &str) -> Result<Self, DomainError> {
+        let normalized = normalize_text(tenant_id);
+        let length = normalized.chars().count();
+        if length == 0
+            || length > Self::MAX_LENGTH
+            || !normalized
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err(DomainError::InvalidTenantId(tenant_id.trim().to_string()));
+        }
+        Ok(Self(normalized))
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for TenantId {
+    fn default() -> Self {
+        Self("default".to_string())
+    }
+}
+
+impl FromStr for TenantId {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl Display for TenantId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Permission
+
+/// リクエストの実行に必要な権限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    Read,
+    Write,
+    /// ユーザーや外部連携の管理
+    Administer,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::Read => "read",
+            Permission::Write => "write",
+            Permission::Administer => "administer",
+        }
+    }
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// Role
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Role {
+    Viewer,
+    Editor,
+    Admin,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Viewer, Role::Editor, Role::Admin];
+
+    pub fn allows(&self, permission: Permission) -> bool {
+        match self {
+            Role::Viewer => permission == Permission::Read,
+            Role::Editor => permission != Permission::Administer,
+            Role::Admin => true,
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = normalize_text(s);
+        Self::ALL
+            .into_iter()
+            .find(|role| role.as_str() == normalized)
+            .ok_or_else(|| DomainError::InvalidRole(s.trim().to_string()))
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// PasswordHash
+
+/// ソルト付きのPBKDF2-HMAC-SHA256で導出したパスワードのハッシュ．平文は保持しない
+/// 反復回数も保存し，既定の回数を変えても保存済みのハッシュを検証できるようにする
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordHash {
+    salt: [u8; 16],
+    rounds: u32,
+    hash: [u8; 32],
+}
+
+impl PasswordHash {
+    #[cfg(not(test))]
+    const ROUNDS: u32 = 100_000;
+    // テストでは反復回数を減らして実行時間を抑える
+    #[cfg(test)]
+    const ROUNDS: u32 = 1_000;
+    pub const MIN_LENGTH: usize = 8;
+
+    pub fn new(password: &str) -> Result<Self, DomainError> {
+        if password.chars().count() < Self::MIN_LENGTH {
+            return Err(DomainError::InvalidPassword(format!(
+                "must be at least {} characters",
+                Self::MIN_LENGTH
+            )));
+        }
+        let salt = *Uuid::new_v4().as_bytes();
+        Ok(Self {
+            salt,
+            rounds: Self::ROUNDS,
+            hash: Self::derive(password, &salt, Self::ROUNDS),
+        })
+    }
+    pub fn verify(&self, password: &str) -> bool {
+        // 一致するまでの時間から推測されないように全てのバイトを比較する
+        Self::derive(password, &self.salt, self.rounds)
+            .iter()
+            .zip(self.hash.iter())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+    fn derive(password: &str, salt: &[u8], rounds: u32) -> [u8; 32] {
+        let mut hash = [0; 32];
+        pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, rounds, &mut hash);
+        hash
+    }
+}
+
+impl std::fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PasswordHash(..)")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        ClientStatus, ContactInfo, ContactKind, Email, Location, PasswordHash, Permission,
+        PhoneNumber, PostalAddress, PostalCode, Role, Tag, TenantId,
+    };
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn client_status_transitions() {
+        use super::ClientStatus::*;
+        assert!(Prospect.can_transition_to(Active));
+        assert!(Active.can_transition_to(Suspended));
+        assert!(Suspended.can_transition_to(Active));
+        assert!(Suspended.can_transition_to(Archived));
+        assert!(!Prospect.can_transition_to(Suspended));
+        assert!(!Active.can_transition_to(Prospect));
+        assert!(!Active.can_transition_to(Active));
+        assert!(ClientStatus::ALL
+            .into_iter()
+            .all(|to| !Archived.can_transition_to(to)));
+
+        assert_eq!(" ＡＣＴＩＶＥ ".parse::<ClientStatus>(), Ok(Active));
+        assert_matches!("deleted".parse::<ClientStatus>(), Err(_));
+    }
+
+    #[test]
+    fn tag_validation() {
+        assert_eq!(Tag::new(" VIP ").unwrap().as_str(), "vip");
+        assert_eq!(Tag::new("ＶＩＰ").unwrap(), Tag::new("vip").unwrap());
+        assert_eq!(Tag::new("見込み客").unwrap().as_str(), "見込み客");
+        assert_eq!(Tag::new("key_account-2").unwrap().as_str(), "key_account-2");
+        for invalid in [
+            "",
+            " ",
+            "two words",
+            "vip!",
+            "a,b",
+            &"a".repeat(Tag::MAX_LENGTH + 1),
+        ] {
+            assert_matches!(Tag::new(invalid), Err(_), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn location_normalization() {
+        let keys = [
+            "Tokyo",
+            "東京",
+            "tokyo ",
+            "東京都",
+            "ＴＯＫＹＯ",
+            "トウキョウ",
+            "Tokyo-to",
+        ]
+        .map(|text| Location::new(text).normalized_key());
+        assert!(keys.iter().all(|key| key == "JP/東京都/"), "{:?}", keys);
+
+        let location = Location::new(" 東京都渋谷区 ");
+        assert_eq!(location.as_str(), "東京都渋谷区");
+        assert_eq!(location.country(), Some("JP"));
+        assert_eq!(location.region(), Some("東京都"));
+        assert_eq!(location.city(), Some("渋谷区"));
+        assert_eq!(
+            location.normalized_key(),
+            Location::new("渋谷区, 東京").normalized_key()
+        );
+
+        let location = Location::new("大阪市北区");
+        assert_eq!(location.region(), Some("大阪府"));
+        assert_eq!(location.city(), Some("大阪市北区"));
+
+        let location = Location::new("Yokohama");
+        assert_eq!(location.region(), Some("神奈川県"));
+        assert_eq!(location.city(), Some("横浜市"));
+
+        let location = Location::new("New York, USA");
+        assert_eq!(location.country(), Some("US"));
+        assert_eq!(location.region(), Some("New York"));
+        assert_eq!(location.city(), Some("New York"));
+
+        let location = Location::new("Japan");
+        assert_eq!(location.country(), Some("JP"));
+        assert_eq!(location.region(), None);
+
+        let location = Location::new("Atlantis");
+        assert!(!location.is_recognized());
+        assert_eq!(location.normalized_key(), "atlantis");
+    }
+
+    #[test]
+    fn location_is_in() {
+        let shibuya = Location::new("東京都渋谷区");
+        assert!(shibuya.is_in(&Location::new("tokyo")));
+        assert!(shibuya.is_in(&Location::new("日本")));
+        assert!(!shibuya.is_in(&Location::new("Osaka")));
+        assert!(!shibuya.is_in(&Location::new("USA")));
+        assert!(Location::new("Atlantis").is_in(&Location::new("ATLANTIS")));
+    }
+
+    #[test]
+    fn email_validation() {
+        assert_eq!(
+            Email::new(" taro.yamada+work@Example.CO.JP ")
+                .unwrap()
+                .as_str(),
+            "taro.yamada+work@example.co.jp"
+        );
+        for invalid in [
+            "",
+            "taro",
+            "@example.com",
+            "taro@",
+            "taro@example",
+            "taro..yamada@example.com",
+            ".taro@example.com",
+            "taro@-example.com",
+            "taro@exa mple.com",
+            "たろう@example.com",
+        ] {
+            assert_matches!(Email::new(invalid), Err(_), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn phone_number_normalization() {
+        assert_eq!(
+            PhoneNumber::new("090-1234-5678").unwrap().as_str(),
+            "+819012345678"
+        );
+        assert_eq!(
+            PhoneNumber::new("(03) 1234 5678").unwrap().as_str(),
+            "+81312345678"
+        );
+        assert_eq!(
+            PhoneNumber::new("０９０－１２３４－５６７８")
+                .unwrap()
+                .as_str(),
+            "+819012345678"
+        );
+        assert_eq!(
+            PhoneNumber::new("+1 (415) 555-2671").unwrap().as_str(),
+            "+14155552671"
+        );
+        // 国番号の後のトランクプレフィックス0は除く
+        for with_trunk_prefix in [
+            "+81 090-1234-5678",
+            "+81 (0)90-1234-5678",
+            "+81-90-1234-5678",
+            "+8109012345678",
+        ] {
+            assert_eq!(
+                PhoneNumber::new(with_trunk_prefix).unwrap().as_str(),
+                "+819012345678",
+                "{}",
+                with_trunk_prefix
+            );
+        }
+        assert_eq!(
+            PhoneNumber::new("+44 020 7946 0958").unwrap().as_str(),
+            "+442079460958"
+        );
+        // イタリアでは0も番号の一部
+        assert_eq!(
+            PhoneNumber::new("+39 06 6982 0000").unwrap().as_str(),
+            "+390669820000"
+        );
+        for invalid in [
+            "",
+            "1234",
+            "9012345678",
+            "+0123456789",
+            "090-1234-abcd",
+            "+1234567890123456",
+        ] {
+            assert_matches!(PhoneNumber::new(invalid), Err(_), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn postal_code_format() {
+        assert_eq!(PostalCode::new("100-0001").unwrap().as_str(), "100-0001");
+        assert_eq!(PostalCode::new("1000001").unwrap().as_str(), "100-0001");
+        assert_eq!(
+            PostalCode::new("〒１００－０００１").unwrap().as_str(),
+            "100-0001"
+        );
+        for invalid in [
+            "",
+            "100-001",
+            "1000-001",
+            "10000001",
+            "abc-defg",
+            "100--0001",
+        ] {
+            assert_matches!(PostalCode::new(invalid), Err(_), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn postal_address_parse() {
+        let address = PostalAddress::parse("〒100-0001　東京都千代田区千代田1-1").unwrap();
+        assert_eq!(address.postal_code().as_str(), "100-0001");
+        assert_eq!(address.address(), "東京都千代田区千代田1-1");
+
+        assert_matches!(PostalAddress::parse("100-0001"), Err(_));
+        assert_matches!(PostalAddress::new("100-0001", " "), Err(_));
+    }
+
+    #[test]
+    fn contact_info_parse() {
+        let info = ContactInfo::parse(ContactKind::Phone, "03-1234-5678").unwrap();
+        assert_eq!(info.kind(), ContactKind::Phone);
+        assert_eq!(info.to_string(), "+81312345678");
+
+        let info = ContactInfo::parse(ContactKind::PostalAddress, "1000001 東京都").unwrap();
+        assert_eq!(info.to_string(), "〒100-0001 東京都");
+
+        assert_matches!(ContactInfo::parse(ContactKind::Email, "taro"), Err(_));
+    }
+
+    #[test]
+    fn tenant_id_new() {
+        assert_eq!(TenantId::new(" Sales-2 ").unwrap().as_str(), "sales-2");
+        assert_eq!(TenantId::default().as_str(), "default");
+        for invalid in ["", "sales team", "営業", &"a".repeat(33)] {
+            assert_matches!(TenantId::new(invalid), Err(_), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn role_permissions() {
+        assert_matches!(" Editor ".parse::<Role>(), Ok(Role::Editor));
+        assert_matches!("owner".parse::<Role>(), Err(_));
+        let allowed = Role::ALL.map(|role| {
+            [Permission::Read, Permission::Write, Permission::Administer]
+                .into_iter()
+                .filter(|permission| role.allows(*permission))
+                .count()
+        });
+        assert_eq!(allowed, [1, 2, 3]);
+    }
+
+    #[test]
+    fn password_hash_verify() {
+        let hash = PasswordHash::new("correct horse").unwrap();
+        assert!(hash.verify("correct horse"));
+        assert!(!hash.verify("correct horse "));
+        // 同じパスワードでもソルトが異なる
+        assert_ne!(hash, PasswordHash::new("correct horse").unwrap());
+        assert_eq!(format!("{:?}", hash), "PasswordHash(..)");
+        assert_matches!(PasswordHash::new("short"), Err(_));
+
+        // 保存したときの反復回数で検証する
+        let salt = [7; 16];
+        let stored = PasswordHash {
+            salt,
+            rounds: 10,
+            hash: PasswordHash::derive("correct horse", &salt, 10),
+        };
+        assert!(stored.verify("correct horse"));
+        assert!(!stored.verify("wrong horse"));
+    }
+}
@@ -1,3 +1,25 @@
+mod config_impl;
+mod encryption_impl;
+mod events_impl;
+mod metrics_impl;
 mod repositories_impl;
+mod sinks_impl;
+mod storage_impl;
+mod tenancy_impl;
+mod tracing_impl;
+mod webhooks_impl;
 
-pub use repositories_impl::InMemoryClientRepository;
+pub use config_impl::{Backend, Config, ConfigSource, OutputFormat};
+pub use encryption_impl::{EncryptedField, EncryptingClientRepository, Keyring};
+pub use events_impl::{InMemoryEventPublisher, OutboxEventPublisher};
+pub use metrics_impl::MetricsEndpoint;
+pub use repositories_impl::{
+    InMemoryAuditRepository, InMemoryClientRepository, InMemoryIdempotencyRepository,
+    InMemoryOutboxRepository, InMemoryProjectRepository, InMemoryRelationshipRepository,
+    InMemoryTombstoneRepository, InMemoryUserRepository, InMemoryWebhookRepository,
+};
+pub use sinks_impl::SinkSpec;
+pub use storage_impl::{FileStore, Persistent};
+pub use tenancy_impl::{TenantDirectory, TenantScopedClientRepository};
+pub use tracing_impl::TracedRepository;
+pub use webhooks_impl::WebhookDispatcher;
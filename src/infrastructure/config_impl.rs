@@ -0,0 +1,637 @@
+use crate::domain::{TenantId, UniquenessPolicy};
+use crate::infrastructure::{EncryptedField, SinkSpec};
+use crate::telemetry::{Level, LogFormat};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use toml::{Spanned, Value};
+
+/// 設定ファイルを置くディレクトリの名前
+const APP_NAME: &str = env!("CARGO_PKG_NAME");
+/// 設定を上書きする環境変数の接頭辞 (page_sizeならDDD_EXAMPLE_PAGE_SIZE)
+const ENV_PREFIX: &str = "DDD_EXAMPLE_";
+
+// -------------------------------------------------------------------------------------------------
+
+/// クライアントを保存する先
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Memory,
+    /// データのディレクトリにJSONファイルとして保存する
+    File,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "memory" => Ok(Backend::Memory),
+            "file" => Ok(Backend::File),
+            _ => Err(format!("Unknown backend: {} (expected memory or file)", s)),
+        }
+    }
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backend::Memory => write!(f, "memory"),
+            Backend::File => write!(f, "file"),
+        }
+    }
+}
+
+/// 一覧の表示形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(format!(
+                "Unknown output format: {} (expected text or json)",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 設定値がどこで決まったか
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    File(PathBuf),
+    Env(String),
+    Flag,
+}
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File(path) => write!(f, "file {}", path.display()),
+            ConfigSource::Env(name) => write!(f, "env {}", name),
+            ConfigSource::Flag => write!(f, "flag"),
+        }
+    }
+}
+
+/// 設定ファイルから読んだ値．環境変数の値は常に文字列として扱う
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RawValue {
+    Text(String),
+    List(Vec<String>),
+}
+
+/// 文字列から読み，設定ファイルに書ける形で表示できる値
+trait ConfigValue: Sized {
+    fn from_raw(raw: &RawValue) -> Result<Self, String>;
+    fn to_toml(&self) -> String;
+}
+
+fn toml_string(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\t', "\\t")
+    )
+}
+
+fn single(raw: &RawValue) -> Result<&str, String> {
+    match raw {
+        RawValue::Text(text) => Ok(text),
+        RawValue::List(_) => Err("expected a single value, not an array".to_string()),
+    }
+}
+
+macro_rules! impl_config_value {
+    // 文字列として書く値
+    (quoted: $($type:ty),*) => {
+        $(
+            impl ConfigValue for $type {
+                fn from_raw(raw: &RawValue) -> Result<Self, String> {
+                    single(raw)?.parse().map_err(|err| format!("{}", err))
+                }
+                fn to_toml(&self) -> String {
+                    toml_string(&self.to_string())
+                }
+            }
+        )*
+    };
+    // 数値・真偽値としてそのまま書く値
+    (bare: $($type:ty),*) => {
+        $(
+            impl ConfigValue for $type {
+                fn from_raw(raw: &RawValue) -> Result<Self, String> {
+                    let text = single(raw)?;
+                    text.trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid {}: {}", stringify!($type), text))
+                }
+                fn to_toml(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+impl_config_value!(
+    quoted: Backend,
+    OutputFormat,
+    TenantId,
+    UniquenessPolicy,
+    EncryptedField,
+    SinkSpec,
+    Level,
+    LogFormat,
+    SocketAddr
+);
+impl_config_value!(bare: usize, u32, u64, bool);
+
+impl ConfigValue for PathBuf {
+    fn from_raw(raw: &RawValue) -> Result<Self, String> {
+        Ok(PathBuf::from(single(raw)?))
+    }
+    fn to_toml(&self) -> String {
+        toml_string(&self.display().to_string())
+    }
+}
+
+/// 空文字列で未指定を表す
+impl<T: ConfigValue> ConfigValue for Option<T> {
+    fn from_raw(raw: &RawValue) -> Result<Self, String> {
+        if single(raw)?.is_empty() {
+            return Ok(None);
+        }
+        T::from_raw(raw).map(Some)
+    }
+    fn to_toml(&self) -> String {
+        match self {
+            Some(value) => value.to_toml(),
+            None => toml_string(""),
+        }
+    }
+}
+
+/// 環境変数ではカンマ区切りで並べる
+impl<T: ConfigValue> ConfigValue for Vec<T> {
+    fn from_raw(raw: &RawValue) -> Result<Self, String> {
+        match raw {
+            RawValue::Text(text) => text
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| T::from_raw(&RawValue::Text(item.to_string())))
+                .collect(),
+            RawValue::List(items) => items
+                .iter()
+                .map(|item| T::from_raw(&RawValue::Text(item.clone())))
+                .collect(),
+        }
+    }
+    fn to_toml(&self) -> String {
+        format!(
+            "[{}]",
+            self.iter()
+                .map(ConfigValue::to_toml)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 設定値と，それを決めた場所
+#[derive(Debug, Clone)]
+pub struct Setting<T> {
+    value: T,
+    source: ConfigSource,
+}
+
+impl<T> Setting<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            source: ConfigSource::Default,
+        }
+    }
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+    pub fn source(&self) -> &ConfigSource {
+        &self.source
+    }
+    pub fn set(&mut self, value: T, source: ConfigSource) {
+        self.value = value;
+        self.source = source;
+    }
+}
+
+fn assign<T: ConfigValue>(
+    setting: &mut Setting<T>,
+    raw: &RawValue,
+    source: ConfigSource,
+) -> Result<(), String> {
+    setting.set(T::from_raw(raw)?, source);
+    Ok(())
+}
+
+macro_rules! config {
+    ($($(#[doc = $doc:literal])* $key:ident: $type:ty = $default:expr,)*) => {
+        /// 既定値 < 設定ファイル < 環境変数 < コマンドラインの順に上書きした設定
+        #[derive(Debug, Clone)]
+        pub struct Config {
+            $(
+                $(#[doc = $doc])*
+                pub $key: Setting<$type>,
+            )*
+        }
+
+        impl Default for Config {
+            fn default() -> Self {
+                Self {
+                    $($key: Setting::new($default),)*
+                }
+            }
+        }
+
+        impl Config {
+            const KEYS: &'static [&'static str] = &[$(stringify!($key)),*];
+
+            fn assign(&mut self, key: &str, raw: &RawValue, source: ConfigSource) -> Result<(), String> {
+                match key {
+                    $(stringify!($key) => assign(&mut self.$key, raw, source),)*
+                    _ => return Err(format!("Unknown setting: {}", key)),
+                }
+                .map_err(|err| format!("{}: {}", key, err))
+            }
+        }
+
+        /// 設定ファイルにそのまま書ける形で，値を決めた場所を添えて表示する
+        impl Display for Config {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                $(
+                    writeln!(
+                        f,
+                        "{} = {}  # {}",
+                        stringify!($key),
+                        self.$key.value().to_toml(),
+                        self.$key.source()
+                    )?;
+                )*
+                Ok(())
+            }
+        }
+    };
+}
+
+config! {
+    /// クライアントの保存先
+    backend: Backend = Backend::Memory,
+    /// fileの保存先．未指定ならXDG_DATA_HOME (なければ~/.local/share) の下
+    data_dir: Option<PathBuf> = None,
+    /// このセッションで扱うテナント
+    tenant: TenantId = TenantId::default(),
+    /// クライアントを暗号化する鍵ファイル
+    encryption_keys: Option<PathBuf> = None,
+    /// 暗号化するクライアントの項目
+    encrypted_fields: Vec<EncryptedField> = EncryptedField::ALL.to_vec(),
+    /// 書き出すファイルの既定のディレクトリ
+    export_dir: PathBuf = PathBuf::from("."),
+    /// 一覧の表示形式
+    output_format: OutputFormat = OutputFormat::Text,
+    /// 一覧の1ページの件数 (0ならページに分けない)
+    page_size: usize = 20,
+    /// 名前と出身地の重複の扱い
+    uniqueness: UniquenessPolicy = UniquenessPolicy::Reject,
+    /// 取り消せる変更の数
+    history_size: usize = 50,
+    /// データを変更するコマンドを拒否する
+    read_only: bool = false,
+    /// リクエストと所要時間をinfoでログに出力する
+    verbose: bool = false,
+    /// 失敗したリクエストを再試行する回数
+    retries: usize = 0,
+    /// イベントの配信先
+    sinks: Vec<SinkSpec> = Vec::new(),
+    /// 配信先への配信を再試行する回数
+    delivery_retries: usize = 2,
+    /// 配信を諦めてデッドレターにするまでに失敗できる回数
+    max_delivery_attempts: u32 = 5,
+    /// 配信に失敗したイベントを次に配信するまでの最初の間隔(ミリ秒)．失敗するたびに倍にする
+    delivery_backoff_ms: u64 = 30_000,
+    /// 出力するログの最も詳細な重要度
+    log_level: Level = Level::Warn,
+    /// ログの1行の形式
+    log_format: LogFormat = LogFormat::Human,
+    /// ログの出力先．未指定なら対話中はddd_example.log，それ以外は標準エラー出力
+    log_file: Option<PathBuf> = None,
+    /// メトリクスを公開するアドレス．未指定なら公開しない
+    metrics_address: Option<SocketAddr> = None,
+}
+
+impl Config {
+    /// XDG_CONFIG_HOME (なければ~/.config) の下の設定ファイル
+    pub fn default_path<F: Fn(&str) -> Option<String>>(var: F) -> Option<PathBuf> {
+        let base = match var("XDG_CONFIG_HOME").filter(|dir| Path::new(dir).is_absolute()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(var("HOME").filter(|home| !home.is_empty())?).join(".config"),
+        };
+        Some(base.join(APP_NAME).join("config.toml"))
+    }
+    /// data_dirが指定されていなければXDG_DATA_HOME (なければ~/.local/share) の下
+    pub fn data_dir_or_default<F: Fn(&str) -> Option<String>>(&self, var: F) -> Option<PathBuf> {
+        if let Some(dir) = self.data_dir.value() {
+            return Some(dir.clone());
+        }
+        let base = match var("XDG_DATA_HOME").filter(|dir| Path::new(dir).is_absolute()) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(var("HOME").filter(|home| !home.is_empty())?)
+                .join(".local")
+                .join("share"),
+        };
+        Some(base.join(APP_NAME))
+    }
+    fn env_name(key: &str) -> String {
+        format!("{}{}", ENV_PREFIX, key.to_uppercase())
+    }
+    /// 書かれていない項目はそのまま残す．知らない項目はタイプミスとして拒否する
+    pub fn apply_file(&mut self, text: &str, path: &Path) -> Result<(), String> {
+        for (line, key, raw) in parse_toml(text)
+            .map_err(|(line, err)| format!("{}:{}: {}", path.display(), line, err))?
+        {
+            self.assign(&key, &raw, ConfigSource::File(path.to_path_buf()))
+                .map_err(|err| format!("{}:{}: {}", path.display(), line, err))?;
+        }
+        Ok(())
+    }
+    pub fn apply_env<I: IntoIterator<Item = (String, String)>>(
+        &mut self,
+        vars: I,
+    ) -> Result<(), String> {
+        let vars = vars.into_iter().collect::<BTreeMap<_, _>>();
+        for key in Self::KEYS {
+            let name = Self::env_name(key);
+            if let Some(value) = vars.get(&name) {
+                self.assign(key, &RawValue::Text(value.clone()), ConfigSource::Env(name))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// 設定ファイルはTOMLとして読み，表を使わない「キー = 値」だけを受け付ける．
+// 値は文字列・整数・真偽値と，それらの配列
+
+/// 行番号，キー，値
+type Entry = (usize, String, RawValue);
+
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+fn parse_toml(text: &str) -> Result<Vec<Entry>, (usize, String)> {
+    let table =
+        toml::from_str::<BTreeMap<Spanned<String>, Spanned<Value>>>(text).map_err(|err| {
+            let line = err.span().map_or(1, |span| line_of(text, span.start));
+            (line, err.message().trim().replace('\n', " "))
+        })?;
+    let mut entries = table
+        .into_iter()
+        .map(|(key, value)| (line_of(text, key.span().start), key, value))
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|(line, _, _)| *line);
+    entries
+        .into_iter()
+        .map(|(line, key, value)| {
+            let raw = raw_value(value.get_ref()).map_err(|err| (line, err))?;
+            Ok((line, key.into_inner(), raw))
+        })
+        .collect()
+}
+
+fn raw_value(value: &Value) -> Result<RawValue, String> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(scalar)
+            .collect::<Result<_, _>>()
+            .map(RawValue::List),
+        value => scalar(value).map(RawValue::Text),
+    }
+}
+
+fn scalar(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Integer(number) => Ok(number.to_string()),
+        Value::Boolean(flag) => Ok(flag.to_string()),
+        Value::Table(_) => Err("Tables are not supported".to_string()),
+        other => Err(format!("Unsupported {}: {}", other.type_str(), other)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Backend, Config, ConfigSource, OutputFormat};
+    use crate::domain::{TenantId, UniquenessPolicy};
+    use crate::infrastructure::EncryptedField;
+    use assert_matches::assert_matches;
+    use std::path::{Path, PathBuf};
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn override_in_layers() {
+        let path = Path::new("/etc/ddd_example/config.toml");
+        let mut config = Config::default();
+        config
+            .apply_file(
+                r#"
+# 共有の設定
+tenant = "acme"
+page_size = 50 # 1ページの件数
+output_format = 'json'
+uniqueness = "warn"
+encrypted_fields = ["name"]
+sinks = ["stdout", "file:C:\\logs\\events.jsonl"]
+delivery_backoff_ms = 1_000
+"#,
+                path,
+            )
+            .unwrap();
+        config
+            .apply_env(env(&[
+                ("DDD_EXAMPLE_PAGE_SIZE", "10"),
+                ("DDD_EXAMPLE_ENCRYPTED_FIELDS", "name, location"),
+                ("PAGE_SIZE", "99"),
+            ]))
+            .unwrap();
+        config
+            .uniqueness
+            .set(UniquenessPolicy::Off, ConfigSource::Flag);
+
+        assert_eq!(config.backend.value(), &Backend::Memory);
+        assert_eq!(config.backend.source(), &ConfigSource::Default);
+        assert_eq!(config.tenant.value(), &TenantId::new("acme").unwrap());
+        assert_eq!(
+            config.tenant.source(),
+            &ConfigSource::File(path.to_path_buf())
+        );
+        assert_eq!(config.output_format.value(), &OutputFormat::Json);
+        assert_eq!(config.page_size.value(), &10);
+        assert_eq!(
+            config.page_size.source(),
+            &ConfigSource::Env("DDD_EXAMPLE_PAGE_SIZE".to_string())
+        );
+        assert_eq!(
+            config.encrypted_fields.value(),
+            &vec![EncryptedField::Name, EncryptedField::Location]
+        );
+        assert_eq!(config.uniqueness.value(), &UniquenessPolicy::Off);
+        assert_eq!(config.uniqueness.source(), &ConfigSource::Flag);
+        assert_eq!(config.sinks.value().len(), 2);
+        assert_eq!(config.delivery_backoff_ms.value(), &1000);
+        assert_eq!(config.history_size.value(), &50);
+    }
+
+    #[test]
+    fn reject_invalid_settings() {
+        let path = Path::new("config.toml");
+        for (text, error) in [
+            ("pagesize = 10", "config.toml:1: Unknown setting: pagesize"),
+            (
+                "\npage_size = \"ten\"",
+                "config.toml:2: page_size: Invalid usize: ten",
+            ),
+            (
+                "output_format = json",
+                "config.toml:1: invalid string expected `\"`, `'`",
+            ),
+            ("page_size = 1.5", "config.toml:1: Unsupported float: 1.5"),
+            ("[output]", "config.toml:1: Tables are not supported"),
+            (
+                "tenant = \"a\"\ntenant = \"b\"",
+                "config.toml:2: duplicate key `tenant` in document root",
+            ),
+            (
+                "sinks = [\"stdout\"",
+                "config.toml:1: invalid array expected `]`",
+            ),
+            (
+                "tenant = [\"acme\"]",
+                "config.toml:1: tenant: expected a single value, not an array",
+            ),
+        ] {
+            assert_eq!(
+                Config::default().apply_file(text, path),
+                Err(error.to_string())
+            );
+        }
+        assert_matches!(
+            Config::default().apply_env(env(&[("DDD_EXAMPLE_BACKEND", "postgres")])),
+            Err(err) if err == "backend: Unknown backend: postgres (expected memory or file)"
+        );
+    }
+
+    #[test]
+    fn show_as_config_file() {
+        let mut config = Config::default();
+        config
+            .apply_env(env(&[
+                ("DDD_EXAMPLE_ENCRYPTION_KEYS", "/run/secrets/keys"),
+                (
+                    "DDD_EXAMPLE_SINKS",
+                    "stdout,webhook:http://localhost:8080/hook",
+                ),
+                ("DDD_EXAMPLE_READ_ONLY", "true"),
+            ]))
+            .unwrap();
+        let shown = config.to_string();
+        assert!(shown.contains(
+            "encryption_keys = \"/run/secrets/keys\"  # env DDD_EXAMPLE_ENCRYPTION_KEYS\n"
+        ));
+        assert!(shown.contains("read_only = true  # env DDD_EXAMPLE_READ_ONLY\n"));
+        assert!(shown.contains("page_size = 20  # default\n"));
+
+        // 表示した内容を設定ファイルとして読み直せる
+        let mut reloaded = Config::default();
+        reloaded
+            .apply_file(&shown, Path::new("config.toml"))
+            .unwrap();
+        assert_eq!(
+            reloaded
+                .to_string()
+                .lines()
+                .map(|line| line.split("  #").next())
+                .collect::<Vec<_>>(),
+            shown
+                .lines()
+                .map(|line| line.split("  #").next())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn default_path_follows_xdg() {
+        let lookup = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(
+            Config::default_path(lookup(&[("XDG_CONFIG_HOME", "/xdg"), ("HOME", "/home/a")])),
+            Some(PathBuf::from("/xdg/ddd_example/config.toml"))
+        );
+        // 相対パスのXDG_CONFIG_HOMEは無視する
+        assert_eq!(
+            Config::default_path(lookup(&[("XDG_CONFIG_HOME", "xdg"), ("HOME", "/home/a")])),
+            Some(PathBuf::from("/home/a/.config/ddd_example/config.toml"))
+        );
+        assert_eq!(Config::default_path(lookup(&[])), None);
+
+        let mut config = Config::default();
+        assert_eq!(
+            config.data_dir_or_default(lookup(&[("HOME", "/home/a")])),
+            Some(PathBuf::from("/home/a/.local/share/ddd_example"))
+        );
+        config
+            .data_dir
+            .set(Some(PathBuf::from("/srv/data")), ConfigSource::Flag);
+        assert_eq!(
+            config.data_dir_or_default(lookup(&[("XDG_DATA_HOME", "/xdg")])),
+            Some(PathBuf::from("/srv/data"))
+        );
+    }
+}
@@ -0,0 +1,748 @@
+use crate::domain::{
+    normalize_text, Client, ClientRepository, ContactInfo, KeyRotation, Location, Note,
+};
+use crate::telemetry::{Level, Tracer};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt::Display;
+use std::rc::Rc;
+use std::str::FromStr;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 暗号化した値の先頭に付ける形式の識別子
+const PREFIX: &str = "enc1";
+
+fn mac(key: &[u8], parts: &[&[u8]]) -> HmacSha256 {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for part in parts {
+        // 区切りが曖昧にならないように長さを前置する
+        mac.update(&(part.len() as u32).to_be_bytes());
+        mac.update(part);
+    }
+    mac
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return Err(format!("Invalid hex: {}", text));
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&text[i..i + 2], 16).map_err(|_| format!("Invalid hex: {}", text))
+        })
+        .collect()
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 暗号化するクライアントの項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptedField {
+    Name,
+    Location,
+    /// 連絡先の値．種類は平文のまま残す
+    Contacts,
+    /// メモの本文
+    Notes,
+}
+
+impl EncryptedField {
+    pub const ALL: [EncryptedField; 4] = [
+        EncryptedField::Name,
+        EncryptedField::Location,
+        EncryptedField::Contacts,
+        EncryptedField::Notes,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EncryptedField::Name => "name",
+            EncryptedField::Location => "location",
+            EncryptedField::Contacts => "contacts",
+            EncryptedField::Notes => "notes",
+        }
+    }
+    /// 完全一致の検索で比較する形
+    fn normalize(&self, value: &str) -> String {
+        match self {
+            EncryptedField::Location => Location::new(value).normalized_key(),
+            _ => normalize_text(value),
+        }
+    }
+    /// 保存したクライアントがこの項目に持つ値
+    fn stored_values<'a>(&self, client: &'a Client) -> Vec<&'a str> {
+        match self {
+            EncryptedField::Name => vec![client.name()],
+            EncryptedField::Location => vec![client.location().as_str()],
+            EncryptedField::Contacts => client
+                .contacts()
+                .iter()
+                .map(|contact| contact.info().opaque_value())
+                .collect(),
+            EncryptedField::Notes => client.notes().into_iter().map(Note::body).collect(),
+        }
+    }
+}
+
+impl FromStr for EncryptedField {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|field| field.as_str() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                format!(
+                    "Unknown field: {} (expected name, location, contacts or notes)",
+                    s
+                )
+            })
+    }
+}
+
+impl Display for EncryptedField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 1つの主鍵から導出した暗号化用とブラインドインデックス用の鍵
+#[derive(Clone)]
+struct FieldKey {
+    id: String,
+    cipher: ChaCha20Poly1305,
+    index: Vec<u8>,
+}
+
+impl FieldKey {
+    fn new(id: &str, master: &[u8]) -> Self {
+        let derive = |label: &str| {
+            mac(master, &[label.as_bytes()])
+                .finalize()
+                .into_bytes()
+                .to_vec()
+        };
+        Self {
+            id: id.to_string(),
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&derive("client-field-encryption"))),
+            index: derive("client-field-blind-index"),
+        }
+    }
+    /// 鍵ID・項目・持ち主を関連データとして認証し，他のレコードや項目への暗号文の付け替えを検出する
+    fn associated_data(&self, field: EncryptedField, owner: &str) -> Vec<u8> {
+        format!("{}.{}.{}", self.id, field, owner).into_bytes()
+    }
+    /// ChaCha20-Poly1305で暗号化する．ナンスは毎回ランダムに選ぶ
+    fn encrypt(&self, field: EncryptedField, owner: &str, value: &str) -> (Vec<u8>, Vec<u8>) {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: value.as_bytes(),
+                    aad: &self.associated_data(field, owner),
+                },
+            )
+            .expect("ChaCha20-Poly1305 encrypts values of any practical size");
+        (nonce.to_vec(), ciphertext)
+    }
+    fn decrypt(
+        &self,
+        field: EncryptedField,
+        owner: &str,
+        nonce: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<String, String> {
+        if nonce.len() != 12 {
+            return Err(format!("Invalid nonce for {} of client {}", field, owner));
+        }
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &self.associated_data(field, owner),
+                },
+            )
+            .map_err(|_| format!("Failed to authenticate {} of client {}", field, owner))?;
+        String::from_utf8(plaintext)
+            .map_err(|_| format!("Failed to decrypt {} of client {}", field, owner))
+    }
+    /// 正規化した値の鍵付きハッシュ．暗号化したまま完全一致で検索するために使う
+    fn blind_index(&self, field: EncryptedField, value: &str) -> String {
+        let index = mac(
+            &self.index,
+            &[field.as_str().as_bytes(), field.normalize(value).as_bytes()],
+        )
+        .finalize()
+        .into_bytes();
+        to_hex(&index[..16])
+    }
+}
+
+/// 項目の暗号化に使う鍵の一覧．最後の鍵で暗号化し，一覧にある全ての鍵で復号する
+#[derive(Clone)]
+pub struct Keyring {
+    keys: Vec<FieldKey>,
+}
+
+impl Keyring {
+    /// "鍵ID=64桁の16進数"を改行かカンマで区切って並べたもの．#で始まる行は無視する
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut keys: Vec<FieldKey> = Vec::new();
+        for entry in text
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(|line| line.split(','))
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (id, master) = entry
+                .split_once('=')
+                .ok_or_else(|| "Encryption keys must be written as ID=HEX".to_string())?;
+            let id = id.trim();
+            if id.is_empty()
+                || !id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(format!("Invalid encryption key ID: {}", id));
+            }
+            if keys.iter().any(|key| key.id == id) {
+                return Err(format!("Duplicate encryption key ID: {}", id));
+            }
+            let master = from_hex(master.trim())
+                .ok()
+                .filter(|master| master.len() == 32)
+                .ok_or_else(|| format!("Encryption key {} must be 64 hex digits", id))?;
+            keys.push(FieldKey::new(id, &master));
+        }
+        match keys.is_empty() {
+            true => Err("No encryption keys".to_string()),
+            false => Ok(Self { keys }),
+        }
+    }
+    fn current(&self) -> &FieldKey {
+        self.keys.last().expect("keyring is never empty")
+    }
+    fn by_id(&self, id: &str) -> Result<&FieldKey, String> {
+        self.keys
+            .iter()
+            .find(|key| key.id == id)
+            .ok_or_else(|| format!("Unknown encryption key: {}", id))
+    }
+}
+
+// 鍵の値は表示しない
+impl std::fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ids = self
+            .keys
+            .iter()
+            .map(|key| key.id.as_str())
+            .collect::<Vec<_>>();
+        write!(f, "Keyring({})", ids.join(", "))
+    }
+}
+
+/// "enc1.鍵ID.ナンス.認証タグ付きの暗号文"の形で保存した値
+struct SealedValue<'a> {
+    key_id: &'a str,
+    nonce: &'a str,
+    ciphertext: &'a str,
+}
+
+impl<'a> SealedValue<'a> {
+    /// 暗号化されていない値はNone
+    fn parse(value: &'a str) -> Option<Self> {
+        match value.split('.').collect::<Vec<_>>().as_slice() {
+            [PREFIX, key_id, nonce, ciphertext] => Some(Self {
+                key_id,
+                nonce,
+                ciphertext,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// 暗号化した名前や出身地の代わりに保存先が索引を作る値
+fn search_key(key: &FieldKey, field: EncryptedField, value: &str) -> String {
+    [PREFIX, &key.id, &key.blind_index(field, value)].join(".")
+}
+
+/// 暗号文を結び付ける持ち主．連絡先とメモはクライアントのIDとそれ自身のID
+fn owner(client_id: Uuid, item_id: Option<Uuid>) -> String {
+    match item_id {
+        Some(item_id) => format!("{}/{}", client_id.hyphenated(), item_id.hyphenated()),
+        None => client_id.hyphenated().to_string(),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 選択した項目を認証付き暗号で暗号化してから保存するリポジトリ．
+/// 暗号化する前から保存されていた平文の値はそのまま読み，再暗号化のときに暗号化する
+pub struct EncryptingClientRepository<T: ClientRepository + ?Sized> {
+    inner: Rc<T>,
+    keyring: Keyring,
+    fields: Vec<EncryptedField>,
+    tracer: Option<Rc<Tracer>>,
+}
+
+impl<T: ClientRepository + ?Sized> EncryptingClientRepository<T> {
+    pub fn new(inner: Rc<T>, keyring: Keyring) -> Self {
+        Self {
+            inner,
+            keyring,
+            fields: EncryptedField::ALL.to_vec(),
+            tracer: None,
+        }
+    }
+    pub fn with_fields(mut self, fields: &[EncryptedField]) -> Self {
+        self.fields = fields.to_vec();
+        self
+    }
+    /// 一覧や検索から除いた復号できないクライアントを警告として記録する
+    pub fn with_tracer(mut self, tracer: Rc<Tracer>) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+    /// 選択した項目なら現在の鍵で暗号化する
+    fn seal(&self, field: EncryptedField, owner: &str, value: &str) -> String {
+        if !self.fields.contains(&field) {
+            return value.to_string();
+        }
+        let key = self.keyring.current();
+        let (nonce, ciphertext) = key.encrypt(field, owner, value);
+        [PREFIX, &key.id, &to_hex(&nonce), &to_hex(&ciphertext)].join(".")
+    }
+    fn open(&self, field: EncryptedField, owner: &str, value: &str) -> Result<String, String> {
+        let sealed = match SealedValue::parse(value) {
+            Some(sealed) => sealed,
+            None => return Ok(value.to_string()),
+        };
+        let key = self.keyring.by_id(sealed.key_id)?;
+        key.decrypt(
+            field,
+            owner,
+            &from_hex(sealed.nonce)?,
+            &from_hex(sealed.ciphertext)?,
+        )
+    }
+    fn encrypt(&self, client: &Client) -> Result<Client, String> {
+        let id = client.id();
+        let (name, location) = (client.name(), client.location().as_str());
+        let mut encrypted = client.clone();
+        encrypted.edit(
+            self.seal(EncryptedField::Name, &owner(id, None), name),
+            self.seal(EncryptedField::Location, &owner(id, None), location),
+        );
+        // 暗号文では検索できないので，ブラインドインデックスを検索キーとして持たせる
+        if self.fields.contains(&EncryptedField::Name)
+            || self.fields.contains(&EncryptedField::Location)
+        {
+            encrypted.set_lookup_key(Some(Client::lookup_key_of(
+                &self.current_search_term(EncryptedField::Name, name),
+                &self.current_search_term(EncryptedField::Location, location),
+            )));
+        }
+        encrypted.map_contact_infos(|contact| {
+            Ok::<_, String>(match self.fields.contains(&EncryptedField::Contacts) {
+                true => ContactInfo::opaque(
+                    contact.kind(),
+                    self.seal(
+                        EncryptedField::Contacts,
+                        &owner(id, Some(contact.id())),
+                        &contact.info().to_string(),
+                    ),
+                ),
+                false => contact.info().clone(),
+            })
+        })?;
+        encrypted.map_note_bodies(|note| {
+            Ok::<_, String>(self.seal(
+                EncryptedField::Notes,
+                &owner(id, Some(note.id())),
+                note.body(),
+            ))
+        })?;
+        Ok(encrypted)
+    }
+    /// 暗号化されていない値はそのまま読む
+    fn decrypt(&self, stored: Client) -> Result<Client, String> {
+        let id = stored.id();
+        let mut client = stored.clone();
+        client.edit(
+            self.open(EncryptedField::Name, &owner(id, None), stored.name())?,
+            self.open(
+                EncryptedField::Location,
+                &owner(id, None),
+                stored.location().as_str(),
+            )?,
+        );
+        client.map_contact_infos(|contact| {
+            let value = contact.info().opaque_value();
+            if SealedValue::parse(value).is_none() {
+                return Ok(contact.info().clone());
+            }
+            let text = self.open(
+                EncryptedField::Contacts,
+                &owner(id, Some(contact.id())),
+                value,
+            )?;
+            ContactInfo::parse(contact.kind(), &text).map_err(|err| err.to_string())
+        })?;
+        client.map_note_bodies(|note| {
+            self.open(
+                EncryptedField::Notes,
+                &owner(id, Some(note.id())),
+                note.body(),
+            )
+        })?;
+        Ok(client)
+    }
+    /// 復号できなければ記録し，Noneを返す
+    fn decrypt_or_warn(&self, stored: Client) -> Option<Client> {
+        let id = stored.id();
+        match self.decrypt(stored) {
+            Ok(client) => Some(client),
+            Err(err) => {
+                if let Some(tracer) = &self.tracer {
+                    let mut span = tracer.span(
+                        Level::Warn,
+                        "EncryptingClientRepository",
+                        "decrypt",
+                        Vec::new(),
+                    );
+                    span.record("id", &id);
+                    span.record_str("error", &err);
+                }
+                None
+            }
+        }
+    }
+    /// 現在の鍵で保存したクライアントを検索で一致させる形
+    fn current_search_term(&self, field: EncryptedField, value: &str) -> String {
+        match self.fields.contains(&field) {
+            true => search_key(self.keyring.current(), field, value),
+            false => value.to_string(),
+        }
+    }
+    /// 検索語が保存されているときに取りうる形．暗号化する項目は鍵ごとの検索キーと，暗号化する前の平文
+    fn search_terms(&self, field: EncryptedField, query: &str) -> Vec<String> {
+        let mut terms = vec![query.to_string()];
+        if self.fields.contains(&field) {
+            terms.extend(
+                self.keyring
+                    .keys
+                    .iter()
+                    .map(|key| search_key(key, field, query)),
+            );
+        }
+        terms
+    }
+    /// 選択した項目が現在の鍵で暗号化され，それ以外の項目は平文になっているか
+    fn is_current(&self, stored: &Client) -> bool {
+        EncryptedField::ALL.into_iter().all(|field| {
+            field.stored_values(stored).into_iter().all(|value| {
+                let sealed = SealedValue::parse(value);
+                match self.fields.contains(&field) {
+                    true => {
+                        sealed.map(|sealed| sealed.key_id)
+                            == Some(self.keyring.current().id.as_str())
+                    }
+                    false => sealed.is_none(),
+                }
+            })
+        })
+    }
+}
+
+impl<T: ClientRepository + ?Sized> ClientRepository for EncryptingClientRepository<T> {
+    fn by_id(&self, id: Uuid) -> Result<Client, String> {
+        self.decrypt(self.inner.by_id(id)?)
+    }
+    fn save(&self, client: Client) -> Result<(), String> {
+        self.inner.save(self.encrypt(&client)?)
+    }
+    fn delete(&self, id: Uuid) -> Result<(), String> {
+        self.inner.delete(id)
+    }
+    /// 復号できないクライアントは含めず，警告として記録する
+    fn all(&self) -> Vec<Client> {
+        self.inner
+            .all()
+            .into_iter()
+            .filter_map(|stored| self.decrypt_or_warn(stored))
+            .collect()
+    }
+    /// 保存先の索引をブラインドインデックスで引く
+    fn by_name_and_location(&self, name: &str, location: &str) -> Vec<Client> {
+        let locations = self.search_terms(EncryptedField::Location, location);
+        let mut found: Vec<Client> = Vec::new();
+        for name in self.search_terms(EncryptedField::Name, name) {
+            for location in locations.iter() {
+                for stored in self.inner.by_name_and_location(&name, location) {
+                    if found.iter().all(|client| client.id() != stored.id()) {
+                        found.extend(self.decrypt_or_warn(stored));
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+impl<T: ClientRepository + ?Sized> KeyRotation for EncryptingClientRepository<T> {
+    fn reencrypt(&self, ids: &[Uuid]) -> Result<usize, String> {
+        let mut count = 0;
+        for id in ids {
+            let stored = self.inner.by_id(*id)?;
+            if self.is_current(&stored) {
+                continue;
+            }
+            let client = self.decrypt(stored)?;
+            self.save(client)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EncryptedField, EncryptingClientRepository, Keyring};
+    use crate::domain::{
+        Client, ClientRepository, ContactInfo, ContactKind, KeyRotation, MockClientRepository,
+    };
+    use crate::infrastructure::InMemoryClientRepository;
+    use crate::telemetry::{Level, LogFormat, Tracer};
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const OLD_KEY: &str = "2024=000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const NEW_KEY: &str = "2025=202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f";
+
+    fn keyring(text: &str) -> Keyring {
+        Keyring::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parse_keyring() {
+        let keyring = keyring(&format!("# rotated yearly\n{}\n{}\n", OLD_KEY, NEW_KEY));
+        assert_eq!(keyring.current().id, "2025");
+        assert_matches!(Keyring::parse(&format!("{},{}", OLD_KEY, NEW_KEY)), Ok(_));
+        for invalid in [
+            "",
+            "2024",
+            "2024=00ff",
+            "a b=00",
+            &format!("{},{}", OLD_KEY, OLD_KEY),
+        ] {
+            assert_matches!(Keyring::parse(invalid), Err(_), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn encrypt_fields_at_rest() {
+        let inner = Rc::new(InMemoryClientRepository::new());
+        let repository = EncryptingClientRepository::new(Rc::clone(&inner), keyring(OLD_KEY));
+        let mut client = Client::new("Taro".to_string(), "東京都渋谷区".to_string());
+        for (kind, value) in [
+            (ContactKind::Email, "taro@example.com"),
+            (ContactKind::Phone, "090-1234-5678"),
+            (ContactKind::PostalAddress, "150-0002 東京都渋谷区渋谷1-1-1"),
+        ] {
+            client.add_contact(ContactInfo::parse(kind, value).unwrap());
+        }
+        client.add_note("Prefers email", Utc::now()).unwrap();
+        repository.save(client.clone()).unwrap();
+
+        let stored = inner.by_id(client.id()).unwrap();
+        assert!(stored.name().starts_with("enc1.2024."));
+        assert_eq!(stored.name().split('.').count(), 4);
+        assert!(!stored.name().contains("Taro"));
+        assert!(!stored.location().as_str().contains("渋谷"));
+        // 連絡先は種類だけ，メモは何も平文で残さない
+        let contacts = stored.contacts();
+        assert_eq!(contacts.len(), 3);
+        for (contact, plaintext) in contacts.iter().zip(["taro", "1234", "渋谷1-1-1"]) {
+            let value = contact.info().opaque_value();
+            assert!(value.starts_with("enc1.2024."), "{}", value);
+            assert!(!value.contains(plaintext));
+        }
+        assert!(stored.notes()[0].body().starts_with("enc1.2024."));
+        assert!(!stored.notes()[0].body().contains("email"));
+        assert_eq!(repository.by_id(client.id()), Ok(client.clone()));
+        assert_eq!(repository.all(), vec![client.clone()]);
+
+        // 同じ値でも暗号文は毎回異なる
+        repository.save(client.clone()).unwrap();
+        assert_ne!(inner.by_id(client.id()).unwrap().name(), stored.name());
+
+        // 平文のまま残す項目を選べる
+        let repository = repository.with_fields(&[EncryptedField::Name]);
+        repository.save(client.clone()).unwrap();
+        let stored = inner.by_id(client.id()).unwrap();
+        assert_eq!(stored.location().as_str(), "東京都渋谷区");
+        assert_eq!(stored.contacts(), client.contacts());
+        assert_eq!(stored.notes(), client.notes());
+        assert_eq!(repository.by_id(client.id()), Ok(client));
+    }
+
+    #[test]
+    fn detect_tampering() {
+        let inner = Rc::new(InMemoryClientRepository::new());
+        let logs = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&logs);
+        let tracer = Tracer::new(Level::Warn, LogFormat::Human, move |line| {
+            sink.borrow_mut().push(line)
+        });
+        let repository = EncryptingClientRepository::new(Rc::clone(&inner), keyring(OLD_KEY))
+            .with_tracer(Rc::new(tracer));
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let jiro = Client::new("Jiro".to_string(), "Osaka".to_string());
+        repository.save(taro.clone()).unwrap();
+        repository.save(jiro.clone()).unwrap();
+
+        // 他のクライアントの暗号文に付け替える
+        let mut swapped = inner.by_id(taro.id()).unwrap();
+        let stored_jiro = inner.by_id(jiro.id()).unwrap();
+        swapped.edit(
+            stored_jiro.name().to_string(),
+            swapped.location().as_str().to_string(),
+        );
+        inner.save(swapped).unwrap();
+        assert_matches!(repository.by_id(taro.id()), Err(_));
+        // 一覧からは除き，警告を記録する
+        assert_eq!(repository.all(), vec![jiro.clone()]);
+        let logs = logs.borrow();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].contains("WARN  EncryptingClientRepository.decrypt"));
+        assert!(logs[0].contains(&taro.id().hyphenated().to_string()));
+        assert!(!logs[0].contains("Taro"));
+
+        // 知らない鍵で暗号化された値は読めない
+        let other = EncryptingClientRepository::new(Rc::clone(&inner), keyring(NEW_KEY));
+        assert_matches!(other.by_id(jiro.id()), Err(err) if err.contains("2024"));
+
+        // 同じクライアントのメモ同士で暗号文を入れ替える
+        let mut hanako = Client::new("Hanako".to_string(), "Kyoto".to_string());
+        hanako.add_note("first", Utc::now()).unwrap();
+        hanako.add_note("second", Utc::now()).unwrap();
+        repository.save(hanako.clone()).unwrap();
+        let mut swapped = inner.by_id(hanako.id()).unwrap();
+        let mut bodies = swapped
+            .notes()
+            .into_iter()
+            .map(|note| note.body().to_string())
+            .collect::<Vec<_>>();
+        swapped
+            .map_note_bodies(|_| Ok::<_, String>(bodies.pop().unwrap()))
+            .unwrap();
+        inner.save(swapped).unwrap();
+        assert_matches!(repository.by_id(hanako.id()), Err(err) if err.contains("notes"));
+    }
+
+    #[test]
+    fn look_up_by_blind_index() {
+        let inner = Rc::new(InMemoryClientRepository::new());
+        let repository = EncryptingClientRepository::new(Rc::clone(&inner), keyring(OLD_KEY));
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        repository.save(taro.clone()).unwrap();
+        repository
+            .save(Client::new("Taro".to_string(), "Osaka".to_string()))
+            .unwrap();
+
+        // 正規化した値で完全一致する
+        assert_eq!(
+            repository.by_name_and_location("ＴＡＲＯ", "tokyo"),
+            vec![taro.clone()]
+        );
+        assert!(repository.by_name_and_location("Tar", "Tokyo").is_empty());
+        assert!(inner.by_name_and_location("Taro", "Tokyo").is_empty());
+
+        // 鍵を追加した後も古い鍵で暗号化された値を検索できる
+        let rotated = EncryptingClientRepository::new(
+            Rc::clone(&inner),
+            keyring(&format!("{},{}", OLD_KEY, NEW_KEY)),
+        );
+        assert_eq!(
+            rotated.by_name_and_location("Taro", "Tokyo"),
+            vec![taro.clone()]
+        );
+
+        // 保存先の索引を検索キーで引き，全件は読まない
+        let stored = inner.by_id(taro.id()).unwrap();
+        let mut mock_inner = MockClientRepository::new();
+        mock_inner.expect_all().never();
+        mock_inner
+            .expect_by_name_and_location()
+            .returning(move |name, location| {
+                match Client::lookup_key_of(name, location) == stored.lookup_key() {
+                    true => vec![stored.clone()],
+                    false => Vec::new(),
+                }
+            });
+        let repository = EncryptingClientRepository::new(Rc::new(mock_inner), keyring(OLD_KEY));
+        assert_eq!(repository.by_name_and_location("taro", "TOKYO"), vec![taro]);
+    }
+
+    #[test]
+    fn reencrypt_with_rotated_key() {
+        let inner = Rc::new(InMemoryClientRepository::new());
+        // 暗号化を有効にする前の平文のクライアント
+        let mut legacy = Client::new("Hanako".to_string(), "Kyoto".to_string());
+        legacy.add_note("Met at the fair", Utc::now()).unwrap();
+        inner.save(legacy.clone()).unwrap();
+        let old = EncryptingClientRepository::new(Rc::clone(&inner), keyring(OLD_KEY));
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        old.save(taro.clone()).unwrap();
+
+        // 他のテナントのクライアント
+        let other = Client::new("Jiro".to_string(), "Osaka".to_string());
+        old.save(other.clone()).unwrap();
+
+        let rotated = EncryptingClientRepository::new(
+            Rc::clone(&inner),
+            keyring(&format!("{},{}", OLD_KEY, NEW_KEY)),
+        );
+        assert_eq!(rotated.by_id(legacy.id()), Ok(legacy.clone()));
+        let ids = [legacy.id(), taro.id()];
+        assert_eq!(rotated.reencrypt(&ids), Ok(2));
+        assert_eq!(rotated.reencrypt(&ids), Ok(0));
+
+        // 古い鍵を外しても読める
+        let new = EncryptingClientRepository::new(Rc::clone(&inner), keyring(NEW_KEY));
+        assert_eq!(new.by_id(taro.id()), Ok(taro.clone()));
+        assert_eq!(new.by_id(legacy.id()), Ok(legacy.clone()));
+        for id in ids {
+            assert!(inner.by_id(id).unwrap().name().starts_with("enc1.2025."));
+        }
+        assert!(inner.by_id(legacy.id()).unwrap().notes()[0]
+            .body()
+            .starts_with("enc1.2025."));
+        // 指定しなかったクライアントはそのまま
+        assert!(inner
+            .by_id(other.id())
+            .unwrap()
+            .name()
+            .starts_with("enc1.2024."));
+        assert_eq!(new.by_name_and_location("Taro", "Tokyo"), vec![taro]);
+    }
+}
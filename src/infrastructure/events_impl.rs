@@ -0,0 +1,140 @@
+use crate::domain::{
+    ClientEvent, EventHistory, EventPublisher, EventSink, OutboxMessage, OutboxRepository,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use uuid::Uuid;
+
+/// 発行されたイベントを順に保持する
+pub struct InMemoryEventPublisher {
+    events: RefCell<Vec<ClientEvent>>,
+}
+
+impl InMemoryEventPublisher {
+    pub fn new() -> Self {
+        Self {
+            events: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl EventHistory for InMemoryEventPublisher {
+    fn events(&self) -> Vec<ClientEvent> {
+        self.events.borrow().clone()
+    }
+}
+
+impl EventPublisher for InMemoryEventPublisher {
+    fn publish(&self, event: ClientEvent) {
+        self.events.borrow_mut().push(event);
+    }
+}
+
+/// 配信されたイベントを履歴として保持する配信先にもなる
+impl EventSink for InMemoryEventPublisher {
+    fn name(&self) -> String {
+        "history".to_string()
+    }
+    fn deliver(&self, message: &OutboxMessage) -> Result<(), String> {
+        self.publish(message.event().clone());
+        Ok(())
+    }
+    fn redact(&self, client_ids: &[Uuid]) -> Result<usize, String> {
+        let mut count = 0;
+        for event in self.events.borrow_mut().iter_mut() {
+            if client_ids.contains(&event.client_id()) && event.has_personal_data() {
+                event.redact();
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// イベントを直接配信せず，アウトボックスに保存する．
+/// クライアントと同じ作業単位で保存されるため，保存と配信の間で停止してもイベントを失わない
+pub struct OutboxEventPublisher<O: OutboxRepository + ?Sized> {
+    outbox_repo: Rc<O>,
+}
+
+impl<O: OutboxRepository + ?Sized> OutboxEventPublisher<O> {
+    pub fn new(outbox_repo: Rc<O>) -> Self {
+        Self { outbox_repo }
+    }
+}
+
+impl<O: OutboxRepository + ?Sized> EventPublisher for OutboxEventPublisher<O> {
+    fn publish(&self, event: ClientEvent) {
+        self.outbox_repo.save(OutboxMessage::new(event));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InMemoryEventPublisher, OutboxEventPublisher};
+    use crate::domain::repositories::MockOutboxRepository;
+    use crate::domain::{
+        Client, ClientEvent, ClientStatus, EventHistory, EventPublisher, EventSink, OutboxMessage,
+    };
+    use chrono::Utc;
+    use fake::{Fake, Faker};
+    use std::rc::Rc;
+
+    #[test]
+    fn publish_events() {
+        let publisher = InMemoryEventPublisher::new();
+        assert!(publisher.events().is_empty());
+
+        let mut client = Client::new(Faker.fake(), Faker.fake());
+        let activated = client
+            .change_status(ClientStatus::Active, Utc::now())
+            .unwrap();
+        let suspended = client
+            .change_status(ClientStatus::Suspended, Utc::now())
+            .unwrap();
+        publisher.publish(activated.clone());
+        publisher.publish(suspended.clone());
+
+        assert_eq!(publisher.events(), vec![activated, suspended]);
+    }
+
+    #[test]
+    fn redact_delivered_events() {
+        let publisher = InMemoryEventPublisher::new();
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let jiro = Client::new("Jiro".to_string(), "Osaka".to_string());
+        for client in [&taro, &jiro] {
+            publisher
+                .deliver(&OutboxMessage::new(ClientEvent::created(
+                    client,
+                    Utc::now(),
+                )))
+                .unwrap();
+        }
+
+        assert_eq!(publisher.redact(&[taro.id()]), Ok(1));
+        assert_eq!(publisher.redact(&[taro.id()]), Ok(0));
+        let events = publisher.events();
+        assert!(!events[0].has_personal_data());
+        assert!(events[1].has_personal_data());
+    }
+
+    #[test]
+    fn publish_events_to_outbox() {
+        let mut client = Client::new(Faker.fake(), Faker.fake());
+        let activated = client
+            .change_status(ClientStatus::Active, Utc::now())
+            .unwrap();
+
+        let mut mock_outbox_repo = MockOutboxRepository::new();
+        let expected = activated.clone();
+        mock_outbox_repo
+            .expect_save()
+            .withf(move |message| *message.event() == expected && message.is_pending())
+            .times(1)
+            .return_const(());
+
+        let publisher = OutboxEventPublisher::new(Rc::new(mock_outbox_repo));
+        publisher.publish(activated);
+    }
+}
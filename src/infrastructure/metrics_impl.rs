@@ -0,0 +1,145 @@
+use crate::telemetry::MetricsRegistry;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// GET /metrics にPrometheusのテキスト形式で応答する．
+/// 対話の入力を待つ間も取得できるように，別のスレッドで受け付ける
+pub struct MetricsEndpoint {
+    listener: TcpListener,
+    metrics: Arc<MetricsRegistry>,
+}
+
+impl MetricsEndpoint {
+    /// ポートに0を指定すると空いているポートを使う
+    pub fn bind(address: SocketAddr, metrics: Arc<MetricsRegistry>) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|err| format!("{}: {}", address, err))?;
+        Ok(Self { listener, metrics })
+    }
+    pub fn local_addr(&self) -> Result<SocketAddr, String> {
+        self.listener.local_addr().map_err(|err| err.to_string())
+    }
+    /// プロセスが終わるまで受け付け続ける
+    pub fn serve(self) {
+        thread::spawn(move || {
+            for stream in self.listener.incoming().flatten() {
+                // 1つの接続の失敗で公開を止めない
+                let _ = respond(stream, &self.metrics);
+            }
+        });
+    }
+}
+
+fn respond(mut stream: TcpStream, metrics: &MetricsRegistry) -> std::io::Result<()> {
+    let timeout = Some(Duration::from_secs(5));
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+
+    // リクエスト行とヘッダーだけを読み，本文は扱わない
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < 8192 {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+
+    let (status, content_type, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            metrics.render(),
+        ),
+        (Some("GET"), Some(_)) => ("404 Not Found", "text/plain", "Not Found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method Not Allowed\n".to_string(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::MetricsEndpoint;
+    use crate::domain::repositories::MockClientRepository;
+    use crate::domain::ClientRepository;
+    use crate::infrastructure::TracedRepository;
+    use crate::telemetry::{Level, LogFormat, MetricsRegistry, Tracer};
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    fn scrape(address: SocketAddr, request_line: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "{}\r\nHost: {}\r\n\r\n", request_line, address).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn scrape_repository_metrics() {
+        let mut mock_repo = MockClientRepository::new();
+        mock_repo.expect_all().times(1).return_const(Vec::new());
+        mock_repo
+            .expect_by_id()
+            .times(1)
+            .return_const(Err("Client not found".to_string()));
+        let metrics = Arc::new(MetricsRegistry::new());
+        let tracer = Rc::new(
+            Tracer::new(Level::Off, LogFormat::Human, |_| {}).with_metrics(Arc::clone(&metrics)),
+        );
+        let repository = TracedRepository::new(Rc::new(mock_repo), tracer, "ClientRepository");
+        repository.all();
+        assert!(repository.by_id(Uuid::new_v4()).is_err());
+
+        let endpoint =
+            MetricsEndpoint::bind("127.0.0.1:0".parse().unwrap(), Arc::clone(&metrics)).unwrap();
+        let address = endpoint.local_addr().unwrap();
+        endpoint.serve();
+
+        let response = scrape(address, "GET /metrics HTTP/1.1");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n"));
+        assert!(response.contains(
+            "ddd_example_repository_calls_total{repository=\"ClientRepository\",operation=\"all\",outcome=\"ok\"} 1\n"
+        ));
+        assert!(response.contains(
+            "ddd_example_repository_calls_total{repository=\"ClientRepository\",operation=\"by_id\",outcome=\"error\"} 1\n"
+        ));
+        assert!(response.contains(
+            "ddd_example_repository_call_duration_seconds_count{repository=\"ClientRepository\",operation=\"by_id\"} 1\n"
+        ));
+
+        // 集計は取得のたびに最新になる
+        metrics.set_client_count("default", 2);
+        assert!(scrape(address, "GET /metrics HTTP/1.1")
+            .contains("ddd_example_clients{tenant=\"default\"} 2\n"));
+
+        assert!(scrape(address, "GET / HTTP/1.1").starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(scrape(address, "POST /metrics HTTP/1.1")
+            .starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}
@@ -1,29 +1,89 @@
-use crate::domain::{Client, ClientRepository};
+use super::storage_impl::Persistent;
+use crate::domain::{
+    AuditEntry, AuditRepository, Client, ClientRepository, IdempotencyRecord,
+    IdempotencyRepository, OutboxMessage, OutboxRepository, Project, ProjectRepository,
+    Relationship, RelationshipRepository, Tombstone, TombstoneRepository, Transactional, User,
+    UserRepository, WebhookDelivery, WebhookRepository, WebhookSubscription,
+};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+type ClientSnapshot = (
+    HashMap<Uuid, Client>,
+    HashMap<Uuid, Uuid>,
+    HashMap<(String, String), HashSet<Uuid>>,
+);
+
 pub struct InMemoryClientRepository {
     clients: RefCell<HashMap<Uuid, Client>>,
+    // エイリアスのID -> 統合先のID
+    aliases: RefCell<HashMap<Uuid, Uuid>>,
+    // 検索キー -> ID
+    name_location_index: RefCell<HashMap<(String, String), HashSet<Uuid>>>,
+    // beginした時点の状態
+    snapshots: RefCell<Vec<ClientSnapshot>>,
 }
 
 impl InMemoryClientRepository {
     pub fn new() -> Self {
         Self {
             clients: RefCell::new(HashMap::new()),
+            aliases: RefCell::new(HashMap::new()),
+            name_location_index: RefCell::new(HashMap::new()),
+            snapshots: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn unindex(&self, client: &Client) {
+        let key = client.lookup_key();
+        let mut index = self.name_location_index.borrow_mut();
+        if let Some(ids) = index.get_mut(&key) {
+            ids.remove(&client.id());
+            if ids.is_empty() {
+                index.remove(&key);
+            }
         }
     }
 }
 
 impl ClientRepository for InMemoryClientRepository {
     fn by_id(&self, id: Uuid) -> Result<Client, String> {
+        let id = self.aliases.borrow().get(&id).copied().unwrap_or(id);
         match self.clients.borrow().get(&id) {
             Some(client) => Ok(client.clone()),
             None => Err("No client found for given ID".to_string()),
         }
     }
-    fn save(&self, client: Client) {
+    fn save(&self, client: Client) -> Result<(), String> {
+        let mut aliases = self.aliases.borrow_mut();
+        aliases.retain(|_, target| *target != client.id());
+        for alias in client.aliases() {
+            aliases.insert(*alias, client.id());
+        }
+
+        if let Some(old_client) = self.clients.borrow().get(&client.id()) {
+            self.unindex(old_client);
+        }
+        self.name_location_index
+            .borrow_mut()
+            .entry(client.lookup_key())
+            .or_default()
+            .insert(client.id());
+
         self.clients.borrow_mut().insert(client.id(), client);
+        Ok(())
+    }
+    fn delete(&self, id: Uuid) -> Result<(), String> {
+        let removed = self.clients.borrow_mut().remove(&id);
+        match removed {
+            Some(client) => {
+                self.unindex(&client);
+                self.aliases.borrow_mut().retain(|_, target| *target != id);
+                Ok(())
+            }
+            None => Err("No client found for given ID".to_string()),
+        }
     }
     fn all(&self) -> Vec<Client> {
         let clients = self.clients.borrow();
@@ -33,24 +93,627 @@ impl ClientRepository for InMemoryClientRepository {
         }
         vec_clients
     }
+    fn by_name_and_location(&self, name: &str, location: &str) -> Vec<Client> {
+        let index = self.name_location_index.borrow();
+        let clients = self.clients.borrow();
+        index
+            .get(&Client::lookup_key_of(name, location))
+            .into_iter()
+            .flatten()
+            .filter_map(|id| clients.get(id).cloned())
+            .collect()
+    }
+}
+
+impl Transactional for InMemoryClientRepository {
+    fn begin(&self) {
+        self.snapshots.borrow_mut().push((
+            self.clients.borrow().clone(),
+            self.aliases.borrow().clone(),
+            self.name_location_index.borrow().clone(),
+        ));
+    }
+    fn commit(&self) {
+        self.snapshots.borrow_mut().pop();
+    }
+    fn rollback(&self) {
+        let snapshot = self.snapshots.borrow_mut().pop();
+        if let Some((clients, aliases, name_location_index)) = snapshot {
+            *self.clients.borrow_mut() = clients;
+            *self.aliases.borrow_mut() = aliases;
+            *self.name_location_index.borrow_mut() = name_location_index;
+        }
+    }
+}
+
+/// エイリアスと索引は保存するときに作り直す
+impl Persistent for InMemoryClientRepository {
+    type State = Vec<Client>;
+    fn state(&self) -> Self::State {
+        self.all()
+    }
+    fn restore(&self, state: Self::State) {
+        self.clients.borrow_mut().clear();
+        self.aliases.borrow_mut().clear();
+        self.name_location_index.borrow_mut().clear();
+        for client in state {
+            self.save(client)
+                .expect("an in-memory repository accepts every client");
+        }
+    }
 }
 
 impl InMemoryClientRepository {
     pub fn new_with_clients() -> Self {
-        let repository = Self {
-            clients: RefCell::new(HashMap::new()),
-        };
-        repository.save(Client::new("Taro".to_string(), "Tokyo".to_string()));
-        repository.save(Client::new("Jiro".to_string(), "Tokyo".to_string()));
+        let repository = Self::new();
+        for client in [
+            Client::new("Taro".to_string(), "Tokyo".to_string()),
+            Client::new("Jiro".to_string(), "Tokyo".to_string()),
+        ] {
+            repository
+                .save(client)
+                .expect("an in-memory repository accepts every client");
+        }
         repository
     }
 }
 
+pub struct InMemoryProjectRepository {
+    projects: RefCell<HashMap<Uuid, Project>>,
+    snapshots: RefCell<Vec<HashMap<Uuid, Project>>>,
+}
+
+impl InMemoryProjectRepository {
+    pub fn new() -> Self {
+        Self {
+            projects: RefCell::new(HashMap::new()),
+            snapshots: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl Transactional for InMemoryProjectRepository {
+    fn begin(&self) {
+        let snapshot = self.projects.borrow().clone();
+        self.snapshots.borrow_mut().push(snapshot);
+    }
+    fn commit(&self) {
+        self.snapshots.borrow_mut().pop();
+    }
+    fn rollback(&self) {
+        let snapshot = self.snapshots.borrow_mut().pop();
+        if let Some(projects) = snapshot {
+            *self.projects.borrow_mut() = projects;
+        }
+    }
+}
+
+impl Persistent for InMemoryProjectRepository {
+    type State = Vec<Project>;
+    fn state(&self) -> Self::State {
+        self.all()
+    }
+    fn restore(&self, state: Self::State) {
+        *self.projects.borrow_mut() = state
+            .into_iter()
+            .map(|project| (project.id(), project))
+            .collect();
+    }
+}
+
+impl ProjectRepository for InMemoryProjectRepository {
+    fn by_id(&self, id: Uuid) -> Result<Project, String> {
+        match self.projects.borrow().get(&id) {
+            Some(project) => Ok(project.clone()),
+            None => Err("No project found for given ID".to_string()),
+        }
+    }
+    fn save(&self, project: Project) {
+        self.projects.borrow_mut().insert(project.id(), project);
+    }
+    fn all(&self) -> Vec<Project> {
+        self.projects.borrow().values().cloned().collect()
+    }
+    fn by_client(&self, client_id: Uuid) -> Vec<Project> {
+        self.projects
+            .borrow()
+            .values()
+            .filter(|project| project.client_id() == client_id)
+            .cloned()
+            .collect()
+    }
+}
+
+pub struct InMemoryRelationshipRepository {
+    relationships: RefCell<HashSet<Relationship>>,
+    snapshots: RefCell<Vec<HashSet<Relationship>>>,
+}
+
+impl InMemoryRelationshipRepository {
+    pub fn new() -> Self {
+        Self {
+            relationships: RefCell::new(HashSet::new()),
+            snapshots: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl Transactional for InMemoryRelationshipRepository {
+    fn begin(&self) {
+        let snapshot = self.relationships.borrow().clone();
+        self.snapshots.borrow_mut().push(snapshot);
+    }
+    fn commit(&self) {
+        self.snapshots.borrow_mut().pop();
+    }
+    fn rollback(&self) {
+        let snapshot = self.snapshots.borrow_mut().pop();
+        if let Some(relationships) = snapshot {
+            *self.relationships.borrow_mut() = relationships;
+        }
+    }
+}
+
+impl Persistent for InMemoryRelationshipRepository {
+    type State = Vec<Relationship>;
+    fn state(&self) -> Self::State {
+        self.all()
+    }
+    fn restore(&self, state: Self::State) {
+        *self.relationships.borrow_mut() = state.into_iter().collect();
+    }
+}
+
+impl RelationshipRepository for InMemoryRelationshipRepository {
+    fn save(&self, relationship: Relationship) {
+        self.relationships.borrow_mut().insert(relationship);
+    }
+    fn delete(&self, relationship: &Relationship) -> Result<(), String> {
+        match self.relationships.borrow_mut().remove(relationship) {
+            true => Ok(()),
+            false => Err("No relationship found".to_string()),
+        }
+    }
+    fn all(&self) -> Vec<Relationship> {
+        self.relationships.borrow().iter().copied().collect()
+    }
+    fn by_client(&self, client_id: Uuid) -> Vec<Relationship> {
+        self.relationships
+            .borrow()
+            .iter()
+            .filter(|relationship| relationship.involves(client_id))
+            .copied()
+            .collect()
+    }
+}
+
+/// 追加した順にメッセージを保持する
+pub struct InMemoryOutboxRepository {
+    messages: RefCell<Vec<OutboxMessage>>,
+    snapshots: RefCell<Vec<Vec<OutboxMessage>>>,
+}
+
+impl InMemoryOutboxRepository {
+    pub fn new() -> Self {
+        Self {
+            messages: RefCell::new(Vec::new()),
+            snapshots: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl OutboxRepository for InMemoryOutboxRepository {
+    fn by_id(&self, id: Uuid) -> Result<OutboxMessage, String> {
+        match self
+            .messages
+            .borrow()
+            .iter()
+            .find(|message| message.id() == id)
+        {
+            Some(message) => Ok(message.clone()),
+            None => Err("No outbox message found for given ID".to_string()),
+        }
+    }
+    fn save(&self, message: OutboxMessage) {
+        let mut messages = self.messages.borrow_mut();
+        match messages.iter_mut().find(|saved| saved.id() == message.id()) {
+            Some(saved) => *saved = message,
+            None => messages.push(message),
+        }
+    }
+    fn all(&self) -> Vec<OutboxMessage> {
+        self.messages.borrow().clone()
+    }
+    fn pending(&self) -> Vec<OutboxMessage> {
+        self.messages
+            .borrow()
+            .iter()
+            .filter(|message| message.is_pending())
+            .cloned()
+            .collect()
+    }
+}
+
+impl Transactional for InMemoryOutboxRepository {
+    fn begin(&self) {
+        let snapshot = self.messages.borrow().clone();
+        self.snapshots.borrow_mut().push(snapshot);
+    }
+    fn commit(&self) {
+        self.snapshots.borrow_mut().pop();
+    }
+    fn rollback(&self) {
+        let snapshot = self.snapshots.borrow_mut().pop();
+        if let Some(messages) = snapshot {
+            *self.messages.borrow_mut() = messages;
+        }
+    }
+}
+
+impl Persistent for InMemoryOutboxRepository {
+    type State = Vec<OutboxMessage>;
+    fn state(&self) -> Self::State {
+        self.all()
+    }
+    fn restore(&self, state: Self::State) {
+        *self.messages.borrow_mut() = state;
+    }
+}
+
+/// 購読は登録した順に保持する
+pub struct InMemoryWebhookRepository {
+    subscriptions: RefCell<Vec<WebhookSubscription>>,
+    deliveries: RefCell<Vec<WebhookDelivery>>,
+    snapshots: RefCell<Vec<(Vec<WebhookSubscription>, Vec<WebhookDelivery>)>>,
+}
+
+impl InMemoryWebhookRepository {
+    pub fn new() -> Self {
+        Self {
+            subscriptions: RefCell::new(Vec::new()),
+            deliveries: RefCell::new(Vec::new()),
+            snapshots: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl WebhookRepository for InMemoryWebhookRepository {
+    fn subscription_by_id(&self, id: Uuid) -> Result<WebhookSubscription, String> {
+        match self
+            .subscriptions
+            .borrow()
+            .iter()
+            .find(|subscription| subscription.id() == id)
+        {
+            Some(subscription) => Ok(subscription.clone()),
+            None => Err("No webhook subscription found for given ID".to_string()),
+        }
+    }
+    fn save_subscription(&self, subscription: WebhookSubscription) {
+        let mut subscriptions = self.subscriptions.borrow_mut();
+        match subscriptions
+            .iter_mut()
+            .find(|saved| saved.id() == subscription.id())
+        {
+            Some(saved) => *saved = subscription,
+            None => subscriptions.push(subscription),
+        }
+    }
+    fn delete_subscription(&self, id: Uuid) -> Result<(), String> {
+        let mut subscriptions = self.subscriptions.borrow_mut();
+        let len = subscriptions.len();
+        subscriptions.retain(|subscription| subscription.id() != id);
+        match subscriptions.len() < len {
+            true => Ok(()),
+            false => Err("No webhook subscription found for given ID".to_string()),
+        }
+    }
+    fn subscriptions(&self) -> Vec<WebhookSubscription> {
+        self.subscriptions.borrow().clone()
+    }
+    fn record_delivery(&self, delivery: WebhookDelivery) {
+        self.deliveries.borrow_mut().push(delivery);
+    }
+    fn deliveries(&self) -> Vec<WebhookDelivery> {
+        self.deliveries.borrow().clone()
+    }
+}
+
+impl Transactional for InMemoryWebhookRepository {
+    fn begin(&self) {
+        let snapshot = (
+            self.subscriptions.borrow().clone(),
+            self.deliveries.borrow().clone(),
+        );
+        self.snapshots.borrow_mut().push(snapshot);
+    }
+    fn commit(&self) {
+        self.snapshots.borrow_mut().pop();
+    }
+    fn rollback(&self) {
+        let snapshot = self.snapshots.borrow_mut().pop();
+        if let Some((subscriptions, deliveries)) = snapshot {
+            *self.subscriptions.borrow_mut() = subscriptions;
+            *self.deliveries.borrow_mut() = deliveries;
+        }
+    }
+}
+
+impl Persistent for InMemoryWebhookRepository {
+    type State = (Vec<WebhookSubscription>, Vec<WebhookDelivery>);
+    fn state(&self) -> Self::State {
+        (self.subscriptions(), self.deliveries())
+    }
+    fn restore(&self, (subscriptions, deliveries): Self::State) {
+        *self.subscriptions.borrow_mut() = subscriptions;
+        *self.deliveries.borrow_mut() = deliveries;
+    }
+}
+
+pub struct InMemoryIdempotencyRepository {
+    records: RefCell<HashMap<String, IdempotencyRecord>>,
+    snapshots: RefCell<Vec<HashMap<String, IdempotencyRecord>>>,
+}
+
+impl InMemoryIdempotencyRepository {
+    pub fn new() -> Self {
+        Self {
+            records: RefCell::new(HashMap::new()),
+            snapshots: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl IdempotencyRepository for InMemoryIdempotencyRepository {
+    fn by_key(&self, key: &str) -> Option<IdempotencyRecord> {
+        self.records.borrow().get(key).cloned()
+    }
+    fn save(&self, record: IdempotencyRecord) {
+        self.records
+            .borrow_mut()
+            .insert(record.key().to_string(), record);
+    }
+    fn keys_for(&self, client_id: Uuid) -> Vec<String> {
+        let mut keys = self
+            .records
+            .borrow()
+            .values()
+            .filter(|record| record.client().id() == client_id)
+            .map(|record| record.key().to_string())
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+    fn forget(&self, client_id: Uuid) -> usize {
+        let mut records = self.records.borrow_mut();
+        let len = records.len();
+        records.retain(|_, record| record.client().id() != client_id);
+        len - records.len()
+    }
+}
+
+impl Persistent for InMemoryIdempotencyRepository {
+    type State = Vec<IdempotencyRecord>;
+    fn state(&self) -> Self::State {
+        self.records.borrow().values().cloned().collect()
+    }
+    fn restore(&self, state: Self::State) {
+        *self.records.borrow_mut() = state
+            .into_iter()
+            .map(|record| (record.key().to_string(), record))
+            .collect();
+    }
+}
+
+impl Transactional for InMemoryIdempotencyRepository {
+    fn begin(&self) {
+        let snapshot = self.records.borrow().clone();
+        self.snapshots.borrow_mut().push(snapshot);
+    }
+    fn commit(&self) {
+        self.snapshots.borrow_mut().pop();
+    }
+    fn rollback(&self) {
+        let snapshot = self.snapshots.borrow_mut().pop();
+        if let Some(records) = snapshot {
+            *self.records.borrow_mut() = records;
+        }
+    }
+}
+
+pub struct InMemoryUserRepository {
+    users: RefCell<Vec<User>>,
+    snapshots: RefCell<Vec<Vec<User>>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self {
+        Self {
+            users: RefCell::new(Vec::new()),
+            snapshots: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl UserRepository for InMemoryUserRepository {
+    fn by_username(&self, username: &str) -> Option<User> {
+        self.users
+            .borrow()
+            .iter()
+            .find(|user| user.username() == username)
+            .cloned()
+    }
+    fn save(&self, user: User) {
+        let mut users = self.users.borrow_mut();
+        match users.iter_mut().find(|saved| saved.id() == user.id()) {
+            Some(saved) => *saved = user,
+            None => users.push(user),
+        }
+    }
+    fn all(&self) -> Vec<User> {
+        self.users.borrow().clone()
+    }
+}
+
+impl Transactional for InMemoryUserRepository {
+    fn begin(&self) {
+        let snapshot = self.users.borrow().clone();
+        self.snapshots.borrow_mut().push(snapshot);
+    }
+    fn commit(&self) {
+        self.snapshots.borrow_mut().pop();
+    }
+    fn rollback(&self) {
+        let snapshot = self.snapshots.borrow_mut().pop();
+        if let Some(users) = snapshot {
+            *self.users.borrow_mut() = users;
+        }
+    }
+}
+
+impl Persistent for InMemoryUserRepository {
+    type State = Vec<User>;
+    fn state(&self) -> Self::State {
+        self.all()
+    }
+    fn restore(&self, state: Self::State) {
+        *self.users.borrow_mut() = state;
+    }
+}
+
+pub struct InMemoryAuditRepository {
+    entries: RefCell<Vec<AuditEntry>>,
+    snapshots: RefCell<Vec<Vec<AuditEntry>>>,
+}
+
+impl InMemoryAuditRepository {
+    pub fn new() -> Self {
+        Self {
+            entries: RefCell::new(Vec::new()),
+            snapshots: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl Transactional for InMemoryAuditRepository {
+    fn begin(&self) {
+        let snapshot = self.entries.borrow().clone();
+        self.snapshots.borrow_mut().push(snapshot);
+    }
+    fn commit(&self) {
+        self.snapshots.borrow_mut().pop();
+    }
+    fn rollback(&self) {
+        let snapshot = self.snapshots.borrow_mut().pop();
+        if let Some(entries) = snapshot {
+            *self.entries.borrow_mut() = entries;
+        }
+    }
+}
+
+impl Persistent for InMemoryAuditRepository {
+    type State = Vec<AuditEntry>;
+    fn state(&self) -> Self::State {
+        self.all()
+    }
+    fn restore(&self, state: Self::State) {
+        *self.entries.borrow_mut() = state;
+    }
+}
+
+impl AuditRepository for InMemoryAuditRepository {
+    fn record(&self, entry: AuditEntry) {
+        self.entries.borrow_mut().push(entry);
+    }
+    fn all(&self) -> Vec<AuditEntry> {
+        self.entries.borrow().clone()
+    }
+    fn redact(&self, subject_ids: &[Uuid]) -> usize {
+        let mut count = 0;
+        for entry in self.entries.borrow_mut().iter_mut() {
+            if matches!(entry.subject_id(), Some(id) if subject_ids.contains(&id)) {
+                entry.redact_subject();
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+pub struct InMemoryTombstoneRepository {
+    tombstones: RefCell<Vec<Tombstone>>,
+    snapshots: RefCell<Vec<Vec<Tombstone>>>,
+}
+
+impl InMemoryTombstoneRepository {
+    pub fn new() -> Self {
+        Self {
+            tombstones: RefCell::new(Vec::new()),
+            snapshots: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl Transactional for InMemoryTombstoneRepository {
+    fn begin(&self) {
+        let snapshot = self.tombstones.borrow().clone();
+        self.snapshots.borrow_mut().push(snapshot);
+    }
+    fn commit(&self) {
+        self.snapshots.borrow_mut().pop();
+    }
+    fn rollback(&self) {
+        let snapshot = self.snapshots.borrow_mut().pop();
+        if let Some(tombstones) = snapshot {
+            *self.tombstones.borrow_mut() = tombstones;
+        }
+    }
+}
+
+impl Persistent for InMemoryTombstoneRepository {
+    type State = Vec<Tombstone>;
+    fn state(&self) -> Self::State {
+        self.all()
+    }
+    fn restore(&self, state: Self::State) {
+        *self.tombstones.borrow_mut() = state;
+    }
+}
+
+impl TombstoneRepository for InMemoryTombstoneRepository {
+    fn save(&self, tombstone: Tombstone) {
+        let mut tombstones = self.tombstones.borrow_mut();
+        tombstones.retain(|saved| saved.client_id() != tombstone.client_id());
+        tombstones.push(tombstone);
+    }
+    fn by_client(&self, id: Uuid) -> Option<Tombstone> {
+        self.tombstones
+            .borrow()
+            .iter()
+            .find(|tombstone| tombstone.ids().contains(&id))
+            .cloned()
+    }
+    fn all(&self) -> Vec<Tombstone> {
+        self.tombstones.borrow().clone()
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{Client, ClientRepository, InMemoryClientRepository};
+    use super::{
+        AuditEntry, AuditRepository, Client, ClientRepository, IdempotencyRecord,
+        IdempotencyRepository, InMemoryAuditRepository, InMemoryClientRepository,
+        InMemoryIdempotencyRepository, InMemoryOutboxRepository, InMemoryProjectRepository,
+        InMemoryRelationshipRepository, InMemoryTombstoneRepository, InMemoryUserRepository,
+        InMemoryWebhookRepository, OutboxMessage, OutboxRepository, Project, ProjectRepository,
+        Relationship, RelationshipRepository, Tombstone, TombstoneRepository, Transactional, User,
+        UserRepository, WebhookDelivery, WebhookRepository, WebhookSubscription,
+    };
+    use crate::domain::{ClientStatus, RelationKind, Role};
     use assert_matches::assert_matches;
+    use chrono::Utc;
     use fake::{Fake, Faker};
+    use uuid::Uuid;
 
     fn check_clients<T: ClientRepository>(repository: &T) {
         let empty_vec_clients: Vec<Client> = Vec::new();
@@ -62,7 +725,7 @@ mod test {
         for _ in 0..client_number {
             let client = Client::new(Faker.fake::<String>(), Faker.fake::<String>());
             vec_clients.push(client.clone());
-            repository.save(client);
+            repository.save(client).unwrap();
         }
 
         // by_idで取得して比較
@@ -79,6 +742,70 @@ mod test {
         let mut all_clients = repository.all();
         all_clients.sort_by_key(|client| client.id());
         assert_eq!(vec_clients, all_clients);
+
+        // by_name_and_locationは表記揺れを吸収する
+        let client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        repository.save(client.clone()).unwrap();
+        assert_eq!(
+            vec![client.clone()],
+            repository.by_name_and_location(" ＴＡＲＯ ", "東京都")
+        );
+        vec_clients.push(client);
+        vec_clients.sort_by_key(|client| client.id());
+
+        let client = &vec_clients[0];
+        // 編集後は古い名前では見つからない
+        let mut edited_client = client.clone();
+        let old_name = client.name().to_string();
+        edited_client.edit(
+            format!("{}_edited", old_name),
+            client.location().to_string(),
+        );
+        repository.save(edited_client.clone()).unwrap();
+        assert!(repository
+            .by_name_and_location(&old_name, client.location().as_str())
+            .is_empty());
+        assert_eq!(
+            vec![edited_client.clone()],
+            repository
+                .by_name_and_location(edited_client.name(), edited_client.location().as_str())
+        );
+        vec_clients[0] = edited_client;
+
+        // 検索キーを別に持たせたクライアントは，名前と出身地ではなくそのキーで引く
+        let mut keyed = vec_clients[0].clone();
+        keyed.set_lookup_key(Some(Client::lookup_key_of(
+            "sealed-name",
+            "sealed-location",
+        )));
+        repository.save(keyed.clone()).unwrap();
+        assert!(repository
+            .by_name_and_location(keyed.name(), keyed.location().as_str())
+            .is_empty());
+        assert_eq!(
+            vec![keyed.clone()],
+            repository.by_name_and_location("sealed-name", "sealed-location")
+        );
+        vec_clients[0] = keyed;
+
+        // 統合したクライアントはエイリアスから取得できる
+        let mut kept = vec_clients.pop().unwrap();
+        let removed = vec_clients.pop().unwrap();
+        let removed_id = removed.id();
+        kept.merge(removed);
+        repository.delete(removed_id).unwrap();
+        repository.save(kept.clone()).unwrap();
+        assert_eq!(kept, repository.by_id(removed_id).unwrap());
+
+        // deleteしたクライアントは取得できない
+        repository.delete(kept.id()).unwrap();
+        assert_matches!(repository.by_id(kept.id()), Err(_));
+        assert_matches!(repository.by_id(removed_id), Err(_));
+        assert!(repository
+            .by_name_and_location(kept.name(), kept.location().as_str())
+            .is_empty());
+        assert_matches!(repository.delete(kept.id()), Err(_));
+        assert_eq!(vec_clients.len(), repository.all().len());
     }
 
     #[test]
@@ -86,4 +813,262 @@ mod test {
         let repository = InMemoryClientRepository::new();
         check_clients(&repository);
     }
+
+    #[test]
+    fn check_project_repository() {
+        let repository = InMemoryProjectRepository::new();
+        assert!(repository.all().is_empty());
+
+        let client_id = Faker.fake();
+        let mut project = Project::new(client_id, "Website renewal", Utc::now()).unwrap();
+        let other = Project::new(Faker.fake(), "Logo design", Utc::now()).unwrap();
+        repository.save(project.clone());
+        repository.save(other.clone());
+
+        assert_eq!(project, repository.by_id(project.id()).unwrap());
+        assert_matches!(repository.by_id(Faker.fake()), Err(_));
+        assert_eq!(vec![project.clone()], repository.by_client(client_id));
+        assert_eq!(2, repository.all().len());
+
+        // 保存し直すと更新される
+        project.close(Utc::now()).unwrap();
+        repository.save(project.clone());
+        assert_eq!(project, repository.by_id(project.id()).unwrap());
+        assert_eq!(2, repository.all().len());
+    }
+
+    #[test]
+    fn check_relationship_repository() {
+        let repository = InMemoryRelationshipRepository::new();
+        let [a, b, c]: [Uuid; 3] = [Faker.fake(), Faker.fake(), Faker.fake()];
+        let subsidiary = Relationship::new(b, a, RelationKind::SubsidiaryOf).unwrap();
+        let referral = Relationship::new(c, b, RelationKind::ReferredBy).unwrap();
+        repository.save(subsidiary);
+        repository.save(referral);
+        // 同じ関係は1つだけ保持する
+        repository.save(subsidiary);
+
+        assert_eq!(2, repository.all().len());
+        assert_eq!(vec![subsidiary], repository.by_client(a));
+        assert_eq!(2, repository.by_client(b).len());
+
+        repository.delete(&subsidiary).unwrap();
+        assert!(repository.by_client(a).is_empty());
+        assert_matches!(repository.delete(&subsidiary), Err(_));
+    }
+
+    #[test]
+    fn check_outbox_repository() {
+        let repository = InMemoryOutboxRepository::new();
+        let mut client = Client::new(Faker.fake(), Faker.fake());
+        let messages = [ClientStatus::Active, ClientStatus::Suspended]
+            .map(|status| OutboxMessage::new(client.change_status(status, Utc::now()).unwrap()));
+        for message in messages.iter() {
+            repository.save(message.clone());
+        }
+        assert_eq!(messages.to_vec(), repository.all());
+        assert_eq!(messages.to_vec(), repository.pending());
+        assert_matches!(repository.by_id(Faker.fake()), Err(_));
+
+        // 配信済みは未配信に含まれず，順序は保たれる
+        let mut delivered = messages[0].clone();
+        delivered.mark_delivered(Utc::now());
+        repository.save(delivered.clone());
+        assert_eq!(delivered, repository.by_id(delivered.id()).unwrap());
+        assert_eq!(vec![messages[1].clone()], repository.pending());
+        assert_eq!(vec![delivered, messages[1].clone()], repository.all());
+
+        repository.begin();
+        repository.save(messages[0].clone());
+        repository.rollback();
+        assert_eq!(1, repository.pending().len());
+    }
+
+    #[test]
+    fn check_webhook_repository() {
+        let repository = InMemoryWebhookRepository::new();
+        let subscriptions = ["http://localhost:8080/a", "http://localhost:8080/b"].map(|url| {
+            WebhookSubscription::new(url, &["client.created".to_string()], "secret").unwrap()
+        });
+        for subscription in subscriptions.iter() {
+            repository.save_subscription(subscription.clone());
+        }
+        assert_eq!(subscriptions.to_vec(), repository.subscriptions());
+        assert_eq!(
+            subscriptions[1],
+            repository
+                .subscription_by_id(subscriptions[1].id())
+                .unwrap()
+        );
+
+        repository
+            .delete_subscription(subscriptions[0].id())
+            .unwrap();
+        assert_eq!(vec![subscriptions[1].clone()], repository.subscriptions());
+        assert_matches!(
+            repository.delete_subscription(subscriptions[0].id()),
+            Err(_)
+        );
+        assert_matches!(repository.subscription_by_id(subscriptions[0].id()), Err(_));
+
+        let deliveries = [Ok(200), Err("timed out".to_string())].map(|result| {
+            WebhookDelivery::new(
+                subscriptions[1].id(),
+                Faker.fake(),
+                "client.created",
+                1,
+                result,
+                Utc::now(),
+            )
+        });
+        for delivery in deliveries.iter() {
+            repository.record_delivery(delivery.clone());
+        }
+        assert_eq!(deliveries.to_vec(), repository.deliveries());
+    }
+
+    #[test]
+    fn check_idempotency_repository() {
+        let repository = InMemoryIdempotencyRepository::new();
+        let client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let record = |key: &str, client: &Client| {
+            IdempotencyRecord::new(key.to_string(), "Taro", "Tokyo", client.clone(), Vec::new())
+        };
+        assert_eq!(None, repository.by_key("create-1"));
+        repository.save(record("create-1", &client));
+        assert_eq!(
+            Some(record("create-1", &client)),
+            repository.by_key("create-1")
+        );
+        repository.save(record("create-0", &client));
+        repository.save(record("create-3", &Faker.fake()));
+        assert_eq!(
+            vec!["create-0", "create-1"],
+            repository.keys_for(client.id())
+        );
+
+        repository.begin();
+        repository.save(record("create-2", &Faker.fake()));
+        repository.rollback();
+        assert_eq!(None, repository.by_key("create-2"));
+
+        assert_eq!(2, repository.forget(client.id()));
+        assert!(repository.keys_for(client.id()).is_empty());
+        assert!(repository.by_key("create-3").is_some());
+    }
+
+    #[test]
+    fn check_user_and_audit_repositories() {
+        let repository = InMemoryUserRepository::new();
+        assert!(repository.all().is_empty());
+        let admin = User::new("admin", "correct horse", Role::Admin).unwrap();
+        repository.save(admin.clone());
+        assert_eq!(Some(admin.clone()), repository.by_username("admin"));
+        assert_eq!(None, repository.by_username("Admin"));
+
+        repository.begin();
+        repository.save(User::new("viewer", "correct horse", Role::Viewer).unwrap());
+        repository.rollback();
+        assert_eq!(vec![admin.clone()], repository.all());
+
+        let audit_repository = InMemoryAuditRepository::new();
+        let entries = ["CreateClientUseCaseRequest", "DeleteClientUseCaseRequest"]
+            .map(|action| AuditEntry::new(&admin, action, Some(Uuid::new_v4()), Utc::now()));
+        for entry in entries.iter() {
+            audit_repository.record(entry.clone());
+        }
+        assert_eq!(entries.to_vec(), audit_repository.all());
+
+        // 取り消した変更の記録は残さない
+        audit_repository.begin();
+        audit_repository.record(AuditEntry::new(
+            &admin,
+            "EditClientUseCaseRequest",
+            None,
+            Utc::now(),
+        ));
+        audit_repository.rollback();
+        assert_eq!(entries.to_vec(), audit_repository.all());
+
+        // 対象のIDだけを外す
+        let subject_id = entries[0].subject_id().unwrap();
+        assert_eq!(audit_repository.redact(&[subject_id]), 1);
+        let redacted = audit_repository.all();
+        assert_eq!(redacted[0].subject_id(), None);
+        assert_eq!(redacted[0].action(), "CreateClientUseCaseRequest");
+        assert_eq!(redacted[1], entries[1]);
+        assert_eq!(audit_repository.redact(&[subject_id]), 0);
+    }
+
+    #[test]
+    fn check_tombstone_repository() {
+        let repository = InMemoryTombstoneRepository::new();
+        let mut client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let merged = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let merged_id = merged.id();
+        client.merge(merged);
+        let tombstone = Tombstone::new(&client, Utc::now());
+
+        repository.begin();
+        repository.save(tombstone.clone());
+        repository.rollback();
+        assert_eq!(repository.by_client(client.id()), None);
+
+        repository.save(tombstone.clone());
+        // 統合したクライアントのIDでも引ける
+        assert_eq!(repository.by_client(client.id()), Some(tombstone.clone()));
+        assert_eq!(repository.by_client(merged_id), Some(tombstone.clone()));
+        assert_eq!(repository.by_client(Uuid::new_v4()), None);
+        repository.save(tombstone.clone());
+        assert_eq!(repository.all(), vec![tombstone]);
+    }
+
+    #[test]
+    fn rollback_restores_snapshot() {
+        let client_repository = InMemoryClientRepository::new();
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        client_repository.save(taro.clone()).unwrap();
+
+        client_repository.begin();
+        let mut edited = taro.clone();
+        edited.edit("Jiro".to_string(), "Osaka".to_string());
+        client_repository.save(edited.clone()).unwrap();
+        client_repository
+            .save(Client::new(Faker.fake(), Faker.fake()))
+            .unwrap();
+        // 入れ子のbeginは内側だけを取り消せる
+        client_repository.begin();
+        client_repository.delete(taro.id()).unwrap();
+        client_repository.rollback();
+        assert_eq!(edited, client_repository.by_id(taro.id()).unwrap());
+        client_repository.rollback();
+
+        assert_eq!(vec![taro.clone()], client_repository.all());
+        assert_eq!(
+            vec![taro.clone()],
+            client_repository.by_name_and_location("Taro", "Tokyo")
+        );
+        assert!(client_repository
+            .by_name_and_location("Jiro", "Osaka")
+            .is_empty());
+
+        // commitした変更は残る
+        client_repository.begin();
+        client_repository.delete(taro.id()).unwrap();
+        client_repository.commit();
+        assert!(client_repository.all().is_empty());
+
+        let project_repository = InMemoryProjectRepository::new();
+        project_repository.begin();
+        project_repository.save(Project::new(Faker.fake(), "Website renewal", Utc::now()).unwrap());
+        project_repository.rollback();
+        assert!(project_repository.all().is_empty());
+
+        let relationship_repository = InMemoryRelationshipRepository::new();
+        relationship_repository.begin();
+        relationship_repository
+            .save(Relationship::new(Faker.fake(), Faker.fake(), RelationKind::ReferredBy).unwrap());
+        relationship_repository.rollback();
+        assert!(relationship_repository.all().is_empty());
+    }
 }
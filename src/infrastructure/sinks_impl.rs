@@ -0,0 +1,462 @@
+use crate::domain::{ClientEvent, EventSink, OutboxMessage};
+use serde_json::{json, Value};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::Duration;
+use url::Url;
+use uuid::Uuid;
+
+/// 配信先に送る1行のJSON
+pub(super) fn to_json(message: &OutboxMessage) -> String {
+    let event = message.event();
+    let data = match event {
+        ClientEvent::Created {
+            client_id,
+            name,
+            location,
+            ..
+        }
+        | ClientEvent::Edited {
+            client_id,
+            name,
+            location,
+            ..
+        } => json!({
+            "client_id": client_id.hyphenated().to_string(),
+            "name": name,
+            "location": location,
+        }),
+        ClientEvent::Deleted { client_id, .. } | ClientEvent::Erased { client_id, .. } => {
+            json!({ "client_id": client_id.hyphenated().to_string() })
+        }
+        ClientEvent::StatusChanged {
+            client_id,
+            from,
+            to,
+            ..
+        } => json!({
+            "client_id": client_id.hyphenated().to_string(),
+            "from": from.to_string(),
+            "to": to.to_string(),
+        }),
+    };
+    json!({
+        "id": message.id().hyphenated().to_string(),
+        "type": event.event_type(),
+        "occurred_at": event.occurred_at().to_rfc3339(),
+        "data": data,
+    })
+    .to_string()
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// HTTPかHTTPSの送信先
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct HttpEndpoint {
+    url: Url,
+}
+
+impl HttpEndpoint {
+    /// 改行などの制御文字や空白を含むURLは，解釈の際に取り除かれて別の送信先になるため受け付けない
+    pub(super) fn parse(url: &str) -> Result<Self, String> {
+        if url.chars().any(|c| c.is_control() || c.is_whitespace()) {
+            return Err(format!("Invalid characters in URL: {:?}", url));
+        }
+        let parsed = Url::parse(url).map_err(|err| format!("Invalid URL: {}: {}", url, err))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("Unsupported URL: {}", url));
+        }
+        if parsed.host().is_none() {
+            return Err(format!("Missing host in URL: {}", url));
+        }
+        Ok(Self { url: parsed })
+    }
+    /// JSONをPOSTし，応答のステータスコードを返す
+    pub(super) fn post_json(&self, body: &str, headers: &[(&str, String)]) -> Result<u16, String> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .redirects(0)
+            .build();
+        let mut request = agent
+            .request_url("POST", &self.url)
+            .set("Content-Type", "application/json");
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+        match request.send_string(body) {
+            Ok(response) => Ok(response.status()),
+            Err(ureq::Error::Status(status, _)) => Ok(status),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for HttpEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.url)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// 標準出力に書き出す
+pub struct StdoutSink;
+
+impl EventSink for StdoutSink {
+    fn name(&self) -> String {
+        "stdout".to_string()
+    }
+    fn deliver(&self, message: &OutboxMessage) -> Result<(), String> {
+        println!("{}", to_json(message));
+        Ok(())
+    }
+    /// 書き出した内容は取り消せない
+    fn redact(&self, _: &[Uuid]) -> Result<usize, String> {
+        Ok(0)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// ファイルに1行ずつ追記する
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl EventSink for FileSink {
+    fn name(&self) -> String {
+        format!("file:{}", self.path.display())
+    }
+    fn deliver(&self, message: &OutboxMessage) -> Result<(), String> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| err.to_string())?;
+        writeln!(file, "{}", to_json(message)).map_err(|err| err.to_string())
+    }
+    /// 該当する行の名前と出身地を書き換えたファイルで置き換える
+    fn redact(&self, client_ids: &[Uuid]) -> Result<usize, String> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err.to_string()),
+        };
+        let mut count = 0;
+        let mut redacted = String::with_capacity(content.len());
+        for line in content.lines() {
+            match redact_line(line, client_ids) {
+                Some(line) => {
+                    redacted.push_str(&line);
+                    count += 1;
+                }
+                None => redacted.push_str(line),
+            }
+            redacted.push('\n');
+        }
+        if count > 0 {
+            let temporary = self.path.with_extension("redacting");
+            std::fs::write(&temporary, redacted).map_err(|err| err.to_string())?;
+            std::fs::rename(&temporary, &self.path).map_err(|err| err.to_string())?;
+        }
+        Ok(count)
+    }
+}
+
+/// 指定したクライアントの名前か出身地を含む行なら，消去した行を返す
+fn redact_line(line: &str, client_ids: &[Uuid]) -> Option<String> {
+    let mut value = serde_json::from_str::<Value>(line).ok()?;
+    let data = value.get_mut("data")?.as_object_mut()?;
+    let client_id = data.get("client_id")?.as_str()?.parse::<Uuid>().ok()?;
+    if !client_ids.contains(&client_id) {
+        return None;
+    }
+    let mut redacted = false;
+    for key in ["name", "location"] {
+        if let Some(field) = data.get_mut(key) {
+            if field != ClientEvent::REDACTED {
+                *field = Value::from(ClientEvent::REDACTED);
+                redacted = true;
+            }
+        }
+    }
+    redacted.then(|| value.to_string())
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// HTTPのエンドポイントにPOSTし，2xxの応答で配信済みとする
+pub struct WebhookSink {
+    endpoint: HttpEndpoint,
+}
+
+impl WebhookSink {
+    /// http://かhttps://のURLのみ受け付ける
+    pub fn new(url: &str) -> Result<Self, String> {
+        Ok(Self {
+            endpoint: HttpEndpoint::parse(url)?,
+        })
+    }
+}
+
+impl EventSink for WebhookSink {
+    fn name(&self) -> String {
+        format!("webhook:{}", self.endpoint)
+    }
+    fn deliver(&self, message: &OutboxMessage) -> Result<(), String> {
+        match self.endpoint.post_json(&to_json(message), &[])? {
+            200..=299 => Ok(()),
+            status => Err(format!("Webhook responded with status {}", status)),
+        }
+    }
+    /// 送信済みの内容は取り消せない．受信側には墓標のイベントで消去を知らせる
+    fn redact(&self, _: &[Uuid]) -> Result<usize, String> {
+        Ok(0)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// コマンドラインで指定する配信先 (stdout, file:PATH, webhook:URL)
+#[derive(Debug, Clone)]
+pub enum SinkSpec {
+    Stdout,
+    File(PathBuf),
+    Webhook(String),
+}
+
+impl SinkSpec {
+    pub fn build(&self) -> Result<Rc<dyn EventSink>, String> {
+        Ok(match self {
+            SinkSpec::Stdout => Rc::new(StdoutSink),
+            SinkSpec::File(path) => Rc::new(FileSink::new(path.clone())),
+            SinkSpec::Webhook(url) => Rc::new(WebhookSink::new(url)?),
+        })
+    }
+}
+
+impl FromStr for SinkSpec {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            _ if s == "stdout" => Ok(SinkSpec::Stdout),
+            Some(("file", path)) if !path.is_empty() => Ok(SinkSpec::File(PathBuf::from(path))),
+            Some(("webhook", url)) => {
+                WebhookSink::new(url)?;
+                Ok(SinkSpec::Webhook(url.to_string()))
+            }
+            _ => Err(format!(
+                "Unknown sink: {} (expected stdout, file:PATH or webhook:URL)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for SinkSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SinkSpec::Stdout => write!(f, "stdout"),
+            SinkSpec::File(path) => write!(f, "file:{}", path.display()),
+            SinkSpec::Webhook(url) => write!(f, "webhook:{}", url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{to_json, FileSink, SinkSpec, WebhookSink};
+    use crate::domain::{Client, ClientEvent, ClientStatus, EventSink, OutboxMessage};
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+    use fake::{Fake, Faker};
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    fn message() -> OutboxMessage {
+        let mut client = Client::new(Faker.fake(), Faker.fake());
+        OutboxMessage::new(
+            client
+                .change_status(ClientStatus::Active, Utc::now())
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn parse_sink_spec() {
+        assert_matches!("stdout".parse::<SinkSpec>(), Ok(SinkSpec::Stdout));
+        assert_matches!(
+            "file:/tmp/outbox.log".parse::<SinkSpec>(),
+            Ok(SinkSpec::File(path)) if path.to_str() == Some("/tmp/outbox.log")
+        );
+        assert_matches!(
+            "webhook:http://localhost:8080/hook".parse::<SinkSpec>(),
+            Ok(SinkSpec::Webhook(url)) if url == "http://localhost:8080/hook"
+        );
+        assert_matches!(
+            "webhook:https://example.com".parse::<SinkSpec>(),
+            Ok(SinkSpec::Webhook(_))
+        );
+        assert_matches!("webhook:http://[::1]:8080/hook".parse::<SinkSpec>(), Ok(_));
+        assert_matches!("webhook:ftp://example.com".parse::<SinkSpec>(), Err(_));
+        assert_matches!("webhook:http://".parse::<SinkSpec>(), Err(_));
+        // 改行でヘッダーを差し込めない
+        assert_matches!(
+            "webhook:http://localhost/hook\r\nX-Injected: 1".parse::<SinkSpec>(),
+            Err(_)
+        );
+        assert_matches!(
+            "webhook:http://local\nhost/hook".parse::<SinkSpec>(),
+            Err(_)
+        );
+        assert_matches!("file:".parse::<SinkSpec>(), Err(_));
+        assert_matches!("kafka".parse::<SinkSpec>(), Err(_));
+    }
+
+    #[test]
+    fn encode_events_as_json() {
+        let client = Client::new("Taro \"T\"\n".to_string(), "東京\\".to_string());
+        let message = OutboxMessage::new(ClientEvent::created(&client, Utc::now()));
+        let value = serde_json::from_str::<serde_json::Value>(&to_json(&message)).unwrap();
+        assert_eq!(value["id"], message.id().hyphenated().to_string());
+        assert_eq!(value["type"], "client.created");
+        assert_eq!(value["data"]["name"], "Taro \"T\"\n");
+        assert_eq!(value["data"]["location"], client.location().as_str());
+        assert!(!to_json(&message).contains('\n'));
+    }
+
+    #[test]
+    fn file_sink_appends_lines() {
+        let path = std::env::temp_dir().join(format!("outbox-{}.log", uuid::Uuid::new_v4()));
+        let sink = FileSink::new(path.clone());
+        let messages = [message(), message()];
+        for message in messages.iter() {
+            sink.deliver(message).unwrap();
+        }
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let expected = messages
+            .iter()
+            .map(|message| format!("{}\n", to_json(message)))
+            .collect::<String>();
+        assert_eq!(written, expected);
+    }
+
+    #[test]
+    fn file_sink_redacts_lines() {
+        let path = std::env::temp_dir().join(format!("outbox-{}.log", uuid::Uuid::new_v4()));
+        let sink = FileSink::new(path.clone());
+        assert_eq!(sink.redact(&[uuid::Uuid::new_v4()]), Ok(0));
+
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let jiro = Client::new("Jiro".to_string(), "Osaka".to_string());
+        let messages = [
+            OutboxMessage::new(ClientEvent::created(&taro, Utc::now())),
+            OutboxMessage::new(ClientEvent::created(&jiro, Utc::now())),
+            OutboxMessage::new(ClientEvent::erased(taro.id(), Utc::now())),
+        ];
+        for message in messages.iter() {
+            sink.deliver(message).unwrap();
+        }
+
+        assert_eq!(sink.redact(&[taro.id()]), Ok(1));
+        assert_eq!(sink.redact(&[taro.id()]), Ok(0));
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines = written.lines().collect::<Vec<_>>();
+        let mut redacted = messages[0].clone();
+        redacted.redact();
+        assert_eq!(
+            lines,
+            vec![
+                to_json(&redacted),
+                to_json(&messages[1]),
+                to_json(&messages[2])
+            ]
+        );
+    }
+
+    /// ヘッダーと本文を全て受け取るまで読む
+    fn read_request(stream: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            let len = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..len]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let content_length = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .and_then(|len| len.parse::<usize>().ok())
+                    .unwrap_or(0);
+                if body.len() >= content_length || len == 0 {
+                    return text;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn webhook_sink_posts_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let statuses = ["200 OK", "500 Internal Server Error"];
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                requests.push(read_request(&mut stream));
+                write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+            }
+            requests
+        });
+
+        let sink = WebhookSink::new(&format!("http://127.0.0.1:{}/events", port)).unwrap();
+        let message = message();
+        assert_matches!(sink.deliver(&message), Ok(()));
+        assert_matches!(sink.deliver(&message), Err(_));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /events HTTP/1.1\r\n"));
+        assert!(requests[0].ends_with(&to_json(&message)));
+    }
+
+    #[test]
+    fn webhook_sink_posts_to_ipv6_hosts() {
+        // IPv6を使えない環境では確かめられない
+        let Ok(listener) = TcpListener::bind("[::1]:0") else {
+            return;
+        };
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_request(&mut stream);
+            write!(
+                stream,
+                "HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n"
+            )
+            .unwrap();
+            request
+        });
+
+        let sink = WebhookSink::new(&format!("http://{}/events", address)).unwrap();
+        assert_matches!(sink.deliver(&message()), Ok(()));
+        assert!(server
+            .join()
+            .unwrap()
+            .starts_with("POST /events HTTP/1.1\r\n"));
+    }
+}
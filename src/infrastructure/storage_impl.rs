@@ -0,0 +1,156 @@
+use crate::domain::Transactional;
+use crate::telemetry::{Level, Tracer};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cell::Cell;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// 状態を丸ごと取り出して戻せるリポジトリ
+pub trait Persistent {
+    type State: Serialize + DeserializeOwned;
+    fn state(&self) -> Self::State;
+    fn restore(&self, state: Self::State);
+}
+
+/// リポジトリの状態をJSONファイルに保存する．作業単位にはリポジトリの代わりに参加し，
+/// 最も外側のcommitでファイルを書き換える
+pub struct FileStore<T: Persistent + Transactional> {
+    inner: Rc<T>,
+    path: PathBuf,
+    depth: Cell<usize>,
+    tracer: Option<Rc<Tracer>>,
+}
+
+impl<T: Persistent + Transactional> FileStore<T> {
+    /// ファイルがあればその内容をリポジトリに読み込む
+    pub fn open(inner: Rc<T>, path: PathBuf) -> Result<Self, String> {
+        match std::fs::read_to_string(&path) {
+            Ok(text) => inner.restore(
+                serde_json::from_str(&text)
+                    .map_err(|err| format!("{}: {}", path.display(), err))?,
+            ),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(format!("{}: {}", path.display(), err)),
+        }
+        Ok(Self {
+            inner,
+            path,
+            depth: Cell::new(0),
+            tracer: None,
+        })
+    }
+    /// commitで書き込めなかったときにエラーとして記録する
+    pub fn with_tracer(mut self, tracer: Rc<Tracer>) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+    // 書きかけのファイルを残さないよう，一時ファイルに書いてから置き換える
+    fn flush(&self) -> Result<(), String> {
+        let write = || -> std::io::Result<()> {
+            if let Some(dir) = self.path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let json = serde_json::to_string(&self.inner.state())?;
+            let temp = self.path.with_extension("saving");
+            std::fs::write(&temp, json)?;
+            std::fs::rename(&temp, &self.path)
+        };
+        write().map_err(|err| format!("{}: {}", self.path.display(), err))
+    }
+}
+
+impl<T: Persistent + Transactional> Transactional for FileStore<T> {
+    fn begin(&self) {
+        self.depth.set(self.depth.get() + 1);
+        self.inner.begin();
+    }
+    /// 書き込みに失敗しても変更はメモリに残り，次のcommitで書き直す
+    fn commit(&self) {
+        self.inner.commit();
+        self.depth.set(self.depth.get().saturating_sub(1));
+        if self.depth.get() > 0 {
+            return;
+        }
+        if let (Err(err), Some(tracer)) = (self.flush(), &self.tracer) {
+            let mut span = tracer.span(Level::Error, "FileStore", "commit", Vec::new());
+            span.record_str("path", &self.path.display().to_string());
+            span.record_str("error", &err);
+        }
+    }
+    fn rollback(&self) {
+        self.inner.rollback();
+        self.depth.set(self.depth.get().saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FileStore;
+    use crate::domain::{Client, ClientRepository, Role, Transactional, User, UserRepository};
+    use crate::infrastructure::{InMemoryClientRepository, InMemoryUserRepository};
+    use assert_matches::assert_matches;
+    use std::rc::Rc;
+    use uuid::Uuid;
+
+    #[test]
+    fn write_on_outermost_commit() {
+        let dir = std::env::temp_dir().join(format!("store-{}", Uuid::new_v4()));
+        let path = dir.join("clients.json");
+        let repository = Rc::new(InMemoryClientRepository::new());
+        let store = FileStore::open(Rc::clone(&repository), path.clone()).unwrap();
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+
+        store.begin();
+        store.begin();
+        repository.save(taro.clone()).unwrap();
+        store.commit();
+        assert!(!path.exists());
+        store.commit();
+        assert!(path.exists());
+
+        // 取り消した変更は書き込まない
+        store.begin();
+        repository
+            .save(Client::new("Jiro".to_string(), "Osaka".to_string()))
+            .unwrap();
+        store.rollback();
+
+        let reopened = Rc::new(InMemoryClientRepository::new());
+        FileStore::open(Rc::clone(&reopened), path.clone()).unwrap();
+        assert_eq!(reopened.all(), vec![taro.clone()]);
+        assert_eq!(reopened.by_name_and_location("taro", "tokyo"), vec![taro]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keep_password_hashes() {
+        let path = std::env::temp_dir().join(format!("users-{}.json", Uuid::new_v4()));
+        let users = Rc::new(InMemoryUserRepository::new());
+        let store = FileStore::open(Rc::clone(&users), path.clone()).unwrap();
+        store.begin();
+        users.save(User::new("admin", "correct horse", Role::Admin).unwrap());
+        store.commit();
+        assert!(!std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("correct horse"));
+
+        let reopened = Rc::new(InMemoryUserRepository::new());
+        FileStore::open(Rc::clone(&reopened), path.clone()).unwrap();
+        let admin = reopened.by_username("admin").unwrap();
+        assert!(admin.verify_password("correct horse"));
+        assert!(!admin.verify_password("wrong horse"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reject_broken_file() {
+        let path = std::env::temp_dir().join(format!("store-{}.json", Uuid::new_v4()));
+        std::fs::write(&path, "{").unwrap();
+        let res =
+            FileStore::open(Rc::new(InMemoryClientRepository::new()), path.clone()).map(|_| ());
+        assert_matches!(res, Err(err) if err.starts_with(&path.display().to_string()));
+        std::fs::remove_file(path).unwrap();
+    }
+}
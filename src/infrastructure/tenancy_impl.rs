@@ -0,0 +1,196 @@
+use super::storage_impl::Persistent;
+use crate::domain::{Client, ClientRepository, TenantId, Transactional};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use uuid::Uuid;
+
+/// クライアントがどのテナントに属するか．同じ保存先を使う全てのテナントで共有する
+pub struct TenantDirectory {
+    owners: RefCell<HashMap<Uuid, TenantId>>,
+    snapshots: RefCell<Vec<HashMap<Uuid, TenantId>>>,
+}
+
+impl TenantDirectory {
+    pub fn new() -> Self {
+        Self {
+            owners: RefCell::new(HashMap::new()),
+            snapshots: RefCell::new(Vec::new()),
+        }
+    }
+    fn is_owned_by(&self, client_id: Uuid, tenant: &TenantId) -> bool {
+        self.owners.borrow().get(&client_id) == Some(tenant)
+    }
+}
+
+impl Transactional for TenantDirectory {
+    fn begin(&self) {
+        let snapshot = self.owners.borrow().clone();
+        self.snapshots.borrow_mut().push(snapshot);
+    }
+    fn commit(&self) {
+        self.snapshots.borrow_mut().pop();
+    }
+    fn rollback(&self) {
+        let snapshot = self.snapshots.borrow_mut().pop();
+        if let Some(owners) = snapshot {
+            *self.owners.borrow_mut() = owners;
+        }
+    }
+}
+
+impl Persistent for TenantDirectory {
+    type State = HashMap<Uuid, TenantId>;
+    fn state(&self) -> Self::State {
+        self.owners.borrow().clone()
+    }
+    fn restore(&self, state: Self::State) {
+        *self.owners.borrow_mut() = state;
+    }
+}
+
+/// 全ての操作を1つのテナントのクライアントに限定するリポジトリ．
+/// 他のテナントのクライアントは存在しないものとして扱う
+pub struct TenantScopedClientRepository<T: ClientRepository + ?Sized> {
+    inner: Rc<T>,
+    directory: Rc<TenantDirectory>,
+    tenant: TenantId,
+}
+
+impl<T: ClientRepository + ?Sized> TenantScopedClientRepository<T> {
+    pub fn new(inner: Rc<T>, directory: Rc<TenantDirectory>, tenant: TenantId) -> Self {
+        Self {
+            inner,
+            directory,
+            tenant,
+        }
+    }
+    fn is_visible(&self, client: &Client) -> bool {
+        self.directory.is_owned_by(client.id(), &self.tenant)
+    }
+}
+
+impl<T: ClientRepository + ?Sized> ClientRepository for TenantScopedClientRepository<T> {
+    fn by_id(&self, id: Uuid) -> Result<Client, String> {
+        // 他のテナントのIDかどうかは区別しない
+        self.inner
+            .by_id(id)
+            .ok()
+            .filter(|client| self.is_visible(client))
+            .ok_or_else(|| "No client found for given ID".to_string())
+    }
+    /// 他のテナントに属するIDでは保存せず，存在しないIDとしてエラーを返す
+    fn save(&self, client: Client) -> Result<(), String> {
+        let mut owners = self.directory.owners.borrow_mut();
+        let owner = owners
+            .entry(client.id())
+            .or_insert_with(|| self.tenant.clone());
+        if *owner != self.tenant {
+            return Err("No client found for given ID".to_string());
+        }
+        drop(owners);
+        self.inner.save(client)
+    }
+    fn delete(&self, id: Uuid) -> Result<(), String> {
+        if !self.directory.is_owned_by(id, &self.tenant) {
+            return Err("No client found for given ID".to_string());
+        }
+        self.inner.delete(id)?;
+        self.directory.owners.borrow_mut().remove(&id);
+        Ok(())
+    }
+    fn all(&self) -> Vec<Client> {
+        self.inner
+            .all()
+            .into_iter()
+            .filter(|client| self.is_visible(client))
+            .collect()
+    }
+    fn by_name_and_location(&self, name: &str, location: &str) -> Vec<Client> {
+        self.inner
+            .by_name_and_location(name, location)
+            .into_iter()
+            .filter(|client| self.is_visible(client))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TenantDirectory, TenantScopedClientRepository};
+    use crate::domain::{Client, ClientRepository, TenantId, Transactional};
+    use crate::infrastructure::InMemoryClientRepository;
+    use assert_matches::assert_matches;
+    use fake::{Fake, Faker};
+    use std::rc::Rc;
+
+    fn scoped_repositories() -> [TenantScopedClientRepository<InMemoryClientRepository>; 2] {
+        let shared = Rc::new(InMemoryClientRepository::new());
+        let directory = Rc::new(TenantDirectory::new());
+        ["sales", "support"].map(|tenant| {
+            TenantScopedClientRepository::new(
+                Rc::clone(&shared),
+                Rc::clone(&directory),
+                TenantId::new(tenant).unwrap(),
+            )
+        })
+    }
+
+    #[test]
+    fn isolate_tenants() {
+        let [sales, support] = scoped_repositories();
+        let clients = (0..5)
+            .map(|_| Client::new(Faker.fake(), Faker.fake()))
+            .collect::<Vec<_>>();
+        for client in clients.iter() {
+            sales.save(client.clone()).unwrap();
+        }
+        let taro = Client::new("Taro".to_string(), "Tokyo".to_string());
+        support.save(taro.clone()).unwrap();
+
+        for client in clients.iter() {
+            assert_matches!(sales.by_id(client.id()), Ok(found) if found == *client);
+            assert_matches!(support.by_id(client.id()), Err(_));
+            assert_matches!(support.delete(client.id()), Err(_));
+        }
+        assert_eq!(sales.all().len(), clients.len());
+        assert_eq!(support.all(), vec![taro.clone()]);
+        assert!(sales.by_name_and_location("Taro", "Tokyo").is_empty());
+        assert_eq!(support.by_name_and_location("taro", "tokyo"), vec![taro]);
+
+        // 他のテナントのIDで上書きできない
+        let mut renamed = clients[0].clone();
+        renamed.edit("Jiro".to_string(), "Osaka".to_string());
+        assert_matches!(support.save(renamed), Err(_));
+        assert_matches!(sales.by_id(clients[0].id()), Ok(found) if found == clients[0]);
+        assert_eq!(support.all().len(), 1);
+    }
+
+    #[test]
+    fn rollback_restores_owners() {
+        let shared = Rc::new(InMemoryClientRepository::new());
+        let directory = Rc::new(TenantDirectory::new());
+        let sales = TenantScopedClientRepository::new(
+            Rc::clone(&shared),
+            Rc::clone(&directory),
+            TenantId::new("sales").unwrap(),
+        );
+        let support = TenantScopedClientRepository::new(
+            Rc::clone(&shared),
+            Rc::clone(&directory),
+            TenantId::new("support").unwrap(),
+        );
+        let client = Client::new(Faker.fake(), Faker.fake());
+
+        shared.begin();
+        directory.begin();
+        sales.save(client.clone()).unwrap();
+        shared.rollback();
+        directory.rollback();
+
+        // 取り消した後は別のテナントが同じIDで保存できる
+        support.save(client.clone()).unwrap();
+        assert_matches!(support.by_id(client.id()), Ok(_));
+        assert_matches!(sales.by_id(client.id()), Err(_));
+    }
+}
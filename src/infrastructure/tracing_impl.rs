@@ -0,0 +1,309 @@
+use crate::domain::{
+    AuditEntry, AuditRepository, Client, ClientRepository, IdempotencyRecord,
+    IdempotencyRepository, OutboxMessage, OutboxRepository, Project, ProjectRepository,
+    Relationship, RelationshipRepository, Tombstone, TombstoneRepository, User, UserRepository,
+    WebhookDelivery, WebhookRepository, WebhookSubscription,
+};
+use crate::telemetry::{Level, Span, Tracer};
+use std::rc::Rc;
+use uuid::Uuid;
+
+/// リポジトリの呼び出しごとにスパンを記録する．
+/// 名前・出身地・ユーザー名・冪等キーのような値は記録せず，IDと件数だけを添える
+pub struct TracedRepository<T: ?Sized> {
+    inner: Rc<T>,
+    tracer: Rc<Tracer>,
+    /// スパンのtargetにする名前 (ClientRepositoryなど)
+    name: &'static str,
+}
+
+impl<T: ?Sized> TracedRepository<T> {
+    pub fn new(inner: Rc<T>, tracer: Rc<Tracer>, name: &'static str) -> Self {
+        Self {
+            inner,
+            tracer,
+            name,
+        }
+    }
+    fn span(&self, operation: &'static str) -> Span<'_> {
+        self.tracer
+            .span(Level::Debug, self.name, operation, Vec::new())
+    }
+}
+
+fn with_outcome<T>(mut span: Span<'_>, result: Result<T, String>) -> Result<T, String> {
+    span.record_str(
+        "outcome",
+        match result {
+            Ok(_) => "ok",
+            Err(_) => "error",
+        },
+    );
+    result
+}
+
+fn with_count<T>(mut span: Span<'_>, items: Vec<T>) -> Vec<T> {
+    span.record("count", &items.len());
+    items
+}
+
+impl<T: ClientRepository + ?Sized> ClientRepository for TracedRepository<T> {
+    fn by_id(&self, id: Uuid) -> Result<Client, String> {
+        let mut span = self.span("by_id");
+        span.record("id", &id);
+        with_outcome(span, self.inner.by_id(id))
+    }
+    fn save(&self, client: Client) -> Result<(), String> {
+        let mut span = self.span("save");
+        span.record("id", &client.id());
+        with_outcome(span, self.inner.save(client))
+    }
+    fn delete(&self, id: Uuid) -> Result<(), String> {
+        let mut span = self.span("delete");
+        span.record("id", &id);
+        with_outcome(span, self.inner.delete(id))
+    }
+    fn all(&self) -> Vec<Client> {
+        with_count(self.span("all"), self.inner.all())
+    }
+    fn by_name_and_location(&self, name: &str, location: &str) -> Vec<Client> {
+        with_count(
+            self.span("by_name_and_location"),
+            self.inner.by_name_and_location(name, location),
+        )
+    }
+}
+
+impl<T: ProjectRepository + ?Sized> ProjectRepository for TracedRepository<T> {
+    fn by_id(&self, id: Uuid) -> Result<Project, String> {
+        let mut span = self.span("by_id");
+        span.record("id", &id);
+        with_outcome(span, self.inner.by_id(id))
+    }
+    fn save(&self, project: Project) {
+        let mut span = self.span("save");
+        span.record("id", &project.id());
+        span.record("client_id", &project.client_id());
+        self.inner.save(project);
+    }
+    fn all(&self) -> Vec<Project> {
+        with_count(self.span("all"), self.inner.all())
+    }
+    fn by_client(&self, client_id: Uuid) -> Vec<Project> {
+        let mut span = self.span("by_client");
+        span.record("client_id", &client_id);
+        with_count(span, self.inner.by_client(client_id))
+    }
+}
+
+impl<T: RelationshipRepository + ?Sized> RelationshipRepository for TracedRepository<T> {
+    fn save(&self, relationship: Relationship) {
+        let mut span = self.span("save");
+        span.record("from", &relationship.from());
+        span.record("to", &relationship.to());
+        self.inner.save(relationship);
+    }
+    fn delete(&self, relationship: &Relationship) -> Result<(), String> {
+        let mut span = self.span("delete");
+        span.record("from", &relationship.from());
+        span.record("to", &relationship.to());
+        with_outcome(span, self.inner.delete(relationship))
+    }
+    fn all(&self) -> Vec<Relationship> {
+        with_count(self.span("all"), self.inner.all())
+    }
+    fn by_client(&self, client_id: Uuid) -> Vec<Relationship> {
+        let mut span = self.span("by_client");
+        span.record("client_id", &client_id);
+        with_count(span, self.inner.by_client(client_id))
+    }
+}
+
+impl<T: OutboxRepository + ?Sized> OutboxRepository for TracedRepository<T> {
+    fn by_id(&self, id: Uuid) -> Result<OutboxMessage, String> {
+        let mut span = self.span("by_id");
+        span.record("id", &id);
+        with_outcome(span, self.inner.by_id(id))
+    }
+    fn save(&self, message: OutboxMessage) {
+        let mut span = self.span("save");
+        span.record("id", &message.id());
+        self.inner.save(message);
+    }
+    fn all(&self) -> Vec<OutboxMessage> {
+        with_count(self.span("all"), self.inner.all())
+    }
+    fn pending(&self) -> Vec<OutboxMessage> {
+        with_count(self.span("pending"), self.inner.pending())
+    }
+}
+
+impl<T: IdempotencyRepository + ?Sized> IdempotencyRepository for TracedRepository<T> {
+    fn by_key(&self, key: &str) -> Option<IdempotencyRecord> {
+        let mut span = self.span("by_key");
+        let record = self.inner.by_key(key);
+        span.record(
+            "client_id",
+            &record.as_ref().map(|record| record.client().id()),
+        );
+        record
+    }
+    fn save(&self, record: IdempotencyRecord) {
+        let mut span = self.span("save");
+        span.record("client_id", &record.client().id());
+        self.inner.save(record);
+    }
+    fn keys_for(&self, client_id: Uuid) -> Vec<String> {
+        let mut span = self.span("keys_for");
+        span.record("client_id", &client_id);
+        with_count(span, self.inner.keys_for(client_id))
+    }
+    fn forget(&self, client_id: Uuid) -> usize {
+        let mut span = self.span("forget");
+        span.record("client_id", &client_id);
+        let count = self.inner.forget(client_id);
+        span.record("count", &count);
+        count
+    }
+}
+
+impl<T: UserRepository + ?Sized> UserRepository for TracedRepository<T> {
+    fn by_username(&self, username: &str) -> Option<User> {
+        let mut span = self.span("by_username");
+        let user = self.inner.by_username(username);
+        span.record("id", &user.as_ref().map(User::id));
+        user
+    }
+    fn save(&self, user: User) {
+        let mut span = self.span("save");
+        span.record("id", &user.id());
+        self.inner.save(user);
+    }
+    fn all(&self) -> Vec<User> {
+        with_count(self.span("all"), self.inner.all())
+    }
+}
+
+impl<T: AuditRepository + ?Sized> AuditRepository for TracedRepository<T> {
+    fn record(&self, entry: AuditEntry) {
+        let mut span = self.span("record");
+        span.record("user_id", &entry.user_id());
+        span.record("subject_id", &entry.subject_id());
+        self.inner.record(entry);
+    }
+    fn all(&self) -> Vec<AuditEntry> {
+        with_count(self.span("all"), self.inner.all())
+    }
+    fn redact(&self, subject_ids: &[Uuid]) -> usize {
+        let mut span = self.span("redact");
+        let count = self.inner.redact(subject_ids);
+        span.record("count", &count);
+        count
+    }
+}
+
+impl<T: TombstoneRepository + ?Sized> TombstoneRepository for TracedRepository<T> {
+    fn save(&self, tombstone: Tombstone) {
+        let mut span = self.span("save");
+        span.record("client_id", &tombstone.client_id());
+        self.inner.save(tombstone);
+    }
+    fn by_client(&self, id: Uuid) -> Option<Tombstone> {
+        let mut span = self.span("by_client");
+        span.record("id", &id);
+        let tombstone = self.inner.by_client(id);
+        span.record("client_id", &tombstone.as_ref().map(Tombstone::client_id));
+        tombstone
+    }
+    fn all(&self) -> Vec<Tombstone> {
+        with_count(self.span("all"), self.inner.all())
+    }
+}
+
+impl<T: WebhookRepository + ?Sized> WebhookRepository for TracedRepository<T> {
+    fn subscription_by_id(&self, id: Uuid) -> Result<WebhookSubscription, String> {
+        let mut span = self.span("subscription_by_id");
+        span.record("id", &id);
+        with_outcome(span, self.inner.subscription_by_id(id))
+    }
+    fn save_subscription(&self, subscription: WebhookSubscription) {
+        let mut span = self.span("save_subscription");
+        span.record("id", &subscription.id());
+        self.inner.save_subscription(subscription);
+    }
+    fn delete_subscription(&self, id: Uuid) -> Result<(), String> {
+        let mut span = self.span("delete_subscription");
+        span.record("id", &id);
+        with_outcome(span, self.inner.delete_subscription(id))
+    }
+    fn subscriptions(&self) -> Vec<WebhookSubscription> {
+        with_count(self.span("subscriptions"), self.inner.subscriptions())
+    }
+    fn record_delivery(&self, delivery: WebhookDelivery) {
+        let mut span = self.span("record_delivery");
+        span.record("subscription_id", &delivery.subscription_id());
+        span.record("message_id", &delivery.message_id());
+        self.inner.record_delivery(delivery);
+    }
+    fn deliveries(&self) -> Vec<WebhookDelivery> {
+        with_count(self.span("deliveries"), self.inner.deliveries())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TracedRepository;
+    use crate::domain::repositories::{MockClientRepository, MockUserRepository};
+    use crate::domain::Role;
+    use crate::domain::{Client, ClientRepository, User, UserRepository};
+    use crate::telemetry::{Level, LogFormat, Tracer};
+    use assert_matches::assert_matches;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn record_ids_without_personal_data() {
+        let client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let admin = User::new("admin", "correct horse", Role::Admin).unwrap();
+        let mut mock_client_repo = MockClientRepository::new();
+        mock_client_repo
+            .expect_by_id()
+            .return_const(Ok(client.clone()));
+        mock_client_repo
+            .expect_by_name_and_location()
+            .return_const(vec![client.clone()]);
+        let mut mock_user_repo = MockUserRepository::new();
+        mock_user_repo
+            .expect_by_username()
+            .return_const(Some(admin.clone()));
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&lines);
+        let tracer = Rc::new(Tracer::new(Level::Debug, LogFormat::Human, move |line| {
+            sink.borrow_mut().push(line)
+        }));
+        let client_repo = TracedRepository::new(
+            Rc::new(mock_client_repo),
+            Rc::clone(&tracer),
+            "ClientRepository",
+        );
+        let user_repo = TracedRepository::new(Rc::new(mock_user_repo), tracer, "UserRepository");
+
+        assert_matches!(client_repo.by_id(client.id()), Ok(found) if found == client);
+        assert_eq!(client_repo.by_name_and_location("Taro", "Tokyo").len(), 1);
+        assert_eq!(user_repo.by_username("admin"), Some(admin.clone()));
+
+        let lines = lines.borrow();
+        assert!(lines[0].contains(&format!(
+            " DEBUG ClientRepository.by_id id={} outcome=ok ",
+            client.id()
+        )));
+        assert!(lines[1].contains(" DEBUG ClientRepository.by_name_and_location count=1 "));
+        assert!(lines[2].contains(&format!(
+            " DEBUG UserRepository.by_username id={} ",
+            admin.id()
+        )));
+        assert!(lines.iter().all(|line| !line.contains("Taro")
+            && !line.contains("Tokyo")
+            && !line.contains("admin")));
+    }
+}
@@ -0,0 +1,270 @@
+use super::sinks_impl::{to_json, HttpEndpoint};
+use crate::domain::{EventSink, OutboxMessage, WebhookDelivery, WebhookRepository};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::rc::Rc;
+use uuid::Uuid;
+
+fn hmac_sha256_hex(secret: &str, data: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(data.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// `{timestamp}.{body}`のHMAC-SHA256を16進数で表した署名．
+/// 受信側はX-Webhook-Timestampが古いリクエストを拒否することで再送攻撃を防げる
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    hmac_sha256_hex(secret, &format!("{}.{}", timestamp, body))
+}
+
+/// イベントを購読しているWebhookに署名付きのJSONを送る．
+/// 待たずに1回だけ送り，失敗した購読先への再送はアウトボックスの次の中継に任せる．全ての試行を送信記録に残す
+pub struct WebhookDispatcher<W: WebhookRepository + ?Sized> {
+    webhook_repo: Rc<W>,
+}
+
+impl<W: WebhookRepository + ?Sized> WebhookDispatcher<W> {
+    pub fn new(webhook_repo: Rc<W>) -> Self {
+        Self { webhook_repo }
+    }
+}
+
+impl<W: WebhookRepository + ?Sized> EventSink for WebhookDispatcher<W> {
+    fn name(&self) -> String {
+        "webhooks".to_string()
+    }
+    /// 一部の購読先にだけ届いた場合もErrを返す．アウトボックスから再送されたときは，
+    /// 送信記録から届いている購読先を除いて送る
+    fn deliver(&self, message: &OutboxMessage) -> Result<(), String> {
+        let event_type = message.event().event_type();
+        let body = to_json(message);
+        let deliveries = self
+            .webhook_repo
+            .deliveries()
+            .into_iter()
+            .filter(|delivery| delivery.message_id() == message.id())
+            .collect::<Vec<_>>();
+        let mut errors = Vec::new();
+        for subscription in self
+            .webhook_repo
+            .subscriptions()
+            .into_iter()
+            .filter(|subscription| subscription.is_subscribed_to(event_type))
+        {
+            let previous = deliveries
+                .iter()
+                .filter(|delivery| delivery.subscription_id() == subscription.id())
+                .collect::<Vec<_>>();
+            // 再配信を指示される前に届いた記録は数えない
+            let delivered = previous.iter().any(|delivery| {
+                delivery.result().is_ok()
+                    && message
+                        .requeued_at()
+                        .is_none_or(|requeued_at| delivery.attempted_at() >= requeued_at)
+            });
+            if delivered {
+                continue;
+            }
+
+            let timestamp = Utc::now().timestamp();
+            let headers = [
+                ("X-Webhook-Id", message.id().hyphenated().to_string()),
+                ("X-Webhook-Event", event_type.to_string()),
+                ("X-Webhook-Timestamp", timestamp.to_string()),
+                (
+                    "X-Webhook-Signature",
+                    format!("sha256={}", sign(subscription.secret(), timestamp, &body)),
+                ),
+            ];
+            let result =
+                HttpEndpoint::parse(subscription.url()).and_then(|endpoint| {
+                    match endpoint.post_json(&body, &headers)? {
+                        status @ 200..=299 => Ok(status),
+                        status => Err(format!("Responded with status {}", status)),
+                    }
+                });
+            self.webhook_repo.record_delivery(WebhookDelivery::new(
+                subscription.id(),
+                message.id(),
+                event_type,
+                previous.len() as u32 + 1,
+                result.clone(),
+                Utc::now(),
+            ));
+            if let Err(err) = result {
+                errors.push(format!("{}: {}", subscription.url(), err));
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors.join(", ")),
+        }
+    }
+    /// 送信記録には本文を残さない．送信済みの内容は受信側に墓標のイベントで消去を知らせる
+    fn redact(&self, _: &[Uuid]) -> Result<usize, String> {
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{hmac_sha256_hex, sign, WebhookDispatcher};
+    use crate::domain::{
+        Client, ClientEvent, EventSink, OutboxMessage, WebhookRepository, WebhookSubscription,
+    };
+    use crate::infrastructure::InMemoryWebhookRepository;
+    use assert_matches::assert_matches;
+    use chrono::Utc;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::rc::Rc;
+    use std::thread;
+
+    // 指定したステータスを順に返すローカルのHTTPサーバー．受け取ったリクエストを返す
+    fn stand_in_server(statuses: Vec<&'static str>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 4096];
+                // ヘッダーと本文を全て受け取るまで読む
+                loop {
+                    let len = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..len]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let content_length = head
+                            .lines()
+                            .find_map(|line| line.strip_prefix("Content-Length: "))
+                            .and_then(|len| len.parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if body.len() >= content_length {
+                            break;
+                        }
+                    }
+                }
+                requests.push(String::from_utf8_lossy(&request).to_string());
+                write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    fn created_message() -> OutboxMessage {
+        let client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        OutboxMessage::new(ClientEvent::created(&client, Utc::now()))
+    }
+
+    #[test]
+    fn sign_with_hmac_sha256() {
+        // RFC 4231 テストケース2
+        assert_eq!(
+            hmac_sha256_hex("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // 日時も署名に含める
+        assert_eq!(
+            sign("secret", 1_700_000_000, "{}"),
+            hmac_sha256_hex("secret", "1700000000.{}")
+        );
+        assert_ne!(
+            sign("secret", 1_700_000_000, "{}"),
+            sign("secret", 1_700_000_001, "{}")
+        );
+    }
+
+    fn header<'a>(head: &'a str, name: &str) -> &'a str {
+        head.lines()
+            .find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+            .unwrap()
+    }
+
+    #[test]
+    fn deliver_signed_payloads_on_each_relay() {
+        let (url, server) = stand_in_server(vec!["503 Service Unavailable", "204 No Content"]);
+        let repository = Rc::new(InMemoryWebhookRepository::new());
+        let subscription =
+            WebhookSubscription::new(&url, &["client.created".to_string()], "secret").unwrap();
+        repository.save_subscription(subscription.clone());
+        // 購読していない種類のイベントは送らない
+        repository.save_subscription(
+            WebhookSubscription::new(&url, &["client.deleted".to_string()], "other").unwrap(),
+        );
+
+        // 失敗しても待たずに返し，再送は次の中継で行う
+        let dispatcher = WebhookDispatcher::new(Rc::clone(&repository));
+        let message = created_message();
+        assert_matches!(
+            dispatcher.deliver(&message),
+            Err(err) if err.contains("Responded with status 503")
+        );
+        assert_matches!(dispatcher.deliver(&message), Ok(()));
+        // 届いた購読先には送り直さない
+        assert_matches!(dispatcher.deliver(&message), Ok(()));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        let (head, body) = requests[1].split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("POST /hooks HTTP/1.1\r\n"));
+        assert_eq!(
+            header(head, "X-Webhook-Id"),
+            message.id().hyphenated().to_string()
+        );
+        assert_eq!(header(head, "X-Webhook-Event"), "client.created");
+        let timestamp = header(head, "X-Webhook-Timestamp").parse::<i64>().unwrap();
+        assert!((Utc::now().timestamp() - timestamp).abs() < 60);
+        assert_eq!(
+            header(head, "X-Webhook-Signature"),
+            format!("sha256={}", sign("secret", timestamp, body))
+        );
+        assert!(body.contains(r#""type":"client.created""#));
+        assert!(body.contains(r#""name":"Taro""#));
+
+        let results = repository
+            .deliveries()
+            .iter()
+            .map(|delivery| (delivery.attempt(), delivery.result().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            vec![
+                (1, Err("Responded with status 503".to_string())),
+                (2, Ok(204))
+            ]
+        );
+    }
+
+    #[test]
+    fn resend_to_every_subscription_after_requeue() {
+        let (url, server) = stand_in_server(vec!["204 No Content"; 2]);
+        let repository = Rc::new(InMemoryWebhookRepository::new());
+        repository.save_subscription(
+            WebhookSubscription::new(&url, &["client.created".to_string()], "secret").unwrap(),
+        );
+
+        let dispatcher = WebhookDispatcher::new(Rc::clone(&repository));
+        let mut message = created_message();
+        assert_matches!(dispatcher.deliver(&message), Ok(()));
+        message.mark_delivered(Utc::now());
+        message.requeue(Utc::now());
+        assert_matches!(dispatcher.deliver(&message), Ok(()));
+
+        server.join().unwrap();
+        let attempts = repository
+            .deliveries()
+            .iter()
+            .map(|delivery| delivery.attempt())
+            .collect::<Vec<_>>();
+        assert_eq!(attempts, vec![1, 2]);
+    }
+}
@@ -2,27 +2,189 @@ mod application;
 mod domain;
 mod infrastructure;
 mod presentation;
+mod telemetry;
 
-use application::handlers_impl::{
-    CreateClientUseCaseHandler, EditClientUseCaseHandler, GetAllClientUseCaseHandler,
-    GetClientUseCaseHandler,
+use application::auth::{AuditMiddleware, PermissionMiddleware};
+use application::datasets::{DatasetGenerator, Locale};
+use application::dtos::{ClientDto, DtoList};
+use application::mediator::RequestKind;
+use application::middlewares::{
+    AuthorizationMiddleware, ClientCountMiddleware, LoggingMiddleware, RetryMiddleware,
+    TimingMiddleware, TracingMiddleware, ValidationMiddleware,
 };
 use application::requests::{
-    CreateClientUseCaseRequest, EditClientUseCaseRequest, GetClientUseCaseRequest, NoneRequest,
+    AddContactUseCaseRequest, AddNoteUseCaseRequest, AddTagUseCaseRequest,
+    AnonymizeClientsUseCaseRequest, ChangeClientStatusUseCaseRequest, CloseProjectUseCaseRequest,
+    CreateClientUseCaseRequest, CreateProjectUseCaseRequest, CreateUserUseCaseRequest,
+    CreateWebhookUseCaseRequest, DeleteClientUseCaseRequest, DeleteNoteUseCaseRequest,
+    DeleteWebhookUseCaseRequest, EditClientUseCaseRequest, EditNoteUseCaseRequest,
+    EraseClientUseCaseRequest, ExportClientPersonalDataUseCaseRequest, ExportClientsUseCaseRequest,
+    FindDuplicatesUseCaseRequest, GetAllClientUseCaseRequest, GetAuditLogUseCaseRequest,
+    GetClientSummariesUseCaseRequest, GetClientUseCaseRequest, GetEditHistoryUseCaseRequest,
+    GetEventHistoryUseCaseRequest, GetHierarchyUseCaseRequest, GetLocationCountsUseCaseRequest,
+    GetNotesUseCaseRequest, GetOutboxUseCaseRequest, GetTagCountsUseCaseRequest,
+    GetWebhookDeliveriesUseCaseRequest, GroupClientsByRegionUseCaseRequest,
+    IsSetupRequiredUseCaseRequest, LinkClientsUseCaseRequest, ListProjectsUseCaseRequest,
+    ListUsersUseCaseRequest, ListWebhooksUseCaseRequest, LoginUseCaseRequest, LogoutUseCaseRequest,
+    MergeClientsUseCaseRequest, RebuildProjectionsUseCaseRequest, RedoUseCaseRequest,
+    ReencryptClientsUseCaseRequest, RelayOutboxUseCaseRequest, RemoveContactUseCaseRequest,
+    RemoveTagUseCaseRequest, ReplayOutboxMessageUseCaseRequest, SetPrimaryContactUseCaseRequest,
+    SetupAdminUseCaseRequest, UndoUseCaseRequest, UnlinkClientsUseCaseRequest,
 };
-use application::Handler;
-use clap::Parser;
-use dialoguer::{Input, Select};
-use infrastructure::InMemoryClientRepository;
+use application::{Container, FromContainer, Mediator, UnitOfWork};
+use chrono::Duration;
+use clap::{Parser, Subcommand};
+use dialoguer::{Confirm, Input, MultiSelect, Password, Select};
+use domain::{
+    ClientEvent, ClientRepository, ClientStatus, ContactKind, EventSink, KeyRotation, RelationKind,
+    Role, TenantId, Transactional, UniquenessPolicy,
+};
+use infrastructure::{
+    Backend, Config, ConfigSource, EncryptedField, EncryptingClientRepository, FileStore,
+    InMemoryAuditRepository, InMemoryClientRepository, InMemoryEventPublisher,
+    InMemoryIdempotencyRepository, InMemoryOutboxRepository, InMemoryProjectRepository,
+    InMemoryRelationshipRepository, InMemoryTombstoneRepository, InMemoryUserRepository,
+    InMemoryWebhookRepository, Keyring, MetricsEndpoint, OutboxEventPublisher, OutputFormat,
+    Persistent, SinkSpec, TenantDirectory, TenantScopedClientRepository, TracedRepository,
+    WebhookDispatcher,
+};
+use presentation::exports::clients_to_json;
+use std::cell::RefCell;
 use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{IsTerminal, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::Arc;
+use telemetry::{Level, LogFormat, MetricsRegistry, Tracer};
 use uuid::Uuid;
 
-fn app<T: domain::ClientRepository>(repository: Rc<T>) -> Result<(), Box<dyn Error>> {
-    let crate_client_use_case_handler = CreateClientUseCaseHandler::new(Rc::clone(&repository));
-    let edit_client_use_case_handler = EditClientUseCaseHandler::new(Rc::clone(&repository));
-    let get_client_use_case_handler = GetClientUseCaseHandler::new(Rc::clone(&repository));
-    let get_all_clients_use_case_handler = GetAllClientUseCaseHandler::new(Rc::clone(&repository));
+/// 対話中にログを書き出す既定のファイル
+const DEFAULT_LOG_FILE: &str = "ddd_example.log";
+/// 対話しないコマンドでログインするパスワード
+const PASSWORD_ENV: &str = "DDD_EXAMPLE_PASSWORD";
+
+fn input_uuid(prompt: &str) -> Result<Uuid, Box<dyn Error>> {
+    let input_id_string = Input::<'_, String>::new()
+        .with_prompt(prompt)
+        .validate_with(|input: &String| -> Result<(), &str> {
+            Uuid::parse_str(input)
+                .map(|_| ())
+                .map_err(|_| "id parse err")
+        })
+        .interact()?;
+    Ok(Uuid::parse_str(&input_id_string)?)
+}
+
+// 設定した形式で表示する．テキストは設定した件数ごとに区切る
+fn print_clients(clients: DtoList<ClientDto>, config: &Config) -> Result<(), Box<dyn Error>> {
+    let page_size = *config.page_size.value();
+    match config.output_format.value() {
+        OutputFormat::Json => println!("{}", clients_to_json(&clients)),
+        OutputFormat::Text if page_size == 0 || clients.len() <= page_size => {
+            println!("{}", clients)
+        }
+        OutputFormat::Text => {
+            let pages = clients.len().div_ceil(page_size);
+            let mut clients = clients.into_iter();
+            for page in 1..=pages {
+                let chunk = clients.by_ref().take(page_size).collect::<DtoList<_>>();
+                println!("{}", chunk);
+                println!("({}/{})", page, pages);
+                if page < pages
+                    && !Confirm::new()
+                        .with_prompt("次のページを表示しますか？")
+                        .default(true)
+                        .interact()?
+                {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// ユーザーがいなければ最初の管理者を作成し，ログインするまで繰り返す
+fn login(mediator: &Mediator) -> Result<(), Box<dyn Error>> {
+    let setup_required = mediator
+        .send(IsSetupRequiredUseCaseRequest)
+        .and_then(|res| res)?;
+    if setup_required {
+        println!("最初の管理者を作成してください．");
+    }
+    loop {
+        let input_username: String = Input::new()
+            .with_prompt("ユーザー名を入力してください >")
+            .interact()?;
+        let res = match setup_required {
+            true => {
+                let input_password = Password::new()
+                    .with_prompt("パスワードを入力してください >")
+                    .with_confirmation("もう一度入力してください >", "パスワードが一致しません")
+                    .interact()?;
+                mediator.send(SetupAdminUseCaseRequest::new(
+                    input_username,
+                    input_password,
+                ))
+            }
+            false => {
+                let input_password = Password::new()
+                    .with_prompt("パスワードを入力してください >")
+                    .interact()?;
+                mediator.send(LoginUseCaseRequest::new(input_username, input_password))
+            }
+        }
+        .and_then(|res| res);
+        match res {
+            Ok(user) => {
+                println!("{}としてログインしました．", user);
+                return Ok(());
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+            }
+        }
+    }
+}
+
+// 再試行しても二重に作成しないよう，冪等キーを付けて作成できる
+fn create(
+    mediator: &Mediator,
+    username: String,
+    request: CreateClientUseCaseRequest,
+) -> Result<(), Box<dyn Error>> {
+    let password =
+        std::env::var(PASSWORD_ENV).map_err(|_| format!("{} is not set", PASSWORD_ENV))?;
+    mediator
+        .send(LoginUseCaseRequest::new(username, password))
+        .and_then(|res| res)?;
+    let created = mediator.send(request).and_then(|res| res)?;
+    for warning in created.warnings() {
+        eprintln!("Warning:{}", warning);
+    }
+    if created.replayed() {
+        eprintln!("同じ冪等キーで作成済みのクライアントです");
+    }
+    println!("{}", created.client());
+    // 配信に失敗したイベントはアウトボックスに残り，次に配信するときに再送する
+    let report = mediator
+        .send(RelayOutboxUseCaseRequest)
+        .and_then(|res| res)?;
+    if report.failed() > 0 || report.dead_lettered() > 0 {
+        eprintln!("{}", report);
+    }
+    Ok(())
+}
+
+fn app(mediator: Mediator, config: &Config) -> Result<(), Box<dyn Error>> {
+    // 起動時点の書き込み側から読み取りモデルを構築する
+    mediator
+        .send(RebuildProjectionsUseCaseRequest)
+        .and_then(|res| res)?;
+
+    login(&mediator)?;
 
     let select_vec = vec![
         "終了 0",
@@ -30,9 +192,62 @@ fn app<T: domain::ClientRepository>(repository: Rc<T>) -> Result<(), Box<dyn Err
         "クライアントを呼び出し 2",
         "クライアントを作成 3",
         "クライアントを編集 4",
+        "重複候補を表示 5",
+        "クライアントを統合 6",
+        "連絡先を追加 7",
+        "連絡先を削除 8",
+        "主連絡先を設定 9",
+        "地域で絞り込んで表示 10",
+        "地域ごとに表示 11",
+        "タグを追加 12",
+        "タグを削除 13",
+        "タグで絞り込んで表示 14",
+        "タグの集計を表示 15",
+        "メモを追加 16",
+        "メモを表示 17",
+        "メモを編集 18",
+        "メモを削除 19",
+        "ステータスを変更 20",
+        "ステータスで絞り込んで表示 21",
+        "イベント履歴を表示 22",
+        "クライアントを削除 23",
+        "プロジェクトを作成 24",
+        "プロジェクトをリストで表示 25",
+        "プロジェクトを完了 26",
+        "クライアントを関連付け 27",
+        "関連付けを解除 28",
+        "階層を表示 29",
+        "クライアントの概要を表示 30",
+        "所在地ごとの件数を表示 31",
+        "読み取りモデルを再構築 32",
+        "アウトボックスを表示 33",
+        "未配信のイベントを配信 34",
+        "イベントを再配信 35",
+        "Webhookを登録 36",
+        "Webhookを削除 37",
+        "Webhookを表示 38",
+        "Webhookの送信記録を表示 39",
+        "操作を取り消す 40",
+        "取り消した操作をやり直す 41",
+        "ユーザーを作成 42",
+        "ユーザーを表示 43",
+        "監査ログを表示 44",
+        "ログアウト 45",
+        "クライアントをエクスポート 46",
+        "クライアントを再暗号化 47",
+        "クライアントの個人データを書き出す 48",
+        "クライアントの個人データを消去 49",
+        "匿名化したクライアントをエクスポート 50",
     ];
 
     'app: loop {
+        // 直前の操作で発行されたイベントを配信する．失敗したものはアウトボックスに残り次回再送する
+        if let Ok(Ok(report)) = mediator.send(RelayOutboxUseCaseRequest) {
+            if report.failed() > 0 || report.dead_lettered() > 0 {
+                eprintln!("{}", report);
+            }
+        }
+
         println!(); // 空行
 
         let select = Select::new()
@@ -44,8 +259,15 @@ fn app<T: domain::ClientRepository>(repository: Rc<T>) -> Result<(), Box<dyn Err
 
         match select {
             1 => {
-                let clients = get_all_clients_use_case_handler.execute(NoneRequest);
-                println!("{}", clients);
+                let clients = mediator.send(GetAllClientUseCaseRequest::new());
+                match clients {
+                    Ok(clients) => {
+                        print_clients(clients, config)?;
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
             }
             2 => {
                 let input_id_string = Input::<'_, String>::new()
@@ -59,8 +281,9 @@ fn app<T: domain::ClientRepository>(repository: Rc<T>) -> Result<(), Box<dyn Err
 
                 let input_id = Uuid::parse_str(&input_id_string)?;
 
-                let client =
-                    get_client_use_case_handler.execute(GetClientUseCaseRequest::new(input_id));
+                let client = mediator
+                    .send(GetClientUseCaseRequest::new(input_id))
+                    .and_then(|res| res);
                 match client {
                     Ok(client) => {
                         println!("{}", client);
@@ -78,10 +301,31 @@ fn app<T: domain::ClientRepository>(repository: Rc<T>) -> Result<(), Box<dyn Err
                 let input_location: String = Input::new()
                     .with_prompt("作成したいクライアントの出身地を入力してください >")
                     .interact()?;
+                let input_idempotency_key: String = Input::new()
+                    .with_prompt("冪等キーを入力してください (空欄で省略) >")
+                    .allow_empty(true)
+                    .interact()?;
 
-                crate_client_use_case_handler
-                    .execute(CreateClientUseCaseRequest::new(input_name, input_location));
-                println!("クライアントが作成されました");
+                let mut request = CreateClientUseCaseRequest::new(input_name, input_location);
+                if !input_idempotency_key.trim().is_empty() {
+                    request = request.with_idempotency_key(input_idempotency_key);
+                }
+                let res = mediator.send(request).and_then(|res| res);
+                match res {
+                    Ok(created) => {
+                        for warning in created.warnings() {
+                            eprintln!("Warning:{}", warning);
+                        }
+                        match created.replayed() {
+                            true => println!("同じ冪等キーで作成済みのクライアントです"),
+                            false => println!("クライアントが作成されました"),
+                        }
+                        println!("{}", created.client());
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
             }
             4 => {
                 let input_id_string = Input::<'_, String>::new()
@@ -101,13 +345,18 @@ fn app<T: domain::ClientRepository>(repository: Rc<T>) -> Result<(), Box<dyn Err
                     .with_prompt("新しい出身地を入力してください >")
                     .interact()?;
 
-                let res = edit_client_use_case_handler.execute(EditClientUseCaseRequest::new(
-                    input_id,
-                    input_name,
-                    input_location,
-                ));
+                let res = mediator
+                    .send(EditClientUseCaseRequest::new(
+                        input_id,
+                        input_name,
+                        input_location,
+                    ))
+                    .and_then(|res| res);
                 match res {
-                    Ok(_) => {
+                    Ok(warnings) => {
+                        for warning in warnings {
+                            eprintln!("Warning:{}", warning);
+                        }
                         println!("クライアントを編集しました．");
                     }
                     Err(err) => {
@@ -115,6 +364,884 @@ fn app<T: domain::ClientRepository>(repository: Rc<T>) -> Result<(), Box<dyn Err
                     }
                 }
             }
+            5 => {
+                let groups = mediator.send(FindDuplicatesUseCaseRequest::new(
+                    domain::DuplicateDetectionService::DEFAULT_THRESHOLD,
+                ));
+                match groups {
+                    Ok(groups) => {
+                        println!("{}", groups);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            6 => {
+                let input_keep_id_string = Input::<'_, String>::new()
+                    .with_prompt("残すクライアントのIDを入力してください >")
+                    .validate_with(|input: &String| -> Result<(), &str> {
+                        Uuid::parse_str(input)
+                            .map(|_| ())
+                            .map_err(|_| "id parse err")
+                    })
+                    .interact()?;
+                let input_merge_ids_string = Input::<'_, String>::new()
+                    .with_prompt("統合するクライアントのIDをカンマ区切りで入力してください >")
+                    .validate_with(|input: &String| -> Result<(), &str> {
+                        input
+                            .split(',')
+                            .try_for_each(|id| Uuid::parse_str(id.trim()).map(|_| ()))
+                            .map_err(|_| "id parse err")
+                    })
+                    .interact()?;
+
+                let input_keep_id = Uuid::parse_str(&input_keep_id_string)?;
+                let input_merge_ids = input_merge_ids_string
+                    .split(',')
+                    .map(|id| Uuid::parse_str(id.trim()))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let res = mediator
+                    .send(MergeClientsUseCaseRequest::new(
+                        input_keep_id,
+                        input_merge_ids,
+                    ))
+                    .and_then(|res| res);
+                match res {
+                    Ok(client) => {
+                        println!("クライアントを統合しました．");
+                        println!("{}", client);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            7 => {
+                let input_id = input_uuid("連絡先を追加するクライアントのIDを入力してください >")?;
+
+                let kinds = [
+                    ContactKind::Email,
+                    ContactKind::Phone,
+                    ContactKind::PostalAddress,
+                ];
+                let select_kind = Select::new()
+                    .with_prompt("連絡先の種類を選択してください")
+                    .items(&["メールアドレス", "電話番号", "住所"])
+                    .interact()?;
+                let prompt = match kinds[select_kind] {
+                    ContactKind::Email => "メールアドレスを入力してください >",
+                    ContactKind::Phone => "電話番号を入力してください >",
+                    ContactKind::PostalAddress => {
+                        "郵便番号と住所を空白で区切って入力してください >"
+                    }
+                };
+                let input_value: String = Input::new().with_prompt(prompt).interact()?;
+
+                let res = mediator
+                    .send(AddContactUseCaseRequest::new(
+                        input_id,
+                        kinds[select_kind],
+                        input_value,
+                    ))
+                    .and_then(|res| res);
+                match res {
+                    Ok(client) => {
+                        println!("連絡先を追加しました．");
+                        println!("{}", client);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            8 => {
+                let input_id = input_uuid("連絡先を削除するクライアントのIDを入力してください >")?;
+                let input_contact_id = input_uuid("削除する連絡先のIDを入力してください >")?;
+
+                let res = mediator
+                    .send(RemoveContactUseCaseRequest::new(input_id, input_contact_id))
+                    .and_then(|res| res);
+                match res {
+                    Ok(client) => {
+                        println!("連絡先を削除しました．");
+                        println!("{}", client);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            9 => {
+                let input_id = input_uuid("クライアントのIDを入力してください >")?;
+                let input_contact_id = input_uuid("主連絡先にする連絡先のIDを入力してください >")?;
+
+                let res = mediator
+                    .send(SetPrimaryContactUseCaseRequest::new(
+                        input_id,
+                        input_contact_id,
+                    ))
+                    .and_then(|res| res);
+                match res {
+                    Ok(client) => {
+                        println!("主連絡先を設定しました．");
+                        println!("{}", client);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            10 => {
+                let input_region: String = Input::new()
+                    .with_prompt("絞り込む地域(都道府県・州・国)を入力してください >")
+                    .interact()?;
+
+                let clients =
+                    mediator.send(GetAllClientUseCaseRequest::new().with_region(input_region));
+                match clients {
+                    Ok(clients) => {
+                        print_clients(clients, config)?;
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            11 => {
+                let groups = mediator.send(GroupClientsByRegionUseCaseRequest);
+                match groups {
+                    Ok(groups) => {
+                        println!("{}", groups);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            12 => {
+                let input_id = input_uuid("タグを追加するクライアントのIDを入力してください >")?;
+                let input_tag: String = Input::new()
+                    .with_prompt("追加するタグを入力してください >")
+                    .interact()?;
+
+                let res = mediator
+                    .send(AddTagUseCaseRequest::new(input_id, input_tag))
+                    .and_then(|res| res);
+                match res {
+                    Ok(client) => {
+                        println!("タグを追加しました．");
+                        println!("{}", client);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            13 => {
+                let input_id = input_uuid("タグを削除するクライアントのIDを入力してください >")?;
+                let input_tag: String = Input::new()
+                    .with_prompt("削除するタグを入力してください >")
+                    .interact()?;
+
+                let res = mediator
+                    .send(RemoveTagUseCaseRequest::new(input_id, input_tag))
+                    .and_then(|res| res);
+                match res {
+                    Ok(client) => {
+                        println!("タグを削除しました．");
+                        println!("{}", client);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            14 => {
+                let input_tag: String = Input::new()
+                    .with_prompt("絞り込むタグを入力してください >")
+                    .interact()?;
+
+                let clients = mediator.send(GetAllClientUseCaseRequest::new().with_tag(input_tag));
+                match clients {
+                    Ok(clients) => {
+                        print_clients(clients, config)?;
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            15 => {
+                let tag_counts = mediator.send(GetTagCountsUseCaseRequest);
+                match tag_counts {
+                    Ok(tag_counts) => {
+                        println!("{}", tag_counts);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            16 => {
+                let input_id = input_uuid("メモを追加するクライアントのIDを入力してください >")?;
+                let input_body: String = Input::new()
+                    .with_prompt("メモを入力してください >")
+                    .interact()?;
+
+                let res = mediator
+                    .send(AddNoteUseCaseRequest::new(input_id, input_body))
+                    .and_then(|res| res);
+                match res {
+                    Ok(note) => {
+                        println!("メモを追加しました．");
+                        println!("{}", note);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            17 => {
+                let input_id = input_uuid("メモを表示するクライアントのIDを入力してください >")?;
+
+                let res = mediator
+                    .send(GetNotesUseCaseRequest::new(input_id))
+                    .and_then(|res| res);
+                match res {
+                    Ok(notes) => {
+                        println!("{}", notes);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            18 => {
+                let input_id = input_uuid("メモを編集するクライアントのIDを入力してください >")?;
+                let input_note_id = input_uuid("編集するメモのIDを入力してください >")?;
+                let input_body: String = Input::new()
+                    .with_prompt("新しいメモを入力してください >")
+                    .interact()?;
+
+                let res = mediator
+                    .send(EditNoteUseCaseRequest::new(
+                        input_id,
+                        input_note_id,
+                        input_body,
+                    ))
+                    .and_then(|res| res);
+                match res {
+                    Ok(note) => {
+                        println!("メモを編集しました．");
+                        println!("{}", note);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            19 => {
+                let input_id = input_uuid("メモを削除するクライアントのIDを入力してください >")?;
+                let input_note_id = input_uuid("削除するメモのIDを入力してください >")?;
+
+                let res = mediator
+                    .send(DeleteNoteUseCaseRequest::new(input_id, input_note_id))
+                    .and_then(|res| res);
+                match res {
+                    Ok(()) => {
+                        println!("メモを削除しました．");
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            20 => {
+                let input_id =
+                    input_uuid("ステータスを変更するクライアントのIDを入力してください >")?;
+                let statuses = ClientStatus::ALL.map(|status| status.as_str());
+                let input_status = Select::new()
+                    .with_prompt("新しいステータスを選択してください")
+                    .items(&statuses)
+                    .interact()?;
+
+                let res = mediator
+                    .send(ChangeClientStatusUseCaseRequest::new(
+                        input_id,
+                        statuses[input_status].to_string(),
+                    ))
+                    .and_then(|res| res);
+                match res {
+                    Ok(client) => {
+                        println!("ステータスを変更しました．");
+                        println!("{}", client);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            21 => {
+                let statuses = ClientStatus::ALL.map(|status| status.as_str());
+                let input_status = Select::new()
+                    .with_prompt("絞り込むステータスを選択してください")
+                    .items(&statuses)
+                    .interact()?;
+
+                let clients = mediator.send(
+                    GetAllClientUseCaseRequest::new()
+                        .with_status(statuses[input_status].to_string()),
+                );
+                match clients {
+                    Ok(clients) => {
+                        print_clients(clients, config)?;
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            22 => match mediator.send(GetEventHistoryUseCaseRequest) {
+                Ok(events) => {
+                    println!("{}", events);
+                }
+                Err(err) => {
+                    eprintln!("{}", err);
+                }
+            },
+            23 => {
+                let input_id = input_uuid("削除するクライアントのIDを入力してください >")?;
+
+                let res = mediator
+                    .send(DeleteClientUseCaseRequest::new(input_id))
+                    .and_then(|res| res);
+                match res {
+                    Ok(()) => {
+                        println!("クライアントを削除しました．");
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            24 => {
+                let input_id =
+                    input_uuid("プロジェクトを作成するクライアントのIDを入力してください >")?;
+                let input_name: String = Input::new()
+                    .with_prompt("プロジェクト名を入力してください >")
+                    .interact()?;
+
+                let res = mediator
+                    .send(CreateProjectUseCaseRequest::new(input_id, input_name))
+                    .and_then(|res| res);
+                match res {
+                    Ok(project) => {
+                        println!("プロジェクトを作成しました．");
+                        println!("{}", project);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            25 => {
+                let input_id_string: String = Input::new()
+                    .with_prompt("クライアントのIDを入力してください(空欄で全て) >")
+                    .allow_empty(true)
+                    .validate_with(|input: &String| -> Result<(), &str> {
+                        if input.is_empty() {
+                            return Ok(());
+                        }
+                        Uuid::parse_str(input)
+                            .map(|_| ())
+                            .map_err(|_| "id parse err")
+                    })
+                    .interact()?;
+
+                let request = match input_id_string.is_empty() {
+                    true => ListProjectsUseCaseRequest::new(),
+                    false => ListProjectsUseCaseRequest::new()
+                        .with_client(Uuid::parse_str(&input_id_string)?),
+                };
+                let res = mediator.send(request).and_then(|res| res);
+                match res {
+                    Ok(projects) => {
+                        println!("{}", projects);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            26 => {
+                let input_id = input_uuid("完了するプロジェクトのIDを入力してください >")?;
+
+                let res = mediator
+                    .send(CloseProjectUseCaseRequest::new(input_id))
+                    .and_then(|res| res);
+                match res {
+                    Ok(project) => {
+                        println!("プロジェクトを完了しました．");
+                        println!("{}", project);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            27 | 28 => {
+                let input_from = input_uuid("関連元のクライアントのIDを入力してください >")?;
+                let kinds = RelationKind::ALL.map(|kind| kind.as_str());
+                let input_kind = Select::new()
+                    .with_prompt("関係の種類を選択してください(関連元が関連先の子会社 / 関連元が関連先から紹介)")
+                    .items(&kinds)
+                    .interact()?;
+                let input_to = input_uuid("関連先のクライアントのIDを入力してください >")?;
+                let kind = kinds[input_kind].to_string();
+
+                let res = match select {
+                    27 => mediator
+                        .send(LinkClientsUseCaseRequest::new(input_from, input_to, kind))
+                        .and_then(|res| res),
+                    _ => mediator
+                        .send(UnlinkClientsUseCaseRequest::new(input_from, input_to, kind))
+                        .and_then(|res| res),
+                };
+                match res {
+                    Ok(()) => {
+                        println!(
+                            "{}しました．",
+                            if select == 27 {
+                                "関連付け"
+                            } else {
+                                "関連付けを解除"
+                            }
+                        );
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            29 => {
+                let input_id = input_uuid("階層を表示するクライアントのIDを入力してください >")?;
+
+                let res = mediator
+                    .send(GetHierarchyUseCaseRequest::new(input_id))
+                    .and_then(|res| res);
+                match res {
+                    Ok(hierarchy) => {
+                        println!("{}", hierarchy);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            30 => {
+                let summaries = mediator.send(GetClientSummariesUseCaseRequest);
+                match summaries {
+                    Ok(summaries) => {
+                        println!("{}", summaries);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            31 => {
+                let location_counts = mediator.send(GetLocationCountsUseCaseRequest);
+                match location_counts {
+                    Ok(location_counts) => {
+                        println!("{}", location_counts);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            32 => {
+                let res = mediator
+                    .send(RebuildProjectionsUseCaseRequest)
+                    .and_then(|res| res);
+                match res {
+                    Ok(count) => {
+                        println!("{}件のクライアントから再構築しました．", count);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            33 => {
+                let messages = mediator.send(GetOutboxUseCaseRequest);
+                match messages {
+                    Ok(messages) => {
+                        println!("{}", messages);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            34 => {
+                let res = mediator.send(RelayOutboxUseCaseRequest).and_then(|res| res);
+                match res {
+                    Ok(report) => {
+                        println!("{}", report);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            35 => {
+                let input_id = input_uuid("再配信するメッセージのIDを入力してください >")?;
+
+                let res = mediator
+                    .send(ReplayOutboxMessageUseCaseRequest::new(input_id))
+                    .and_then(|res| res);
+                match res {
+                    Ok(message) => {
+                        println!("{}", message);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            36 => {
+                let input_url: String = Input::new()
+                    .with_prompt("送信先のURLを入力してください >")
+                    .interact()?;
+                let select_types = MultiSelect::new()
+                    .with_prompt("購読するイベントを選択してください")
+                    .items(&ClientEvent::TYPES)
+                    .interact()?;
+                let input_secret: String = Input::new()
+                    .with_prompt("署名に使う秘密鍵を入力してください >")
+                    .interact()?;
+
+                let event_types = select_types
+                    .into_iter()
+                    .map(|index| ClientEvent::TYPES[index].to_string())
+                    .collect();
+                let res = mediator
+                    .send(CreateWebhookUseCaseRequest::new(
+                        input_url,
+                        event_types,
+                        input_secret,
+                    ))
+                    .and_then(|res| res);
+                match res {
+                    Ok(subscription) => {
+                        println!("Webhookを登録しました．");
+                        println!("{}", subscription);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            37 => {
+                let input_id = input_uuid("削除するWebhookのIDを入力してください >")?;
+
+                let res = mediator
+                    .send(DeleteWebhookUseCaseRequest::new(input_id))
+                    .and_then(|res| res);
+                match res {
+                    Ok(()) => {
+                        println!("Webhookを削除しました．");
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            38 => {
+                let subscriptions = mediator.send(ListWebhooksUseCaseRequest);
+                match subscriptions {
+                    Ok(subscriptions) => {
+                        println!("{}", subscriptions);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            39 => {
+                let input_id_string = Input::<'_, String>::new()
+                    .with_prompt("WebhookのIDを入力してください (空欄で全て) >")
+                    .allow_empty(true)
+                    .interact()?;
+
+                let mut request = GetWebhookDeliveriesUseCaseRequest::new();
+                if !input_id_string.trim().is_empty() {
+                    request = request.with_subscription(Uuid::parse_str(input_id_string.trim())?);
+                }
+                let deliveries = mediator.send(request).and_then(|res| res);
+                match deliveries {
+                    Ok(deliveries) => {
+                        println!("{}", deliveries);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            40 | 41 => {
+                // 適用する変更を確認してから実行する
+                let history = mediator
+                    .send(GetEditHistoryUseCaseRequest)
+                    .and_then(|res| res);
+                let history = match history {
+                    Ok(history) => history,
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        continue 'app;
+                    }
+                };
+                let change = match select {
+                    40 => history.undo(),
+                    _ => history.redo(),
+                };
+                let change = match change {
+                    Some(change) => change,
+                    None => {
+                        println!("{}", history);
+                        continue 'app;
+                    }
+                };
+                println!("{}", change);
+                let confirmed = Confirm::new()
+                    .with_prompt("この変更を適用しますか？")
+                    .interact()?;
+                if !confirmed {
+                    continue 'app;
+                }
+
+                let res = match select {
+                    40 => mediator.send(UndoUseCaseRequest),
+                    _ => mediator.send(RedoUseCaseRequest),
+                }
+                .and_then(|res| res);
+                match res {
+                    Ok(change) => {
+                        println!("変更を適用しました．");
+                        println!("{}", change);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            42 => {
+                let input_username: String = Input::new()
+                    .with_prompt("ユーザー名を入力してください >")
+                    .interact()?;
+                let input_password = Password::new()
+                    .with_prompt("パスワードを入力してください >")
+                    .with_confirmation("もう一度入力してください >", "パスワードが一致しません")
+                    .interact()?;
+                let input_role = Select::new()
+                    .with_prompt("ロールを選択してください")
+                    .items(&Role::ALL)
+                    .default(0)
+                    .interact()?;
+
+                let res = mediator
+                    .send(CreateUserUseCaseRequest::new(
+                        input_username,
+                        input_password,
+                        Role::ALL[input_role].to_string(),
+                    ))
+                    .and_then(|res| res);
+                match res {
+                    Ok(user) => {
+                        println!("ユーザーを作成しました．");
+                        println!("{}", user);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            43 => {
+                let users = mediator.send(ListUsersUseCaseRequest);
+                match users {
+                    Ok(users) => {
+                        println!("{}", users);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            44 => {
+                let entries = mediator.send(GetAuditLogUseCaseRequest);
+                match entries {
+                    Ok(entries) => {
+                        println!("{}", entries);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            45 => {
+                let res = mediator.send(LogoutUseCaseRequest).and_then(|res| res);
+                match res {
+                    Ok(()) => {
+                        println!("ログアウトしました．");
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+                login(&mediator)?;
+            }
+            46 => {
+                let res = mediator
+                    .send(ExportClientsUseCaseRequest)
+                    .and_then(|res| res);
+                let export = match res {
+                    Ok(export) => export,
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        continue 'app;
+                    }
+                };
+                let input_path: String = Input::new()
+                    .with_prompt("書き出すファイルのパスを入力してください >")
+                    .default(
+                        config
+                            .export_dir
+                            .value()
+                            .join(format!("clients-{}.json", export.tenant()))
+                            .display()
+                            .to_string(),
+                    )
+                    .interact()?;
+                match std::fs::write(&input_path, export.to_string()) {
+                    Ok(()) => {
+                        println!(
+                            "{}件のクライアントを{}に書き出しました．",
+                            export.clients().len(),
+                            input_path
+                        );
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            47 => {
+                let res = mediator
+                    .send(ReencryptClientsUseCaseRequest)
+                    .and_then(|res| res);
+                match res {
+                    Ok(count) => {
+                        println!("{}件のクライアントを現在の鍵で暗号化しました．", count);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            48 => {
+                let input_id = input_uuid("書き出すクライアントのIDを入力してください >")?;
+                let res = mediator
+                    .send(ExportClientPersonalDataUseCaseRequest::new(input_id))
+                    .and_then(|res| res);
+                let personal_data = match res {
+                    Ok(personal_data) => personal_data,
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        continue 'app;
+                    }
+                };
+                let input_path: String = Input::new()
+                    .with_prompt("書き出すファイルのパスを入力してください >")
+                    .default(
+                        config
+                            .export_dir
+                            .value()
+                            .join(format!("client-{}.json", input_id.hyphenated()))
+                            .display()
+                            .to_string(),
+                    )
+                    .interact()?;
+                match std::fs::write(&input_path, personal_data.to_string()) {
+                    Ok(()) => {
+                        println!("{}に書き出しました．", input_path);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            49 => {
+                let input_id = input_uuid("消去するクライアントのIDを入力してください >")?;
+                let confirmed = Confirm::new()
+                    .with_prompt("消去したデータは元に戻せません．消去しますか？")
+                    .default(false)
+                    .interact()?;
+                if !confirmed {
+                    continue 'app;
+                }
+                let res = mediator
+                    .send(EraseClientUseCaseRequest::new(input_id))
+                    .and_then(|res| res);
+                match res {
+                    Ok(report) => {
+                        println!("{}", report);
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
+            50 => {
+                let input_seed: u64 = Input::new()
+                    .with_prompt("匿名化に使う種を入力してください >")
+                    .default(0)
+                    .interact()?;
+                let res = mediator
+                    .send(AnonymizeClientsUseCaseRequest::new(input_seed))
+                    .and_then(|res| res);
+                let export = match res {
+                    Ok(export) => export,
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        continue 'app;
+                    }
+                };
+                let input_path: String = Input::new()
+                    .with_prompt("書き出すファイルのパスを入力してください >")
+                    .default(
+                        config
+                            .export_dir
+                            .value()
+                            .join(format!("clients-{}-anonymized.json", export.tenant()))
+                            .display()
+                            .to_string(),
+                    )
+                    .interact()?;
+                match std::fs::write(&input_path, export.to_string()) {
+                    Ok(()) => {
+                        println!(
+                            "{}件の匿名化したクライアントを{}に書き出しました．",
+                            export.clients().len(),
+                            input_path
+                        );
+                    }
+                    Err(err) => {
+                        eprintln!("{}", err);
+                    }
+                }
+            }
             0 => {
                 println!("終了します");
                 break 'app;
@@ -130,18 +1257,505 @@ fn app<T: domain::ClientRepository>(repository: Rc<T>) -> Result<(), Box<dyn Err
 
 #[derive(Parser)]
 struct Cli {
+    /// configuration file (default: $XDG_CONFIG_HOME/ddd_example/config.toml)
+    #[arg(long)]
+    config: Option<PathBuf>,
     /// with some samples
     #[arg(long)]
     sample: bool,
+    /// storage of clients (memory, file)
+    #[arg(long)]
+    backend: Option<Backend>,
+    /// directory of the file backend (default: $XDG_DATA_HOME/ddd_example)
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+    /// uniqueness policy of name and location (off, warn, reject)
+    #[arg(long)]
+    uniqueness: Option<UniquenessPolicy>,
+    /// log each request and its duration to the log sink, at info level or more detailed
+    #[arg(long)]
+    verbose: bool,
+    /// reject commands that modify data (login is still allowed)
+    #[arg(long)]
+    read_only: bool,
+    /// number of retries when a request fails
+    #[arg(long)]
+    retries: Option<usize>,
+    /// destination of client events (stdout, file:PATH, webhook:URL), repeatable
+    #[arg(long = "sink")]
+    sinks: Vec<SinkSpec>,
+    /// number of retries when delivering an event to a sink fails
+    #[arg(long)]
+    delivery_retries: Option<usize>,
+    /// number of failed relays after which an event is moved to the dead letters
+    #[arg(long)]
+    max_delivery_attempts: Option<u32>,
+    /// initial interval in milliseconds before a failed event is relayed again, doubled on each failure
+    #[arg(long)]
+    delivery_backoff_ms: Option<u64>,
+    /// tenant whose clients are visible in this session
+    #[arg(long)]
+    tenant: Option<TenantId>,
+    /// keyfile of ID=HEX entries for encrypting clients at rest, the last one being current
+    /// (CLIENT_ENCRYPTION_KEYS is used when omitted)
+    #[arg(long)]
+    encryption_keys: Option<PathBuf>,
+    /// client fields to encrypt (name, location, contacts, notes)
+    #[arg(long, value_delimiter = ',')]
+    encrypted_fields: Vec<EncryptedField>,
+    /// number of changes that can be undone in a session
+    #[arg(long)]
+    history_size: Option<usize>,
+    /// default directory of exported files
+    #[arg(long)]
+    export_dir: Option<PathBuf>,
+    /// format of client lists (text, json)
+    #[arg(long)]
+    output_format: Option<OutputFormat>,
+    /// number of clients per page in text lists, 0 for no paging
+    #[arg(long)]
+    page_size: Option<usize>,
+    /// most detailed level of logs (off, error, warn, info, debug, trace)
+    #[arg(long)]
+    log_level: Option<Level>,
+    /// format of log lines (human, json)
+    #[arg(long)]
+    log_format: Option<LogFormat>,
+    /// file to append logs to (default: ddd_example.log when interactive, otherwise stderr)
+    #[arg(long)]
+    log_file: Option<PathBuf>,
+    /// address to serve Prometheus metrics on at /metrics, e.g. 127.0.0.1:9464
+    #[arg(long)]
+    metrics_address: Option<SocketAddr>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// generate fictitious clients into the selected tenant; the file backend exits after
+    /// saving them, the memory backend starts a session with them
+    Seed {
+        /// number of clients to generate
+        #[arg(long, default_value_t = 100)]
+        count: usize,
+        /// the same seed always generates the same names, locations and statuses
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// locale of names and locations (ja, en)
+        #[arg(long, default_value_t = Locale::Ja)]
+        locale: Locale,
+    },
+    /// create a client and exit, signing in as --username with the password in DDD_EXAMPLE_PASSWORD
+    Create {
+        /// user to sign in as
+        #[arg(long)]
+        username: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        location: String,
+        /// retrying with the same key returns the first result instead of creating another client
+        #[arg(long)]
+        idempotency_key: Option<String>,
+    },
+    /// inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// print the effective configuration and where each value comes from
+    Show,
+}
+
+// 既定値 < 設定ファイル < 環境変数 < コマンドラインの順に上書きする
+fn load_config(cli: &Cli) -> Result<Config, Box<dyn Error>> {
+    let mut config = Config::default();
+    // 指定したファイルは必須，既定の場所のファイルは無ければ使わない
+    let path = match &cli.config {
+        Some(path) => Some(path.clone()),
+        None => Config::default_path(|name| std::env::var(name).ok()).filter(|path| path.exists()),
+    };
+    if let Some(path) = path {
+        let text =
+            std::fs::read_to_string(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        config.apply_file(&text, &path)?;
+    }
+    config.apply_env(
+        std::env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        }),
+    )?;
+
+    macro_rules! override_with_flags {
+        ($($key:ident),*) => {
+            $(
+                if let Some(value) = &cli.$key {
+                    config.$key.set(value.clone(), ConfigSource::Flag);
+                }
+            )*
+        };
+    }
+    override_with_flags!(
+        backend,
+        tenant,
+        export_dir,
+        output_format,
+        page_size,
+        uniqueness,
+        history_size,
+        retries,
+        delivery_retries,
+        max_delivery_attempts,
+        delivery_backoff_ms,
+        log_level,
+        log_format
+    );
+    if cli.data_dir.is_some() {
+        config
+            .data_dir
+            .set(cli.data_dir.clone(), ConfigSource::Flag);
+    }
+    if cli.log_file.is_some() {
+        config
+            .log_file
+            .set(cli.log_file.clone(), ConfigSource::Flag);
+    }
+    if cli.metrics_address.is_some() {
+        config
+            .metrics_address
+            .set(cli.metrics_address, ConfigSource::Flag);
+    }
+    if cli.encryption_keys.is_some() {
+        config
+            .encryption_keys
+            .set(cli.encryption_keys.clone(), ConfigSource::Flag);
+    }
+    if !cli.encrypted_fields.is_empty() {
+        config
+            .encrypted_fields
+            .set(cli.encrypted_fields.clone(), ConfigSource::Flag);
+    }
+    if !cli.sinks.is_empty() {
+        config.sinks.set(cli.sinks.clone(), ConfigSource::Flag);
+    }
+    if cli.read_only {
+        config.read_only.set(true, ConfigSource::Flag);
+    }
+    if cli.verbose {
+        config.verbose.set(true, ConfigSource::Flag);
+    }
+    Ok(config)
+}
+
+// 対話中は画面を崩さないようにファイルへ書き出す．ファイルは最初に書き出すときに開く
+fn log_sink(config: &Config) -> Box<dyn Fn(String)> {
+    let path = match config.log_file.value() {
+        Some(path) => path.clone(),
+        None if std::io::stdin().is_terminal() => PathBuf::from(DEFAULT_LOG_FILE),
+        None => return Box::new(|line| eprintln!("{}", line)),
+    };
+    let file = RefCell::new(None);
+    Box::new(move |line| {
+        let mut file = file.borrow_mut();
+        if file.is_none() {
+            *file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .ok();
+        }
+        if let Some(file) = file.as_mut() {
+            let _ = writeln!(file, "{}", line);
+        }
+    })
+}
+
+/// ファイルから読み込み，作業単位のcommitごとに書き出す
+fn file_store<T: Persistent + Transactional + 'static>(
+    repository: &Rc<T>,
+    path: PathBuf,
+    tracer: &Rc<Tracer>,
+) -> Result<Rc<dyn Transactional>, String> {
+    Ok(Rc::new(
+        FileStore::open(Rc::clone(repository), path)?.with_tracer(Rc::clone(tracer)),
+    ))
+}
+
+fn traced<T: ?Sized>(
+    inner: &Rc<T>,
+    tracer: &Rc<Tracer>,
+    name: &'static str,
+) -> Rc<TracedRepository<T>> {
+    Rc::new(TracedRepository::new(
+        Rc::clone(inner),
+        Rc::clone(tracer),
+        name,
+    ))
+}
+
+// 外側から順に実行する
+fn build_mediator(
+    container: &Container,
+    config: &Config,
+    tracer: &Rc<Tracer>,
+    client_count: ClientCountMiddleware,
+) -> Mediator {
+    let mut mediator = Mediator::from_container(container)
+        .with_middleware(TracingMiddleware::new(Rc::clone(tracer)))
+        .with_middleware(client_count);
+    if *config.verbose.value() {
+        let logging_tracer = Rc::clone(tracer);
+        let timing_tracer = Rc::clone(tracer);
+        mediator = mediator
+            .with_middleware(LoggingMiddleware::new(move |log| {
+                logging_tracer.event(Level::Info, "mediator", &log)
+            }))
+            .with_middleware(TimingMiddleware::new(move |log| {
+                timing_tracer.event(Level::Info, "mediator", &log)
+            }));
+    }
+    if *config.read_only.value() {
+        mediator = mediator.with_middleware(AuthorizationMiddleware::new(|context| {
+            match (context.kind(), context.permission()) {
+                (RequestKind::Command, Some(_)) => Err(format!(
+                    "{} is not allowed in read-only mode",
+                    context.name()
+                )),
+                _ => Ok(()),
+            }
+        }));
+    }
+    mediator
+        .with_middleware(PermissionMiddleware::new(container.session()))
+        .with_middleware(
+            AuditMiddleware::new(container.audit_repo(), container.session())
+                .with_unit_of_work(container.unit_of_work()),
+        )
+        .with_middleware(ValidationMiddleware)
+        .with_middleware(RetryMiddleware::new(*config.retries.value()))
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
-    let repository = match cli.sample {
-        true => Rc::new(InMemoryClientRepository::new_with_clients()),
-        false => Rc::new(InMemoryClientRepository::new()),
+    let config = load_config(&cli)?;
+    if let Some(Command::Config {
+        action: ConfigCommand::Show,
+    }) = cli.command
+    {
+        print!("{}", config);
+        return Ok(());
+    }
+    // メトリクスはスパンから集計し，アドレスが指定されたときだけ公開する
+    let metrics = Arc::new(MetricsRegistry::new());
+    if let Some(address) = config.metrics_address.value() {
+        let endpoint = MetricsEndpoint::bind(*address, Arc::clone(&metrics))?;
+        eprintln!("metrics: http://{}/metrics", endpoint.local_addr()?);
+        endpoint.serve();
+    }
+    // verboseではリクエストの記録がinfoで出力されるよう，少なくともinfoまで出力する
+    let log_level = match *config.verbose.value() {
+        true => (*config.log_level.value()).max(Level::Info),
+        false => *config.log_level.value(),
+    };
+    let tracer = Rc::new(
+        Tracer::new(log_level, *config.log_format.value(), log_sink(&config))
+            .with_metrics(Arc::clone(&metrics)),
+    );
+    // 全てのテナントのクライアントを1つの保存先に置き，選択したテナントの分だけを扱う
+    let shared_repository = Rc::new(InMemoryClientRepository::new());
+    let tenant_directory = Rc::new(TenantDirectory::new());
+    let project_repository = Rc::new(InMemoryProjectRepository::new());
+    let relationship_repository = Rc::new(InMemoryRelationshipRepository::new());
+    let outbox_repository = Rc::new(InMemoryOutboxRepository::new());
+    let webhook_repository = Rc::new(InMemoryWebhookRepository::new());
+    let idempotency_repository = Rc::new(InMemoryIdempotencyRepository::new());
+    let user_repository = Rc::new(InMemoryUserRepository::new());
+    let audit_repository = Rc::new(InMemoryAuditRepository::new());
+    let tombstone_repository = Rc::new(InMemoryTombstoneRepository::new());
+
+    // fileではリポジトリの代わりにファイルに書き出すストアが作業単位に参加する
+    let data_dir = || {
+        config
+            .data_dir_or_default(|name| std::env::var(name).ok())
+            .ok_or("data_dir is not set and neither XDG_DATA_HOME nor HOME is set (pass --data-dir or set data_dir in the config file)")
+    };
+    let stored: [Rc<dyn Transactional>; 9] = match config.backend.value() {
+        Backend::Memory => [
+            Rc::clone(&shared_repository) as Rc<dyn Transactional>,
+            Rc::clone(&tenant_directory) as Rc<dyn Transactional>,
+            Rc::clone(&project_repository) as Rc<dyn Transactional>,
+            Rc::clone(&relationship_repository) as Rc<dyn Transactional>,
+            Rc::clone(&outbox_repository) as Rc<dyn Transactional>,
+            Rc::clone(&webhook_repository) as Rc<dyn Transactional>,
+            Rc::clone(&idempotency_repository) as Rc<dyn Transactional>,
+            Rc::clone(&audit_repository) as Rc<dyn Transactional>,
+            Rc::clone(&tombstone_repository) as Rc<dyn Transactional>,
+        ],
+        Backend::File => {
+            let dir = data_dir()?;
+            [
+                file_store(&shared_repository, dir.join("clients.json"), &tracer)?,
+                file_store(&tenant_directory, dir.join("tenants.json"), &tracer)?,
+                file_store(&project_repository, dir.join("projects.json"), &tracer)?,
+                file_store(
+                    &relationship_repository,
+                    dir.join("relationships.json"),
+                    &tracer,
+                )?,
+                file_store(&outbox_repository, dir.join("outbox.json"), &tracer)?,
+                file_store(&webhook_repository, dir.join("webhooks.json"), &tracer)?,
+                file_store(
+                    &idempotency_repository,
+                    dir.join("idempotency.json"),
+                    &tracer,
+                )?,
+                file_store(&audit_repository, dir.join("audit.json"), &tracer)?,
+                file_store(&tombstone_repository, dir.join("tombstones.json"), &tracer)?,
+            ]
+        }
+    };
+    // ユーザーがいなければ誰でも最初の管理者になれるため，データディレクトリがあれば保存先によらずファイルに残す
+    let users = match data_dir() {
+        Ok(dir) => file_store(&user_repository, dir.join("users.json"), &tracer)?,
+        Err(err) => {
+            eprintln!("{}; users are kept in memory", err);
+            Rc::clone(&user_repository) as Rc<dyn Transactional>
+        }
+    };
+    let unit_of_work = stored
+        .into_iter()
+        .fold(UnitOfWork::new(), UnitOfWork::with_participant)
+        .with_participant(users);
+    // 鍵が与えられたときは選択した項目を暗号化して保存する
+    let keys = match config.encryption_keys.value() {
+        Some(path) => Some(std::fs::read_to_string(path)?),
+        None => std::env::var("CLIENT_ENCRYPTION_KEYS").ok(),
+    };
+    let mut key_rotation = None;
+    let stored_repository: Rc<dyn ClientRepository> = match keys {
+        Some(keys) => {
+            let encrypting_repository = Rc::new(
+                EncryptingClientRepository::new(
+                    Rc::clone(&shared_repository),
+                    Keyring::parse(&keys)?,
+                )
+                .with_fields(config.encrypted_fields.value())
+                .with_tracer(Rc::clone(&tracer)),
+            );
+            key_rotation = Some(Rc::clone(&encrypting_repository) as Rc<dyn KeyRotation>);
+            encrypting_repository
+        }
+        None => Rc::clone(&shared_repository) as Rc<dyn ClientRepository>,
     };
+    let repository = Rc::new(TenantScopedClientRepository::new(
+        stored_repository,
+        Rc::clone(&tenant_directory),
+        config.tenant.value().clone(),
+    ));
+    if cli.sample {
+        unit_of_work.run(|| {
+            InMemoryClientRepository::new_with_clients()
+                .all()
+                .into_iter()
+                // 再起動のたびに同じサンプルが増えないよう，保存済みのものは飛ばす
+                .filter(|client| {
+                    repository
+                        .by_name_and_location(client.name(), client.location().as_str())
+                        .is_empty()
+                })
+                .try_for_each(|client| repository.save(client))
+        })?;
+    }
+    if let Some(Command::Seed {
+        count,
+        seed,
+        locale,
+    }) = cli.command
+    {
+        unit_of_work.run(|| {
+            DatasetGenerator::new(seed)
+                .generate(count, locale)
+                .into_iter()
+                .try_for_each(|client| repository.save(client))
+        })?;
+        println!("{}件のクライアントを生成しました．", count);
+        // メモリに生成したものは終了とともに消えるため，そのまま操作を始める
+        if *config.backend.value() != Backend::Memory {
+            return Ok(());
+        }
+    }
+
+    // イベントはアウトボックスを経由して，履歴とWebhook，指定された配信先に届ける
+    let event_publisher =
+        OutboxEventPublisher::new(traced(&outbox_repository, &tracer, "OutboxRepository"));
+    let event_history = Rc::new(InMemoryEventPublisher::new());
+    let webhook_dispatcher =
+        WebhookDispatcher::new(traced(&webhook_repository, &tracer, "WebhookRepository"));
+    // リポジトリの呼び出しはリクエストのスパンの子として記録する
+    let mut container = Container::new(
+        traced(&repository, &tracer, "ClientRepository"),
+        traced(&project_repository, &tracer, "ProjectRepository"),
+        traced(&relationship_repository, &tracer, "RelationshipRepository"),
+        traced(&outbox_repository, &tracer, "OutboxRepository"),
+        traced(&webhook_repository, &tracer, "WebhookRepository"),
+        traced(&user_repository, &tracer, "UserRepository"),
+        traced(&audit_repository, &tracer, "AuditRepository"),
+    )
+    .with_tenant(config.tenant.value().clone())
+    .with_uniqueness_policy(*config.uniqueness.value())
+    .with_idempotency_repo(traced(
+        &idempotency_repository,
+        &tracer,
+        "IdempotencyRepository",
+    ))
+    .with_tombstone_repo(traced(
+        &tombstone_repository,
+        &tracer,
+        "TombstoneRepository",
+    ))
+    .with_unit_of_work(unit_of_work)
+    .with_history_size(*config.history_size.value())
+    .with_event_publisher(Rc::new(event_publisher))
+    .with_event_sink(Rc::clone(&event_history) as Rc<dyn EventSink>)
+    .with_event_history(event_history)
+    .with_event_sink(Rc::new(webhook_dispatcher))
+    .with_delivery_retries(*config.delivery_retries.value())
+    .with_max_delivery_attempts(*config.max_delivery_attempts.value())
+    .with_delivery_backoff(Duration::milliseconds(
+        *config.delivery_backoff_ms.value() as i64
+    ));
+    if let Some(key_rotation) = key_rotation {
+        container = container.with_key_rotation(key_rotation);
+    }
+    for sink in config.sinks.value().iter() {
+        container = container.with_event_sink(sink.build()?);
+    }
 
-    app(repository)?;
+    // 集計のための数え上げはスパンに含めない
+    let client_count = ClientCountMiddleware::new(
+        metrics,
+        Rc::clone(&repository) as Rc<dyn ClientRepository>,
+        config.tenant.value().clone(),
+    );
+    let mediator = build_mediator(&container, &config, &tracer, client_count);
+    if let Some(Command::Create {
+        username,
+        name,
+        location,
+        idempotency_key,
+    }) = cli.command
+    {
+        let mut request = CreateClientUseCaseRequest::new(name, location);
+        if let Some(idempotency_key) = idempotency_key {
+            request = request.with_idempotency_key(idempotency_key);
+        }
+        return create(&mediator, username, request);
+    }
+    app(mediator, &config)?;
     Ok(())
 }
@@ -0,0 +1,287 @@
+use chrono::{DateTime, Utc};
+use std::fmt::Display;
+
+use crate::application::dtos::{
+    AuditEntryDto, ClientDto, ClientExportDto, ClientPersonalDataDto, DtoList, NoteDto,
+    OutboxMessageDto, ProjectDto, RelationshipDto, WebhookDeliveryDto,
+};
+use crate::domain::{ClientEvent, ContactKind};
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json_time(time: Option<DateTime<Utc>>) -> String {
+    match time {
+        Some(time) => format!(r#""{}""#, time.to_rfc3339()),
+        None => "null".to_string(),
+    }
+}
+
+fn json_array<I: IntoIterator<Item = String>>(items: I) -> String {
+    format!("[{}]", items.into_iter().collect::<Vec<_>>().join(","))
+}
+
+fn client_json(client: &ClientDto) -> String {
+    let contacts = client.contacts().iter().map(|contact| {
+        let kind = match contact.kind() {
+            ContactKind::Email => "email",
+            ContactKind::Phone => "phone",
+            ContactKind::PostalAddress => "address",
+        };
+        format!(
+            r#"{{"kind":"{}","value":{},"primary":{}}}"#,
+            kind,
+            json_string(&contact.info().to_string()),
+            contact.is_primary()
+        )
+    });
+    format!(
+        r#"{{"id":"{}","name":{},"location":{},"status":"{}","tags":{},"contacts":{}}}"#,
+        client.id().hyphenated(),
+        json_string(client.name()),
+        json_string(client.location()),
+        client.status(),
+        json_array(client.tags().into_iter().map(json_string)),
+        json_array(contacts)
+    )
+}
+
+fn note_json(note: &NoteDto) -> String {
+    format!(
+        r#"{{"id":"{}","body":{},"created_at":{},"updated_at":{}}}"#,
+        note.id().hyphenated(),
+        json_string(note.body()),
+        json_time(Some(note.created_at())),
+        json_time(note.updated_at())
+    )
+}
+
+fn project_json(project: &ProjectDto) -> String {
+    format!(
+        r#"{{"id":"{}","name":{},"status":"{}","created_at":{},"closed_at":{}}}"#,
+        project.id().hyphenated(),
+        json_string(project.name()),
+        project.status(),
+        json_time(Some(project.created_at())),
+        json_time(project.closed_at())
+    )
+}
+
+fn relationship_json(relationship: &RelationshipDto) -> String {
+    format!(
+        r#"{{"from":"{}","to":"{}","kind":"{}"}}"#,
+        relationship.source_id().hyphenated(),
+        relationship.target_id().hyphenated(),
+        relationship.kind()
+    )
+}
+
+fn event_json(message: &OutboxMessageDto) -> String {
+    let event = message.event();
+    let data = match event.event() {
+        ClientEvent::Created { name, location, .. }
+        | ClientEvent::Edited { name, location, .. } => {
+            format!(
+                r#"{{"name":{},"location":{}}}"#,
+                json_string(name),
+                json_string(location)
+            )
+        }
+        ClientEvent::StatusChanged { from, to, .. } => {
+            format!(r#"{{"from":"{}","to":"{}"}}"#, from, to)
+        }
+        ClientEvent::Deleted { .. } | ClientEvent::Erased { .. } => "{}".to_string(),
+    };
+    format!(
+        r#"{{"id":"{}","client_id":"{}","type":"{}","occurred_at":{},"delivered_at":{},"data":{}}}"#,
+        message.id().hyphenated(),
+        event.client_id().hyphenated(),
+        event.event().event_type(),
+        json_time(Some(event.event().occurred_at())),
+        json_time(message.delivered_at()),
+        data
+    )
+}
+
+fn delivery_json(delivery: &WebhookDeliveryDto) -> String {
+    let (status, error) = match delivery.result() {
+        Ok(status) => (status.to_string(), "null".to_string()),
+        Err(err) => ("null".to_string(), json_string(err)),
+    };
+    format!(
+        r#"{{"subscription_id":"{}","message_id":"{}","attempt":{},"status":{},"error":{},"attempted_at":{}}}"#,
+        delivery.subscription_id().hyphenated(),
+        delivery.message_id().hyphenated(),
+        delivery.attempt(),
+        status,
+        error,
+        json_time(Some(delivery.attempted_at()))
+    )
+}
+
+fn audit_entry_json(entry: &AuditEntryDto) -> String {
+    format!(
+        r#"{{"username":{},"action":{},"occurred_at":{}}}"#,
+        json_string(entry.username()),
+        json_string(entry.action()),
+        json_time(Some(entry.occurred_at()))
+    )
+}
+
+/// ファイルに書き出すJSONの文書
+impl Display for ClientExportDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            r#"{{"tenant":{},"exported_at":"{}","clients":{}}}"#,
+            json_string(self.tenant()),
+            self.exported_at().to_rfc3339(),
+            json_array(self.clients().iter().map(client_json))
+        )
+    }
+}
+
+/// 一覧をJSONで表示するときの配列
+pub fn clients_to_json(clients: &DtoList<ClientDto>) -> String {
+    json_array(clients.iter().map(client_json))
+}
+
+/// 本人に渡すJSONの文書
+impl Display for ClientPersonalDataDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            r#"{{"exported_at":{},"client":{},"notes":{},"projects":{},"relationships":{},"events":{},"webhook_deliveries":{},"idempotency_keys":{},"audit_log":{}}}"#,
+            json_time(Some(self.exported_at())),
+            client_json(self.client()),
+            json_array(self.notes().iter().map(note_json)),
+            json_array(self.projects().iter().map(project_json)),
+            json_array(self.relationships().iter().map(relationship_json)),
+            json_array(self.events().iter().map(event_json)),
+            json_array(self.deliveries().iter().map(delivery_json)),
+            json_array(self.idempotency_keys().iter().map(|key| json_string(key))),
+            json_array(self.audit_entries().iter().map(audit_entry_json))
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::clients_to_json;
+    use crate::application::dtos::{
+        AuditEntryDto, ClientDto, ClientExportDto, ClientPersonalDataDto, DtoList, NoteDto,
+        OutboxMessageDto, ProjectDto, RelationshipDto, WebhookDeliveryDto,
+    };
+    use crate::domain::{
+        AuditEntry, Client, ClientEvent, ContactInfo, ContactKind, OutboxMessage, Project,
+        RelationKind, Relationship, Role, Tag, TenantId, User, WebhookDelivery,
+    };
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    #[test]
+    fn client_export_dto_print() {
+        let mut client = Client::new("Taro \"T\"".to_string(), "Tokyo".to_string());
+        client.add_tag(Tag::new("vip").unwrap());
+        client.add_contact(ContactInfo::parse(ContactKind::Email, "taro@example.com").unwrap());
+        let exported_at = Utc::now();
+        let export = ClientExportDto::new(
+            TenantId::new("sales").unwrap(),
+            DtoList::new(vec![ClientDto::from(client.clone())]),
+            exported_at,
+        );
+        assert_eq!(
+            export.to_string(),
+            format!(
+                "{{\"tenant\":\"sales\",\"exported_at\":\"{}\",\"clients\":[{{\"id\":\"{}\",\"name\":\"Taro \\\"T\\\"\",\"location\":\"Tokyo\",\"status\":\"prospect\",\"tags\":[\"vip\"],\"contacts\":[{{\"kind\":\"email\",\"value\":\"taro@example.com\",\"primary\":true}}]}}]}}\n",
+                exported_at.to_rfc3339(),
+                client.id().hyphenated()
+            )
+        );
+        assert_eq!(
+            clients_to_json(export.clients()),
+            format!(
+                "[{{\"id\":\"{}\",\"name\":\"Taro \\\"T\\\"\",\"location\":\"Tokyo\",\"status\":\"prospect\",\"tags\":[\"vip\"],\"contacts\":[{{\"kind\":\"email\",\"value\":\"taro@example.com\",\"primary\":true}}]}}]",
+                client.id().hyphenated()
+            )
+        );
+    }
+
+    #[test]
+    fn client_personal_data_dto_print() {
+        let now = Utc::now();
+        let mut client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let note_id = client.add_note("prefers email", now).unwrap();
+        let other_id = Uuid::new_v4();
+        let project = Project::new(client.id(), "Website", now).unwrap();
+        let relationship =
+            Relationship::new(client.id(), other_id, RelationKind::ReferredBy).unwrap();
+        let mut created = OutboxMessage::new(ClientEvent::created(&client, now));
+        created.redact();
+        let delivery = WebhookDelivery::new(
+            Uuid::nil(),
+            created.id(),
+            "client.created",
+            1,
+            Err("timed out".to_string()),
+            now,
+        );
+        let personal_data = ClientPersonalDataDto::new(
+            ClientDto::from(client.clone()),
+            DtoList::new(vec![NoteDto::from(client.note(note_id).unwrap().clone())]),
+            DtoList::new(vec![ProjectDto::from(project.clone())]),
+            DtoList::new(vec![RelationshipDto::from(relationship)]),
+            DtoList::new(vec![OutboxMessageDto::from(created.clone())]),
+            DtoList::new(vec![WebhookDeliveryDto::from(delivery)]),
+            vec!["create-1".to_string()],
+            DtoList::new(vec![AuditEntryDto::from(AuditEntry::new(
+                &User::new("admin", "correct horse", Role::Admin).unwrap(),
+                "AddNoteUseCaseRequest",
+                Some(client.id()),
+                now,
+            ))]),
+            now,
+        );
+        let time = now.to_rfc3339();
+        let client_id = client.id().hyphenated();
+        assert_eq!(
+            personal_data.to_string(),
+            format!(
+                concat!(
+                    r#"{{"exported_at":"{time}","#,
+                    r#""client":{{"id":"{client_id}","name":"Taro","location":"Tokyo","status":"prospect","tags":[],"contacts":[]}},"#,
+                    r#""notes":[{{"id":"{note_id}","body":"prefers email","created_at":"{time}","updated_at":null}}],"#,
+                    r#""projects":[{{"id":"{project_id}","name":"Website","status":"open","created_at":"{time}","closed_at":null}}],"#,
+                    r#""relationships":[{{"from":"{client_id}","to":"{other_id}","kind":"referred_by"}}],"#,
+                    r#""events":[{{"id":"{message_id}","client_id":"{client_id}","type":"client.created","occurred_at":"{time}","delivered_at":null,"data":{{"name":"[erased]","location":"[erased]"}}}}],"#,
+                    r#""webhook_deliveries":[{{"subscription_id":"{nil}","message_id":"{message_id}","attempt":1,"status":null,"error":"timed out","attempted_at":"{time}"}}],"#,
+                    r#""idempotency_keys":["create-1"],"#,
+                    r#""audit_log":[{{"username":"admin","action":"AddNoteUseCaseRequest","occurred_at":"{time}"}}]}}"#,
+                    "\n"
+                ),
+                time = time,
+                client_id = client_id,
+                note_id = note_id.hyphenated(),
+                project_id = project.id().hyphenated(),
+                other_id = other_id.hyphenated(),
+                message_id = created.id().hyphenated(),
+                nil = Uuid::nil().hyphenated(),
+            )
+        );
+    }
+}
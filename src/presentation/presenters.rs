@@ -1,16 +1,52 @@
+use chrono::{DateTime, Local, Utc};
 use std::fmt::Display;
 
-use crate::application::dtos::{ClientDto, DtoList};
+use crate::application::dtos::{
+    AuditEntryDto, ClientChangeDto, ClientDto, ClientEventDto, ClientSummaryDto, DtoList,
+    DuplicateGroupDto, EditHistoryDto, ErasureReportDto, HierarchyDto, LocationCountDto, NoteDto,
+    OutboxMessageDto, ProjectDto, RegionGroupDto, RelayReportDto, TagCountDto, UserDto,
+    WebhookDeliveryDto, WebhookSubscriptionDto,
+};
+use crate::application::history::ClientChange;
+use crate::domain::{ClientEvent, ContactKind};
+
+fn contact_kind_label(kind: ContactKind) -> &'static str {
+    match kind {
+        ContactKind::Email => "email",
+        ContactKind::Phone => "phone",
+        ContactKind::PostalAddress => "address",
+    }
+}
 
 impl Display for ClientDto {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Client #{}: {}, from {}",
+            "Client #{}: {}, from {} ({})",
             self.id().hyphenated(),
             self.name(),
-            self.location()
-        )
+            self.location(),
+            self.status()
+        )?;
+        let tags = self.tags();
+        if !tags.is_empty() {
+            write!(f, " [{}]", tags.join(", "))?;
+        }
+        for contact in self.contacts() {
+            write!(
+                f,
+                "\n  Contact #{}: {} {}{}",
+                contact.id().hyphenated(),
+                contact_kind_label(contact.kind()),
+                contact.info(),
+                if contact.is_primary() {
+                    " (primary)"
+                } else {
+                    ""
+                }
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -30,13 +66,613 @@ impl Display for DtoList<ClientDto> {
     }
 }
 
+impl Display for DuplicateGroupDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Duplicate candidates (score {:.2})", self.score())?;
+        for client_dto in self.clients().iter() {
+            writeln!(f, "  {}", client_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DtoList<DuplicateGroupDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No duplicate candidates");
+        }
+
+        writeln!(f, "Duplicate candidate list")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for group_dto in self.iter() {
+            writeln!(f, "{}", group_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for RegionGroupDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.region(), self.country()) {
+            (Some(region), Some(country)) => write!(f, "{} ({})", region, country)?,
+            (None, Some(country)) => write!(f, "{}", country)?,
+            _ => write!(f, "Unknown region")?,
+        }
+        writeln!(f, ": {} clients", self.clients().len())?;
+        for client_dto in self.clients().iter() {
+            writeln!(f, "  {}", client_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DtoList<RegionGroupDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No clients");
+        }
+
+        writeln!(f, "Clients by region")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for group_dto in self.iter() {
+            writeln!(f, "{}", group_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TagCountDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.tag(), self.count())
+    }
+}
+
+impl Display for DtoList<TagCountDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No tags");
+        }
+
+        writeln!(f, "Tag counts")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for tag_count_dto in self.iter() {
+            writeln!(f, "{}", tag_count_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for NoteDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Note #{} ({}): {}",
+            self.id().hyphenated(),
+            format_timestamp(self.created_at()),
+            self.body()
+        )?;
+        if let Some(updated_at) = self.updated_at() {
+            write!(f, " (edited {})", format_timestamp(updated_at))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DtoList<NoteDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No notes");
+        }
+
+        writeln!(f, "Notes")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for note_dto in self.iter() {
+            writeln!(f, "{}", note_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ProjectDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Project #{}: {} ({}), client #{}, opened {}",
+            self.id().hyphenated(),
+            self.name(),
+            self.status(),
+            self.client_id().hyphenated(),
+            format_timestamp(self.created_at())
+        )?;
+        if let Some(closed_at) = self.closed_at() {
+            write!(f, ", closed {}", format_timestamp(closed_at))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DtoList<ProjectDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No projects");
+        }
+
+        writeln!(f, "Project list")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for project_dto in self.iter() {
+            writeln!(f, "{}", project_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for HierarchyDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Hierarchy")?;
+        writeln!(f, "----------------------------------------\n")?;
+        write_hierarchy_node(f, self, 0)
+    }
+}
+
+// 子会社を字下げして表示し，起点のクライアントに印を付ける
+fn write_hierarchy_node(
+    f: &mut std::fmt::Formatter<'_>,
+    node: &HierarchyDto,
+    depth: usize,
+) -> std::fmt::Result {
+    writeln!(
+        f,
+        "{}- {} #{}{}",
+        "  ".repeat(depth),
+        node.client().name(),
+        node.client().id().hyphenated(),
+        if node.is_focused() { " *" } else { "" }
+    )?;
+    for child in node.children() {
+        write_hierarchy_node(f, child, depth + 1)?;
+    }
+    Ok(())
+}
+
+impl Display for ClientEventDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.event() {
+            ClientEvent::Created {
+                client_id,
+                name,
+                location,
+                occurred_at,
+            } => write!(
+                f,
+                "[{}] Client #{}: created as {}, from {}",
+                format_timestamp(*occurred_at),
+                client_id.hyphenated(),
+                name,
+                location
+            ),
+            ClientEvent::Edited {
+                client_id,
+                name,
+                location,
+                occurred_at,
+            } => write!(
+                f,
+                "[{}] Client #{}: edited to {}, from {}",
+                format_timestamp(*occurred_at),
+                client_id.hyphenated(),
+                name,
+                location
+            ),
+            ClientEvent::Deleted {
+                client_id,
+                occurred_at,
+            } => write!(
+                f,
+                "[{}] Client #{}: deleted",
+                format_timestamp(*occurred_at),
+                client_id.hyphenated()
+            ),
+            ClientEvent::StatusChanged {
+                client_id,
+                from,
+                to,
+                occurred_at,
+            } => write!(
+                f,
+                "[{}] Client #{}: status changed from {} to {}",
+                format_timestamp(*occurred_at),
+                client_id.hyphenated(),
+                from,
+                to
+            ),
+            ClientEvent::Erased {
+                client_id,
+                occurred_at,
+            } => write!(
+                f,
+                "[{}] Client #{}: personal data erased",
+                format_timestamp(*occurred_at),
+                client_id.hyphenated()
+            ),
+        }
+    }
+}
+
+impl Display for DtoList<ClientEventDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No events");
+        }
+
+        writeln!(f, "Event history")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for event_dto in self.iter() {
+            writeln!(f, "{}", event_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for OutboxMessageDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.delivered_at(), self.dead_lettered_at()) {
+            (Some(delivered_at), _) => write!(
+                f,
+                "Message #{} (delivered {}, {} attempts)",
+                self.id().hyphenated(),
+                format_timestamp(delivered_at),
+                self.attempts()
+            )?,
+            (None, Some(dead_lettered_at)) => write!(
+                f,
+                "Message #{} (dead letter since {}, {} attempts)",
+                self.id().hyphenated(),
+                format_timestamp(dead_lettered_at),
+                self.attempts()
+            )?,
+            (None, None) => write!(
+                f,
+                "Message #{} (pending, {} attempts)",
+                self.id().hyphenated(),
+                self.attempts()
+            )?,
+        }
+        write!(f, "\n  {}", self.event())?;
+        if self.delivered_at().is_none() && !self.delivered_to().is_empty() {
+            write!(f, "\n  delivered to: {}", self.delivered_to().join(", "))?;
+        }
+        if let Some(last_error) = self.last_error() {
+            write!(f, "\n  last error: {}", last_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DtoList<OutboxMessageDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No messages");
+        }
+
+        writeln!(f, "Outbox")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for message_dto in self.iter() {
+            writeln!(f, "{}", message_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for RelayReportDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Delivered {} messages, {} failed",
+            self.delivered(),
+            self.failed()
+        )?;
+        if self.dead_lettered() > 0 {
+            write!(f, ", {} moved to dead letters", self.dead_lettered())?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ErasureReportDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Erased client #{}: closed {} projects, removed {} relationships, redacted {} events and {} audit entries, forgot {} changes",
+            self.client_id().hyphenated(),
+            self.closed_projects(),
+            self.relationships(),
+            self.redacted_events(),
+            self.redacted_audit_entries(),
+            self.forgotten_changes()
+        )
+    }
+}
+
+impl Display for ClientSummaryDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#{}: {}, from {} ({}, {} tags)",
+            self.id().hyphenated(),
+            self.name(),
+            self.location(),
+            self.status(),
+            self.tag_count()
+        )
+    }
+}
+
+impl Display for DtoList<ClientSummaryDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No clients");
+        }
+
+        writeln!(f, "Client summaries")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for summary_dto in self.iter() {
+            writeln!(f, "{}", summary_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for LocationCountDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location(), self.count())
+    }
+}
+
+impl Display for DtoList<LocationCountDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No clients");
+        }
+
+        writeln!(f, "Clients per location")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for location_count_dto in self.iter() {
+            writeln!(f, "{}", location_count_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for WebhookSubscriptionDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Webhook #{}: {} [{}]",
+            self.id().hyphenated(),
+            self.url(),
+            self.event_types().join(", ")
+        )
+    }
+}
+
+impl Display for DtoList<WebhookSubscriptionDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No webhooks");
+        }
+
+        writeln!(f, "Webhooks")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for subscription_dto in self.iter() {
+            writeln!(f, "{}", subscription_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for WebhookDeliveryDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} to #{} (attempt {}): ",
+            format_timestamp(self.attempted_at()),
+            self.event_type(),
+            self.subscription_id().hyphenated(),
+            self.attempt()
+        )?;
+        match self.result() {
+            Ok(status) => write!(f, "{}", status),
+            Err(err) => write!(f, "failed: {}", err),
+        }
+    }
+}
+
+impl Display for DtoList<WebhookDeliveryDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No deliveries");
+        }
+
+        writeln!(f, "Webhook deliveries")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for delivery_dto in self.iter() {
+            writeln!(f, "{}", delivery_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for UserDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "User #{}: {} ({})",
+            self.id().hyphenated(),
+            self.username(),
+            self.role()
+        )
+    }
+}
+
+impl Display for DtoList<UserDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No users");
+        }
+
+        writeln!(f, "Users")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for user_dto in self.iter() {
+            writeln!(f, "{}", user_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for AuditEntryDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {}",
+            format_timestamp(self.occurred_at()),
+            self.username(),
+            self.action()
+        )?;
+        match self.subject_id() {
+            Some(subject_id) => write!(f, " #{}", subject_id.hyphenated()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for DtoList<AuditEntryDto> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No audit entries");
+        }
+
+        writeln!(f, "Audit log")?;
+        writeln!(f, "----------------------------------------\n")?;
+
+        for entry_dto in self.iter() {
+            writeln!(f, "{}", entry_dto)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ClientChangeDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let id = self.client_id().hyphenated();
+        let (client, relationships) = match self.change() {
+            ClientChange::Created {
+                client,
+                relationships,
+            } => {
+                write!(f, "Create client #{}: ", id)?;
+                (client, relationships)
+            }
+            ClientChange::Edited {
+                before: (before_name, before_location),
+                after: (after_name, after_location),
+                ..
+            } => {
+                return write!(
+                    f,
+                    "Edit client #{}: {}, from {} -> {}, from {}",
+                    id, before_name, before_location, after_name, after_location
+                );
+            }
+            ClientChange::Deleted {
+                client,
+                relationships,
+            } => {
+                write!(f, "Delete client #{}: ", id)?;
+                (client, relationships)
+            }
+            ClientChange::Merged { after, merged, .. } => {
+                return write!(
+                    f,
+                    "Merge {} clients into client #{}: {}, from {}",
+                    merged.len(),
+                    id,
+                    after.name(),
+                    after.location()
+                );
+            }
+            ClientChange::Unmerged {
+                after, restored, ..
+            } => {
+                return write!(
+                    f,
+                    "Unmerge {} clients from client #{}: {}, from {}",
+                    restored.len(),
+                    id,
+                    after.name(),
+                    after.location()
+                );
+            }
+        };
+        write!(f, "{}, from {}", client.name(), client.location())?;
+        if !relationships.is_empty() {
+            write!(f, " with {} relationships", relationships.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for EditHistoryDto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.undo() {
+            Some(change) => writeln!(f, "Undo ({}): {}", self.undo_count(), change)?,
+            None => writeln!(f, "Undo: nothing")?,
+        }
+        match self.redo() {
+            Some(change) => write!(f, "Redo ({}): {}", self.redo_count(), change),
+            None => write!(f, "Redo: nothing"),
+        }
+    }
+}
+
+// 表示はローカル時刻で行う
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp
+        .with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
 #[cfg(test)]
 mod test {
-    use crate::application::dtos::{ClientDto, DtoList};
-    use crate::domain::Client;
+    use crate::application::dtos::{
+        AuditEntryDto, ClientDto, ClientEventDto, ClientSummaryDto, DtoList, DuplicateGroupDto,
+        EditHistoryDto, ErasureReportDto, HierarchyDto, LocationCountDto, NoteDto,
+        OutboxMessageDto, ProjectDto, RegionGroupDto, RelayReportDto, TagCountDto, UserDto,
+        WebhookDeliveryDto, WebhookSubscriptionDto,
+    };
+    use crate::application::history::ClientChange;
+    use crate::domain::{
+        AuditEntry, Client, ClientEvent, ClientStatus, ContactInfo, ContactKind, DuplicateGroup,
+        OutboxMessage, Project, RelationKind, Relationship, Role, Tag, User, WebhookDelivery,
+        WebhookSubscription,
+    };
+    use chrono::{Local, Utc};
     use fake::faker::{address::en::CityName, name::en::Name};
-    use fake::Fake;
+    use fake::{Fake, Faker};
     use std::fmt::Write;
+    use uuid::Uuid;
 
     #[test]
     fn client_dto_print() {
@@ -44,7 +680,25 @@ mod test {
         assert_eq!(
             client_dto.to_string(),
             format!(
-                "Client #{}: {}, from {}",
+                "Client #{}: {}, from {} (prospect)",
+                client_dto.id().hyphenated(),
+                client_dto.name(),
+                client_dto.location()
+            )
+        );
+    }
+
+    #[test]
+    fn client_dto_with_tags_print() {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        client.add_tag(Tag::new("vip").unwrap());
+        client.add_tag(Tag::new("prospect").unwrap());
+        let client_dto: ClientDto = client.into();
+
+        assert_eq!(
+            client_dto.to_string(),
+            format!(
+                "Client #{}: {}, from {} (prospect) [prospect, vip]",
                 client_dto.id().hyphenated(),
                 client_dto.name(),
                 client_dto.location()
@@ -52,6 +706,266 @@ mod test {
         );
     }
 
+    #[test]
+    fn tag_count_dto_list_print() {
+        let tag_counts = DtoList::new(vec![
+            TagCountDto::new("vip".to_string(), 2),
+            TagCountDto::new("prospect".to_string(), 1),
+        ]);
+        assert_eq!(
+            tag_counts.to_string(),
+            "Tag counts\n----------------------------------------\n\nvip: 2\nprospect: 1\n"
+        );
+
+        let tag_counts: DtoList<TagCountDto> = DtoList::new(Vec::new());
+        assert_eq!(tag_counts.to_string(), "No tags\n");
+    }
+
+    #[test]
+    fn outbox_message_dto_list_print() {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        let event = client
+            .change_status(ClientStatus::Active, Utc::now())
+            .unwrap();
+        let mut message = OutboxMessage::new(event.clone());
+        message.mark_delivered_to("history".to_string());
+        message.mark_failed("stdout: closed".to_string());
+        let event_dto: ClientEventDto = event.into();
+        let pending_dto: OutboxMessageDto = message.clone().into();
+
+        let dead_lettered_at = Utc::now();
+        let mut dead_letter = message.clone();
+        dead_letter.mark_dead_lettered(dead_lettered_at);
+        let dead_letter_dto: OutboxMessageDto = dead_letter.into();
+
+        let delivered_at = Utc::now();
+        message.mark_delivered_to("stdout".to_string());
+        message.mark_delivered(delivered_at);
+        let delivered_dto: OutboxMessageDto = message.clone().into();
+
+        let messages = DtoList::new(vec![pending_dto, dead_letter_dto, delivered_dto]);
+        assert_eq!(
+            messages.to_string(),
+            format!(
+                "Outbox\n----------------------------------------\n\nMessage #{id} (pending, 1 attempts)\n  {event}\n  delivered to: history\n  last error: stdout: closed\nMessage #{id} (dead letter since {dead_lettered_at}, 1 attempts)\n  {event}\n  delivered to: history\n  last error: stdout: closed\nMessage #{id} (delivered {delivered_at}, 2 attempts)\n  {event}\n",
+                id = message.id().hyphenated(),
+                event = event_dto,
+                dead_lettered_at = dead_lettered_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                delivered_at = delivered_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            )
+        );
+
+        let messages: DtoList<OutboxMessageDto> = DtoList::new(Vec::new());
+        assert_eq!(messages.to_string(), "No messages\n");
+
+        assert_eq!(
+            RelayReportDto::new(2, 1, 0).to_string(),
+            "Delivered 2 messages, 1 failed"
+        );
+        assert_eq!(
+            RelayReportDto::new(0, 1, 1).to_string(),
+            "Delivered 0 messages, 1 failed, 1 moved to dead letters"
+        );
+    }
+
+    #[test]
+    fn erasure_report_dto_print() {
+        let id = Uuid::new_v4();
+        assert_eq!(
+            ErasureReportDto::new(id, 1, 1, 3, 4, 2).to_string(),
+            format!(
+                "Erased client #{}: closed 1 projects, removed 1 relationships, redacted 3 events and 4 audit entries, forgot 2 changes",
+                id.hyphenated()
+            )
+        );
+    }
+
+    #[test]
+    fn edit_history_dto_print() {
+        let before = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let mut after = before.clone();
+        after.edit("Jiro".to_string(), "Osaka".to_string());
+        let related = Client::new(Name().fake(), CityName().fake());
+        let relationship =
+            Relationship::new(related.id(), before.id(), RelationKind::SubsidiaryOf).unwrap();
+
+        let history = EditHistoryDto::new(
+            Some(ClientChange::edited(&after, &before).into()),
+            Some(ClientChange::deleted(&before, vec![relationship]).into()),
+            2,
+            1,
+        );
+        assert_eq!(
+            history.to_string(),
+            format!(
+                "Undo (2): Edit client #{id}: Jiro, from Osaka -> Taro, from Tokyo\nRedo (1): Delete client #{id}: Taro, from Tokyo with 1 relationships",
+                id = before.id().hyphenated()
+            )
+        );
+
+        let history = EditHistoryDto::new(Some(ClientChange::created(&before).into()), None, 1, 0);
+        assert_eq!(
+            history.to_string(),
+            format!(
+                "Undo (1): Create client #{}: Taro, from Tokyo\nRedo: nothing",
+                before.id().hyphenated()
+            )
+        );
+        assert_eq!(
+            EditHistoryDto::new(None, None, 0, 0).to_string(),
+            "Undo: nothing\nRedo: nothing"
+        );
+    }
+
+    #[test]
+    fn webhook_dto_list_print() {
+        let subscription = WebhookSubscription::new(
+            "http://localhost:8080/hooks",
+            &["client.deleted".to_string(), "client.created".to_string()],
+            "secret",
+        )
+        .unwrap();
+        let subscriptions = DtoList::new(vec![WebhookSubscriptionDto::from(subscription.clone())]);
+        assert_eq!(
+            subscriptions.to_string(),
+            format!(
+                "Webhooks\n----------------------------------------\n\nWebhook #{}: http://localhost:8080/hooks [client.created, client.deleted]\n",
+                subscription.id().hyphenated()
+            )
+        );
+        let subscriptions: DtoList<WebhookSubscriptionDto> = DtoList::new(Vec::new());
+        assert_eq!(subscriptions.to_string(), "No webhooks\n");
+
+        let attempted_at = Utc::now();
+        let message_id = Uuid::new_v4();
+        let deliveries = DtoList::new(vec![
+            WebhookDeliveryDto::from(WebhookDelivery::new(
+                subscription.id(),
+                message_id,
+                "client.created",
+                1,
+                Err("connection refused".to_string()),
+                attempted_at,
+            )),
+            WebhookDeliveryDto::from(WebhookDelivery::new(
+                subscription.id(),
+                message_id,
+                "client.created",
+                2,
+                Ok(204),
+                attempted_at,
+            )),
+        ]);
+        assert_eq!(
+            deliveries.to_string(),
+            format!(
+                "Webhook deliveries\n----------------------------------------\n\n{at} client.created to #{id} (attempt 1): failed: connection refused\n{at} client.created to #{id} (attempt 2): 204\n",
+                at = attempted_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                id = subscription.id().hyphenated(),
+            )
+        );
+        let deliveries: DtoList<WebhookDeliveryDto> = DtoList::new(Vec::new());
+        assert_eq!(deliveries.to_string(), "No deliveries\n");
+    }
+
+    #[test]
+    fn user_and_audit_entry_dto_list_print() {
+        let user = User::new("hanako", "correct horse", Role::Editor).unwrap();
+        let users = DtoList::new(vec![UserDto::from(user.clone())]);
+        assert_eq!(
+            users.to_string(),
+            format!(
+                "Users\n----------------------------------------\n\nUser #{}: hanako (editor)\n",
+                user.id().hyphenated()
+            )
+        );
+        let users: DtoList<UserDto> = DtoList::new(Vec::new());
+        assert_eq!(users.to_string(), "No users\n");
+
+        let occurred_at = Utc::now();
+        let subject_id = Uuid::new_v4();
+        let entries = DtoList::new(vec![
+            AuditEntryDto::from(AuditEntry::new(
+                &user,
+                "CreateClientUseCaseRequest",
+                None,
+                occurred_at,
+            )),
+            AuditEntryDto::from(AuditEntry::new(
+                &user,
+                "DeleteClientUseCaseRequest",
+                Some(subject_id),
+                occurred_at,
+            )),
+        ]);
+        let timestamp = occurred_at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+        assert_eq!(
+            entries.to_string(),
+            format!(
+                "Audit log\n----------------------------------------\n\n{} hanako: CreateClientUseCaseRequest\n{} hanako: DeleteClientUseCaseRequest #{}\n",
+                timestamp,
+                timestamp,
+                subject_id.hyphenated()
+            )
+        );
+        let entries: DtoList<AuditEntryDto> = DtoList::new(Vec::new());
+        assert_eq!(entries.to_string(), "No audit entries\n");
+    }
+
+    #[test]
+    fn client_summary_dto_list_print() {
+        let mut client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        client.add_tag(Tag::new("vip").unwrap());
+        let summaries = DtoList::new(vec![ClientSummaryDto::from(&client)]);
+        assert_eq!(
+            summaries.to_string(),
+            format!(
+                "Client summaries\n----------------------------------------\n\n#{}: Taro, from Tokyo (prospect, 1 tags)\n",
+                client.id().hyphenated()
+            )
+        );
+
+        let summaries: DtoList<ClientSummaryDto> = DtoList::new(Vec::new());
+        assert_eq!(summaries.to_string(), "No clients\n");
+    }
+
+    #[test]
+    fn location_count_dto_list_print() {
+        let location_counts = DtoList::new(vec![
+            LocationCountDto::new("Osaka".to_string(), 1),
+            LocationCountDto::new("Tokyo".to_string(), 2),
+        ]);
+        assert_eq!(
+            location_counts.to_string(),
+            "Clients per location\n----------------------------------------\n\nOsaka: 1\nTokyo: 2\n"
+        );
+    }
+
+    #[test]
+    fn client_dto_with_contacts_print() {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        let email_id =
+            client.add_contact(ContactInfo::parse(ContactKind::Email, "taro@example.com").unwrap());
+        let phone_id =
+            client.add_contact(ContactInfo::parse(ContactKind::Phone, "03-1234-5678").unwrap());
+        let phone2_id =
+            client.add_contact(ContactInfo::parse(ContactKind::Phone, "090-1234-5678").unwrap());
+        let client_dto: ClientDto = client.into();
+
+        assert_eq!(
+            client_dto.to_string(),
+            format!(
+                "Client #{}: {}, from {} (prospect)\n  Contact #{}: email taro@example.com (primary)\n  Contact #{}: phone +81312345678 (primary)\n  Contact #{}: phone +819012345678",
+                client_dto.id().hyphenated(),
+                client_dto.name(),
+                client_dto.location(),
+                email_id.hyphenated(),
+                phone_id.hyphenated(),
+                phone2_id.hyphenated(),
+            )
+        );
+    }
+
     #[test]
     fn client_dto_list_empty_print() {
         let client_dto_list: DtoList<ClientDto> = DtoList::new(Vec::new());
@@ -78,4 +992,175 @@ mod test {
 
         assert_eq!(expected_string, dto_lists.to_string());
     }
+
+    #[test]
+    fn duplicate_group_dto_print() {
+        let clients = (0..2)
+            .map(|_| Client::new(Name().fake(), CityName().fake()))
+            .collect::<Vec<_>>();
+        let group_dto: DuplicateGroupDto = DuplicateGroup {
+            clients: clients.clone(),
+            score: 0.9,
+        }
+        .into();
+
+        let mut expected_string = String::new();
+        writeln!(expected_string, "Duplicate candidates (score 0.90)").unwrap();
+        for client in clients {
+            writeln!(expected_string, "  {}", ClientDto::from(client)).unwrap();
+        }
+        assert_eq!(expected_string, group_dto.to_string());
+
+        let group_dto_list: DtoList<DuplicateGroupDto> = DtoList::new(Vec::new());
+        assert_eq!(group_dto_list.to_string(), "No duplicate candidates\n");
+    }
+
+    #[test]
+    fn region_group_dto_print() {
+        let client_dto: ClientDto = Client::new(Name().fake(), "東京".to_string()).into();
+        let group_dto = RegionGroupDto::new(
+            Some("JP".to_string()),
+            Some("東京都".to_string()),
+            DtoList::new(vec![client_dto.clone()]),
+        );
+        assert_eq!(
+            group_dto.to_string(),
+            format!("東京都 (JP): 1 clients\n  {}\n", client_dto)
+        );
+
+        let group_dto = RegionGroupDto::new(None, None, DtoList::new(Vec::new()));
+        assert_eq!(group_dto.to_string(), "Unknown region: 0 clients\n");
+    }
+
+    #[test]
+    fn note_dto_list_print() {
+        let mut client = Client::new(Name().fake(), CityName().fake());
+        let created_at = Utc::now();
+        let note_id = client.add_note("wants quote", created_at).unwrap();
+        let note_dto: NoteDto = client.note(note_id).unwrap().clone().into();
+        let timestamp = created_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string();
+        assert_eq!(
+            note_dto.to_string(),
+            format!(
+                "Note #{} ({}): wants quote",
+                note_id.hyphenated(),
+                timestamp
+            )
+        );
+
+        client.edit_note(note_id, "sent quote", created_at).unwrap();
+        let notes: DtoList<NoteDto> = client
+            .notes()
+            .into_iter()
+            .cloned()
+            .map(Into::into)
+            .collect();
+        assert_eq!(
+            notes.to_string(),
+            format!(
+                "Notes\n----------------------------------------\n\nNote #{} ({}): sent quote (edited {})\n",
+                note_id.hyphenated(),
+                timestamp,
+                timestamp
+            )
+        );
+
+        let notes: DtoList<NoteDto> = DtoList::new(Vec::new());
+        assert_eq!(notes.to_string(), "No notes\n");
+    }
+
+    #[test]
+    fn client_event_dto_list_print() {
+        let mut client = Client::new("Taro".to_string(), "Tokyo".to_string());
+        let changed_at = Utc::now();
+        let created = ClientEvent::created(&client, changed_at);
+        let event = client
+            .change_status(ClientStatus::Active, changed_at)
+            .unwrap();
+        client.edit("Jiro".to_string(), "Osaka".to_string());
+        let edited = ClientEvent::edited(&client, changed_at);
+        let deleted = ClientEvent::deleted(client.id(), changed_at);
+        let events: DtoList<ClientEventDto> = DtoList::new(vec![
+            created.into(),
+            event.into(),
+            edited.into(),
+            deleted.into(),
+        ]);
+        assert_eq!(
+            events.to_string(),
+            format!(
+                "Event history\n----------------------------------------\n\n[{at}] Client #{id}: created as Taro, from Tokyo\n[{at}] Client #{id}: status changed from prospect to active\n[{at}] Client #{id}: edited to Jiro, from Osaka\n[{at}] Client #{id}: deleted\n",
+                at = changed_at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                id = client.id().hyphenated()
+            )
+        );
+
+        let events: DtoList<ClientEventDto> = DtoList::new(Vec::new());
+        assert_eq!(events.to_string(), "No events\n");
+    }
+
+    #[test]
+    fn project_dto_list_print() {
+        let client_id = Faker.fake::<Uuid>();
+        let now = Utc::now();
+        let timestamp = now.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+        let mut project = Project::new(client_id, "Website renewal", now).unwrap();
+        let project_dto: ProjectDto = project.clone().into();
+        assert_eq!(
+            project_dto.to_string(),
+            format!(
+                "Project #{}: Website renewal (open), client #{}, opened {}",
+                project.id().hyphenated(),
+                client_id.hyphenated(),
+                timestamp
+            )
+        );
+
+        project.close(now).unwrap();
+        let projects: DtoList<ProjectDto> = DtoList::new(vec![project.clone().into()]);
+        assert_eq!(
+            projects.to_string(),
+            format!(
+                "Project list\n----------------------------------------\n\nProject #{}: Website renewal (closed), client #{}, opened {}, closed {}\n",
+                project.id().hyphenated(),
+                client_id.hyphenated(),
+                timestamp,
+                timestamp
+            )
+        );
+
+        let projects: DtoList<ProjectDto> = DtoList::new(Vec::new());
+        assert_eq!(projects.to_string(), "No projects\n");
+    }
+
+    #[test]
+    fn hierarchy_dto_print() {
+        let parent: ClientDto = Client::new("Holdings".to_string(), "Tokyo".to_string()).into();
+        let child: ClientDto = Client::new("Tokyo Branch".to_string(), "Tokyo".to_string()).into();
+        let grandchild: ClientDto =
+            Client::new("Shibuya Office".to_string(), "Tokyo".to_string()).into();
+        let ids = [parent.id(), child.id(), grandchild.id()];
+
+        let hierarchy = HierarchyDto::new(
+            parent,
+            false,
+            vec![HierarchyDto::new(
+                child,
+                true,
+                vec![HierarchyDto::new(grandchild, false, Vec::new())],
+            )],
+        );
+        assert_eq!(
+            hierarchy.to_string(),
+            format!(
+                "Hierarchy\n----------------------------------------\n\n- Holdings #{}\n  - Tokyo Branch #{} *\n    - Shibuya Office #{}\n",
+                ids[0].hyphenated(),
+                ids[1].hyphenated(),
+                ids[2].hyphenated()
+            )
+        );
+    }
 }
@@ -0,0 +1,5 @@
+mod metrics;
+mod tracer;
+
+pub use metrics::{MetricsRegistry, REQUEST_TARGET};
+pub use tracer::{FieldValue, Level, LogFormat, Span, Tracer};
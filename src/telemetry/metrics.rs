@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// リクエストのスパンの対象．メトリクスではユースケースとして集計する
+pub const REQUEST_TARGET: &str = "mediator";
+
+/// 処理時間のヒストグラムの上限(秒)
+const DURATION_BUCKETS: [f64; 10] = [0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+const REQUESTS_TOTAL: (&str, &str) = (
+    "ddd_example_requests_total",
+    "Number of handled requests by use case and outcome.",
+);
+const REQUEST_DURATION: (&str, &str) = (
+    "ddd_example_request_duration_seconds",
+    "Time spent handling a request by use case.",
+);
+const REPOSITORY_CALLS_TOTAL: (&str, &str) = (
+    "ddd_example_repository_calls_total",
+    "Number of repository calls by repository, operation and outcome.",
+);
+const REPOSITORY_DURATION: (&str, &str) = (
+    "ddd_example_repository_call_duration_seconds",
+    "Time spent in a repository call by repository and operation.",
+);
+const CLIENTS: (&str, &str) = ("ddd_example_clients", "Number of clients in the tenant.");
+
+// -------------------------------------------------------------------------------------------------
+
+type Labels = Vec<(&'static str, String)>;
+
+enum Series {
+    Counter(u64),
+    Gauge(f64),
+    Histogram {
+        /// 各上限以下の件数(累積ではない)
+        buckets: [u64; DURATION_BUCKETS.len()],
+        sum: f64,
+        count: u64,
+    },
+}
+
+struct Family {
+    help: &'static str,
+    kind: &'static str,
+    series: BTreeMap<Labels, Series>,
+}
+
+/// カウンター，ゲージ，ヒストグラムを集計し，Prometheusのテキスト形式で出力する．
+/// 公開用のスレッドからも読むため，内部はロックで守る
+#[derive(Default)]
+pub struct MetricsRegistry {
+    families: Mutex<BTreeMap<&'static str, Family>>,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// 閉じたスパンを集計する．outcomeを記録していないスパンは成功とみなす
+    pub fn record_span(
+        &self,
+        target: &'static str,
+        name: &'static str,
+        fields: &[(&'static str, String)],
+        elapsed: Duration,
+    ) {
+        let outcome = fields
+            .iter()
+            .rev()
+            .find(|(key, _)| *key == "outcome")
+            .map(|(_, value)| value.clone())
+            .unwrap_or_else(|| "ok".to_string());
+        let seconds = elapsed.as_secs_f64();
+        if target == REQUEST_TARGET {
+            let labels = vec![("request", name.to_string())];
+            self.increment(REQUESTS_TOTAL, with_label(&labels, "outcome", outcome));
+            self.observe(REQUEST_DURATION, labels, seconds);
+        } else {
+            let labels = vec![
+                ("repository", target.to_string()),
+                ("operation", name.to_string()),
+            ];
+            self.increment(
+                REPOSITORY_CALLS_TOTAL,
+                with_label(&labels, "outcome", outcome),
+            );
+            self.observe(REPOSITORY_DURATION, labels, seconds);
+        }
+    }
+    pub fn set_client_count(&self, tenant: &str, count: usize) {
+        self.update(
+            CLIENTS,
+            "gauge",
+            vec![("tenant", tenant.to_string())],
+            |series| *series = Series::Gauge(count as f64),
+        );
+    }
+    fn increment(&self, metric: (&'static str, &'static str), labels: Labels) {
+        self.update(metric, "counter", labels, |series| {
+            if let Series::Counter(value) = series {
+                *value += 1;
+            }
+        });
+    }
+    fn observe(&self, metric: (&'static str, &'static str), labels: Labels, seconds: f64) {
+        self.update(metric, "histogram", labels, |series| {
+            if let Series::Histogram {
+                buckets,
+                sum,
+                count,
+            } = series
+            {
+                if let Some(index) = DURATION_BUCKETS.iter().position(|le| seconds <= *le) {
+                    buckets[index] += 1;
+                }
+                *sum += seconds;
+                *count += 1;
+            }
+        });
+    }
+    fn update<F: FnOnce(&mut Series)>(
+        &self,
+        (name, help): (&'static str, &'static str),
+        kind: &'static str,
+        labels: Labels,
+        f: F,
+    ) {
+        let mut families = self.families.lock().unwrap_or_else(|err| err.into_inner());
+        let family = families.entry(name).or_insert_with(|| Family {
+            help,
+            kind,
+            series: BTreeMap::new(),
+        });
+        let series = family.series.entry(labels).or_insert_with(|| match kind {
+            "counter" => Series::Counter(0),
+            "gauge" => Series::Gauge(0.0),
+            _ => Series::Histogram {
+                buckets: [0; DURATION_BUCKETS.len()],
+                sum: 0.0,
+                count: 0,
+            },
+        });
+        f(series);
+    }
+    /// Prometheusのテキスト形式(0.0.4)
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap_or_else(|err| err.into_inner());
+        let mut text = String::new();
+        for (name, family) in families.iter() {
+            text.push_str(&format!("# HELP {} {}\n", name, family.help));
+            text.push_str(&format!("# TYPE {} {}\n", name, family.kind));
+            for (labels, series) in family.series.iter() {
+                match series {
+                    Series::Counter(value) => {
+                        text.push_str(&format!("{}{} {}\n", name, render_labels(labels), value))
+                    }
+                    Series::Gauge(value) => {
+                        text.push_str(&format!("{}{} {}\n", name, render_labels(labels), value))
+                    }
+                    Series::Histogram {
+                        buckets,
+                        sum,
+                        count,
+                    } => {
+                        let mut cumulative = 0;
+                        for (le, bucket) in DURATION_BUCKETS.iter().zip(buckets.iter()) {
+                            cumulative += bucket;
+                            text.push_str(&format!(
+                                "{}_bucket{} {}\n",
+                                name,
+                                render_labels(&with_label(labels, "le", le.to_string())),
+                                cumulative
+                            ));
+                        }
+                        text.push_str(&format!(
+                            "{}_bucket{} {}\n",
+                            name,
+                            render_labels(&with_label(labels, "le", "+Inf".to_string())),
+                            count
+                        ));
+                        text.push_str(&format!("{}_sum{} {}\n", name, render_labels(labels), sum));
+                        text.push_str(&format!(
+                            "{}_count{} {}\n",
+                            name,
+                            render_labels(labels),
+                            count
+                        ));
+                    }
+                }
+            }
+        }
+        text
+    }
+}
+
+fn with_label(labels: &Labels, key: &'static str, value: String) -> Labels {
+    let mut labels = labels.clone();
+    labels.push((key, value));
+    labels
+}
+
+fn render_labels(labels: &Labels) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let labels = labels
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}=\"{}\"",
+                key,
+                value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{{{}}}", labels)
+}
+
+#[cfg(test)]
+mod test {
+    use super::MetricsRegistry;
+    use std::time::Duration;
+
+    #[test]
+    fn render_counters_histograms_and_gauges() {
+        let metrics = MetricsRegistry::new();
+        metrics.record_span(
+            "mediator",
+            "GetClientUseCaseRequest",
+            &[("outcome", "ok".to_string())],
+            Duration::from_millis(2),
+        );
+        metrics.record_span(
+            "mediator",
+            "GetClientUseCaseRequest",
+            &[("outcome", "failed".to_string())],
+            Duration::from_secs(10),
+        );
+        metrics.record_span("ClientRepository", "all", &[], Duration::from_micros(50));
+        metrics.set_client_count("acme \"west\"", 3);
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE ddd_example_requests_total counter\n"));
+        assert!(text.contains(
+            "ddd_example_requests_total{request=\"GetClientUseCaseRequest\",outcome=\"failed\"} 1\n"
+        ));
+        assert!(text.contains(
+            "ddd_example_requests_total{request=\"GetClientUseCaseRequest\",outcome=\"ok\"} 1\n"
+        ));
+        assert!(text.contains("# TYPE ddd_example_request_duration_seconds histogram\n"));
+        assert!(text.contains(
+            "ddd_example_request_duration_seconds_bucket{request=\"GetClientUseCaseRequest\",le=\"0.001\"} 0\n"
+        ));
+        assert!(text.contains(
+            "ddd_example_request_duration_seconds_bucket{request=\"GetClientUseCaseRequest\",le=\"0.005\"} 1\n"
+        ));
+        assert!(text.contains(
+            "ddd_example_request_duration_seconds_bucket{request=\"GetClientUseCaseRequest\",le=\"5\"} 1\n"
+        ));
+        assert!(text.contains(
+            "ddd_example_request_duration_seconds_bucket{request=\"GetClientUseCaseRequest\",le=\"+Inf\"} 2\n"
+        ));
+        assert!(text.contains(
+            "ddd_example_request_duration_seconds_count{request=\"GetClientUseCaseRequest\"} 2\n"
+        ));
+        assert!(text.contains(
+            "ddd_example_repository_calls_total{repository=\"ClientRepository\",operation=\"all\",outcome=\"ok\"} 1\n"
+        ));
+        assert!(text.contains("ddd_example_clients{tenant=\"acme \\\"west\\\"\"} 3\n"));
+    }
+}
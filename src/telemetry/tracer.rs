@@ -0,0 +1,427 @@
+use super::MetricsRegistry;
+use chrono::{SecondsFormat, Utc};
+use std::cell::{Cell, RefCell};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+use uuid::Uuid;
+
+// -------------------------------------------------------------------------------------------------
+
+/// ログの重要度．設定ではOffで全て出力しない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub const ALL: [Level; 6] = [
+        Level::Off,
+        Level::Error,
+        Level::Warn,
+        Level::Info,
+        Level::Debug,
+        Level::Trace,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Off => "off",
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.as_str() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                format!(
+                    "Unknown log level: {} (expected off, error, warn, info, debug or trace)",
+                    s
+                )
+            })
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// 1行ごとの出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Human,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "human" => Ok(LogFormat::Human),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "Unknown log format: {} (expected human or json)",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Human => write!(f, "human"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// スパンに添える値．個人データを含めないようにIDや件数だけを渡す
+pub trait FieldValue {
+    fn field_value(&self) -> String;
+}
+
+impl FieldValue for Uuid {
+    fn field_value(&self) -> String {
+        self.hyphenated().to_string()
+    }
+}
+
+impl FieldValue for u64 {
+    fn field_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl FieldValue for usize {
+    fn field_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn field_value(&self) -> String {
+        match self {
+            Some(value) => value.field_value(),
+            None => "none".to_string(),
+        }
+    }
+}
+
+impl<T: FieldValue> FieldValue for Vec<T> {
+    fn field_value(&self) -> String {
+        self.iter()
+            .map(FieldValue::field_value)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+
+/// スパンを入れ子にして記録する．スパンは閉じたときに所要時間と共に1行で出力する
+pub struct Tracer {
+    level: Level,
+    format: LogFormat,
+    sink: Box<dyn Fn(String)>,
+    next_id: Cell<u64>,
+    /// 開いているスパンのID．最後が現在のスパン
+    open_spans: RefCell<Vec<u64>>,
+    metrics: Option<Arc<MetricsRegistry>>,
+}
+
+impl Tracer {
+    pub fn new<F: Fn(String) + 'static>(level: Level, format: LogFormat, sink: F) -> Self {
+        Self {
+            level,
+            format,
+            sink: Box::new(sink),
+            next_id: Cell::new(1),
+            open_spans: RefCell::new(Vec::new()),
+            metrics: None,
+        }
+    }
+    /// 閉じたスパンをログの重要度に関わらずメトリクスにも集計する
+    pub fn with_metrics(mut self, metrics: Arc<MetricsRegistry>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+    pub fn is_enabled(&self, level: Level) -> bool {
+        level != Level::Off && level <= self.level
+    }
+    /// targetは処理の層(mediator，ClientRepositoryなど)，nameはその中の操作
+    pub fn span(
+        &self,
+        level: Level,
+        target: &'static str,
+        name: &'static str,
+        fields: Vec<(&'static str, String)>,
+    ) -> Span<'_> {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        let parent = self.open_spans.borrow().last().copied();
+        self.open_spans.borrow_mut().push(id);
+        Span {
+            tracer: self,
+            id,
+            parent,
+            level,
+            target,
+            name,
+            fields,
+            started_at: Instant::now(),
+        }
+    }
+    /// スパンを開かずに1行のメッセージを出力する．開いているスパンがあればその子とする
+    pub fn event(&self, level: Level, target: &'static str, message: &str) {
+        if !self.is_enabled(level) {
+            return;
+        }
+        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let parent = self.open_spans.borrow().last().copied();
+        let line = match self.format {
+            LogFormat::Human => {
+                let mut line = format!(
+                    "{} {:<5} {} {}",
+                    timestamp,
+                    level.as_str().to_uppercase(),
+                    target,
+                    message
+                );
+                if let Some(parent) = parent {
+                    line.push_str(&format!(" parent={}", parent));
+                }
+                line
+            }
+            LogFormat::Json => format!(
+                r#"{{"timestamp":"{}","level":"{}","target":{},"parent":{},"message":{}}}"#,
+                timestamp,
+                level,
+                json_string(target),
+                parent
+                    .map(|parent| parent.to_string())
+                    .unwrap_or_else(|| "null".to_string()),
+                json_string(message)
+            ),
+        };
+        (self.sink)(line);
+    }
+    fn emit(&self, span: &Span<'_>) {
+        if !self.is_enabled(span.level) {
+            return;
+        }
+        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let elapsed_ms = span.started_at.elapsed().as_secs_f64() * 1000.0;
+        let line = match self.format {
+            LogFormat::Human => {
+                let mut line = format!(
+                    "{} {:<5} {}.{}",
+                    timestamp,
+                    span.level.as_str().to_uppercase(),
+                    span.target,
+                    span.name
+                );
+                for (key, value) in span.fields.iter() {
+                    line.push_str(&format!(" {}={}", key, value));
+                }
+                line.push_str(&format!(" elapsed_ms={:.3} span={}", elapsed_ms, span.id));
+                if let Some(parent) = span.parent {
+                    line.push_str(&format!(" parent={}", parent));
+                }
+                line
+            }
+            LogFormat::Json => {
+                let fields = span
+                    .fields
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", json_string(key), json_string(value)))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!(
+                    r#"{{"timestamp":"{}","level":"{}","target":{},"name":{},"span":{},"parent":{},"elapsed_ms":{:.3},"fields":{{{}}}}}"#,
+                    timestamp,
+                    span.level,
+                    json_string(span.target),
+                    json_string(span.name),
+                    span.id,
+                    span.parent
+                        .map(|parent| parent.to_string())
+                        .unwrap_or_else(|| "null".to_string()),
+                    elapsed_ms,
+                    fields
+                )
+            }
+        };
+        (self.sink)(line);
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// 開いている処理．破棄したときに閉じて出力する
+pub struct Span<'a> {
+    tracer: &'a Tracer,
+    id: u64,
+    parent: Option<u64>,
+    level: Level,
+    target: &'static str,
+    name: &'static str,
+    fields: Vec<(&'static str, String)>,
+    started_at: Instant,
+}
+
+impl Span<'_> {
+    pub fn record<V: FieldValue>(&mut self, key: &'static str, value: &V) {
+        self.fields.push((key, value.field_value()));
+    }
+    /// 結果のように文字列で表す値
+    pub fn record_str(&mut self, key: &'static str, value: &str) {
+        self.fields.push((key, value.to_string()));
+    }
+    /// 失敗したときなどに，より重要な記録として出力する
+    pub fn raise_to(&mut self, level: Level) {
+        self.level = self.level.min(level);
+    }
+}
+
+impl Drop for Span<'_> {
+    fn drop(&mut self) {
+        self.tracer.emit(self);
+        if let Some(metrics) = &self.tracer.metrics {
+            metrics.record_span(
+                self.target,
+                self.name,
+                &self.fields,
+                self.started_at.elapsed(),
+            );
+        }
+        let mut open_spans = self.tracer.open_spans.borrow_mut();
+        if let Some(position) = open_spans.iter().rposition(|id| *id == self.id) {
+            open_spans.remove(position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Level, LogFormat, Tracer};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use uuid::Uuid;
+
+    fn tracer(level: Level, format: LogFormat) -> (Tracer, Rc<RefCell<Vec<String>>>) {
+        let lines = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&lines);
+        (
+            Tracer::new(level, format, move |line| sink.borrow_mut().push(line)),
+            lines,
+        )
+    }
+
+    #[test]
+    fn parse_level() {
+        assert_eq!("WARN".parse::<Level>(), Ok(Level::Warn));
+        assert!("verbose".parse::<Level>().is_err());
+    }
+
+    #[test]
+    fn nest_spans_and_filter_by_level() {
+        let (tracer, lines) = tracer(Level::Info, LogFormat::Human);
+        let id = Uuid::new_v4();
+        {
+            let mut request = tracer.span(Level::Info, "mediator", "GetClient", Vec::new());
+            request.record("id", &id);
+            {
+                let mut query = tracer.span(Level::Info, "ClientRepository", "by_id", Vec::new());
+                query.record_str("outcome", "error");
+            }
+            // 閾値より詳細なスパンは出力しない
+            let _debug = tracer.span(Level::Debug, "ClientRepository", "all", Vec::new());
+            request.raise_to(Level::Warn);
+        }
+        // 閉じたスパンは親にならない
+        drop(tracer.span(Level::Info, "mediator", "GetAllClient", Vec::new()));
+
+        let lines = lines.borrow();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains(" INFO  ClientRepository.by_id outcome=error elapsed_ms="));
+        assert!(lines[0].ends_with(" span=2 parent=1"));
+        assert!(lines[1].contains(&format!(" WARN  mediator.GetClient id={} elapsed_ms=", id)));
+        assert!(lines[1].ends_with(" span=1"));
+        assert!(lines[2].ends_with(" span=4"));
+    }
+
+    #[test]
+    fn write_json_lines() {
+        let (tracer, lines) = tracer(Level::Trace, LogFormat::Json);
+        {
+            let _span = tracer.span(
+                Level::Debug,
+                "ClientRepository",
+                "save",
+                vec![("id", "a\"b".to_string())],
+            );
+        }
+        let (tracer_off, lines_off) = self::tracer(Level::Off, LogFormat::Json);
+        drop(tracer_off.span(Level::Error, "mediator", "Login", Vec::new()));
+        assert!(lines_off.borrow().is_empty());
+
+        let line = &lines.borrow()[0];
+        assert!(line.starts_with(r#"{"timestamp":""#));
+        assert!(line.contains(
+            r#""level":"debug","target":"ClientRepository","name":"save","span":1,"parent":null,"elapsed_ms":"#
+        ));
+        assert!(line.ends_with(r#","fields":{"id":"a\"b"}}"#));
+    }
+
+    #[test]
+    fn write_events_under_the_current_span() {
+        let (tracer, lines) = tracer(Level::Info, LogFormat::Human);
+        tracer.event(Level::Debug, "mediator", "-> GetClient");
+        {
+            let _request = tracer.span(Level::Debug, "mediator", "GetClient", Vec::new());
+            tracer.event(Level::Info, "mediator", "-> GetClient");
+        }
+        let (json_tracer, json_lines) = self::tracer(Level::Info, LogFormat::Json);
+        json_tracer.event(Level::Warn, "mediator", "<- \"GetClient\" ok");
+
+        let lines = lines.borrow();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with(" INFO  mediator -> GetClient parent=1"));
+        assert!(json_lines.borrow()[0].ends_with(
+            r#""level":"warn","target":"mediator","parent":null,"message":"<- \"GetClient\" ok"}"#
+        ));
+    }
+}